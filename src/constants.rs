@@ -1,6 +1,8 @@
 pub const PRICES_KEY: &str = "prices";
 pub const SUMMARY_KEY: &str = "summary";
 pub const CHANNEL_NAME: &str = "all_data";
+pub const ANALYTICS_CHANNEL_NAME: &str = "analytics";
+pub const ALERT_CHANNEL_NAME: &str = "alerts";
 
 pub const DELAY_MILISEC: u64 = 100;
 
@@ -12,6 +14,57 @@ pub const SELL_LOG_PDA_SEED: &str = "sell_log_pda_seed";
 pub const BUY_LOG_PDA_SEED: &str = "buy_log_pda_seed";
 
 pub const GD_ORDER_DEPTH: usize = 20;
+pub const CONSOLIDATED_ORDER_DEPTH: usize = 20;
+// Consolidated levels count price/amount in 1e-9 units across venues
+pub const CONSOLIDATED_LOT_DECIMALS: u8 = 9;
+pub const AMM_LADDER_DEPTH: usize = 20;
+pub const AMM_LADDER_STEP_BPS: u64 = 10;
+pub const AMM_DEFAULT_FEE_BPS: u64 = 25;
 
+pub const DEPTH_BPS: [u64; 3] = [10, 50, 100];
+pub const BOOK_METRICS_INTERVAL_SECS: u64 = 10;
+pub const SNAPSHOT_INTERVAL_SECS: u64 = 60;
+pub const MAKER_QUOTE_BPS: u64 = 50;
+pub const MAKER_STATS_INTERVAL_SECS: u64 = 60;
+pub const LEDGER_MARK_INTERVAL_SECS: u64 = 5;
+
+pub const ALERT_RULES_KEY: &str = "alert_rules";
+pub const ALERT_COOLDOWN_SECS: u64 = 300;
+pub const ALERT_RULES_TTL_SECS: u64 = 10;
+pub const ALERT_PRICE_WINDOW_SECS: u64 = SECONDS_PER_DAY;
+
+pub const INDICATOR_CONFIG_KEY: &str = "indicator_config";
+pub const SMA_PERIODS: [usize; 2] = [20, 50];
+pub const EMA_PERIODS: [usize; 2] = [12, 26];
+pub const RSI_PERIOD: usize = 14;
+pub const BOLLINGER_PERIOD: usize = 20;
+pub const BOLLINGER_STD_DEV: f64 = 2.0;
+
+pub const SOL_ORACLE_KEY: &str = "sol";
+pub const SOL_PRICE_REFRESH_SECS: u64 = 5;
+
+pub const AUM_KEY: &str = "aum";
+// Jupiter Perpetuals stores usd values with USD_DECIMALS = 6
+pub const AUM_USD_DECIMALS: u8 = 6;
+
+pub const PYTH_PROGRAM_ID: &str = "FsJ3A3u2vn5cTVofAjvy6y5kwABJAqYWpe4975bi2epH";
+pub const SWITCHBOARD_PROGRAM_ID: &str = "SW1TCH7qEPTdLsDHRgPuMQjbQxKdH2aBStViMFnt64f";
 pub const GIGADEX_PROGRAM_ID: &str = "833pSHchW8AWggrvx8394HHkH1cMHxdyYcDro8ABYUXC";
-pub const OPENBOOK_PROGRAM_ID: &str = "srmqPvymJeFKQ4zGQed1GFppgkRHL9kaELCbyksJtPX";
\ No newline at end of file
+pub const OPENBOOK_PROGRAM_ID: &str = "srmqPvymJeFKQ4zGQed1GFppgkRHL9kaELCbyksJtPX";
+pub const OPENBOOK_V2_PROGRAM_ID: &str = "opnb2LAfJYbRMAHHvqjCwQxanZn7ReEHp1k81EohpZb";
+pub const PHOENIX_PROGRAM_ID: &str = "PhoeNiXZ8ByJGLkxNfZRnkUfjvmuYqLR89jjFHGqdXY";
+
+pub const TX_FILL_WAIT_SLOTS: u64 = 3;
+pub const TX_FILL_KEEP_SLOTS: u64 = 150;
+pub const OB_NEW_ORDER_V3_TAG: u32 = 10;
+pub const OB_SEND_TAKE_TAG: u32 = 13;
+pub const PHOENIX_LOG_INSTRUCTION_TAG: u8 = 15;
+pub const GD_NON_FILL_INSTRUCTIONS: [&str; 5] = ["Cancel", "Deposit", "Withdraw", "Claim", "Init"];
+pub const OB_V2_TAKER_INSTRUCTIONS: [&str; 3] = ["PlaceOrder", "PlaceOrderPegged", "PlaceTakeOrder"];
+
+pub const QUERY_DEFAULT_LIMIT: u64 = 100;
+
+// Rpc's getMultipleAccounts takes at most 100 keys
+pub const RPC_MAX_MULTIPLE_ACCOUNTS: usize = 100;
+pub const RECORD_FLUSH_RECORDS: usize = 256;
+pub const RECORD_FLUSH_INTERVAL_MS: u64 = 1000;
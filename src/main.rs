@@ -13,8 +13,7 @@ use std::{env, time::Duration};
 use tokio::{time::sleep, try_join};
 use yellowstone_grpc_client::GeyserGrpcClient;
 use crate::processor::*;
-
-use crate::structs::*;
+use crate::structs::{query::QueryState, subscribe::SubscribeOptions};
 
 #[tokio::main]
 async fn main() {
@@ -29,8 +28,26 @@ async fn main() {
     env::var("SUPABASE_AUTH_TOKEN").expect("SUPABASE_AUTH_TOKEN not set in .env");
     let triton_url = env::var("TRITON_URL").expect("TRITON_URL not set in .env");
     let triton_token = env::var("TRITON_TOKEN").expect("TRITON_TOKEN not set in .env");
-
-    let anchor_account_address = "5BUwFW4nRbftYTDMbgxykoFWqWHPzahFSNAaaaJtVKsq";
+    let subscribe_options = SubscribeOptions {
+        analytics_notionals: env::var("ANALYTICS_NOTIONALS")
+            .unwrap_or("1,10,100".to_string())
+            .split(',')
+            .filter_map(|x| x.trim().parse::<f64>().ok())
+            .collect(),
+        sol_oracle_address: env::var("SOL_ORACLE_ADDRESS").ok(),
+        aum_account_address: env::var("AUM_ACCOUNT_ADDRESS").ok(),
+        subscribe_transactions: env::var("SUBSCRIBE_TRANSACTIONS")
+            .map(|x| x == "true")
+            .unwrap_or(false),
+    };
+    let record_path = env::var("GEYSER_RECORD_PATH").ok();
+    let replay_path = env::var("REPLAY_PATH").ok();
+    let replay_speed = env::var("REPLAY_SPEED")
+        .map(|x| x.parse::<f64>().unwrap_or(0.0))
+        .unwrap_or(0.0);
+    let query_port = env::var("QUERY_PORT")
+        .ok()
+        .map(|x| x.parse::<u16>().expect("Invalid QUERY_PORT"));
 
     // Tracing configuration
     let subscriber = tracing_subscriber::FmtSubscriber::builder()
@@ -39,6 +56,14 @@ async fn main() {
     tracing::subscriber::set_global_default(subscriber).expect("failed to set global tracing sub");
     tracing::info!("Initializing server v11");
 
+    // Replay recorded account updates into in-memory sinks instead of subscribing
+    if let Some(path) = replay_path {
+        replay::replay_recording(&path, replay_speed)
+            .await
+            .expect("Replay failed");
+        return;
+    }
+
     // Connect redis
     let redis_client = redis::Client::open(redis_url.clone()).expect("Failed to connect to redis");
 
@@ -46,6 +71,22 @@ async fn main() {
     let supabase_client =
         Postgrest::new(supabase_url).insert_header("apikey", supabase_auth_token.clone());
 
+    // Serve queries from supabase/redis over http alongside subscribing
+    let query_task = tokio::spawn({
+        let query_state = QueryState {
+            supabase_client: supabase_client.clone(),
+            redis_client: redis_client.clone(),
+        };
+
+        async move {
+            if let Some(port) = query_port {
+                if let Err(e) = query::serve_queries(port, query_state).await {
+                    tracing::error!("Query server error: {:?}", e);
+                }
+            }
+        }
+    });
+
     // Connect geyser client
     let mut geyser_client = GeyserGrpcClient::connect_with_timeout(
         triton_url,
@@ -67,8 +108,10 @@ async fn main() {
 
         async move {
             loop {
-                let ret = subscribe_geyser(
+                let ret = run_subscribe(
                     api_url.clone(),
+                    &subscribe_options,
+                    record_path.clone(),
                     &redis_client,
                     &supabase_client,
                     &rpc_client,
@@ -78,6 +121,7 @@ async fn main() {
 
                 if ret.is_err() {
                     tracing::error!("Subscribe error: {:?}", ret.err());
+                    sleep(Duration::from_millis(constants::DELAY_MILISEC)).await;
                 };
             }
         }
@@ -93,21 +137,5 @@ async fn main() {
     });
 
     // Wait for join tasks
-    try_join!(subscribe_task, health_check_task,).expect("Error to finish task");
-
-    //jack-dev new plugin output 1
-    let ( price, amount, is_buy ) = extractor(
-        api_url: api_url, 
-        redis_client: redis_client, 
-        market_address: String
-    );
-    println!("price: {}, amount: {}, is_buy: {}", price, amount, is_buy);
-
-    //jack-dev new plugin output 2
-    if let Some(aum_usd_value) = get_aum_usd_data(anchor_account_address) {
-        println!("aum_usd: {}", aum_usd_value);
-    } else {
-        println!("Error retrieving aum_usd data.");
-    }
-
+    try_join!(subscribe_task, health_check_task, query_task).expect("Error to finish task");
 }
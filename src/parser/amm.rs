@@ -0,0 +1,553 @@
+use async_trait::async_trait;
+use num_traits::FromPrimitive;
+use redis::{Connection, ConnectionLike};
+use sqlx::types::Decimal;
+use std::{
+    collections::HashMap,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use yellowstone_grpc_proto::prelude::SubscribeRequestFilterAccounts;
+
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::{nonblocking::rpc_client::RpcClient, rpc_config::RpcAccountInfoConfig};
+use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey};
+
+use crate::{
+    constants::{AMM_LADDER_DEPTH, AMM_LADDER_STEP_BPS},
+    processor::{
+        adapter::{AccountsFilterMap, DexAdapter},
+        db::DbSink,
+    },
+    structs::{
+        adapter::{DexEvent, VenueSnapshot},
+        amm::{
+            construct_amm_levels, detect_amm_swap, meteora_pool_vaults, meteora_vault_lp_mint,
+            AmmKind, AmmPoolInfo, AmmReserves, AmmState, MeteoraVaults, WhirlpoolState,
+        },
+        decode::DecodeError,
+        geyser::Account,
+        market::{MarketConfig, MarketOrders, MarketTrade, VenueMarket},
+    },
+};
+
+/*
+ * Function: parse_amm_account
+ * 1. For vault pools, skip stale writes by write version and collect writes of a transaction by its signature
+ * 2. Settle vault reserves once every pool account was written by the transaction, or once another transaction writes
+ * 3. For whirlpools, settle virtual reserves of active liquidity on every pool write
+ */
+pub fn parse_amm_account(
+    pool: &AmmPoolInfo,
+    state: &mut AmmState,
+    account: &Account,
+) -> anyhow::Result<Vec<DexEvent>> {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+
+    if pool.kind == AmmKind::Whirlpool {
+        let whirlpool = WhirlpoolState::load(&account.data).ok_or(DecodeError::Corrupted {
+            account: "whirlpool",
+            reason: "invalid pool layout",
+        })?;
+        let liquidity = whirlpool.liquidity;
+        // Active liquidity change is deposit/withdraw or tick crossing, amounts aren't derivable
+        let swap_allowed = liquidity == state.liquidity;
+        state.liquidity = liquidity;
+        state.pending_slot = account.slot;
+        state.pending_signature = Some(account.txn_signature.clone());
+        return Ok(settle_amm_reserves(
+            pool,
+            state,
+            whirlpool.reserves(pool),
+            swap_allowed,
+            now,
+        ));
+    }
+
+    let amount = pool
+        .account_amount(&account.pubkey, &account.data, now)
+        .ok_or(DecodeError::Corrupted {
+            account: "amm vault",
+            reason: "invalid vault account layout",
+        })?;
+    let write_version = state.write_versions.entry(account.pubkey).or_default();
+    if account.write_version > 0 && account.write_version <= *write_version {
+        return Ok(vec![]);
+    }
+    *write_version = account.write_version;
+
+    // Writes of previous transaction are complete once another transaction writes
+    let mut events: Vec<DexEvent> = Vec::new();
+    if state
+        .pending_signature
+        .as_ref()
+        .is_some_and(|x| x != &account.txn_signature)
+    {
+        events.extend(settle_vault_reserves(pool, state, now));
+    }
+
+    state.amounts.insert(account.pubkey, amount);
+    if state.pending_signature.is_none() {
+        state.pending_slot = account.slot;
+        state.pending_signature = Some(account.txn_signature.clone());
+    }
+    state.pending_accounts.insert(account.pubkey);
+    if state.pending_accounts.len() == pool.accounts().len() {
+        events.extend(settle_vault_reserves(pool, state, now));
+    }
+
+    Ok(events)
+}
+
+/*
+ * Helper function for settling vault pool reserves from latest amounts of its accounts
+ */
+fn settle_vault_reserves(pool: &AmmPoolInfo, state: &mut AmmState, now: u64) -> Vec<DexEvent> {
+    match pool.vault_reserves(&state.amounts) {
+        Some(reserves) => settle_amm_reserves(pool, state, reserves, true, now),
+        None => {
+            state.pending_signature = None;
+            state.pending_accounts.clear();
+            vec![]
+        }
+    }
+}
+
+/*
+ * Helper function for settling reserves written by pending transaction
+ * 1. Build book side events of synthetic ladder around pool price
+ * 2. If reserves moved in opposite directions since last settled reserves, then build trades event of pending transaction
+ */
+fn settle_amm_reserves(
+    pool: &AmmPoolInfo,
+    state: &mut AmmState,
+    reserves: AmmReserves,
+    swap_allowed: bool,
+    now: u64,
+) -> Vec<DexEvent> {
+    let signature = state.pending_signature.take().unwrap_or_default();
+    state.pending_accounts.clear();
+    state.reserves = reserves;
+
+    let mut events: Vec<DexEvent> = Vec::new();
+    for is_bid in [true, false] {
+        events.push(DexEvent::BookSide {
+            market: pool.name.clone(),
+            venue: pool.venue,
+            address: pool.address.to_string(),
+            is_bid,
+            levels: construct_amm_levels(
+                &state.reserves,
+                pool,
+                is_bid,
+                AMM_LADDER_DEPTH,
+                AMM_LADDER_STEP_BPS,
+            ),
+            quotes: vec![],
+        });
+    }
+
+    let swap = match state.settled {
+        Some(settled) if swap_allowed => detect_amm_swap(&settled, &state.reserves),
+        _ => None,
+    };
+    state.settled = Some(state.reserves);
+
+    if let Some(swap) = swap {
+        tracing::info!(
+            "AMM swap: {} {} - {}, {}",
+            pool.name,
+            pool.venue.as_str(),
+            swap.price,
+            swap.amount
+        );
+
+        events.push(DexEvent::Trades(vec![MarketTrade {
+            slug: pool.name.clone(),
+            order_id: None,
+            market_address: pool.address.to_string(),
+            market_buy: if swap.is_buy { 1 } else { 0 },
+            avg_price: Decimal::from_f64(swap.price).unwrap_or_default(),
+            amount: Decimal::from_f64(swap.amount).unwrap_or_default(),
+            index: 0,
+            timestamp: now,
+            blocktime: now,
+            avg_price_lots: Decimal::from_f64(swap.price * 10f64.powi(pool.quote_decimals as i32))
+                .unwrap_or_default()
+                .round(),
+            amount_lots: Decimal::from_f64(swap.amount * 10f64.powi(pool.base_decimals as i32))
+                .unwrap_or_default()
+                .round(),
+            slot: state.pending_slot,
+            transaction_signature: signature,
+            maker: None,
+            price_usd: None,
+            notional_usd: None,
+            quote_usd_rate: None,
+            instruction_index: None,
+            inner_instruction_index: None,
+            taker: None,
+            fee_payer: None,
+            attribution: None,
+            taker_account: None,
+            is_taker_buy: swap.is_buy,
+        }]));
+    }
+
+    events
+}
+
+/*
+ * Helper function for discovering Meteora vaults backing dynamic pool
+ * 1. Read a/b vaults and pool's vault lp token accounts from pool account
+ * 2. Read lp mints from vault accounts
+ */
+async fn load_meteora_vaults(
+    rpc_client: &RpcClient,
+    pool: &Pubkey,
+    rpc_config: &RpcAccountInfoConfig,
+) -> anyhow::Result<Option<MeteoraVaults>> {
+    let pool_account = rpc_client
+        .get_multiple_accounts_with_config(&[*pool], rpc_config.clone())
+        .await?
+        .value;
+    let [a_vault, b_vault, a_vault_lp, b_vault_lp] = match pool_account
+        .first()
+        .and_then(|x| x.as_ref())
+        .and_then(|x| meteora_pool_vaults(&x.data))
+    {
+        Some(vaults) => vaults,
+        None => return Ok(None),
+    };
+
+    let vault_accounts = rpc_client
+        .get_multiple_accounts_with_config(&[a_vault, b_vault], rpc_config.clone())
+        .await?
+        .value;
+    let lp_mint = |i: usize| {
+        vault_accounts
+            .get(i)
+            .and_then(|x| x.as_ref())
+            .and_then(|x| meteora_vault_lp_mint(&x.data))
+    };
+    let (a_lp_mint, b_lp_mint) = match (lp_mint(0), lp_mint(1)) {
+        (Some(a_lp_mint), Some(b_lp_mint)) => (a_lp_mint, b_lp_mint),
+        _ => return Ok(None),
+    };
+
+    Ok(Some(MeteoraVaults {
+        a_vault,
+        b_vault,
+        a_vault_lp,
+        b_vault_lp,
+        a_lp_mint,
+        b_lp_mint,
+    }))
+}
+
+/*
+ * Function: parse_amm_pools
+ * 1. Parse market_info amm_pools entries of every market into pool infos
+ * 2. Get pool accounts using rpc client and build initial reserves
+ */
+pub async fn parse_amm_pools(
+    rpc_client: &RpcClient,
+    markets: &[MarketConfig],
+) -> anyhow::Result<Vec<(AmmPoolInfo, AmmState)>> {
+    let rpc_config = RpcAccountInfoConfig {
+        encoding: Some(UiAccountEncoding::Base64),
+        data_slice: None,
+        commitment: Some(CommitmentConfig::confirmed()),
+        min_context_slot: None,
+    };
+
+    let mut pools: Vec<(AmmPoolInfo, AmmState)> = Vec::new();
+    for market in markets.iter() {
+        let configs = match market.info("amm_pools") {
+            Some(configs) => configs,
+            None => continue,
+        };
+        for config in configs.split(',').filter(|x| !x.trim().is_empty()) {
+            match AmmPoolInfo::from_config(market, config) {
+                Ok(mut pool) => {
+                    if pool.kind == AmmKind::MeteoraVault {
+                        match load_meteora_vaults(rpc_client, &pool.address, &rpc_config).await? {
+                            Some(meteora) => pool.meteora = meteora,
+                            None => {
+                                tracing::error!(
+                                    "Invalid meteora pool of {}: {}",
+                                    market.slug,
+                                    pool.address
+                                );
+                                continue;
+                            }
+                        }
+                    }
+
+                    let accounts = pool.accounts();
+                    let results = rpc_client
+                        .get_multiple_accounts_with_config(&accounts, rpc_config.clone())
+                        .await?
+                        .value;
+
+                    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+                    let mut state = AmmState::default();
+                    for (address, result) in accounts.iter().zip(results.into_iter()) {
+                        let account = match result {
+                            Some(account) => account,
+                            None => continue,
+                        };
+                        if pool.kind == AmmKind::Whirlpool {
+                            if let Some(whirlpool) = WhirlpoolState::load(&account.data) {
+                                pool.fee_rate_bps = whirlpool.fee_rate_bps();
+                                state.liquidity = whirlpool.liquidity;
+                                state.reserves = whirlpool.reserves(&pool);
+                            }
+                        } else if let Some(amount) =
+                            pool.account_amount(address, &account.data, now)
+                        {
+                            state.amounts.insert(*address, amount);
+                        }
+                    }
+                    if pool.kind != AmmKind::Whirlpool {
+                        state.reserves = pool.vault_reserves(&state.amounts).unwrap_or_default();
+                    }
+                    state.settled = Some(state.reserves);
+                    pools.push((pool, state));
+                }
+                Err(e) => tracing::error!("Invalid amm pool of {}: {:?}", market.slug, e),
+            }
+        }
+    }
+
+    Ok(pools)
+}
+
+/*
+ * AMM pools venue adapter, serving every raydium/meteora/orca pool
+ */
+#[derive(Default)]
+pub struct AmmAdapter {
+    pub pools: Vec<AmmPoolInfo>,
+    pub states: HashMap<Pubkey, AmmState>,
+}
+
+#[async_trait]
+impl DexAdapter for AmmAdapter {
+    fn name(&self) -> &'static str {
+        "amm"
+    }
+
+    async fn load_markets(
+        &mut self,
+        rpc_client: &RpcClient,
+        markets: &[MarketConfig],
+    ) -> anyhow::Result<()> {
+        for (pool, state) in parse_amm_pools(rpc_client, markets).await? {
+            self.states.insert(pool.address, state);
+            self.pools.push(pool);
+        }
+
+        Ok(())
+    }
+
+    fn account_filters(&self) -> AccountsFilterMap {
+        if self.pools.is_empty() {
+            return HashMap::new();
+        }
+
+        // Vaults are owned by token program and pools by their amm program, so filter by accounts only
+        HashMap::from([(
+            "amm".to_string(),
+            SubscribeRequestFilterAccounts {
+                account: self
+                    .pools
+                    .iter()
+                    .flat_map(|x| x.accounts())
+                    .map(|x| x.to_string())
+                    .collect(),
+                owner: [].into(),
+                filters: [].into(),
+            },
+        )])
+    }
+
+    async fn load_snapshot(
+        &mut self,
+        _rpc_client: &RpcClient,
+        _redis_conn: &mut Connection,
+    ) -> anyhow::Result<Vec<VenueSnapshot>> {
+        let snapshots = self
+            .pools
+            .iter()
+            .map(|pool| {
+                let reserves = self
+                    .states
+                    .get(&pool.address)
+                    .map(|x| x.reserves)
+                    .unwrap_or(AmmReserves::default());
+                VenueSnapshot {
+                    market: pool.name.clone(),
+                    venue_market: VenueMarket {
+                        venue: pool.venue,
+                        address: pool.address.to_string(),
+                        fee_rate_bps: pool.fee_rate_bps,
+                    },
+                    orders: MarketOrders {
+                        asks: construct_amm_levels(
+                            &reserves,
+                            pool,
+                            false,
+                            AMM_LADDER_DEPTH,
+                            AMM_LADDER_STEP_BPS,
+                        ),
+                        bids: construct_amm_levels(
+                            &reserves,
+                            pool,
+                            true,
+                            AMM_LADDER_DEPTH,
+                            AMM_LADDER_STEP_BPS,
+                        ),
+                    },
+                }
+            })
+            .collect();
+
+        Ok(snapshots)
+    }
+
+    async fn process_account(
+        &mut self,
+        account: &mut Account,
+        _redis_conn: &mut (dyn ConnectionLike + Send),
+        _supabase_client: &(dyn DbSink + Sync),
+    ) -> anyhow::Result<Vec<DexEvent>> {
+        let pool = match self
+            .pools
+            .iter()
+            .find(|x| x.is_valid_account(&account.pubkey))
+        {
+            Some(pool) => pool,
+            None => return Ok(vec![]),
+        };
+
+        let state = self.states.entry(pool.address).or_default();
+        parse_amm_account(pool, state, account)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structs::fixtures::{account_write, amm_pool, token_account_data};
+
+    fn pool_state(pool: &AmmPoolInfo, base_atoms: u64, quote_atoms: u64) -> AmmState {
+        let amounts = HashMap::from([
+            (pool.base_vault, base_atoms),
+            (pool.quote_vault, quote_atoms),
+        ]);
+        let reserves = pool.vault_reserves(&amounts).unwrap();
+        AmmState {
+            reserves,
+            amounts,
+            settled: Some(reserves),
+            ..Default::default()
+        }
+    }
+
+    fn trades(events: &[DexEvent]) -> Vec<MarketTrade> {
+        events
+            .iter()
+            .flat_map(|x| match x {
+                DexEvent::Trades(trades) => trades.clone(),
+                _ => vec![],
+            })
+            .collect()
+    }
+
+    #[test]
+    fn pairs_vault_writes_of_same_slot_swaps_by_signature() {
+        let pool = amm_pool("SOL-USDC");
+        let mut state = pool_state(&pool, 100_000_000_000, 10_000_000_000);
+        let (base, quote) = (pool.base_vault, pool.quote_vault);
+
+        // Two swaps of one slot, buy of 1 SOL then sell of 2 SOL
+        let writes = [
+            account_write(&base, 5, 1, "buy", token_account_data(99_000_000_000)),
+            account_write(&quote, 5, 2, "buy", token_account_data(10_101_000_000)),
+            account_write(&base, 5, 3, "sell", token_account_data(101_000_000_000)),
+            account_write(&quote, 5, 4, "sell", token_account_data(9_903_000_000)),
+        ];
+        let mut events: Vec<Vec<DexEvent>> = Vec::new();
+        for write in writes.iter() {
+            events.push(parse_amm_account(&pool, &mut state, write).unwrap());
+        }
+
+        // Half written swap publishes nothing
+        assert!(events[0].is_empty());
+        assert!(events[2].is_empty());
+        let buy = trades(&events[1]);
+        assert_eq!(buy.len(), 1);
+        assert_eq!(buy[0].transaction_signature, "buy");
+        assert_eq!(buy[0].market_buy, 1);
+        assert_eq!(buy[0].amount_lots, Decimal::from(1_000_000_000));
+        assert_eq!(buy[0].avg_price, Decimal::from(101));
+        let sell = trades(&events[3]);
+        assert_eq!(sell.len(), 1);
+        assert_eq!(sell[0].transaction_signature, "sell");
+        assert_eq!(sell[0].market_buy, 0);
+        assert_eq!(sell[0].amount_lots, Decimal::from(2_000_000_000));
+        assert_eq!(sell[0].avg_price, Decimal::from(99));
+        assert_eq!(state.reserves.base, 101.0);
+    }
+
+    #[test]
+    fn settles_one_sided_write_once_next_transaction_writes() {
+        let pool = amm_pool("SOL-USDC");
+        let mut state = pool_state(&pool, 100_000_000_000, 10_000_000_000);
+        let (base, quote) = (pool.base_vault, pool.quote_vault);
+
+        // Donation to base vault only, then a buy
+        let donation = account_write(&base, 5, 1, "donate", token_account_data(110_000_000_000));
+        assert!(parse_amm_account(&pool, &mut state, &donation)
+            .unwrap()
+            .is_empty());
+
+        let buy_base = account_write(&base, 6, 2, "buy", token_account_data(109_000_000_000));
+        let events = parse_amm_account(&pool, &mut state, &buy_base).unwrap();
+        // Donation settles as book update without trade
+        assert_eq!(events.len(), 2);
+        assert!(trades(&events).is_empty());
+        assert_eq!(state.settled.unwrap().base, 110.0);
+
+        let buy_quote = account_write(&quote, 6, 3, "buy", token_account_data(10_092_000_000));
+        let buy = trades(&parse_amm_account(&pool, &mut state, &buy_quote).unwrap());
+        assert_eq!(buy.len(), 1);
+        assert_eq!(buy[0].slot, 6);
+        assert_eq!(buy[0].avg_price, Decimal::from(92));
+    }
+
+    #[test]
+    fn skips_stale_vault_writes() {
+        let pool = amm_pool("SOL-USDC");
+        let mut state = pool_state(&pool, 100_000_000_000, 10_000_000_000);
+        let (base, quote) = (pool.base_vault, pool.quote_vault);
+
+        let write = account_write(&base, 5, 10, "swap", token_account_data(99_000_000_000));
+        parse_amm_account(&pool, &mut state, &write).unwrap();
+        let stale = account_write(&base, 5, 9, "older", token_account_data(50_000_000_000));
+        assert!(parse_amm_account(&pool, &mut state, &stale)
+            .unwrap()
+            .is_empty());
+        assert_eq!(state.amounts.get(&base), Some(&99_000_000_000));
+
+        let write = account_write(&quote, 5, 11, "swap", token_account_data(10_101_000_000));
+        assert_eq!(
+            trades(&parse_amm_account(&pool, &mut state, &write).unwrap()).len(),
+            1
+        );
+
+        // Corrupted vault account is a decode error
+        let corrupted = account_write(&quote, 6, 12, "bad", vec![0; 10]);
+        assert!(parse_amm_account(&pool, &mut state, &corrupted).is_err());
+    }
+}
@@ -1,55 +1,91 @@
 use anyhow::Ok;
+use async_trait::async_trait;
 use num_traits::{FromPrimitive, ToPrimitive};
-use postgrest::Postgrest;
-use redis::{Client, Commands, Connection};
+use redis::{Commands, Connection, ConnectionLike};
 use sqlx::types::Decimal;
 use std::{
     collections::HashMap,
+    mem::size_of,
     str::FromStr,
     time::{SystemTime, UNIX_EPOCH},
     vec,
 };
 
 use anchor_lang::AnchorDeserialize;
+use bytemuck::Pod;
 use solana_account_decoder::UiAccountEncoding;
-use solana_client::{nonblocking::rpc_client::RpcClient, rpc_config::RpcAccountInfoConfig};
+use solana_client::{
+    nonblocking::rpc_client::RpcClient,
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    rpc_filter::{Memcmp, RpcFilterType},
+};
 use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey};
+use yellowstone_grpc_proto::prelude::{
+    subscribe_request_filter_accounts_filter::Filter as AccountsFilterOneof,
+    subscribe_request_filter_accounts_filter_memcmp::Data as AccountsFilterMemcmpOneof,
+    SubscribeRequestFilterAccounts, SubscribeRequestFilterAccountsFilter,
+    SubscribeRequestFilterAccountsFilterMemcmp,
+};
 
 use crate::{
     constants::{
         BUY_LOG_PDA_SEED, CHANNEL_NAME, GD_ORDER_DEPTH, GIGADEX_PROGRAM_ID, SELL_LOG_PDA_SEED,
+        TX_FILL_KEEP_SLOTS, TX_FILL_WAIT_SLOTS,
+    },
+    processor::{
+        adapter::{AccountsFilterMap, DexAdapter, DynConnection},
+        db::DbSink,
+        transactions::instruction_names,
     },
-    processor::market::{publish_trades_data, update_trades},
     structs::{
-        geyser::Account,
+        adapter::{DexEvent, VenueSnapshot},
+        decode::{anchor_discriminator, check_anchor_discriminator, read_pod, DecodeError},
+        geyser::{Account, Transaction},
         gigadex::{
-            GdAsksData, GdBalance, GdBalanceData, GdBidsData, GdMarketInfo, GdMarketOrder,
-            GdMarketOrderLog, GdMarketState, GdOrderData, OrderTree, UserBalances,
+            FilledOrder, GdAsksData, GdBalance, GdBalanceData, GdBalanceEvent, GdBalanceEventData,
+            GdBalanceEventKind, GdBalanceTracker, GdBidsData, GdFillState, GdMarketInfo,
+            GdMarketOrder, GdMarketOrderLog, GdMarketState, GdOrderData, GdOrderEvent,
+            GdOrderEventData, GdOrderEventKind, GdPendingBalanceEvent, GdPendingLog, GdTreeDeltas,
+            GdTreeFills, GdUidWallet, GdUserAccount, OrderTree, UserBalances, GD_MARKET_ACCOUNT,
+            GD_ORDER_LOG_ACCOUNT, GD_ORDER_TREE_ACCOUNT, GD_USER_ACCOUNT, GD_USER_BALANCES_ACCOUNT,
+            MAX_FILLS_PER_MARKET_ORDER, USERS_PER_MARKET,
+        },
+        makers::MakerQuote,
+        market::{
+            EventData, MarketConfig, MarketOrder, MarketOrders, MarketTrade, Venue, VenueMarket,
         },
-        market::{MarketConfig, MarketOrder, MarketOrders, MarketTrade},
     },
-    utils::{generate_publish_uid_data, token_factor},
+    utils::{fetch_accounts, generate_publish_uid_data, token_factor},
 };
 
+const GD_DISCRIMINATOR_LEN: usize = 8;
+
 /*
  * Function: parse_gigadex_account
  * 1. Parse account data from geyser subscribe
- * 2. If ask/bids account, then update orderbook data and publish compressed_orderbook
- * 3. If buy/sell account, then build trades data with price/amount calculation and call update_trades
+ * 2. If ask/bids account, then build book side event with levels and maker quotes
+ *    Publish typed order events per uid from tree's delta log and insert them into tb_events
+ * 3. If buy/sell account, then match new order log counter with maker fills of the ask/bid tree
+ * 4. Build per-maker trades event with price/amount calculation
+ * 5. If balances account, then publish changed balances and collect their transitions into balance_events
+ * Every uid-scoped publish carries the uid's registered wallet as owner
  */
-pub async fn parse_gigadex_account(
-    api_url: String,
-    redis_client: Client,
-    supabase_client: Postgrest,
-    market: GdMarketInfo,
+pub fn parse_gigadex_account(
+    supabase_client: &(impl DbSink + ?Sized),
+    market: &GdMarketInfo,
     account: &mut Account,
-    redis_conn: &mut Connection,
-    market_orders: &mut HashMap<String, MarketOrders>,
+    redis_conn: &mut impl ConnectionLike,
     prev_uid_asks: &mut HashMap<String, HashMap<u64, Vec<GdMarketOrder>>>,
     prev_uid_bids: &mut HashMap<String, HashMap<u64, Vec<GdMarketOrder>>>,
     prev_balances: &mut HashMap<String, HashMap<u64, GdBalance>>,
-) -> anyhow::Result<()> {
-    let market_state = market_orders.get_mut(&market.address.to_string()).unwrap();
+    fill_states: &mut HashMap<String, GdFillState>,
+    prev_deltas: &mut HashMap<String, GdTreeDeltas>,
+    uid_wallets: &HashMap<String, HashMap<u64, String>>,
+    balance_events: &mut Vec<GdPendingBalanceEvent>,
+) -> anyhow::Result<Vec<DexEvent>> {
+    let mut events: Vec<DexEvent> = Vec::new();
+    let empty_owners = HashMap::new();
+    let uid_owners = uid_wallets.get(&market.name).unwrap_or(&empty_owners);
     let mut trades_to_insert: Vec<MarketTrade> = Vec::new();
 
     // Built account_info for parse data
@@ -59,12 +95,9 @@ pub async fn parse_gigadex_account(
         let gd_orders = parse_order_account(&account.data)?;
 
         // Get previous uid orders for market
-        let prev_uid_orders = (if is_bid {
-            prev_uid_bids.get_mut(&market.name)
-        } else {
-            prev_uid_asks.get_mut(&market.name)
-        })
-        .unwrap();
+        let prev_uid_orders = (if is_bid { prev_uid_bids } else { prev_uid_asks })
+            .entry(market.name.clone())
+            .or_default();
 
         // Build current orders map
         let mut cur_orders: HashMap<u64, Vec<GdMarketOrder>> = HashMap::new();
@@ -94,8 +127,14 @@ pub async fn parse_gigadex_account(
                 let prev_orders = prev_uid_orders.get(uid);
 
                 if prev_orders.is_some_and(|_orders| _orders != orders) || prev_orders.is_none() {
-                    let msg =
-                        build_order_data(is_bid, &market.name, *uid, &orders_data, account.slot);
+                    let msg = build_order_data(
+                        is_bid,
+                        &market.name,
+                        *uid,
+                        &orders_data,
+                        account.slot,
+                        uid_owners.get(uid).cloned(),
+                    );
                     redis_conn.publish(CHANNEL_NAME, msg)?;
                 }
 
@@ -122,7 +161,14 @@ pub async fn parse_gigadex_account(
             for (uid, _) in prev_uid_orders.into_iter() {
                 // If uid not exists in cur_orders, then means ask/bid is empty
                 if cur_orders.get(&uid).is_none() {
-                    let msg = build_order_data(is_bid, &market.name, *uid, &vec![], account.slot);
+                    let msg = build_order_data(
+                        is_bid,
+                        &market.name,
+                        *uid,
+                        &vec![],
+                        account.slot,
+                        uid_owners.get(uid).cloned(),
+                    );
                     redis_conn.publish(CHANNEL_NAME, msg)?;
                 };
             }
@@ -131,54 +177,99 @@ pub async fn parse_gigadex_account(
         }
 
         let orders = sort_orders(&gd_orders, &market, GD_ORDER_DEPTH, is_bid);
+        let quotes = convert_quotes(&gd_orders, &market);
 
-        // Update local market state
-        if is_bid {
-            market_state.bids = orders;
-        } else {
-            market_state.asks = orders;
+        events.push(DexEvent::BookSide {
+            market: market.name.clone(),
+            venue: Venue::Gigadex,
+            address: market.address.to_string(),
+            is_bid,
+            levels: orders,
+            quotes,
+        });
+
+        // Publish order lifecycle events from the tree's delta log
+        let tree_deltas = parse_tree_deltas(&account.data)?;
+        let tree_key = account.pubkey.to_string();
+        if prev_deltas.get(&tree_key) != Some(&tree_deltas) {
+            let order_events = build_order_events(&market, is_bid, &tree_deltas, account.slot);
+            for order_event in order_events.iter() {
+                let msg = generate_publish_uid_data(
+                    &market.name,
+                    &GdOrderEventData {
+                        order_event: order_event.clone(),
+                    },
+                    order_event.uid,
+                    uid_owners.get(&order_event.uid).cloned(),
+                );
+                redis_conn.publish(CHANNEL_NAME, msg)?;
+            }
+
+            let events: Vec<EventData> = order_events
+                .iter()
+                .map(|x| EventData {
+                    event: x.kind.as_str().to_string(),
+                    user: x.uid.to_string(),
+                    amount: Decimal::from_f64(x.amount).unwrap_or_default(),
+                    price: Decimal::from_f64(x.price).unwrap_or_default(),
+                    tx: account.txn_signature.clone(),
+                    market: market.name.clone(),
+                    filled: x.kind == GdOrderEventKind::Filled,
+                })
+                .collect();
+            if !events.is_empty() {
+                supabase_client.store_events(events);
+            }
+
+            prev_deltas.insert(tree_key, tree_deltas);
         }
 
-        // Publish ask/bid updates to redis
-        publish_trades_data(&market.name, &market_state, redis_conn, account.slot)?;
+        // Match pending order logs with maker fills of the tree
+        let fills = parse_tree_fills(&account.data)?;
+        let fill_state = fill_states.entry(market.name.clone()).or_default();
+        if !fills.is_empty() {
+            let tree_fills = GdTreeFills {
+                slot: account.slot,
+                write_version: account.write_version,
+                txn_signature: account.txn_signature.clone(),
+                fills,
+            };
+            let queue = if is_bid {
+                &mut fill_state.bid_fills
+            } else {
+                &mut fill_state.ask_fills
+            };
+            queue.push(tree_fills);
+            queue.sort_by_key(|x| x.write_version);
+        }
+        trades_to_insert.extend(resolve_fills(&market, fill_state, account.slot)?);
     } else if market.buy_order_log.eq(&account.pubkey) || market.sell_order_log.eq(&account.pubkey)
     {
-        let order: GdMarketOrderLog = AnchorDeserialize::deserialize(&mut &account.data[8..])?;
-        if order.amount == 0 {
-            return Ok(());
-        }
-
-        let price_lots = Decimal::from(order.total_value_lamports) / Decimal::from(order.amount);
-        let amount_lots = order.amount;
-
-        let price =
-            price_lots_to_number(price_lots, market.base_decimals, market.quote_decimals, 0);
-        let amount = base_lots_to_number(amount_lots, market.base_decimals);
+        check_anchor_discriminator(&account.data, GD_ORDER_LOG_ACCOUNT, "gigadex order log")?;
+        let mut order_bytes = &account.data[GD_DISCRIMINATOR_LEN..];
+        let order: GdMarketOrderLog = AnchorDeserialize::deserialize(&mut order_bytes)?;
+        let market_buy = market.buy_order_log.eq(&account.pubkey);
+        let fill_state = fill_states.entry(market.name.clone()).or_default();
 
-        let market_buy = if market.buy_order_log.eq(&account.pubkey) {
-            1
+        // Skip if order log counter already processed
+        let last_counter = if market_buy {
+            &mut fill_state.buy_counter
         } else {
-            0
+            &mut fill_state.sell_counter
         };
+        if order.amount == 0 || last_counter.is_some_and(|x| x >= order.counter) {
+            return Ok(events);
+        }
+        *last_counter = Some(order.counter);
 
-        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
-        tracing::info!("GD fill: {} - {}, {}", market.name, price, amount,);
-
-        trades_to_insert.push(MarketTrade {
-            slug: market.name.clone(),
-            market_address: market.address.to_string(),
+        // Wait for maker fills of the matched tree
+        fill_state.pending_logs.push(GdPendingLog {
+            order,
             market_buy,
-            avg_price: Decimal::from_f64(price).unwrap(),
-            amount: Decimal::from_f64(amount).unwrap(),
-            index: 0,
-            timestamp: now,
-            blocktime: now,
-            avg_price_lots: price_lots,
-            amount_lots: Decimal::from(amount_lots),
             slot: account.slot,
-            transaction_signature: account.txn_signature.clone(),
-            order_id: None,
+            txn_signature: account.txn_signature.clone(),
         });
+        trades_to_insert.extend(resolve_fills(&market, fill_state, account.slot)?);
     } else if market.balances.eq(&account.pubkey) {
         let market_balances = parse_balances_account(&account.data, &market)?;
 
@@ -188,26 +279,37 @@ pub async fn parse_gigadex_account(
             redis_conn.del(&balances_key)?;
 
             let mut uid_balances = vec![];
-            let prev_market_balances = prev_balances.get_mut(&market.name).unwrap();
+            let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+            let prev_market_balances = prev_balances.entry(market.name.clone()).or_default();
             for (uid, balance) in market_balances.iter() {
-                // Compare with previous balances and publish event
-                let _prev_balance = prev_market_balances.get(uid);
-                match _prev_balance {
-                    Some(_balance) => {
-                        if _balance != balance {
-                            let msg = generate_publish_uid_data(
-                                &market.name,
-                                &GdBalanceData {
-                                    claimable_balance: balance.clone(),
-                                    slot: account.slot,
-                                },
-                                *uid,
-                            );
-                            redis_conn.publish(CHANNEL_NAME, msg)?;
-                        }
-                    }
-                    None => {}
-                };
+                // Compare with previous balances and publish event, uid first seen starts from zero
+                let _prev_balance = prev_market_balances.get(uid).cloned().unwrap_or_default();
+                if _prev_balance != *balance {
+                    let msg = generate_publish_uid_data(
+                        &market.name,
+                        &GdBalanceData {
+                            claimable_balance: balance.clone(),
+                            slot: account.slot,
+                        },
+                        *uid,
+                        uid_owners.get(uid).cloned(),
+                    );
+                    redis_conn.publish(CHANNEL_NAME, msg)?;
+
+                    // Record balance transition
+                    balance_events.push(GdPendingBalanceEvent {
+                        balance_event: build_balance_event(
+                            &market,
+                            *uid,
+                            &_prev_balance,
+                            balance,
+                            account.slot,
+                            &account.txn_signature,
+                            now,
+                        ),
+                        owner: uid_owners.get(uid).cloned(),
+                    });
+                }
 
                 let data = serde_json::to_string(&GdBalanceData {
                     claimable_balance: balance.clone(),
@@ -223,114 +325,242 @@ pub async fn parse_gigadex_account(
     }
 
     if trades_to_insert.len() > 0 {
-        tokio::spawn({
-            let supabase_clone = supabase_client.clone();
-            let redis_clone = redis_client.clone();
-            let url_clone = api_url.clone();
-
-            async move {
-                let _ =
-                    update_trades(url_clone, redis_clone, supabase_clone, trades_to_insert).await;
-            }
-        });
+        events.push(DexEvent::Trades(trades_to_insert));
     }
 
-    Ok(())
+    Ok(events)
 }
 
 /*
  * Function: parse_gd_markets
- * 1. Get account data using rpc client
- * 2. Parse market account and build market info account with market configuration
+ * 1. Get market accounts using rpc client
+ * 2. Build market infos from fetched accounts
  */
 pub async fn parse_gd_markets(
     rpc_client: &RpcClient,
-    markets: &Vec<MarketConfig>,
+    markets: &[MarketConfig],
 ) -> anyhow::Result<Vec<GdMarketInfo>> {
-    let rpc_config = RpcAccountInfoConfig {
-        encoding: Some(UiAccountEncoding::Base64),
-        data_slice: None,
-        commitment: Some(CommitmentConfig::confirmed()),
-        min_context_slot: None,
-    };
-
     let market_keys = markets
         .iter()
-        .filter(|x| x.gd_market_address.is_some())
-        .map(|x| Pubkey::from_str(&x.gd_market_address.clone().unwrap()).unwrap())
+        .filter_map(|x| x.info("gd_market_address"))
+        .filter_map(|x| Pubkey::from_str(x).ok())
         .collect::<Vec<Pubkey>>();
-    let mut market_results = rpc_client
-        .get_multiple_accounts_with_config(&market_keys, rpc_config.clone())
-        .await?
-        .value;
-
-    let gigadex_pubkey = Pubkey::from_str(GIGADEX_PROGRAM_ID)?;
-    let market_infos: Vec<GdMarketInfo> = market_results
-        .iter_mut()
-        .enumerate()
-        .map(|(idx, r)| {
-            let get_account_result = r.as_mut().unwrap();
-
-            let address = *market_keys.get(idx).unwrap();
-            let mut market_bytes: &[u8] = &mut get_account_result.data[8..];
-            let raw_market: GdMarketState =
-                AnchorDeserialize::deserialize(&mut market_bytes).unwrap();
-            let (buy_order_log, _) = Pubkey::find_program_address(
-                &[&address.to_bytes(), BUY_LOG_PDA_SEED.as_bytes()],
-                &gigadex_pubkey,
-            );
-            let (sell_order_log, _) = Pubkey::find_program_address(
-                &[&address.to_bytes(), SELL_LOG_PDA_SEED.as_bytes()],
-                &gigadex_pubkey,
-            );
+    let accounts = fetch_accounts(rpc_client, &market_keys).await?;
 
-            let market_config = markets
-                .iter()
-                .find(|x| x.gd_market_address.clone().unwrap_or_default() == address.to_string())
-                .unwrap();
-
-            GdMarketInfo {
-                address,
-                name: market_config.slug.clone(),
-                base_decimals: market_config.base_decimals,
-                quote_decimals: market_config.quote_decimals,
-                asks: raw_market.asks,
-                bids: raw_market.bids,
-                balances: raw_market.balances,
-                buy_order_log,
-                sell_order_log,
-                multiplier: 1000000,
+    Ok(build_gd_markets(markets, &accounts))
+}
+
+/*
+ * Function: build_gd_markets
+ * 1. Parse market account of every gigadex market config from fetched accounts
+ * 2. Build market info with market configuration and order log PDAs of the market
+ * 3. Skip market whose address or market account is invalid, logging why
+ */
+pub fn build_gd_markets(
+    markets: &[MarketConfig],
+    accounts: &HashMap<Pubkey, Vec<u8>>,
+) -> Vec<GdMarketInfo> {
+    let mut market_infos: Vec<GdMarketInfo> = Vec::new();
+    for market_config in markets.iter() {
+        let address = match market_config
+            .info("gd_market_address")
+            .map(|x| Pubkey::from_str(x))
+        {
+            Some(Result::Ok(address)) => address,
+            Some(Err(e)) => {
+                tracing::warn!("Skipped gigadex market {}: {}", market_config.slug, e);
+                continue;
             }
-        })
-        .collect();
+            None => continue,
+        };
 
-    Ok(market_infos)
+        match build_gd_market(market_config, address, accounts) {
+            Result::Ok(market_info) => market_infos.push(market_info),
+            Err(e) => tracing::warn!("Skipped gigadex market {}: {}", market_config.slug, e),
+        }
+    }
+
+    market_infos
 }
 
 /*
- * Function: parse_gd_orders
- * 1. Get account data using rpc client
- * 2. Parse order account and build levels as 20 limit
+ * Helper function for building market info of market config from its market account
  */
-pub async fn parse_gd_orders(
-    rpc_client: &RpcClient,
+fn build_gd_market(
+    market_config: &MarketConfig,
     address: Pubkey,
-) -> anyhow::Result<Vec<GdMarketOrder>> {
-    let rpc_config = RpcAccountInfoConfig {
-        encoding: Some(UiAccountEncoding::Base64),
-        data_slice: None,
-        commitment: Some(CommitmentConfig::confirmed()),
-        min_context_slot: None,
+    accounts: &HashMap<Pubkey, Vec<u8>>,
+) -> Result<GdMarketInfo, DecodeError> {
+    let gigadex_pubkey = Pubkey::from_str(GIGADEX_PROGRAM_ID).unwrap();
+    let data = accounts.get(&address).ok_or(DecodeError::MissingAccount {
+        account: "gigadex market",
+    })?;
+
+    // Market state is six pubkeys following discriminator, later fields are ignored
+    check_anchor_discriminator(data, GD_MARKET_ACCOUNT, "gigadex market")?;
+    let raw_market: &GdMarketState = read_pod(data, "gigadex market", GD_DISCRIMINATOR_LEN)?;
+    let (buy_order_log, _) = Pubkey::find_program_address(
+        &[&address.to_bytes(), BUY_LOG_PDA_SEED.as_bytes()],
+        &gigadex_pubkey,
+    );
+    let (sell_order_log, _) = Pubkey::find_program_address(
+        &[&address.to_bytes(), SELL_LOG_PDA_SEED.as_bytes()],
+        &gigadex_pubkey,
+    );
+
+    Result::Ok(GdMarketInfo {
+        address,
+        name: market_config.slug.clone(),
+        base_decimals: market_config.base_decimals,
+        quote_decimals: market_config.quote_decimals,
+        asks: raw_market.asks,
+        bids: raw_market.bids,
+        balances: raw_market.balances,
+        buy_order_log,
+        sell_order_log,
+        multiplier: 1000000,
+        fee_rate_bps: market_config
+            .info("gd_fee_rate_bps")
+            .and_then(|x| x.parse::<u64>().ok())
+            .unwrap_or_default(),
+    })
+}
+
+/*
+ * Function: parse_gd_user_accounts
+ * 1. Get gigadex user accounts using rpc client filtered by account discriminator
+ * 2. Build uid to wallet map per market
+ */
+pub async fn parse_gd_user_accounts(
+    rpc_client: &RpcClient,
+    markets: &[GdMarketInfo],
+) -> anyhow::Result<HashMap<String, HashMap<u64, String>>> {
+    let rpc_config = RpcProgramAccountsConfig {
+        filters: Some(vec![RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
+            0,
+            anchor_discriminator(GD_USER_ACCOUNT).to_vec(),
+        ))]),
+        account_config: RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            data_slice: None,
+            commitment: Some(CommitmentConfig::confirmed()),
+            min_context_slot: None,
+        },
+        with_context: None,
     };
 
-    let account = rpc_client
-        .get_account_with_config(&address, rpc_config.clone())
-        .await?
-        .value
-        .unwrap();
+    let accounts = rpc_client
+        .get_program_accounts_with_config(&Pubkey::from_str(GIGADEX_PROGRAM_ID)?, rpc_config)
+        .await?;
 
-    let orders = parse_order_account(account.data.as_slice())?;
-    Ok(orders)
+    let mut uid_wallets: HashMap<String, HashMap<u64, String>> = HashMap::new();
+    for (address, account) in accounts.iter() {
+        let user = match parse_user_account(&account.data) {
+            Result::Ok(user) => user,
+            Err(e) => {
+                tracing::warn!("Skip gigadex user account {}: {}", address, e);
+                continue;
+            }
+        };
+        if let Some(market) = markets.iter().find(|x| x.address.eq(&user.market)) {
+            uid_wallets
+                .entry(market.name.clone())
+                .or_default()
+                .insert(user.uid, user.owner.to_string());
+        }
+    }
+
+    Ok(uid_wallets)
+}
+
+/*
+ * Function: update_uid_wallet
+ * 1. Match user account from geyser subscribe with gigadex market
+ * 2. If uid wallet changed, update local map and redis's uid_wallets
+ * 3. Insert or update uid wallet mapping into supabase
+ */
+pub fn update_uid_wallet(
+    supabase_client: &(impl DbSink + ?Sized),
+    markets: &[GdMarketInfo],
+    user: GdUserAccount,
+    redis_conn: &mut impl ConnectionLike,
+    uid_wallets: &mut HashMap<String, HashMap<u64, String>>,
+) -> anyhow::Result<()> {
+    let market = match markets.iter().find(|x| x.address.eq(&user.market)) {
+        Some(market) => market,
+        None => return Ok(()),
+    };
+
+    let owner = user.owner.to_string();
+    let market_wallets = uid_wallets.entry(market.name.clone()).or_default();
+    if market_wallets.get(&user.uid) == Some(&owner) {
+        return Ok(());
+    }
+
+    tracing::info!("GD user: {} - {}, {}", market.name, user.uid, owner);
+    market_wallets.insert(user.uid, owner.clone());
+    redis_conn.hset(format!("uid_wallets:{}", market.name), user.uid, &owner)?;
+
+    supabase_client.store_uid_wallets(vec![GdUidWallet {
+        market: market.name.clone(),
+        uid: user.uid,
+        owner,
+    }]);
+
+    Ok(())
+}
+
+/*
+ * Function: parse_user_account
+ * 1. Check user account discriminator and decode registered market, owner and uid
+ */
+pub fn parse_user_account(data: &[u8]) -> anyhow::Result<GdUserAccount> {
+    check_anchor_discriminator(data, GD_USER_ACCOUNT, "gigadex user account")?;
+    let mut user_bytes = &data[GD_DISCRIMINATOR_LEN..];
+
+    Ok(AnchorDeserialize::deserialize(&mut user_bytes)?)
+}
+
+/*
+ * Function: get_uid_wallets
+ * 1. Load market's uid to wallet map from redis's uid_wallets, or only uid's wallet if set
+ */
+pub fn get_uid_wallets(
+    redis_conn: &mut impl ConnectionLike,
+    market: &String,
+    uid: Option<u64>,
+) -> anyhow::Result<HashMap<u64, String>> {
+    let wallets_key = format!("uid_wallets:{}", market);
+    let wallets = match uid {
+        Some(uid) => {
+            let owner: Option<String> = redis_conn.hget(&wallets_key, uid)?;
+            owner.map(|x| (uid, x)).into_iter().collect()
+        }
+        None => redis_conn.hgetall(&wallets_key)?,
+    };
+
+    Ok(wallets)
+}
+
+/*
+ * Helper function for loading fixed size gigadex account following discriminator of account_name
+ */
+pub fn load_gd_account<'a, T: Pod>(
+    data: &'a [u8],
+    account_name: &str,
+    account: &'static str,
+) -> Result<&'a T, DecodeError> {
+    let expected = GD_DISCRIMINATOR_LEN + size_of::<T>();
+    if data.len() != expected {
+        return Err(DecodeError::InvalidLength {
+            account,
+            expected,
+            actual: data.len(),
+        });
+    }
+    check_anchor_discriminator(data, account_name, account)?;
+
+    read_pod(data, account, GD_DISCRIMINATOR_LEN)
 }
 
 /*
@@ -338,7 +568,8 @@ pub async fn parse_gd_orders(
  * 1. Decode orderTree account and build orders data from nodes
  */
 pub fn parse_order_account(data: &[u8]) -> anyhow::Result<Vec<GdMarketOrder>> {
-    let order_tree = bytemuck::from_bytes::<OrderTree>(&data[8..]);
+    let order_tree =
+        load_gd_account::<OrderTree>(data, GD_ORDER_TREE_ACCOUNT, "gigadex order tree")?;
     let mut orders: Vec<GdMarketOrder> = Vec::new();
 
     for r in order_tree.nodes {
@@ -354,6 +585,62 @@ pub fn parse_order_account(data: &[u8]) -> anyhow::Result<Vec<GdMarketOrder>> {
     Ok(orders)
 }
 
+/*
+ * Function: parse_tree_fills
+ * 1. Decode orderTree account and build maker fills of the last matched market order
+ * 2. If fills are not recorded, fall back to filled/decreased nodes of the delta log
+ *    unless the last instruction cancelled orders
+ */
+pub fn parse_tree_fills(data: &[u8]) -> anyhow::Result<Vec<FilledOrder>> {
+    let order_tree =
+        load_gd_account::<OrderTree>(data, GD_ORDER_TREE_ACCOUNT, "gigadex order tree")?;
+    let fills = order_tree.fills;
+    let node_delta = order_tree.node_delta;
+    let num_fills = usize::min(order_tree.num_fills as usize, MAX_FILLS_PER_MARKET_ORDER);
+    let num_deltas = usize::min(order_tree.num_deltas as usize, MAX_FILLS_PER_MARKET_ORDER);
+
+    if num_fills > 0 {
+        return Ok(fills[..num_fills]
+            .iter()
+            .filter(|x| x.amount > 0)
+            .cloned()
+            .collect());
+    }
+    if order_tree.amount_cancelled > 0 {
+        return Ok(vec![]);
+    }
+
+    Ok(node_delta[..num_deltas]
+        .iter()
+        .filter(|x| (x.is_delete > 0 || x.is_delta > 0) && x.amount > 0)
+        .map(|x| FilledOrder {
+            price: x.price,
+            amount: x.amount,
+            uid: x.uid,
+        })
+        .collect())
+}
+
+/*
+ * Function: parse_tree_deltas
+ * 1. Decode orderTree account and build node delta log of the last instruction
+ * 2. Keep recorded fills and cancelled amount to classify the deltas
+ */
+pub fn parse_tree_deltas(data: &[u8]) -> anyhow::Result<GdTreeDeltas> {
+    let order_tree =
+        load_gd_account::<OrderTree>(data, GD_ORDER_TREE_ACCOUNT, "gigadex order tree")?;
+    let fills = order_tree.fills;
+    let node_delta = order_tree.node_delta;
+    let num_fills = usize::min(order_tree.num_fills as usize, MAX_FILLS_PER_MARKET_ORDER);
+    let num_deltas = usize::min(order_tree.num_deltas as usize, MAX_FILLS_PER_MARKET_ORDER);
+
+    Ok(GdTreeDeltas {
+        deltas: node_delta[..num_deltas].to_vec(),
+        fills: fills[..num_fills].to_vec(),
+        amount_cancelled: order_tree.amount_cancelled,
+    })
+}
+
 /*
  * Function: parse_balances_account
  * 1. Decode UserBalances account and build balances
@@ -362,11 +649,22 @@ pub fn parse_balances_account(
     data: &[u8],
     market: &GdMarketInfo,
 ) -> anyhow::Result<HashMap<u64, GdBalance>> {
-    let user_balances = bytemuck::from_bytes::<UserBalances>(&data[8..]);
+    let user_balances =
+        load_gd_account::<UserBalances>(data, GD_USER_BALANCES_ACCOUNT, "gigadex balances")?;
     let mut balances: HashMap<u64, GdBalance> = HashMap::new();
 
-    let max_users = user_balances.num_users + 1;
-    for uid in 1..max_users {
+    // Uids start from 1, so the last uid must still index entries
+    let num_users = user_balances.num_users;
+    if num_users >= USERS_PER_MARKET as u64 {
+        return Err(DecodeError::OutOfBounds {
+            account: "gigadex balances",
+            index: num_users as usize,
+            len: USERS_PER_MARKET,
+        }
+        .into());
+    }
+
+    for uid in 1..num_users + 1 {
         let r = user_balances.entries[uid as usize];
         balances.insert(
             uid,
@@ -382,6 +680,346 @@ pub fn parse_balances_account(
     Ok(balances)
 }
 
+/*
+ * Helper function for match pending order logs with maker fills of the opposite tree
+ * 1. Logs are taken in counter order and each one takes the first queued tree fills of its
+ *    slot with the same signature, so unsigned updates only pair with unsigned ones
+ * 2. Logs which never matched before current slot are counted as unmatched and dropped
+ * 3. Tree fills left unmatched from earlier slots are dropped
+ */
+pub fn resolve_fills(
+    market: &GdMarketInfo,
+    fill_state: &mut GdFillState,
+    slot: u64,
+) -> anyhow::Result<Vec<MarketTrade>> {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    let mut trades: Vec<MarketTrade> = Vec::new();
+
+    let mut pending_logs = std::mem::take(&mut fill_state.pending_logs);
+    pending_logs.sort_by_key(|x| (x.slot, x.order.counter));
+    for log in pending_logs {
+        // Market buy matches against asks tree, market sell against bids tree
+        let tree_fills = if log.market_buy {
+            &mut fill_state.ask_fills
+        } else {
+            &mut fill_state.bid_fills
+        };
+
+        let matched = tree_fills
+            .iter()
+            .position(|x| x.slot == log.slot && x.txn_signature == log.txn_signature);
+        if let Some(idx) = matched {
+            let fills = tree_fills.remove(idx).fills;
+            trades.extend(build_maker_trades(market, &log, &fills, now));
+        } else if log.slot < slot {
+            fill_state.unmatched_logs += 1;
+            tracing::warn!(
+                "GD unmatched order log: {} - {}, {}, {} unmatched",
+                market.name,
+                log.order.counter,
+                log.txn_signature,
+                fill_state.unmatched_logs
+            );
+        } else {
+            fill_state.pending_logs.push(log);
+        }
+    }
+
+    fill_state.ask_fills.retain(|x| x.slot >= slot);
+    fill_state.bid_fills.retain(|x| x.slot >= slot);
+
+    Ok(trades)
+}
+
+/*
+ * Helper function for build per-maker trades from tree fills of an order log
+ */
+pub fn build_maker_trades(
+    market: &GdMarketInfo,
+    log: &GdPendingLog,
+    fills: &Vec<FilledOrder>,
+    now: u64,
+) -> Vec<MarketTrade> {
+    fills
+        .iter()
+        .enumerate()
+        .map(|(idx, fill)| {
+            let price = price_lots_to_number(
+                Decimal::from(fill.price),
+                market.base_decimals,
+                market.quote_decimals,
+                market.multiplier,
+            );
+            let amount = base_lots_to_number(fill.amount, market.base_decimals);
+            tracing::info!(
+                "GD fill: {} - {}, {}, {}",
+                market.name,
+                price,
+                amount,
+                fill.uid
+            );
+
+            MarketTrade {
+                slug: market.name.clone(),
+                order_id: Some(format!("{}:{}", log.order.counter, idx)),
+                market_address: market.address.to_string(),
+                market_buy: if log.market_buy { 1 } else { 0 },
+                avg_price: Decimal::from_f64(price).unwrap_or_default(),
+                amount: Decimal::from_f64(amount).unwrap_or_default(),
+                index: idx as u64,
+                timestamp: now,
+                blocktime: now,
+                avg_price_lots: Decimal::from(fill.price),
+                amount_lots: Decimal::from(fill.amount),
+                slot: log.slot,
+                transaction_signature: log.txn_signature.clone(),
+                maker: Some(fill.uid.to_string()),
+                price_usd: None,
+                notional_usd: None,
+                quote_usd_rate: None,
+                instruction_index: None,
+                inner_instruction_index: None,
+                taker: None,
+                fee_payer: None,
+                attribution: None,
+                taker_account: None,
+                is_taker_buy: log.market_buy,
+            }
+        })
+        .collect()
+}
+
+/*
+ * Helper function for classify node deltas into order lifecycle events
+ * Delete and insert of the same uid in one delta log is an amend
+ * Deleted/decreased nodes are fills when their uid and price are in the recorded fills (any node when none
+ * are recorded and nothing was cancelled), otherwise cancels when the instruction cancelled an amount
+ */
+pub fn build_order_events(
+    market: &GdMarketInfo,
+    is_bid: bool,
+    tree_deltas: &GdTreeDeltas,
+    slot: u64,
+) -> Vec<GdOrderEvent> {
+    let deltas = &tree_deltas.deltas;
+    let is_cancel = tree_deltas.amount_cancelled > 0;
+
+    deltas
+        .iter()
+        .filter_map(|x| {
+            let is_replaced = |is_insert: bool| {
+                deltas.iter().any(|y| {
+                    y.uid == x.uid
+                        && y.key != x.key
+                        && if is_insert {
+                            y.is_delete > 0
+                        } else {
+                            y.is_insert > 0
+                        }
+                })
+            };
+
+            let is_fill = if tree_deltas.fills.is_empty() {
+                !is_cancel
+            } else {
+                tree_deltas
+                    .fills
+                    .iter()
+                    .any(|y| y.uid == x.uid && y.price == x.price)
+            };
+
+            let kind = if x.is_insert > 0 {
+                if is_replaced(true) {
+                    GdOrderEventKind::Amended
+                } else {
+                    GdOrderEventKind::Placed
+                }
+            } else if x.is_delete > 0 && is_replaced(false) {
+                // Reported by the insert side of the amend
+                return None;
+            } else if x.is_delete > 0 || x.is_delta > 0 {
+                if is_fill {
+                    if x.is_delete > 0 {
+                        GdOrderEventKind::Filled
+                    } else {
+                        GdOrderEventKind::PartiallyFilled
+                    }
+                } else if is_cancel {
+                    GdOrderEventKind::Cancelled
+                } else {
+                    tracing::warn!(
+                        "GD unclassified node delta: {} - {}, {}, {}",
+                        market.name,
+                        x.uid,
+                        x.price,
+                        x.amount
+                    );
+                    return None;
+                }
+            } else {
+                return None;
+            };
+
+            Some(GdOrderEvent {
+                kind,
+                uid: x.uid,
+                is_bid,
+                amount: base_lots_to_number(x.amount, market.base_decimals),
+                price: price_lots_to_number(
+                    Decimal::from(x.price),
+                    market.base_decimals,
+                    market.quote_decimals,
+                    market.multiplier,
+                ),
+                price_lots: x.price,
+                amount_lots: x.amount,
+                slot,
+            })
+        })
+        .collect()
+}
+
+/*
+ * Helper function for build balance transition of uid
+ * Classified by direction until instruction names of its transaction are known (classify_balance_event):
+ * any credited side is a fill credit, otherwise decreased lots are a claim and decreased lamports a withdraw
+ */
+pub fn build_balance_event(
+    market: &GdMarketInfo,
+    uid: u64,
+    prev_balance: &GdBalance,
+    balance: &GdBalance,
+    slot: u64,
+    signature: &String,
+    timestamp: u64,
+) -> GdBalanceEvent {
+    let lamports_delta = balance.lamports - prev_balance.lamports;
+    let lots_delta = balance.lots - prev_balance.lots;
+
+    let kind = if lamports_delta > 0.0 || lots_delta > 0.0 {
+        GdBalanceEventKind::FillCredit
+    } else if lots_delta < 0.0 {
+        GdBalanceEventKind::Claim
+    } else {
+        GdBalanceEventKind::Withdraw
+    };
+
+    GdBalanceEvent {
+        market: market.name.clone(),
+        uid,
+        kind,
+        lamports_delta,
+        lots_delta,
+        lamports: balance.lamports,
+        lots: balance.lots,
+        slot,
+        signature: signature.clone(),
+        timestamp,
+    }
+}
+
+/*
+ * Function: publish_balance_events
+ * 1. Publish balance transitions per uid with its wallet
+ * 2. Insert them into tb_balance_history
+ */
+pub fn publish_balance_events(
+    supabase_client: &(impl DbSink + ?Sized),
+    redis_conn: &mut impl ConnectionLike,
+    balance_events: Vec<GdPendingBalanceEvent>,
+) -> anyhow::Result<()> {
+    for x in balance_events.iter() {
+        let msg = generate_publish_uid_data(
+            &x.balance_event.market,
+            &GdBalanceEventData {
+                balance_event: x.balance_event.clone(),
+            },
+            x.balance_event.uid,
+            x.owner.clone(),
+        );
+        redis_conn.publish(CHANNEL_NAME, msg)?;
+    }
+
+    supabase_client.store_balance_events(
+        balance_events
+            .into_iter()
+            .map(|x| x.balance_event)
+            .collect(),
+    );
+
+    Ok(())
+}
+
+/*
+ * Function: track_gd_transaction
+ * 1. Keep slot and gigadex instruction names of transactions invoking gigadex
+ * 2. Drop tracked transactions older than keep window
+ */
+pub fn track_gd_transaction(tx: &Transaction, tracker: &mut GdBalanceTracker) {
+    tracker.last_slot = tracker.last_slot.max(tx.slot);
+
+    let gigadex_program = Pubkey::from_str(GIGADEX_PROGRAM_ID).unwrap_or_default();
+    if !tx.is_failed
+        && tx
+            .instructions
+            .iter()
+            .any(|x| x.program_id == gigadex_program)
+    {
+        let names: Vec<String> = instruction_names(&tx.log_messages)
+            .into_values()
+            .filter(|(program, _)| program == GIGADEX_PROGRAM_ID)
+            .map(|(_, name)| name)
+            .collect();
+        tracker
+            .instructions
+            .insert(tx.signature.clone(), (tx.slot, names));
+    }
+
+    // Prune old transactions
+    let min_slot = tracker.last_slot.saturating_sub(TX_FILL_KEEP_SLOTS);
+    tracker
+        .instructions
+        .retain(|_, (slot, _)| *slot >= min_slot);
+}
+
+/*
+ * Function: drain_ready_balance_events
+ * 1. Balance events are ready once their transaction is tracked or wait window passed
+ * 2. Classify ready events by gigadex instructions of their transaction
+ */
+pub fn drain_ready_balance_events(tracker: &mut GdBalanceTracker) -> Vec<GdPendingBalanceEvent> {
+    let pending = std::mem::take(&mut tracker.pending);
+    let mut ready: Vec<GdPendingBalanceEvent> = Vec::new();
+    for mut x in pending {
+        let balance_event = &mut x.balance_event;
+        match tracker.instructions.get(&balance_event.signature) {
+            Some((_, names)) => classify_balance_event(balance_event, names),
+            None if tracker.last_slot <= balance_event.slot + TX_FILL_WAIT_SLOTS => {
+                tracker.pending.push(x);
+                continue;
+            }
+            None => {}
+        }
+        ready.push(x);
+    }
+
+    ready
+}
+
+/*
+ * Helper function for classify balance transition by gigadex instruction names of its transaction
+ * Withdraw and claim instructions move balance out, any other instruction credits fills
+ */
+pub fn classify_balance_event(balance_event: &mut GdBalanceEvent, names: &Vec<String>) {
+    balance_event.kind = if names.iter().any(|x| x.contains("Withdraw")) {
+        GdBalanceEventKind::Withdraw
+    } else if names.iter().any(|x| x.contains("Claim")) {
+        GdBalanceEventKind::Claim
+    } else {
+        GdBalanceEventKind::FillCredit
+    };
+}
+
 /*
  * Helper function for convert price_lots into readable price
  */
@@ -449,6 +1087,7 @@ pub fn sort_orders(
                 market.multiplier,
             ),
             amount: base_lots_to_number(x.1, market.base_decimals),
+            venue: Venue::Gigadex,
         })
         .collect()
 }
@@ -473,12 +1112,32 @@ pub fn convert_orders_data(orders: &Vec<GdMarketOrder>, market: &GdMarketInfo) -
         .collect()
 }
 
+/*
+ * Helper function for convert GdMarketOrders into per-uid maker quotes
+ */
+pub fn convert_quotes(orders: &Vec<GdMarketOrder>, market: &GdMarketInfo) -> Vec<MakerQuote> {
+    orders
+        .iter()
+        .map(|x| MakerQuote {
+            maker: x.uid.to_string(),
+            price: price_lots_to_number(
+                Decimal::from(x.price_lots),
+                market.base_decimals,
+                market.quote_decimals,
+                market.multiplier,
+            ),
+            amount: base_lots_to_number(x.amount_lots, market.base_decimals),
+        })
+        .collect()
+}
+
 pub fn build_order_data(
     is_bid: bool,
     market: &String,
     uid: u64,
     orders: &Vec<GdOrderData>,
     slot: u64,
+    owner: Option<String>,
 ) -> String {
     if is_bid {
         generate_publish_uid_data(
@@ -488,6 +1147,7 @@ pub fn build_order_data(
                 slot: slot,
             },
             uid,
+            owner,
         )
     } else {
         generate_publish_uid_data(
@@ -497,6 +1157,1110 @@ pub fn build_order_data(
                 slot: slot,
             },
             uid,
+            owner,
+        )
+    }
+}
+
+/*
+ * Gigadex venue adapter, keeping per-uid orders, balances, fill matching and uid wallets
+ * Startup uid wallets wait in pending_uid_wallets until the first account update stores them
+ * If hold_balance_events, balance transitions wait in balance_tracker for their transaction's instructions
+ */
+#[derive(Default)]
+pub struct GdAdapter {
+    pub markets: Vec<GdMarketInfo>,
+    pub uid_asks: HashMap<String, HashMap<u64, Vec<GdMarketOrder>>>,
+    pub uid_bids: HashMap<String, HashMap<u64, Vec<GdMarketOrder>>>,
+    pub balances: HashMap<String, HashMap<u64, GdBalance>>,
+    pub fill_states: HashMap<String, GdFillState>,
+    pub tree_deltas: HashMap<String, GdTreeDeltas>,
+    pub uid_wallets: HashMap<String, HashMap<u64, String>>,
+    pub pending_uid_wallets: Vec<GdUidWallet>,
+    pub hold_balance_events: bool,
+    pub balance_tracker: GdBalanceTracker,
+}
+
+impl GdAdapter {
+    /*
+     * Function: build_snapshot
+     * 1. Parse fetched ask/bid trees of every market into uid orders, seeding delta logs already on chain
+     * 2. Parse fetched balances account into initial balances, so only later transitions are recorded
+     * 3. Build initial orderbook of every market
+     */
+    pub fn build_snapshot(
+        &mut self,
+        accounts: &HashMap<Pubkey, Vec<u8>>,
+    ) -> anyhow::Result<Vec<VenueSnapshot>> {
+        let account = |address: &Pubkey, name: &str| {
+            accounts
+                .get(address)
+                .map(|x| x.as_slice())
+                .ok_or(anyhow::anyhow!(
+                    "Gigadex {} account {} not found",
+                    name,
+                    address
+                ))
+        };
+
+        let mut snapshots: Vec<VenueSnapshot> = Vec::new();
+        for market in self.markets.iter() {
+            let asks_data = account(&market.asks, "order")?;
+            let bids_data = account(&market.bids, "order")?;
+            let asks = parse_order_account(asks_data)?;
+            let bids = parse_order_account(bids_data)?;
+
+            // Seed delta logs already on chain, so they aren't replayed as order events
+            self.tree_deltas
+                .insert(market.asks.to_string(), parse_tree_deltas(asks_data)?);
+            self.tree_deltas
+                .insert(market.bids.to_string(), parse_tree_deltas(bids_data)?);
+
+            // Build initial uid orders
+            let uid_asks = self.uid_asks.entry(market.name.clone()).or_default();
+            asks.iter().for_each(|x| {
+                uid_asks.entry(x.uid).or_default().push(x.clone());
+            });
+            let uid_bids = self.uid_bids.entry(market.name.clone()).or_default();
+            bids.iter().for_each(|x| {
+                uid_bids.entry(x.uid).or_default().push(x.clone());
+            });
+
+            // Build initial balances data, so only later transitions are recorded
+            let balances = parse_balances_account(account(&market.balances, "balances")?, market)?;
+            self.balances.insert(market.name.clone(), balances);
+            self.fill_states
+                .insert(market.name.clone(), GdFillState::default());
+
+            snapshots.push(VenueSnapshot {
+                market: market.name.clone(),
+                venue_market: VenueMarket {
+                    venue: Venue::Gigadex,
+                    address: market.address.to_string(),
+                    fee_rate_bps: market.fee_rate_bps,
+                },
+                orders: MarketOrders {
+                    asks: sort_orders(&asks, market, GD_ORDER_DEPTH, false),
+                    bids: sort_orders(&bids, market, GD_ORDER_DEPTH, true),
+                },
+            });
+        }
+
+        Ok(snapshots)
+    }
+}
+
+#[async_trait]
+impl DexAdapter for GdAdapter {
+    fn name(&self) -> &'static str {
+        Venue::Gigadex.as_str()
+    }
+
+    async fn load_markets(
+        &mut self,
+        rpc_client: &RpcClient,
+        markets: &[MarketConfig],
+    ) -> anyhow::Result<()> {
+        self.markets = parse_gd_markets(rpc_client, markets).await?;
+
+        Ok(())
+    }
+
+    fn account_filters(&self) -> AccountsFilterMap {
+        let accounts = self
+            .markets
+            .iter()
+            .flat_map(|x| {
+                [
+                    x.asks,
+                    x.bids,
+                    x.balances,
+                    x.buy_order_log,
+                    x.sell_order_log,
+                ]
+            })
+            .map(|x| x.to_string())
+            .collect();
+
+        HashMap::from([
+            (
+                "gigadex".to_string(),
+                SubscribeRequestFilterAccounts {
+                    account: accounts,
+                    owner: [GIGADEX_PROGRAM_ID.to_string()].into(),
+                    filters: [].into(),
+                },
+            ),
+            (
+                "gd_users".to_string(),
+                SubscribeRequestFilterAccounts {
+                    account: [].into(),
+                    owner: [GIGADEX_PROGRAM_ID.to_string()].into(),
+                    filters: [SubscribeRequestFilterAccountsFilter {
+                        filter: Some(AccountsFilterOneof::Memcmp(
+                            SubscribeRequestFilterAccountsFilterMemcmp {
+                                offset: 0,
+                                data: Some(AccountsFilterMemcmpOneof::Bytes(
+                                    anchor_discriminator(GD_USER_ACCOUNT).to_vec(),
+                                )),
+                            },
+                        )),
+                    }]
+                    .into(),
+                },
+            ),
+        ])
+    }
+
+    fn market_accounts(&self) -> HashMap<Pubkey, String> {
+        self.markets
+            .iter()
+            .flat_map(|x| {
+                [
+                    x.address,
+                    x.asks,
+                    x.bids,
+                    x.balances,
+                    x.buy_order_log,
+                    x.sell_order_log,
+                ]
+                .map(|account| (account, x.address.to_string()))
+            })
+            .collect()
+    }
+
+    fn startup_accounts(&self) -> Vec<Pubkey> {
+        self.markets
+            .iter()
+            .flat_map(|x| [x.address, x.asks, x.bids, x.balances])
+            .collect()
+    }
+
+    async fn load_snapshot(
+        &mut self,
+        rpc_client: &RpcClient,
+        redis_conn: &mut Connection,
+    ) -> anyhow::Result<Vec<VenueSnapshot>> {
+        // Build initial uid wallets data from registered user accounts
+        self.uid_wallets = parse_gd_user_accounts(rpc_client, &self.markets).await?;
+        for (market, wallets) in self.uid_wallets.iter() {
+            let uid_wallets: Vec<(&u64, &String)> = wallets.iter().collect();
+            if !uid_wallets.is_empty() {
+                redis_conn.hset_multiple(format!("uid_wallets:{}", market), &uid_wallets)?;
+            }
+            self.pending_uid_wallets
+                .extend(wallets.iter().map(|(uid, owner)| GdUidWallet {
+                    market: market.clone(),
+                    uid: *uid,
+                    owner: owner.clone(),
+                }));
+        }
+
+        let accounts = fetch_accounts(rpc_client, &self.startup_accounts()).await?;
+        self.build_snapshot(&accounts)
+    }
+
+    async fn process_account(
+        &mut self,
+        account: &mut Account,
+        redis_conn: &mut (dyn ConnectionLike + Send),
+        supabase_client: &(dyn DbSink + Sync),
+    ) -> anyhow::Result<Vec<DexEvent>> {
+        self.balance_tracker.last_slot = self.balance_tracker.last_slot.max(account.slot);
+
+        // Store startup uid wallets
+        if !self.pending_uid_wallets.is_empty() {
+            supabase_client.store_uid_wallets(std::mem::take(&mut self.pending_uid_wallets));
+        }
+
+        // Update uid wallets of user account
+        if check_anchor_discriminator(&account.data, GD_USER_ACCOUNT, "gigadex user account")
+            .is_ok()
+        {
+            let user = parse_user_account(&account.data)?;
+            update_uid_wallet(
+                supabase_client,
+                &self.markets,
+                user,
+                &mut DynConnection(redis_conn),
+                &mut self.uid_wallets,
+            )?;
+            return Ok(vec![]);
+        }
+
+        let market = match self
+            .markets
+            .iter()
+            .find(|x| x.is_valid_account(&account.pubkey))
+        {
+            Some(market) => market,
+            None => return Ok(vec![]),
+        };
+
+        let mut balance_events: Vec<GdPendingBalanceEvent> = Vec::new();
+        let events = parse_gigadex_account(
+            supabase_client,
+            market,
+            account,
+            &mut DynConnection(redis_conn),
+            &mut self.uid_asks,
+            &mut self.uid_bids,
+            &mut self.balances,
+            &mut self.fill_states,
+            &mut self.tree_deltas,
+            &self.uid_wallets,
+            &mut balance_events,
+        )?;
+
+        // Classify and record balance history once its transaction is known
+        if self.hold_balance_events {
+            self.balance_tracker.pending.extend(balance_events);
+        } else if !balance_events.is_empty() {
+            publish_balance_events(
+                supabase_client,
+                &mut DynConnection(redis_conn),
+                balance_events,
+            )?;
+        }
+
+        Ok(events)
+    }
+
+    fn process_transaction(&mut self, transaction: &Transaction) -> anyhow::Result<Vec<DexEvent>> {
+        if self.hold_balance_events {
+            track_gd_transaction(transaction, &mut self.balance_tracker);
+        }
+
+        Ok(vec![])
+    }
+
+    fn flush_pending(
+        &mut self,
+        redis_conn: &mut (dyn ConnectionLike + Send),
+        supabase_client: &(dyn DbSink + Sync),
+    ) -> anyhow::Result<()> {
+        let balance_events = drain_ready_balance_events(&mut self.balance_tracker);
+        if !balance_events.is_empty() {
+            publish_balance_events(
+                supabase_client,
+                &mut DynConnection(redis_conn),
+                balance_events,
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use crate::structs::{
+        decode::anchor_discriminator,
+        fixtures::{
+            account, account_write, gd_market, gd_market_data, gd_order_log, gd_order_tree,
+            gd_user_account, gd_user_balances, market_config, Lcg,
+        },
+        geyser::TxInstruction,
+        gigadex::NodeDeltaLog,
+        replay::{MemoryDb, MemoryRedis},
+    };
+
+    use super::*;
+
+    fn order(uid: u64, price_lots: u64, amount_lots: u64) -> GdMarketOrder {
+        GdMarketOrder {
+            uid,
+            price_lots,
+            amount_lots,
+        }
+    }
+
+    fn delta(is_delete: u64, is_delta: u64, uid: u64, price: u64, amount: u64) -> NodeDeltaLog {
+        NodeDeltaLog {
+            key: 0,
+            is_delete,
+            is_insert: 0,
+            is_delta,
+            amount,
+            uid,
+            price,
+        }
+    }
+
+    #[test]
+    fn parses_resting_orders() {
+        let data = gd_order_tree(
+            &[
+                order(1, 50_000, 2_000_000_000),
+                order(2, 50_100, 0),
+                order(3, 49_900, 500_000_000),
+            ],
+            &[],
+            &[],
+        );
+
+        assert_eq!(
+            parse_order_account(&data).unwrap(),
+            vec![
+                order(1, 50_000, 2_000_000_000),
+                order(3, 49_900, 500_000_000)
+            ]
+        );
+    }
+
+    #[test]
+    fn sorts_orders_into_levels() {
+        let market = gd_market("sol-usdc");
+        let orders = vec![
+            order(1, 50_000, 2_000_000_000),
+            order(2, 49_900, 500_000_000),
+            order(3, 50_000, 1_000_000_000),
+            order(4, 50_100, 100_000_000),
+        ];
+
+        let bids = sort_orders(&orders, &market, 2, true);
+        let lots: Vec<(u64, u64)> = bids.iter().map(|x| (x.price_lots, x.size_lots)).collect();
+        assert_eq!(lots, vec![(50_100, 100_000_000), (50_000, 3_000_000_000)]);
+        assert_eq!(bids[1].price, 50.0);
+        assert_eq!(bids[1].amount, 3.0);
+        assert!(bids.iter().all(|x| x.venue == Venue::Gigadex));
+
+        let asks = sort_orders(&orders, &market, 20, false);
+        let prices: Vec<f64> = asks.iter().map(|x| x.price).collect();
+        assert_eq!(prices, vec![49.9, 50.0, 50.1]);
+    }
+
+    #[test]
+    fn sorted_levels_are_aggregated_and_within_depth() {
+        let market = gd_market("sol-usdc");
+        let mut rng = Lcg(11);
+        for _ in 0..200 {
+            let num_orders = rng.range(0, 40);
+            let orders: Vec<GdMarketOrder> = (0..num_orders)
+                .map(|i| order(i, rng.range(49_990, 50_010), rng.range(1, 1_000_000)))
+                .collect();
+            let is_bid = rng.range(0, 2) == 1;
+            let depth = rng.range(1, 10) as usize;
+
+            // Round trip through tree account, then expect best distinct prices with summed amounts
+            let parsed = parse_order_account(&gd_order_tree(&orders, &[], &[])).unwrap();
+            assert_eq!(parsed, orders);
+
+            let mut expected: BTreeMap<u64, u64> = BTreeMap::new();
+            for x in orders.iter() {
+                *expected.entry(x.price_lots).or_default() += x.amount_lots;
+            }
+            let mut expected: Vec<(u64, u64)> = expected.into_iter().collect();
+            if is_bid {
+                expected.reverse();
+            }
+            expected.truncate(depth);
+
+            let levels = sort_orders(&parsed, &market, depth, is_bid);
+            let lots: Vec<(u64, u64)> =
+                levels.iter().map(|x| (x.price_lots, x.size_lots)).collect();
+            assert!(levels.len() <= depth);
+            assert_eq!(lots, expected);
+        }
+    }
+
+    fn event_kinds(
+        deltas: Vec<NodeDeltaLog>,
+        fills: Vec<FilledOrder>,
+        amount_cancelled: u64,
+    ) -> Vec<(u64, GdOrderEventKind)> {
+        let tree_deltas = GdTreeDeltas {
+            deltas,
+            fills,
+            amount_cancelled,
+        };
+        build_order_events(&gd_market("sol-usdc"), true, &tree_deltas, 100)
+            .iter()
+            .map(|x| (x.uid, x.kind))
+            .collect()
+    }
+
+    fn fill(uid: u64, price: u64, amount: u64) -> FilledOrder {
+        FilledOrder { price, amount, uid }
+    }
+
+    #[test]
+    fn cancelled_amount_classifies_deletes() {
+        assert_eq!(
+            event_kinds(vec![delta(1, 0, 3, 50_000, 700)], vec![], 700),
+            vec![(3, GdOrderEventKind::Cancelled)]
+        );
+        assert_eq!(
+            event_kinds(
+                vec![delta(1, 0, 3, 50_000, 700)],
+                vec![fill(3, 50_000, 700)],
+                0
+            ),
+            vec![(3, GdOrderEventKind::Filled)]
+        );
+
+        // Fills left recorded from an earlier match don't turn a cancel into a fill
+        assert_eq!(
+            event_kinds(
+                vec![delta(1, 0, 3, 50_000, 700)],
+                vec![fill(9, 49_000, 100)],
+                700
+            ),
+            vec![(3, GdOrderEventKind::Cancelled)]
+        );
+    }
+
+    #[test]
+    fn delta_is_partial_fill_or_cancel() {
+        assert_eq!(
+            event_kinds(vec![delta(0, 1, 4, 50_100, 300)], vec![], 0),
+            vec![(4, GdOrderEventKind::PartiallyFilled)]
+        );
+        assert_eq!(
+            event_kinds(vec![delta(0, 1, 4, 50_100, 300)], vec![], 300),
+            vec![(4, GdOrderEventKind::Cancelled)]
+        );
+
+        // Neither filled nor cancelled is left unclassified instead of labelled amended
+        assert!(event_kinds(
+            vec![delta(0, 1, 4, 50_100, 300)],
+            vec![fill(9, 49_000, 100)],
+            0
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn partial_fill_and_cancel_in_one_update() {
+        assert_eq!(
+            event_kinds(
+                vec![delta(0, 1, 4, 50_100, 300), delta(1, 0, 5, 50_200, 700)],
+                vec![fill(4, 50_100, 300)],
+                700
+            ),
+            vec![
+                (4, GdOrderEventKind::PartiallyFilled),
+                (5, GdOrderEventKind::Cancelled)
+            ]
+        );
+    }
+
+    #[test]
+    fn delete_and_insert_of_uid_is_one_amend() {
+        let removed = NodeDeltaLog {
+            key: 1,
+            ..delta(1, 0, 5, 50_000, 700)
+        };
+        let inserted = NodeDeltaLog {
+            key: 2,
+            is_insert: 1,
+            ..delta(0, 0, 5, 50_200, 700)
+        };
+        let placed = NodeDeltaLog {
+            key: 3,
+            is_insert: 1,
+            ..delta(0, 0, 6, 50_300, 100)
+        };
+
+        assert_eq!(
+            event_kinds(vec![removed, inserted, placed], vec![], 0),
+            vec![
+                (5, GdOrderEventKind::Amended),
+                (6, GdOrderEventKind::Placed)
+            ]
+        );
+    }
+
+    fn pending_log(counter: u64, slot: u64, txn_signature: &str) -> GdPendingLog {
+        GdPendingLog {
+            order: GdMarketOrderLog {
+                amount: 1_000,
+                total_value_lamports: 50_000_000,
+                counter,
+            },
+            market_buy: true,
+            slot,
+            txn_signature: txn_signature.to_string(),
+        }
+    }
+
+    fn tree_fills(slot: u64, write_version: u64, txn_signature: &str, uid: u64) -> GdTreeFills {
+        GdTreeFills {
+            slot,
+            write_version,
+            txn_signature: txn_signature.to_string(),
+            fills: vec![FilledOrder {
+                price: 50_000,
+                amount: 1_000,
+                uid,
+            }],
+        }
+    }
+
+    #[test]
+    fn matches_logs_of_one_slot_to_their_own_fills() {
+        let market = gd_market("sol-usdc");
+        let makers = |trades: &Vec<MarketTrade>| -> Vec<(String, String)> {
+            trades
+                .iter()
+                .map(|x| (x.order_id.clone().unwrap(), x.maker.clone().unwrap()))
+                .collect()
+        };
+
+        // Signatures pair each log with the tree update of its transaction
+        let mut fill_state = GdFillState {
+            pending_logs: vec![pending_log(8, 100, "tx-b"), pending_log(7, 100, "tx-a")],
+            ask_fills: vec![tree_fills(100, 1, "tx-a", 1), tree_fills(100, 2, "tx-b", 2)],
+            ..Default::default()
+        };
+        let trades = resolve_fills(&market, &mut fill_state, 100).unwrap();
+        assert_eq!(
+            makers(&trades),
+            vec![
+                ("7:0".to_string(), "1".to_string()),
+                ("8:0".to_string(), "2".to_string())
+            ]
+        );
+        assert!(fill_state.pending_logs.is_empty() && fill_state.ask_fills.is_empty());
+
+        // Without signatures, logs take tree updates of their slot in counter and write order
+        let mut fill_state = GdFillState {
+            pending_logs: vec![pending_log(8, 100, ""), pending_log(7, 100, "")],
+            ask_fills: vec![tree_fills(100, 1, "", 1), tree_fills(100, 2, "", 2)],
+            ..Default::default()
+        };
+        let trades = resolve_fills(&market, &mut fill_state, 100).unwrap();
+        assert_eq!(
+            makers(&trades),
+            vec![
+                ("7:0".to_string(), "1".to_string()),
+                ("8:0".to_string(), "2".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn unmatched_log_is_counted_once_slot_passes() {
+        let market = gd_market("sol-usdc");
+        let mut fill_state = GdFillState {
+            pending_logs: vec![pending_log(7, 100, "tx-a")],
+            ask_fills: vec![tree_fills(100, 1, "tx-b", 1)],
+            ..Default::default()
+        };
+
+        assert!(resolve_fills(&market, &mut fill_state, 100)
+            .unwrap()
+            .is_empty());
+        assert_eq!(fill_state.pending_logs.len(), 1);
+
+        // No averaged trade is made up for the log
+        assert!(resolve_fills(&market, &mut fill_state, 101)
+            .unwrap()
+            .is_empty());
+        assert_eq!(fill_state.unmatched_logs, 1);
+        assert!(fill_state.pending_logs.is_empty() && fill_state.ask_fills.is_empty());
+    }
+
+    #[test]
+    fn unsigned_update_does_not_pair_with_signed_one() {
+        let market = gd_market("sol-usdc");
+        let mut fill_state = GdFillState {
+            pending_logs: vec![pending_log(7, 100, "tx-a"), pending_log(8, 100, "")],
+            ask_fills: vec![tree_fills(100, 1, "", 1), tree_fills(100, 2, "tx-b", 2)],
+            ..Default::default()
+        };
+
+        // Unsigned log takes the unsigned tree update, signed log waits for its own transaction
+        let trades = resolve_fills(&market, &mut fill_state, 100).unwrap();
+        assert_eq!(trades.len(), 1);
+        assert_eq!(trades[0].order_id, Some("8:0".to_string()));
+        assert_eq!(trades[0].maker, Some("1".to_string()));
+        assert_eq!(fill_state.pending_logs.len(), 1);
+
+        assert!(resolve_fills(&market, &mut fill_state, 101)
+            .unwrap()
+            .is_empty());
+        assert_eq!(fill_state.unmatched_logs, 1);
+    }
+
+    #[test]
+    fn parses_tree_fills_before_delta_log() {
+        let fills = [
+            FilledOrder {
+                price: 50_000,
+                amount: 1_000,
+                uid: 1,
+            },
+            FilledOrder {
+                price: 50_100,
+                amount: 0,
+                uid: 2,
+            },
+        ];
+        let deltas = [
+            delta(1, 0, 3, 50_000, 700),
+            delta(0, 1, 4, 50_100, 300),
+            delta(0, 0, 5, 50_200, 900),
+        ];
+
+        let data = gd_order_tree(&[], &fills, &deltas);
+        let parsed: Vec<(u64, u64, u64)> = parse_tree_fills(&data)
+            .unwrap()
+            .iter()
+            .map(|x| (x.uid, x.price, x.amount))
+            .collect();
+        assert_eq!(parsed, vec![(1, 50_000, 1_000)]);
+
+        // Without recorded fills, deleted and decreased nodes of delta log are the fills
+        let data = gd_order_tree(&[], &[], &deltas);
+        let parsed: Vec<(u64, u64, u64)> = parse_tree_fills(&data)
+            .unwrap()
+            .iter()
+            .map(|x| (x.uid, x.price, x.amount))
+            .collect();
+        assert_eq!(parsed, vec![(3, 50_000, 700), (4, 50_100, 300)]);
+
+        let tree_deltas = parse_tree_deltas(&data).unwrap();
+        assert_eq!(tree_deltas.deltas, deltas.to_vec());
+        assert!(tree_deltas.fills.is_empty());
+        assert_eq!(tree_deltas.amount_cancelled, 0);
+
+        // Deleted/decreased nodes of a cancel are not fills
+        let mut data = gd_order_tree(&[], &[], &deltas);
+        bytemuck::from_bytes_mut::<OrderTree>(&mut data[GD_DISCRIMINATOR_LEN..]).amount_cancelled =
+            1_000;
+        assert!(parse_tree_fills(&data).unwrap().is_empty());
+        assert_eq!(parse_tree_deltas(&data).unwrap().amount_cancelled, 1_000);
+    }
+
+    #[test]
+    fn parses_balances() {
+        let market = gd_market("sol-usdc");
+        let data = gd_user_balances(&[(1, 1_500_000, 2_000_000_000), (3, 0, 500_000_000)]);
+
+        let balances = parse_balances_account(&data, &market).unwrap();
+        assert_eq!(balances.len(), 3);
+        assert_eq!(
+            balances[&1],
+            GdBalance {
+                lamports: 1.5,
+                lots: 2.0
+            }
+        );
+        assert_eq!(
+            balances[&2],
+            GdBalance {
+                lamports: 0.0,
+                lots: 0.0
+            }
+        );
+        assert_eq!(
+            balances[&3],
+            GdBalance {
+                lamports: 0.0,
+                lots: 0.5
+            }
+        );
+    }
+
+    #[test]
+    fn records_balance_transitions_of_new_and_changed_uids() {
+        let market = gd_market("sol-usdc");
+        let (mut asks, mut bids, mut fill_states, mut tree_deltas) = Default::default();
+        let mut prev_balances = HashMap::from([(
+            market.name.clone(),
+            HashMap::from([(1, GdBalance::default())]),
+        )]);
+        let mut redis = MemoryRedis::default();
+        let db = MemoryDb::default();
+
+        // Uid 1 is credited, uid 2 is first seen with a balance, uid 3 first seen empty
+        let data = gd_user_balances(&[(1, 1_000_000, 0), (2, 0, 500_000_000), (3, 0, 0)]);
+        let mut balance_events = Vec::new();
+        let events = parse_gigadex_account(
+            &db,
+            &market,
+            &mut account(&market.balances, 100, data),
+            &mut redis,
+            &mut asks,
+            &mut bids,
+            &mut prev_balances,
+            &mut fill_states,
+            &mut tree_deltas,
+            &mut HashMap::new(),
+            &mut balance_events,
         )
+        .unwrap();
+        assert!(events.is_empty());
+
+        let mut recorded: Vec<(u64, GdBalanceEventKind, f64, f64)> = balance_events
+            .iter()
+            .map(|x| {
+                let e = &x.balance_event;
+                (e.uid, e.kind, e.lamports_delta, e.lots_delta)
+            })
+            .collect();
+        recorded.sort_by_key(|x| x.0);
+        assert_eq!(
+            recorded,
+            vec![
+                (1, GdBalanceEventKind::FillCredit, 1.0, 0.0),
+                (2, GdBalanceEventKind::FillCredit, 0.0, 0.5)
+            ]
+        );
+        assert_eq!(prev_balances[&market.name].len(), 3);
+    }
+
+    fn gd_transaction(signature: &str, slot: u64, names: &[&str]) -> Transaction {
+        let program_id = Pubkey::from_str(GIGADEX_PROGRAM_ID).unwrap();
+        Transaction {
+            slot,
+            signature: signature.to_string(),
+            index: 0,
+            fee_payer: Pubkey::new_unique(),
+            is_failed: false,
+            instructions: (0..names.len() as u32)
+                .map(|index| TxInstruction {
+                    index,
+                    inner_index: None,
+                    program_id,
+                    accounts: vec![],
+                    signers: vec![],
+                    data: vec![],
+                })
+                .collect(),
+            log_messages: names
+                .iter()
+                .flat_map(|name| {
+                    [
+                        format!("Program {} invoke [1]", GIGADEX_PROGRAM_ID),
+                        format!("Program log: Instruction: {}", name),
+                        format!("Program {} success", GIGADEX_PROGRAM_ID),
+                    ]
+                })
+                .collect(),
+        }
+    }
+
+    fn pending_balance_event(signature: &str, slot: u64, lots_delta: f64) -> GdPendingBalanceEvent {
+        GdPendingBalanceEvent {
+            balance_event: GdBalanceEvent {
+                market: "sol-usdc".to_string(),
+                uid: 1,
+                kind: if lots_delta > 0.0 {
+                    GdBalanceEventKind::FillCredit
+                } else {
+                    GdBalanceEventKind::Claim
+                },
+                lamports_delta: 0.0,
+                lots_delta,
+                lamports: 0.0,
+                lots: 1.0,
+                slot,
+                signature: signature.to_string(),
+                timestamp: 0,
+            },
+            owner: None,
+        }
+    }
+
+    #[test]
+    fn classifies_balance_events_by_instruction_names() {
+        let mut tracker = GdBalanceTracker::default();
+        track_gd_transaction(
+            &gd_transaction("tx-claim", 100, &["ClaimLots"]),
+            &mut tracker,
+        );
+        track_gd_transaction(
+            &gd_transaction("tx-withdraw", 100, &["Withdraw"]),
+            &mut tracker,
+        );
+        track_gd_transaction(
+            &gd_transaction("tx-fill", 100, &["MarketBuy"]),
+            &mut tracker,
+        );
+        // Transaction without gigadex instructions isn't tracked
+        let mut other = gd_transaction("tx-other", 100, &["Swap"]);
+        other.instructions[0].program_id = Pubkey::new_unique();
+        track_gd_transaction(&other, &mut tracker);
+        assert!(!tracker.instructions.contains_key("tx-other"));
+
+        tracker.pending = vec![
+            // Credited lots of a claim transaction are still a claim
+            pending_balance_event("tx-claim", 100, 1.0),
+            pending_balance_event("tx-withdraw", 100, -1.0),
+            pending_balance_event("tx-fill", 100, -1.0),
+            // Not tracked yet, so held
+            pending_balance_event("tx-unseen", 100, 1.0),
+        ];
+
+        let kinds: Vec<(String, GdBalanceEventKind)> = drain_ready_balance_events(&mut tracker)
+            .into_iter()
+            .map(|x| (x.balance_event.signature, x.balance_event.kind))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ("tx-claim".to_string(), GdBalanceEventKind::Claim),
+                ("tx-withdraw".to_string(), GdBalanceEventKind::Withdraw),
+                ("tx-fill".to_string(), GdBalanceEventKind::FillCredit)
+            ]
+        );
+        assert_eq!(tracker.pending.len(), 1);
+
+        // Untracked transaction keeps its direction kind after wait window
+        tracker.last_slot = 100 + TX_FILL_WAIT_SLOTS + 1;
+        let ready = drain_ready_balance_events(&mut tracker);
+        assert_eq!(ready.len(), 1);
+        assert_eq!(ready[0].balance_event.kind, GdBalanceEventKind::FillCredit);
+
+        // Transactions older than keep window are dropped
+        let slot = 100 + TX_FILL_KEEP_SLOTS + 1;
+        track_gd_transaction(
+            &gd_transaction("tx-late", slot, &["MarketBuy"]),
+            &mut tracker,
+        );
+        assert_eq!(
+            tracker.instructions.keys().collect::<Vec<&String>>(),
+            vec!["tx-late"]
+        );
+    }
+
+    #[tokio::test]
+    async fn adapter_holds_balance_events_until_transaction_is_tracked() {
+        let market = gd_market("sol-usdc");
+        let mut adapter = GdAdapter {
+            markets: vec![market.clone()],
+            balances: HashMap::from([(
+                market.name.clone(),
+                HashMap::from([(1, GdBalance::default())]),
+            )]),
+            hold_balance_events: true,
+            ..Default::default()
+        };
+        let mut redis = MemoryRedis::default();
+        let db = MemoryDb::default();
+
+        let data = gd_user_balances(&[(1, 0, 500_000_000)]);
+        let mut update = account_write(&market.balances, 100, 100, "tx-claim", data);
+        let events = adapter
+            .process_account(&mut update, &mut redis, &db)
+            .await
+            .unwrap();
+        assert!(events.is_empty());
+        adapter.flush_pending(&mut redis, &db).unwrap();
+        assert!(db.balance_events.lock().unwrap().is_empty());
+        assert_eq!(adapter.balance_tracker.pending.len(), 1);
+
+        // Credited lots of the claim transaction are recorded as a claim once it's tracked
+        adapter
+            .process_transaction(&gd_transaction("tx-claim", 100, &["ClaimLots"]))
+            .unwrap();
+        adapter.flush_pending(&mut redis, &db).unwrap();
+        let balance_events = db.balance_events.lock().unwrap();
+        assert_eq!(balance_events.len(), 1);
+        assert_eq!(balance_events[0].kind, GdBalanceEventKind::Claim);
+        assert!(adapter.balance_tracker.pending.is_empty());
+    }
+
+    #[test]
+    fn registers_wallet_of_user_account() {
+        let market = gd_market("sol-usdc");
+        let owner = Pubkey::new_unique();
+        let data = gd_user_account(&GdUserAccount {
+            market: market.address,
+            owner,
+            uid: 7,
+        });
+
+        let mut redis = MemoryRedis::default();
+        let db = MemoryDb::default();
+        let mut uid_wallets = HashMap::new();
+        for _ in 0..2 {
+            let user = parse_user_account(&data).unwrap();
+            update_uid_wallet(&db, &[market.clone()], user, &mut redis, &mut uid_wallets).unwrap();
+        }
+
+        // Registered wallet is stored once
+        assert_eq!(
+            uid_wallets[&market.name],
+            HashMap::from([(7, owner.to_string())])
+        );
+        assert_eq!(redis.hashes["uid_wallets:sol-usdc"]["7"], owner.to_string());
+        assert_eq!(db.uid_wallets.lock().unwrap().len(), 1);
+
+        // User account of another market is ignored, other accounts are rejected
+        let other = parse_user_account(&gd_user_account(&GdUserAccount {
+            market: Pubkey::new_unique(),
+            owner,
+            uid: 8,
+        }))
+        .unwrap();
+        update_uid_wallet(&db, &[market.clone()], other, &mut redis, &mut uid_wallets).unwrap();
+        assert_eq!(uid_wallets[&market.name].len(), 1);
+        assert!(parse_user_account(&gd_order_tree(&[], &[], &[])).is_err());
+    }
+
+    #[test]
+    fn rejects_malformed_accounts() {
+        let market = gd_market("sol-usdc");
+
+        // Order tree cut short is rejected instead of panicking on the layout cast
+        let mut data = gd_order_tree(&[order(1, 50_000, 100)], &[], &[]);
+        data.truncate(data.len() - 1);
+        for result in [
+            parse_order_account(&data).map(|_| ()),
+            parse_tree_fills(&data).map(|_| ()),
+            parse_tree_deltas(&data).map(|_| ()),
+        ] {
+            assert!(matches!(
+                result.unwrap_err().downcast::<DecodeError>().unwrap(),
+                DecodeError::InvalidLength {
+                    account: "gigadex order tree",
+                    ..
+                }
+            ));
+        }
+
+        // User count beyond entries is rejected instead of indexing out of bounds
+        let mut data = gd_user_balances(&[(1, 1, 1)]);
+        data[8..16].copy_from_slice(&(USERS_PER_MARKET as u64).to_le_bytes());
+        assert_eq!(
+            parse_balances_account(&data, &market)
+                .unwrap_err()
+                .downcast::<DecodeError>()
+                .unwrap(),
+            DecodeError::OutOfBounds {
+                account: "gigadex balances",
+                index: USERS_PER_MARKET,
+                len: USERS_PER_MARKET,
+            }
+        );
+    }
+
+    #[test]
+    fn checks_account_discriminators() {
+        let market = gd_market("sol-usdc");
+        let decode_error = |result: anyhow::Result<()>| -> DecodeError {
+            result.unwrap_err().downcast::<DecodeError>().unwrap()
+        };
+
+        // Tree and balances of zeroed discriminator aren't read as their layout
+        let mut data = gd_order_tree(&[order(1, 50_000, 100)], &[], &[]);
+        data[..GD_DISCRIMINATOR_LEN].fill(0);
+        assert_eq!(
+            decode_error(parse_order_account(&data).map(|_| ())),
+            DecodeError::InvalidDiscriminator {
+                account: "gigadex order tree"
+            }
+        );
+        let mut data = gd_user_balances(&[(1, 1, 1)]);
+        data[..GD_DISCRIMINATOR_LEN].fill(0);
+        assert_eq!(
+            decode_error(parse_balances_account(&data, &market).map(|_| ())),
+            DecodeError::InvalidDiscriminator {
+                account: "gigadex balances"
+            }
+        );
+
+        // Order log is only queued once its discriminator matches
+        let log = GdMarketOrderLog {
+            amount: 1_000,
+            total_value_lamports: 50_000_000,
+            counter: 7,
+        };
+        let mut fill_states = HashMap::new();
+        let mut parse_log = |data: Vec<u8>| {
+            parse_gigadex_account(
+                &MemoryDb::default(),
+                &market,
+                &mut account(&market.buy_order_log, 100, data),
+                &mut MemoryRedis::default(),
+                &mut HashMap::new(),
+                &mut HashMap::new(),
+                &mut HashMap::new(),
+                &mut fill_states,
+                &mut HashMap::new(),
+                &mut HashMap::new(),
+                &mut Vec::new(),
+            )
+            .map(|_| ())
+        };
+
+        let mut data = gd_order_log(&log);
+        data[..GD_DISCRIMINATOR_LEN].copy_from_slice(&anchor_discriminator(GD_ORDER_TREE_ACCOUNT));
+        assert_eq!(
+            decode_error(parse_log(data)),
+            DecodeError::InvalidDiscriminator {
+                account: "gigadex order log"
+            }
+        );
+        parse_log(gd_order_log(&log)).unwrap();
+        assert_eq!(fill_states[&market.name].buy_counter, Some(7));
+    }
+
+    #[test]
+    fn skips_invalid_markets() {
+        let valid = gd_market("sol-usdc");
+        let truncated = gd_market("eth-usdc");
+        let foreign = gd_market("wif-usdc");
+        let missing = Pubkey::new_unique();
+        let markets = [
+            ("sol-usdc", valid.address.to_string()),
+            ("eth-usdc", truncated.address.to_string()),
+            ("jup-usdc", missing.to_string()),
+            ("bonk-usdc", "invalid".to_string()),
+            ("wif-usdc", foreign.address.to_string()),
+        ]
+        .map(|(slug, address)| market_config(slug, &[("gd_market_address", address)]));
+        let accounts = HashMap::from([
+            (valid.address, gd_market_data(&valid)),
+            (truncated.address, gd_market_data(&truncated)[..40].to_vec()),
+            (
+                foreign.address,
+                gd_order_log(&GdMarketOrderLog {
+                    amount: 0,
+                    total_value_lamports: 0,
+                    counter: 0,
+                }),
+            ),
+        ]);
+
+        // Only the market with a whole market account is built
+        let market_infos = build_gd_markets(&markets, &accounts);
+        assert_eq!(market_infos.len(), 1);
+        assert_eq!(market_infos[0].name, "sol-usdc");
+        assert_eq!(
+            (
+                market_infos[0].asks,
+                market_infos[0].bids,
+                market_infos[0].balances
+            ),
+            (valid.asks, valid.bids, valid.balances)
+        );
+
+        assert_eq!(
+            build_gd_market(&markets[1], truncated.address, &accounts).unwrap_err(),
+            DecodeError::InvalidLength {
+                account: "gigadex market",
+                expected: 200,
+                actual: 40,
+            }
+        );
+        assert_eq!(
+            build_gd_market(&markets[2], missing, &accounts).unwrap_err(),
+            DecodeError::MissingAccount {
+                account: "gigadex market"
+            }
+        );
+        // Other gigadex account at the market address isn't read as a market
+        assert_eq!(
+            build_gd_market(&markets[4], foreign.address, &accounts).unwrap_err(),
+            DecodeError::InvalidDiscriminator {
+                account: "gigadex market"
+            }
+        );
     }
 }
@@ -1,5 +1,13 @@
+pub mod amm;
 pub mod openbook;
+pub mod openbook_v2;
 pub mod gigadex;
+pub mod oracle;
+pub mod phoenix;
 
+pub use amm::*;
 pub use openbook::*;
-pub use gigadex::*;
\ No newline at end of file
+pub use openbook_v2::*;
+pub use gigadex::*;
+pub use oracle::*;
+pub use phoenix::*;
\ No newline at end of file
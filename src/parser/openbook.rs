@@ -1,17 +1,14 @@
-use openbook_dex::{
-    matching::Side,
-    state::{strip_header, Event, EventQueueHeader, EventView, Queue},
-};
-use postgrest::Postgrest;
-use redis::{Client, Connection};
+use async_trait::async_trait;
+use openbook_dex::state::{strip_header, Event, EventQueueHeader, Queue};
+use redis::{Connection, ConnectionLike};
 use solana_sdk::account_info::AccountInfo;
-use sqlx::types::Decimal;
 use std::time::{SystemTime, UNIX_EPOCH};
 use std::{
     collections::{HashMap, HashSet},
-    error::Error,
+    mem::size_of,
     str::FromStr,
 };
+use yellowstone_grpc_proto::prelude::SubscribeRequestFilterAccounts;
 
 use anchor_lang::AnchorDeserialize;
 use solana_account_decoder::UiAccountEncoding;
@@ -19,33 +16,52 @@ use solana_client::{nonblocking::rpc_client::RpcClient, rpc_config::RpcAccountIn
 use solana_sdk::{commitment_config::CommitmentConfig, program_pack::Pack, pubkey::Pubkey};
 
 use crate::{
-    processor::market::{publish_trades_data, update_trades},
+    constants::OPENBOOK_PROGRAM_ID,
+    processor::{
+        adapter::{AccountsFilterMap, DexAdapter},
+        db::DbSink,
+    },
     structs::{
+        adapter::{DexEvent, VenueSnapshot},
+        decode::{read_bytes, DecodeError},
+        extractor::extract_fill,
         geyser::Account,
-        market::{MarketConfig, MarketOrder, MarketOrders, MarketTrade},
+        market::{MarketConfig, MarketOrder, MarketOrders, MarketTrade, Venue, VenueMarket},
         mint::Mint,
         openbook::{ObMarketInfo, ObMarketState},
-        slab::{construct_levels, Slab},
+        slab::{
+            check_account_padding, construct_levels, construct_quotes, Slab,
+            ACCOUNT_FLAG_EVENT_QUEUE, ACCOUNT_FLAG_INITIALIZED,
+        },
     },
-    utils::{array_to_pubkey, token_factor},
+    utils::{array_to_pubkey, fetch_accounts},
 };
 
 /*
  * Function: parse_openbook_account
  * 1. Parse account data from geyser subscribe
- * 2. If ask/bids account, then update orderbook data and publish compressed_orderbook
- * 3. If fill account, then build trades data with price/amount calculation and call update_trades
+ * 2. If ask/bids account, then build book side event with levels and maker quotes
+ * 3. If fill account, then build trades event with price/amount calculation
  */
-pub async fn parse_openbook_account(
-    api_url: String,
-    redis_client: Client,
-    supabase_client: Postgrest,
-    market: ObMarketInfo,
+pub fn parse_openbook_account(
+    market: &ObMarketInfo,
     account: &mut Account,
-    redis_conn: &mut Connection,
-    market_orders: &mut HashMap<String, MarketOrders>,
     filled_order_ids: &mut HashSet<u128>,
-) -> Result<(), Box<dyn Error>> {
+) -> anyhow::Result<Vec<DexEvent>> {
+    let mut events: Vec<DexEvent> = Vec::new();
+
+    // Check event queue flags before openbook's header strip, which panics on malformed data
+    if market.event_queue.eq(&account.pubkey) {
+        let flags = check_account_padding(&account.data, "openbook event queue")?;
+        if flags & ACCOUNT_FLAG_INITIALIZED == 0 || flags & ACCOUNT_FLAG_EVENT_QUEUE == 0 {
+            return Err(DecodeError::InvalidFlags {
+                account: "openbook event queue",
+                flags,
+            }
+            .into());
+        }
+    }
+
     // Built account_info for parse data
     let account_info = AccountInfo::new(
         &account.pubkey,
@@ -57,252 +73,241 @@ pub async fn parse_openbook_account(
         account.executable,
         account.rent_epoch,
     );
-    let market_state = market_orders.get_mut(&market.address.to_string()).unwrap();
 
     if market.event_queue.eq(&account.pubkey) {
-        let ret = strip_header::<EventQueueHeader, Event>(&account_info, false).unwrap();
+        let ret = strip_header::<EventQueueHeader, Event>(&account_info, false).map_err(|_| {
+            DecodeError::Corrupted {
+                account: "openbook event queue",
+                reason: "invalid queue header",
+            }
+        })?;
         let mut trades_to_insert: Vec<MarketTrade> = Vec::new();
-        let events = Queue::new(ret.0, ret.1);
+        let queue = Queue::new(ret.0, ret.1);
 
         // Parse events
-        for event in events.iter() {
-            let view = event.as_view()?;
-            match view {
-                // Process fill event only
-                EventView::Fill {
-                    side,
-                    maker,
-                    native_qty_paid,
-                    native_fee_or_rebate,
-                    native_qty_received,
-                    order_id,
-                    owner: _,
-                    owner_slot: _,
-                    fee_tier: _,
-                    client_order_id: _,
-                } => {
-                    // Check already processed
-                    if filled_order_ids.contains(&order_id) {
-                        continue;
-                    }
-
-                    // Skip if not maker
-                    if !maker {
-                        continue;
-                    }
-
-                    let base_factor = token_factor(market.base_decimals);
-                    let quote_factor = token_factor(market.quote_decimals);
-
-                    let price_before_fees = Decimal::from(match side {
-                        Side::Bid => native_qty_paid + native_fee_or_rebate,
-                        Side::Ask => native_qty_received - native_fee_or_rebate,
-                    });
-
-                    let price = match side {
-                        Side::Bid => {
-                            (price_before_fees * base_factor)
-                                / (quote_factor * Decimal::from(native_qty_received))
-                        }
-                        Side::Ask => {
-                            (price_before_fees * base_factor)
-                                / (quote_factor * Decimal::from(native_qty_paid))
-                        }
-                    };
-
-                    let price_lots = price
-                        .checked_mul(quote_factor)
-                        .unwrap_or_default()
-                        .checked_mul(Decimal::from(market.base_lot_size))
-                        .unwrap_or_default()
-                        .checked_div(base_factor)
-                        .unwrap_or_default()
-                        .checked_div(Decimal::from(market.quote_lot_size))
-                        .unwrap_or_default()
-                        .round();
-
-                    let size = match side {
-                        Side::Bid => Decimal::from(native_qty_received),
-                        Side::Ask => Decimal::from(native_qty_paid),
-                    }
-                    .checked_div(base_factor)
-                    .unwrap_or_default();
-                    let size_lots = size
-                        .checked_mul(quote_factor)
-                        .unwrap_or_default()
-                        .checked_div(Decimal::from(market.quote_lot_size))
-                        .unwrap_or_default();
-
-                    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
-
-                    tracing::info!(
-                        "OB fill: {} - {}, {}, {}",
-                        market.name,
-                        price,
-                        size,
-                        order_id
-                    );
-
-                    // Update filled order ids
-                    filled_order_ids.insert(order_id);
-                    trades_to_insert.push(MarketTrade {
-                        slug: market.name.clone(),
-                        order_id: Some(order_id.to_string()),
-                        market_address: market.address.to_string(),
-                        market_buy: match side {
-                            Side::Ask => 0,
-                            Side::Bid => 1,
-                        },
-                        avg_price: price,
-                        amount: size,
-                        index: 0,
-                        timestamp: now,
-                        blocktime: now,
-                        avg_price_lots: price_lots,
-                        amount_lots: size_lots,
-                        slot: account.slot,
-                        transaction_signature: account.txn_signature.clone(),
-                    });
+        let mut group_start = 0;
+        for event in queue.iter() {
+            let view = event.as_view().map_err(|_| DecodeError::Corrupted {
+                account: "openbook event queue",
+                reason: "invalid event flags",
+            })?;
+            let fill = match extract_fill(view, &market) {
+                Some(fill) => fill,
+                None => continue,
+            };
+
+            // Taker fill follows maker fills of its order, tag them with taker's open orders
+            if !fill.maker {
+                for trade in trades_to_insert[group_start..].iter_mut() {
+                    trade.taker_account = Some(fill.owner.to_string());
                 }
-                // Skip out event
-                _ => {}
+                group_start = trades_to_insert.len();
+                continue;
             }
+
+            // Check already processed
+            if filled_order_ids.contains(&fill.order_id) {
+                continue;
+            }
+
+            let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+
+            tracing::info!(
+                "OB fill: {} - {}, {}, {}",
+                market.name,
+                fill.price,
+                fill.size,
+                fill.order_id
+            );
+
+            // Update filled order ids
+            filled_order_ids.insert(fill.order_id);
+            trades_to_insert.push(MarketTrade {
+                slug: market.name.clone(),
+                order_id: Some(fill.order_id.to_string()),
+                market_address: market.address.to_string(),
+                // Fill side is the maker's
+                market_buy: if fill.is_buy { 1 } else { 0 },
+                avg_price: fill.price,
+                amount: fill.size,
+                index: 0,
+                timestamp: now,
+                blocktime: now,
+                avg_price_lots: fill.price_lots,
+                amount_lots: fill.size_lots,
+                slot: account.slot,
+                transaction_signature: account.txn_signature.clone(),
+                maker: Some(fill.owner.to_string()),
+                price_usd: None,
+                notional_usd: None,
+                quote_usd_rate: None,
+                instruction_index: None,
+                inner_instruction_index: None,
+                taker: None,
+                fee_payer: None,
+                attribution: None,
+                taker_account: None,
+                is_taker_buy: !fill.is_buy,
+            });
         }
 
-        // Insert trades into DB
         if trades_to_insert.len() > 0 {
-            tokio::spawn({
-                let supabase_clone = supabase_client.clone();
-                let redis_clone = redis_client.clone();
-                let url_clone = api_url.clone();
-
-                async move {
-                    let _ = update_trades(url_clone, redis_clone, supabase_clone, trades_to_insert)
-                        .await;
-                }
-            });
+            events.push(DexEvent::Trades(trades_to_insert));
         }
-    } else {
+    } else if market.is_book_account(&account.pubkey) {
         // Get ask/bid orders from account
         let is_bid = market.bids.eq(&account.pubkey);
-        let data = Slab::new(&mut account.data);
-        let leaves = data.traverse(is_bid);
+        let data = Slab::new(&mut account.data, is_bid)?;
+        let leaves = data.traverse(is_bid)?;
+        let quotes = construct_quotes(&leaves, &market);
         let levels = construct_levels(leaves, &market, 20);
 
-        // Update local market state
-        if is_bid {
-            market_state.bids = levels;
-        } else {
-            market_state.asks = levels;
-        }
-
-        /*
-        tracing::info!(
-            "OB orders : {} - {} bids, {} asks",
-            market.name,
-            market_state.bids.len(),
-            market_state.asks.len()
-        );
-        */
-
-        // Publish ask/bid updates to redis
-        publish_trades_data(&market.name, &market_state, redis_conn, account.slot)?;
+        events.push(DexEvent::BookSide {
+            market: market.name.clone(),
+            venue: Venue::Openbook,
+            address: market.address.to_string(),
+            is_bid,
+            levels,
+            quotes,
+        });
     }
 
-    Ok(())
+    Ok(events)
+}
+
+/*
+ * Helper function for openbook market address of market_info markets, version 1 unless ob_version is set
+ */
+pub fn ob_market_address(market: &MarketConfig, version: u8) -> Option<&String> {
+    let market_version = market
+        .info("ob_version")
+        .and_then(|x| x.parse::<u8>().ok())
+        .unwrap_or(1);
+    market
+        .info("ob_market_address")
+        .filter(|_| market_version == version)
 }
 
 /*
  * Function: parse_ob_markets
- * 1. Get account data using rpc client
- * 2. Parse market account and build market info account with market configuration
+ * 1. Get market accounts and their mint accounts using rpc client
+ * 2. Build market infos from fetched accounts
  */
 pub async fn parse_ob_markets(
     rpc_client: &RpcClient,
-    markets: Vec<MarketConfig>,
+    markets: &[MarketConfig],
 ) -> anyhow::Result<Vec<ObMarketInfo>> {
-    let rpc_config = RpcAccountInfoConfig {
-        encoding: Some(UiAccountEncoding::Base64),
-        data_slice: None,
-        commitment: Some(CommitmentConfig::confirmed()),
-        min_context_slot: None,
-    };
-
     let market_keys = markets
         .iter()
-        .filter(|x| x.ob_market_address.is_some())
-        .map(|x| Pubkey::from_str(&x.ob_market_address.clone().unwrap()).unwrap())
+        .filter_map(|x| ob_market_address(x, 1))
+        .filter_map(|x| Pubkey::from_str(x).ok())
         .collect::<Vec<Pubkey>>();
-    let mut market_results = rpc_client
-        .get_multiple_accounts_with_config(&market_keys, rpc_config.clone())
-        .await?
-        .value;
-
-    let mut mint_key_map = HashMap::new();
-
-    let mut market_infos = market_results
-        .iter_mut()
-        .map(|r| {
-            let get_account_result = r.as_mut().unwrap();
-
-            let mut market_bytes: &[u8] = &mut get_account_result.data[5..];
-            let raw_market: ObMarketState =
-                AnchorDeserialize::deserialize(&mut market_bytes).unwrap();
-
-            let market_address = array_to_pubkey(raw_market.own_address);
-            let bids_key = array_to_pubkey(raw_market.bids);
-            let asks_key = array_to_pubkey(raw_market.asks);
-            let event_queue_key = array_to_pubkey(raw_market.event_q);
-            let base_mint_key = array_to_pubkey(raw_market.coin_mint);
-            let quote_mint_key = array_to_pubkey(raw_market.pc_mint);
-            mint_key_map.insert(base_mint_key, 0);
-            mint_key_map.insert(quote_mint_key, 0);
-
-            let market_name = markets
-                .iter()
-                .find(|x| {
-                    x.ob_market_address.clone().unwrap_or_default() == market_address.to_string()
-                })
-                .unwrap()
-                .slug
-                .clone();
-
-            ObMarketInfo {
-                name: market_name,
-                address: market_address,
-                base_decimals: 0,
-                quote_decimals: 0,
-                base_mint: base_mint_key,
-                quote_mint: quote_mint_key,
-                bids: bids_key,
-                asks: asks_key,
-                event_queue: event_queue_key,
-                base_lot_size: raw_market.coin_lot_size,
-                quote_lot_size: raw_market.pc_lot_size,
-            }
-        })
-        .collect::<Vec<ObMarketInfo>>();
+    let mut accounts = fetch_accounts(rpc_client, &market_keys).await?;
+
+    let mint_keys = accounts
+        .values()
+        .filter_map(|data| ob_market_state(data).ok())
+        .flat_map(|x| [array_to_pubkey(x.coin_mint), array_to_pubkey(x.pc_mint)])
+        .collect::<HashSet<Pubkey>>()
+        .into_iter()
+        .collect::<Vec<Pubkey>>();
+    accounts.extend(fetch_accounts(rpc_client, &mint_keys).await?);
 
-    let mint_keys = mint_key_map.keys().cloned().collect::<Vec<Pubkey>>();
+    Ok(build_ob_markets(markets, &accounts))
+}
 
-    let mint_results = rpc_client
-        .get_multiple_accounts_with_config(&mint_keys, rpc_config)
-        .await?
-        .value;
-    for i in 0..mint_results.len() {
-        let mut mint_account = mint_results[i].as_ref().unwrap().clone();
-        let mut mint_bytes: &[u8] = &mut mint_account.data[..];
-        let mint = Mint::unpack_from_slice(&mut mint_bytes).unwrap();
+/*
+ * Function: build_ob_markets
+ * 1. Parse market account of every openbook market config from fetched accounts
+ * 2. Build market info with market configuration and decimals of its mint accounts
+ * 3. Skip market whose address, market or mint accounts are invalid, logging why
+ */
+pub fn build_ob_markets(
+    markets: &[MarketConfig],
+    accounts: &HashMap<Pubkey, Vec<u8>>,
+) -> Vec<ObMarketInfo> {
+    let mut market_infos: Vec<ObMarketInfo> = Vec::new();
+    for market in markets.iter() {
+        let address = match ob_market_address(market, 1).map(|x| Pubkey::from_str(x)) {
+            Some(Ok(address)) => address,
+            Some(Err(e)) => {
+                tracing::warn!("Skipped openbook market {}: {}", market.slug, e);
+                continue;
+            }
+            None => continue,
+        };
 
-        mint_key_map.insert(mint_keys[i], mint.decimals);
+        match build_ob_market(market, &address, accounts) {
+            Ok(market_info) => market_infos.push(market_info),
+            Err(e) => tracing::warn!("Skipped openbook market {}: {}", market.slug, e),
+        }
     }
 
-    for i in 0..market_infos.len() {
-        market_infos[i].base_decimals = *mint_key_map.get(&market_infos[i].base_mint).unwrap();
-        market_infos[i].quote_decimals = *mint_key_map.get(&market_infos[i].quote_mint).unwrap();
-    }
+    market_infos
+}
+
+/*
+ * Helper function for building market info of market config from its market and mint accounts
+ */
+fn build_ob_market(
+    market: &MarketConfig,
+    address: &Pubkey,
+    accounts: &HashMap<Pubkey, Vec<u8>>,
+) -> Result<ObMarketInfo, DecodeError> {
+    let data = accounts.get(address).ok_or(DecodeError::MissingAccount {
+        account: "openbook market",
+    })?;
+    let raw_market = ob_market_state(data)?;
+    let base_mint = array_to_pubkey(raw_market.coin_mint);
+    let quote_mint = array_to_pubkey(raw_market.pc_mint);
+
+    Ok(ObMarketInfo {
+        name: market.slug.clone(),
+        address: array_to_pubkey(raw_market.own_address),
+        base_decimals: mint_decimals(accounts, &base_mint)?,
+        quote_decimals: mint_decimals(accounts, &quote_mint)?,
+        base_mint,
+        quote_mint,
+        bids: array_to_pubkey(raw_market.bids),
+        asks: array_to_pubkey(raw_market.asks),
+        event_queue: array_to_pubkey(raw_market.event_q),
+        base_lot_size: raw_market.coin_lot_size,
+        quote_lot_size: raw_market.pc_lot_size,
+        fee_rate_bps: raw_market.fee_rate_bps,
+    })
+}
 
-    Ok(market_infos)
+/*
+ * Helper function for decoding openbook market account following its serum head padding
+ */
+fn ob_market_state(data: &[u8]) -> Result<ObMarketState, DecodeError> {
+    check_account_padding(data, "openbook market")?;
+    let mut market_bytes = &data[5..];
+
+    AnchorDeserialize::deserialize(&mut market_bytes).map_err(|_| DecodeError::InvalidLength {
+        account: "openbook market",
+        expected: 5 + size_of::<ObMarketState>(),
+        actual: data.len(),
+    })
+}
+
+/*
+ * Helper function for decimals of fetched mint account
+ */
+fn mint_decimals(accounts: &HashMap<Pubkey, Vec<u8>>, mint: &Pubkey) -> Result<u8, DecodeError> {
+    let data = accounts
+        .get(mint)
+        .ok_or(DecodeError::MissingAccount { account: "mint" })?;
+
+    // Token-2022 mints keep extensions after the base mint layout
+    let mint = Mint::unpack_from_slice(read_bytes(data, "mint", 0, Mint::LEN)?).map_err(|_| {
+        DecodeError::Corrupted {
+            account: "mint",
+            reason: "invalid mint layout",
+        }
+    })?;
+
+    Ok(mint.decimals)
 }
 
 /*
@@ -327,11 +332,317 @@ pub async fn parse_ob_orders(
         .get_account_with_config(&address, rpc_config.clone())
         .await?
         .value
-        .unwrap();
+        .ok_or(anyhow::anyhow!("Openbook order account not found"))?;
 
-    let data = Slab::new(&mut account.data);
-    let leaves = data.traverse(is_bid);
+    let data = Slab::new(&mut account.data, is_bid)?;
+    let leaves = data.traverse(is_bid)?;
     let orders = construct_levels(leaves, &market, 20);
 
     Ok(orders)
 }
+
+/*
+ * Openbook v1 venue adapter
+ */
+#[derive(Default)]
+pub struct ObAdapter {
+    pub markets: Vec<ObMarketInfo>,
+    pub filled_order_ids: HashSet<u128>,
+}
+
+#[async_trait]
+impl DexAdapter for ObAdapter {
+    fn name(&self) -> &'static str {
+        Venue::Openbook.as_str()
+    }
+
+    async fn load_markets(
+        &mut self,
+        rpc_client: &RpcClient,
+        markets: &[MarketConfig],
+    ) -> anyhow::Result<()> {
+        self.markets = parse_ob_markets(rpc_client, markets).await?;
+
+        Ok(())
+    }
+
+    fn account_filters(&self) -> AccountsFilterMap {
+        let accounts = self
+            .markets
+            .iter()
+            .flat_map(|x| [x.asks, x.bids, x.event_queue])
+            .map(|x| x.to_string())
+            .collect();
+
+        HashMap::from([(
+            "openbook".to_string(),
+            SubscribeRequestFilterAccounts {
+                account: accounts,
+                owner: [OPENBOOK_PROGRAM_ID.to_string()].into(),
+                filters: [].into(),
+            },
+        )])
+    }
+
+    fn market_accounts(&self) -> HashMap<Pubkey, String> {
+        self.markets
+            .iter()
+            .flat_map(|x| {
+                [x.address, x.asks, x.bids, x.event_queue]
+                    .map(|account| (account, x.address.to_string()))
+            })
+            .collect()
+    }
+
+    fn startup_accounts(&self) -> Vec<Pubkey> {
+        self.markets
+            .iter()
+            .flat_map(|x| [x.address, x.base_mint, x.quote_mint])
+            .collect()
+    }
+
+    async fn load_snapshot(
+        &mut self,
+        rpc_client: &RpcClient,
+        _redis_conn: &mut Connection,
+    ) -> anyhow::Result<Vec<VenueSnapshot>> {
+        let mut snapshots: Vec<VenueSnapshot> = Vec::new();
+        for market in self.markets.iter() {
+            let asks = parse_ob_orders(rpc_client, market.asks, false, market.clone())
+                .await
+                .unwrap_or_default();
+            let bids = parse_ob_orders(rpc_client, market.bids, true, market.clone())
+                .await
+                .unwrap_or_default();
+
+            snapshots.push(VenueSnapshot {
+                market: market.name.clone(),
+                venue_market: VenueMarket {
+                    venue: Venue::Openbook,
+                    address: market.address.to_string(),
+                    fee_rate_bps: market.fee_rate_bps,
+                },
+                orders: MarketOrders { asks, bids },
+            });
+        }
+
+        Ok(snapshots)
+    }
+
+    async fn process_account(
+        &mut self,
+        account: &mut Account,
+        _redis_conn: &mut (dyn ConnectionLike + Send),
+        _supabase_client: &(dyn DbSink + Sync),
+    ) -> anyhow::Result<Vec<DexEvent>> {
+        let market = match self
+            .markets
+            .iter()
+            .find(|x| x.is_valid_account(&account.pubkey))
+        {
+            Some(market) => market,
+            None => return Ok(vec![]),
+        };
+
+        parse_openbook_account(market, account, &mut self.filled_order_ids)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use openbook_dex::matching::Side;
+    use sqlx::types::Decimal;
+
+    use crate::structs::fixtures::{
+        account, market_config, mint_data, ob_event_queue, ob_fill_event, ob_leaf, ob_market,
+        ob_market_data, ob_slab,
+    };
+
+    use super::*;
+
+    #[test]
+    fn builds_maker_trades_from_event_queue() {
+        let market = ob_market("sol-usdc");
+        // Maker bid paid 100 quote with 0.02 rebate for 2 base, taker ask sold 2 base for 99.9 after 0.1 fee
+        let events = [
+            ob_fill_event(
+                Side::Bid,
+                true,
+                99_980_000,
+                2_000_000_000,
+                20_000,
+                7,
+                [1; 4],
+            ),
+            ob_fill_event(
+                Side::Ask,
+                false,
+                2_000_000_000,
+                99_900_000,
+                100_000,
+                8,
+                [9; 4],
+            ),
+        ];
+        // Head at end of ring buffer, so taker fill wraps around
+        let data = ob_event_queue(&events, 3, 4);
+        let mut filled_order_ids = HashSet::new();
+
+        let mut update = account(&market.event_queue, 10, data.clone());
+        let parsed = parse_openbook_account(&market, &mut update, &mut filled_order_ids).unwrap();
+        let trades = match &parsed[..] {
+            [DexEvent::Trades(trades)] => trades,
+            _ => panic!("expected one trades event"),
+        };
+        assert_eq!(trades.len(), 1);
+        assert_eq!(trades[0].order_id, Some("7".to_string()));
+        // Taker sold into the maker bid, published market_buy is the maker's bid side
+        assert_eq!(trades[0].market_buy, 1);
+        assert!(!trades[0].is_taker_buy);
+        assert_eq!(trades[0].avg_price, Decimal::from(50));
+        assert_eq!(trades[0].amount, Decimal::from(2));
+        assert_eq!(trades[0].avg_price_lots, Decimal::from(50_000));
+        assert_eq!(trades[0].amount_lots, Decimal::from(2_000_000));
+        assert_eq!(trades[0].slot, 10);
+        assert_eq!(trades[0].maker, Some(array_to_pubkey([1; 4]).to_string()));
+        assert_eq!(
+            trades[0].taker_account,
+            Some(array_to_pubkey([9; 4]).to_string())
+        );
+
+        // Same queue seen again doesn't repeat processed fills
+        let mut update = account(&market.event_queue, 11, data);
+        let parsed = parse_openbook_account(&market, &mut update, &mut filled_order_ids).unwrap();
+        assert!(parsed.is_empty());
+    }
+
+    #[test]
+    fn builds_book_side_from_slab() {
+        let market = ob_market("sol-usdc");
+        let data = ob_slab(
+            &[
+                ob_leaf(50_100, 100, 1, [1; 4]),
+                ob_leaf(50_000, 200, 2, [2; 4]),
+            ],
+            false,
+        );
+
+        let mut update = account(&market.asks, 10, data);
+        let parsed = parse_openbook_account(&market, &mut update, &mut HashSet::new()).unwrap();
+        match &parsed[..] {
+            [DexEvent::BookSide {
+                is_bid,
+                levels,
+                quotes,
+                venue,
+                ..
+            }] => {
+                assert!(!is_bid);
+                assert_eq!(*venue, Venue::Openbook);
+                assert_eq!(levels[0].price, 50.0);
+                assert_eq!(levels[1].price, 50.1);
+                assert_eq!(quotes.len(), 2);
+            }
+            _ => panic!("expected one book side event"),
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_accounts() {
+        let market = ob_market("sol-usdc");
+        let decode_error = |pubkey: &Pubkey, data: Vec<u8>| {
+            let mut update = account(pubkey, 10, data);
+            parse_openbook_account(&market, &mut update, &mut HashSet::new())
+                .unwrap_err()
+                .downcast::<DecodeError>()
+                .unwrap()
+        };
+
+        // Bids account served as asks
+        assert!(matches!(
+            decode_error(&market.asks, ob_slab(&[], true)),
+            DecodeError::InvalidFlags { .. }
+        ));
+
+        // Book side account served as event queue
+        assert!(matches!(
+            decode_error(&market.event_queue, ob_slab(&[], true)),
+            DecodeError::InvalidFlags { .. }
+        ));
+
+        // Truncated event queue
+        let mut data = ob_event_queue(&[], 0, 4);
+        data.truncate(10);
+        assert!(matches!(
+            decode_error(&market.event_queue, data),
+            DecodeError::InvalidLength { .. }
+        ));
+    }
+
+    #[test]
+    fn skips_invalid_markets() {
+        let valid = ob_market("sol-usdc");
+        let truncated = ob_market("eth-usdc");
+        let no_mint = ob_market("btc-usdc");
+        let missing = Pubkey::new_unique();
+        let markets = [
+            ("sol-usdc", valid.address.to_string()),
+            ("eth-usdc", truncated.address.to_string()),
+            ("btc-usdc", no_mint.address.to_string()),
+            ("jup-usdc", missing.to_string()),
+            ("bonk-usdc", "invalid".to_string()),
+        ]
+        .map(|(slug, address)| market_config(slug, &[("ob_market_address", address)]));
+
+        let mut truncated_data = ob_market_data(&truncated);
+        truncated_data.truncate(100);
+        let accounts = HashMap::from([
+            (valid.address, ob_market_data(&valid)),
+            (valid.base_mint, mint_data(9)),
+            (valid.quote_mint, mint_data(6)),
+            (truncated.address, truncated_data),
+            (no_mint.address, ob_market_data(&no_mint)),
+            (no_mint.base_mint, mint_data(9)),
+        ]);
+
+        // Only the market with valid market and mint accounts is built
+        let market_infos = build_ob_markets(&markets, &accounts);
+        assert_eq!(market_infos.len(), 1);
+        assert_eq!(market_infos[0].name, "sol-usdc");
+        assert_eq!(market_infos[0].bids, valid.bids);
+        assert_eq!(
+            (
+                market_infos[0].base_decimals,
+                market_infos[0].quote_decimals
+            ),
+            (9, 6)
+        );
+
+        for (market, address, error) in [
+            (
+                &markets[1],
+                truncated.address,
+                DecodeError::InvalidPadding {
+                    account: "openbook market",
+                },
+            ),
+            (
+                &markets[2],
+                no_mint.address,
+                DecodeError::MissingAccount { account: "mint" },
+            ),
+            (
+                &markets[3],
+                missing,
+                DecodeError::MissingAccount {
+                    account: "openbook market",
+                },
+            ),
+        ] {
+            assert_eq!(
+                build_ob_market(market, &address, &accounts).unwrap_err(),
+                error
+            );
+        }
+    }
+}
@@ -0,0 +1,428 @@
+use async_trait::async_trait;
+use redis::{Commands, Connection, ConnectionLike};
+use std::{
+    collections::HashMap,
+    str::FromStr,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use yellowstone_grpc_proto::prelude::SubscribeRequestFilterAccounts;
+
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::{nonblocking::rpc_client::RpcClient, rpc_config::RpcAccountInfoConfig};
+use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey};
+
+use crate::{
+    constants::OPENBOOK_V2_PROGRAM_ID,
+    parser::openbook::ob_market_address,
+    processor::{
+        adapter::{AccountsFilterMap, DexAdapter, DynConnection},
+        db::DbSink,
+    },
+    structs::{
+        adapter::{DexEvent, VenueSnapshot},
+        extractor::extract_v2_fill,
+        geyser::Account,
+        market::{MarketConfig, MarketOrder, MarketOrders, MarketTrade, Venue, VenueMarket},
+        openbook::ObMarketInfo,
+        openbook_v2::{
+            construct_v2_levels, construct_v2_quotes, oracle_price_lots, parse_event_heap,
+            ObV2BookSide, ObV2MarketState,
+        },
+        oracle::OraclePrice,
+    },
+};
+
+/*
+ * Function: parse_openbook_v2_account
+ * 1. Parse account data from geyser subscribe
+ * 2. If bids/asks bookside account, then build book side event with levels and maker quotes of fixed and oracle pegged orders
+ * 3. If event heap account, then build trades event from fill events above market's last processed sequence number
+ */
+pub fn parse_openbook_v2_account(
+    market: &ObMarketInfo,
+    account: &Account,
+    oracle_price_lots: Option<i64>,
+    filled_seq_nums: &mut HashMap<Pubkey, u64>,
+) -> anyhow::Result<Vec<DexEvent>> {
+    let mut events: Vec<DexEvent> = Vec::new();
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+
+    if market.event_queue.eq(&account.pubkey) {
+        let mut trades_to_insert: Vec<MarketTrade> = Vec::new();
+
+        for event in parse_event_heap(&account.data)? {
+            let fill = match extract_v2_fill(&event, market) {
+                Some(fill) => fill,
+                None => continue,
+            };
+
+            // Check already processed, heap keeps fills in market sequence order
+            if matches!(filled_seq_nums.get(&market.address), Some(last) if event.market_seq_num <= *last)
+            {
+                continue;
+            }
+            filled_seq_nums.insert(market.address, event.market_seq_num);
+
+            tracing::info!(
+                "OBv2 fill: {} - {}, {}, {}",
+                market.name,
+                fill.price,
+                fill.size,
+                fill.order_id
+            );
+
+            trades_to_insert.push(MarketTrade {
+                slug: market.name.clone(),
+                order_id: Some(fill.order_id.to_string()),
+                market_address: market.address.to_string(),
+                // Fill side is the maker's, market_buy is the taker's
+                market_buy: if fill.is_buy { 0 } else { 1 },
+                avg_price: fill.price,
+                amount: fill.size,
+                index: 0,
+                timestamp: now,
+                blocktime: event.timestamp,
+                avg_price_lots: fill.price_lots,
+                amount_lots: fill.size_lots,
+                slot: account.slot,
+                transaction_signature: account.txn_signature.clone(),
+                maker: Some(fill.owner.to_string()),
+                price_usd: None,
+                notional_usd: None,
+                quote_usd_rate: None,
+                instruction_index: None,
+                inner_instruction_index: None,
+                taker: None,
+                fee_payer: None,
+                attribution: None,
+                taker_account: Some(Pubkey::new_from_array(event.taker).to_string()),
+                is_taker_buy: !fill.is_buy,
+            });
+        }
+
+        if !trades_to_insert.is_empty() {
+            events.push(DexEvent::Trades(trades_to_insert));
+        }
+    } else if market.is_book_account(&account.pubkey) {
+        // Get ask/bid orders from bookside account
+        let is_bid = market.bids.eq(&account.pubkey);
+        let book_side = ObV2BookSide::new(&account.data)?;
+        let orders = book_side.orders(is_bid, now, oracle_price_lots);
+
+        events.push(DexEvent::BookSide {
+            market: market.name.clone(),
+            venue: Venue::OpenbookV2,
+            address: market.address.to_string(),
+            is_bid,
+            levels: construct_v2_levels(&orders, market, 20),
+            quotes: construct_v2_quotes(&orders, market),
+        });
+    }
+
+    Ok(events)
+}
+
+/*
+ * Function: parse_ob_v2_markets
+ * 1. Get market accounts of markets configured with ob_version 2 using rpc client
+ * 2. Parse market account and build market info with bookside/event heap accounts, lot sizes and taker fee
+ */
+pub async fn parse_ob_v2_markets(
+    rpc_client: &RpcClient,
+    markets: &[MarketConfig],
+) -> anyhow::Result<Vec<ObMarketInfo>> {
+    let rpc_config = RpcAccountInfoConfig {
+        encoding: Some(UiAccountEncoding::Base64),
+        data_slice: None,
+        commitment: Some(CommitmentConfig::confirmed()),
+        min_context_slot: None,
+    };
+
+    let v2_markets = markets
+        .iter()
+        .filter(|x| ob_market_address(x, 2).is_some())
+        .collect::<Vec<&MarketConfig>>();
+    let market_keys = v2_markets
+        .iter()
+        .filter_map(|x| ob_market_address(x, 2))
+        .map(|x| Pubkey::from_str(x))
+        .collect::<Result<Vec<Pubkey>, _>>()?;
+    let market_results = rpc_client
+        .get_multiple_accounts_with_config(&market_keys, rpc_config)
+        .await?
+        .value;
+
+    let mut market_infos: Vec<ObMarketInfo> = Vec::new();
+    for (i, result) in market_results.iter().enumerate() {
+        let account = match result {
+            Some(account) => account,
+            None => continue,
+        };
+        let raw_market = match ObV2MarketState::load(&account.data) {
+            Ok(raw_market) => raw_market,
+            Err(e) => {
+                tracing::error!("Skip openbook v2 market {}: {}", market_keys[i], e);
+                continue;
+            }
+        };
+
+        // Fees are scaled by 1e6
+        let taker_fee = raw_market.taker_fee;
+        market_infos.push(ObMarketInfo {
+            name: v2_markets[i].slug.clone(),
+            address: market_keys[i],
+            base_mint: Pubkey::new_from_array(raw_market.base_mint),
+            quote_mint: Pubkey::new_from_array(raw_market.quote_mint),
+            bids: Pubkey::new_from_array(raw_market.bids),
+            asks: Pubkey::new_from_array(raw_market.asks),
+            event_queue: Pubkey::new_from_array(raw_market.event_heap),
+            base_decimals: raw_market.base_decimals,
+            quote_decimals: raw_market.quote_decimals,
+            base_lot_size: raw_market.base_lot_size as u64,
+            quote_lot_size: raw_market.quote_lot_size as u64,
+            fee_rate_bps: (taker_fee.max(0) / 100) as u64,
+        });
+    }
+
+    Ok(market_infos)
+}
+
+/*
+ * Function: parse_ob_v2_orders
+ * 1. Get bookside account data using rpc client
+ * 2. Parse fixed and oracle pegged order trees and build levels as 20 limit
+ */
+pub async fn parse_ob_v2_orders(
+    rpc_client: &RpcClient,
+    address: Pubkey,
+    is_bid: bool,
+    market: &ObMarketInfo,
+    oracle_price_lots: Option<i64>,
+) -> anyhow::Result<Vec<MarketOrder>> {
+    let rpc_config = RpcAccountInfoConfig {
+        encoding: Some(UiAccountEncoding::Base64),
+        data_slice: None,
+        commitment: Some(CommitmentConfig::confirmed()),
+        min_context_slot: None,
+    };
+
+    let account = rpc_client
+        .get_account_with_config(&address, rpc_config)
+        .await?
+        .value
+        .ok_or(anyhow::anyhow!("Openbook v2 bookside account not found"))?;
+
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    let book_side = ObV2BookSide::new(&account.data)?;
+    let orders = construct_v2_levels(
+        &book_side.orders(is_bid, now, oracle_price_lots),
+        market,
+        20,
+    );
+
+    Ok(orders)
+}
+
+/*
+ * Helper function for market's oracle price lots from redis's oracle_price, None if market has no oracle
+ */
+fn load_oracle_price_lots(
+    redis_conn: &mut impl ConnectionLike,
+    market: &ObMarketInfo,
+) -> Option<i64> {
+    let data: Option<String> = redis_conn
+        .get(format!("oracle_price:{}", market.name))
+        .ok()?;
+    let oracle_price = serde_json::from_str::<OraclePrice>(&data?).ok()?;
+
+    oracle_price_lots(oracle_price.price, market)
+}
+
+/*
+ * Openbook v2 venue adapter
+ */
+#[derive(Default)]
+pub struct ObV2Adapter {
+    pub markets: Vec<ObMarketInfo>,
+    pub filled_seq_nums: HashMap<Pubkey, u64>,
+}
+
+#[async_trait]
+impl DexAdapter for ObV2Adapter {
+    fn name(&self) -> &'static str {
+        Venue::OpenbookV2.as_str()
+    }
+
+    async fn load_markets(
+        &mut self,
+        rpc_client: &RpcClient,
+        markets: &[MarketConfig],
+    ) -> anyhow::Result<()> {
+        self.markets = parse_ob_v2_markets(rpc_client, markets).await?;
+
+        Ok(())
+    }
+
+    fn account_filters(&self) -> AccountsFilterMap {
+        if self.markets.is_empty() {
+            return HashMap::new();
+        }
+
+        let accounts = self
+            .markets
+            .iter()
+            .flat_map(|x| [x.asks, x.bids, x.event_queue])
+            .map(|x| x.to_string())
+            .collect();
+
+        HashMap::from([(
+            "openbook_v2".to_string(),
+            SubscribeRequestFilterAccounts {
+                account: accounts,
+                owner: [OPENBOOK_V2_PROGRAM_ID.to_string()].into(),
+                filters: [].into(),
+            },
+        )])
+    }
+
+    fn market_accounts(&self) -> HashMap<Pubkey, String> {
+        self.markets
+            .iter()
+            .flat_map(|x| {
+                [x.address, x.asks, x.bids, x.event_queue]
+                    .map(|account| (account, x.address.to_string()))
+            })
+            .collect()
+    }
+
+    async fn load_snapshot(
+        &mut self,
+        rpc_client: &RpcClient,
+        redis_conn: &mut Connection,
+    ) -> anyhow::Result<Vec<VenueSnapshot>> {
+        let mut snapshots: Vec<VenueSnapshot> = Vec::new();
+        for market in self.markets.iter() {
+            let oracle_price_lots = load_oracle_price_lots(redis_conn, market);
+            let asks =
+                parse_ob_v2_orders(rpc_client, market.asks, false, market, oracle_price_lots)
+                    .await
+                    .unwrap_or_default();
+            let bids = parse_ob_v2_orders(rpc_client, market.bids, true, market, oracle_price_lots)
+                .await
+                .unwrap_or_default();
+
+            snapshots.push(VenueSnapshot {
+                market: market.name.clone(),
+                venue_market: VenueMarket {
+                    venue: Venue::OpenbookV2,
+                    address: market.address.to_string(),
+                    fee_rate_bps: market.fee_rate_bps,
+                },
+                orders: MarketOrders { asks, bids },
+            });
+        }
+
+        Ok(snapshots)
+    }
+
+    async fn process_account(
+        &mut self,
+        account: &mut Account,
+        redis_conn: &mut (dyn ConnectionLike + Send),
+        _supabase_client: &(dyn DbSink + Sync),
+    ) -> anyhow::Result<Vec<DexEvent>> {
+        let market = match self
+            .markets
+            .iter()
+            .find(|x| x.is_valid_account(&account.pubkey))
+        {
+            Some(market) => market,
+            None => return Ok(vec![]),
+        };
+
+        // Pegged orders are priced from market's published oracle price
+        let oracle_price_lots = if market.is_book_account(&account.pubkey) {
+            load_oracle_price_lots(&mut DynConnection(redis_conn), market)
+        } else {
+            None
+        };
+
+        parse_openbook_v2_account(
+            market,
+            account,
+            oracle_price_lots,
+            &mut self.filled_seq_nums,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structs::fixtures::{account, ob_market, ob_v2_event_heap, ob_v2_fill_event};
+
+    fn fill_count(events: &Vec<DexEvent>) -> usize {
+        events
+            .iter()
+            .map(|x| match x {
+                DexEvent::Trades(trades) => trades.len(),
+                _ => 0,
+            })
+            .sum()
+    }
+
+    #[test]
+    fn dedupes_fills_by_sequence_number_per_market() {
+        let sol = ob_market("SOL-USDC");
+        let bonk = ob_market("BONK-USDC");
+        let mut filled_seq_nums: HashMap<Pubkey, u64> = HashMap::new();
+
+        // Both markets start their sequence numbers at the same value
+        let heap = ob_v2_event_heap(&[
+            ob_v2_fill_event(1, 20_000, 5, 1),
+            ob_v2_fill_event(2, 20_010, 6, 0),
+        ]);
+        let sol_events = parse_openbook_v2_account(
+            &sol,
+            &account(&sol.event_queue, 1, heap.clone()),
+            None,
+            &mut filled_seq_nums,
+        )
+        .unwrap();
+        let bonk_events = parse_openbook_v2_account(
+            &bonk,
+            &account(&bonk.event_queue, 1, heap.clone()),
+            None,
+            &mut filled_seq_nums,
+        )
+        .unwrap();
+        assert_eq!(fill_count(&sol_events), 2);
+        assert_eq!(fill_count(&bonk_events), 2);
+
+        // Heap still holding processed fills only emits the new one
+        let heap = ob_v2_event_heap(&[
+            ob_v2_fill_event(2, 20_010, 6, 0),
+            ob_v2_fill_event(3, 20_020, 7, 1),
+        ]);
+        let sol_events = parse_openbook_v2_account(
+            &sol,
+            &account(&sol.event_queue, 2, heap),
+            None,
+            &mut filled_seq_nums,
+        )
+        .unwrap();
+        assert_eq!(fill_count(&sol_events), 1);
+        match &sol_events[0] {
+            DexEvent::Trades(trades) => {
+                assert_eq!(trades[0].order_id, Some("3".to_string()));
+                // Taker sold into a maker bid
+                assert_eq!(trades[0].market_buy, 0);
+                assert_eq!(
+                    trades[0].taker_account,
+                    Some(Pubkey::new_from_array([2; 32]).to_string())
+                );
+            }
+            _ => panic!("expected trades event"),
+        }
+    }
+}
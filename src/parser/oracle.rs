@@ -0,0 +1,162 @@
+use arrayref::array_ref;
+
+use crate::structs::{
+    geyser::Account,
+    oracle::{OracleInfo, OraclePrice, OracleType},
+};
+
+// Pyth v2 price account layout
+const PYTH_MAGIC: u32 = 0xa1b2c3d4;
+const PYTH_EXPO_OFFSET: usize = 20;
+const PYTH_AGG_PRICE_OFFSET: usize = 208;
+const PYTH_AGG_CONF_OFFSET: usize = 216;
+const PYTH_AGG_STATUS_OFFSET: usize = 224;
+const PYTH_STATUS_TRADING: u32 = 1;
+
+// Switchboard v2 aggregator account layout, latest_confirmed_round's result/std_deviation
+const SWITCHBOARD_RESULT_OFFSET: usize = 366;
+const SWITCHBOARD_STD_DEVIATION_OFFSET: usize = 386;
+
+/*
+ * Function: parse_oracle_account
+ * 1. Detect oracle type from account's owner program
+ * 2. Decode pyth aggregate price or switchboard latest confirmed round
+ * Returns None if the price isn't tradable or account isn't an oracle
+ */
+pub fn parse_oracle_account(oracle: &OracleInfo, account: &Account) -> Option<OraclePrice> {
+    let oracle_type = OracleType::from_owner(&account.owner)?;
+    let (price, confidence) = match oracle_type {
+        OracleType::Pyth => parse_pyth_price(&account.data)?,
+        OracleType::Switchboard => parse_switchboard_price(&account.data)?,
+    };
+
+    Some(OraclePrice {
+        key: oracle.key.clone(),
+        address: oracle.address.to_string(),
+        oracle_type,
+        price,
+        confidence,
+        slot: account.slot,
+    })
+}
+
+/*
+ * Helper function for decode pyth v2 aggregate price and confidence
+ */
+pub fn parse_pyth_price(data: &Vec<u8>) -> Option<(f64, f64)> {
+    if data.len() < PYTH_AGG_STATUS_OFFSET + 4 {
+        return None;
+    }
+    if u32::from_le_bytes(*array_ref![data, 0, 4]) != PYTH_MAGIC {
+        return None;
+    }
+
+    let status = u32::from_le_bytes(*array_ref![data, PYTH_AGG_STATUS_OFFSET, 4]);
+    if status != PYTH_STATUS_TRADING {
+        return None;
+    }
+
+    let expo = i32::from_le_bytes(*array_ref![data, PYTH_EXPO_OFFSET, 4]);
+    let price = i64::from_le_bytes(*array_ref![data, PYTH_AGG_PRICE_OFFSET, 8]);
+    let conf = u64::from_le_bytes(*array_ref![data, PYTH_AGG_CONF_OFFSET, 8]);
+    let factor = 10f64.powi(expo);
+
+    Some((price as f64 * factor, conf as f64 * factor))
+}
+
+/*
+ * Helper function for decode switchboard v2 latest confirmed result and std deviation
+ */
+pub fn parse_switchboard_price(data: &Vec<u8>) -> Option<(f64, f64)> {
+    if data.len() < SWITCHBOARD_STD_DEVIATION_OFFSET + 20 {
+        return None;
+    }
+
+    let price = switchboard_decimal(data, SWITCHBOARD_RESULT_OFFSET);
+    let confidence = switchboard_decimal(data, SWITCHBOARD_STD_DEVIATION_OFFSET);
+
+    Some((price, confidence))
+}
+
+fn switchboard_decimal(data: &Vec<u8>, offset: usize) -> f64 {
+    let mantissa = i128::from_le_bytes(*array_ref![data, offset, 16]);
+    let scale = u32::from_le_bytes(*array_ref![data, offset + 16, 4]);
+
+    mantissa as f64 / 10f64.powi(scale as i32)
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_sdk::pubkey::Pubkey;
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::{
+        constants::{PYTH_PROGRAM_ID, SWITCHBOARD_PROGRAM_ID},
+        structs::fixtures::{account, pyth_price_data, switchboard_aggregator_data},
+    };
+
+    #[test]
+    fn decodes_pyth_aggregate_price() {
+        let data = pyth_price_data(-2, 5000, 10, PYTH_STATUS_TRADING);
+
+        assert_eq!(parse_pyth_price(&data), Some((50.0, 0.1)));
+    }
+
+    #[test]
+    fn pyth_price_is_none_unless_trading() {
+        assert_eq!(parse_pyth_price(&pyth_price_data(-2, 5000, 10, 0)), None);
+        assert_eq!(parse_pyth_price(&pyth_price_data(-2, 5000, 10, 2)), None);
+
+        let mut data = pyth_price_data(-2, 5000, 10, PYTH_STATUS_TRADING);
+        data[0] = 0;
+        assert_eq!(parse_pyth_price(&data), None);
+        assert_eq!(
+            parse_pyth_price(&data[..PYTH_AGG_STATUS_OFFSET].to_vec()),
+            None
+        );
+    }
+
+    #[test]
+    fn decodes_switchboard_latest_confirmed_round() {
+        let data = switchboard_aggregator_data((2_512_345, 4), (-15, 2));
+
+        assert_eq!(parse_switchboard_price(&data), Some((251.2345, -0.15)));
+        assert_eq!(
+            parse_switchboard_price(&data[..SWITCHBOARD_STD_DEVIATION_OFFSET + 19].to_vec()),
+            None
+        );
+    }
+
+    #[test]
+    fn oracle_type_follows_account_owner() {
+        let oracle = OracleInfo {
+            key: "sol".to_string(),
+            address: Pubkey::new_unique(),
+        };
+        let mut pyth = account(&oracle.address, 7, pyth_price_data(-3, 24_500, 25, 1));
+        pyth.owner = Pubkey::from_str(PYTH_PROGRAM_ID).unwrap();
+        let mut switchboard = account(
+            &oracle.address,
+            8,
+            switchboard_aggregator_data((245, 1), (3, 1)),
+        );
+        switchboard.owner = Pubkey::from_str(SWITCHBOARD_PROGRAM_ID).unwrap();
+
+        let price = parse_oracle_account(&oracle, &pyth).unwrap();
+        assert_eq!(price.oracle_type, OracleType::Pyth);
+        assert_eq!(
+            (price.price, price.confidence, price.slot),
+            (24.5, 0.025, 7)
+        );
+        assert_eq!(price.address, oracle.address.to_string());
+
+        let price = parse_oracle_account(&oracle, &switchboard).unwrap();
+        assert_eq!(price.oracle_type, OracleType::Switchboard);
+        assert_eq!((price.price, price.confidence, price.slot), (24.5, 0.3, 8));
+
+        // Pyth layout owned by another program isn't an oracle
+        pyth.owner = Pubkey::new_unique();
+        assert_eq!(parse_oracle_account(&oracle, &pyth), None);
+    }
+}
@@ -0,0 +1,444 @@
+use async_trait::async_trait;
+use redis::{Connection, ConnectionLike};
+use sqlx::types::Decimal;
+use std::{
+    collections::HashMap,
+    str::FromStr,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use yellowstone_grpc_proto::prelude::SubscribeRequestFilterAccounts;
+
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::{nonblocking::rpc_client::RpcClient, rpc_config::RpcAccountInfoConfig};
+use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey};
+
+use crate::{
+    constants::PHOENIX_PROGRAM_ID,
+    processor::{
+        adapter::{AccountsFilterMap, DexAdapter},
+        db::DbSink,
+    },
+    structs::{
+        adapter::{DexEvent, VenueSnapshot},
+        decode::DecodeError,
+        geyser::{Account, Transaction},
+        market::{MarketConfig, MarketOrders, MarketTrade, TradeAttribution, Venue, VenueMarket},
+        phoenix::{
+            construct_phoenix_levels, construct_phoenix_quotes, detect_phoenix_fills,
+            parse_phoenix_book, parse_phoenix_log, phoenix_is_bid, PhoenixBook, PhoenixFifoMarket,
+            PhoenixMarketHeader, PhoenixMarketInfo,
+        },
+    },
+};
+
+/*
+ * Function: parse_phoenix_account
+ * 1. Parse market account data from geyser subscribe into fifo orderbook
+ * 2. Build book side events of bids and asks with levels and maker quotes
+ * 3. If fills aren't decoded from market events and sequence number moved, then build trades event from fills inferred against previous book
+ */
+pub fn parse_phoenix_account(
+    market: &PhoenixMarketInfo,
+    account: &Account,
+    prev_book: Option<&PhoenixBook>,
+    decode_events: bool,
+) -> anyhow::Result<(PhoenixBook, Vec<DexEvent>)> {
+    let book = parse_phoenix_book(&account.data, market).ok_or(DecodeError::Corrupted {
+        account: "phoenix market",
+        reason: "invalid market layout",
+    })?;
+
+    let mut events: Vec<DexEvent> = vec![true, false]
+        .into_iter()
+        .map(|is_bid| {
+            let orders = if is_bid { &book.bids } else { &book.asks };
+            DexEvent::BookSide {
+                market: market.name.clone(),
+                venue: Venue::Phoenix,
+                address: market.address.to_string(),
+                is_bid,
+                levels: construct_phoenix_levels(orders, market, 20),
+                quotes: construct_phoenix_quotes(orders, &book.traders, market),
+            }
+        })
+        .collect();
+
+    if let Some(prev_book) = prev_book.filter(|_| !decode_events) {
+        // Account updates carry no block time, inferred fills are stamped with receive time
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        let trades: Vec<MarketTrade> = detect_phoenix_fills(prev_book, &book)
+            .into_iter()
+            .map(|fill| {
+                let price = market.readable_price(fill.order.price_in_ticks);
+                let amount = market.readable_quantity(fill.base_lots);
+                tracing::info!(
+                    "Phoenix fill: {} - {}, {}, {}",
+                    market.name,
+                    price,
+                    amount,
+                    fill.order.order_sequence_number
+                );
+
+                MarketTrade {
+                    slug: market.name.clone(),
+                    order_id: Some(fill.order.order_sequence_number.to_string()),
+                    market_address: market.address.to_string(),
+                    // Resting order is the maker's, market_buy is the taker's
+                    market_buy: if fill.order.is_bid { 0 } else { 1 },
+                    avg_price: price,
+                    amount,
+                    index: 0,
+                    timestamp: now,
+                    blocktime: now,
+                    avg_price_lots: Decimal::from(fill.order.price_in_ticks),
+                    amount_lots: Decimal::from(fill.base_lots),
+                    slot: account.slot,
+                    transaction_signature: account.txn_signature.clone(),
+                    maker: Some(fill.maker.to_string()),
+                    price_usd: None,
+                    notional_usd: None,
+                    quote_usd_rate: None,
+                    instruction_index: None,
+                    inner_instruction_index: None,
+                    taker: None,
+                    fee_payer: None,
+                    attribution: None,
+                    taker_account: None,
+                    is_taker_buy: !fill.order.is_bid,
+                }
+            })
+            .collect();
+
+        if !trades.is_empty() {
+            events.push(DexEvent::Trades(trades));
+        }
+    }
+
+    Ok((book, events))
+}
+
+/*
+ * Function: parse_phoenix_transaction
+ * 1. Decode self-CPI log instructions of phoenix invocations into audit header and fill events
+ * 2. Build trades event per market of fills with block time, signer and instruction of the log header
+ */
+pub fn parse_phoenix_transaction(
+    markets: &[PhoenixMarketInfo],
+    transaction: &Transaction,
+) -> anyhow::Result<Vec<DexEvent>> {
+    let mut trades: HashMap<String, Vec<MarketTrade>> = HashMap::new();
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    for ix in transaction
+        .instructions
+        .iter()
+        .filter(|x| x.inner_index.is_some() && x.program_id.to_string() == PHOENIX_PROGRAM_ID)
+    {
+        let log = match parse_phoenix_log(&ix.data) {
+            Some(log) => log,
+            None => continue,
+        };
+        let market_address = Pubkey::new_from_array(log.header.market);
+        let market = match markets.iter().find(|x| x.is_valid_account(&market_address)) {
+            Some(market) => market,
+            None => continue,
+        };
+
+        for fill in log.fills {
+            let price = market.readable_price(fill.price_in_ticks);
+            let amount = market.readable_quantity(fill.base_lots_filled);
+            tracing::info!(
+                "Phoenix fill: {} - {}, {}, {}",
+                market.name,
+                price,
+                amount,
+                fill.order_sequence_number
+            );
+
+            trades
+                .entry(market.name.clone())
+                .or_default()
+                .push(MarketTrade {
+                    slug: market.name.clone(),
+                    order_id: Some(fill.order_sequence_number.to_string()),
+                    market_address: market.address.to_string(),
+                    // Resting order is the maker's, market_buy is the taker's
+                    market_buy: if phoenix_is_bid(fill.order_sequence_number) {
+                        0
+                    } else {
+                        1
+                    },
+                    avg_price: price,
+                    amount,
+                    index: fill.index as u64,
+                    timestamp: now,
+                    blocktime: log.header.timestamp.max(0) as u64,
+                    avg_price_lots: Decimal::from(fill.price_in_ticks),
+                    amount_lots: Decimal::from(fill.base_lots_filled),
+                    slot: transaction.slot,
+                    transaction_signature: transaction.signature.clone(),
+                    maker: Some(Pubkey::new_from_array(fill.maker_id).to_string()),
+                    price_usd: None,
+                    notional_usd: None,
+                    quote_usd_rate: None,
+                    instruction_index: Some(ix.index),
+                    inner_instruction_index: ix.inner_index,
+                    taker: Some(Pubkey::new_from_array(log.header.signer).to_string()),
+                    fee_payer: Some(transaction.fee_payer.to_string()),
+                    attribution: Some(TradeAttribution::LogEvent),
+                    taker_account: None,
+                    is_taker_buy: !phoenix_is_bid(fill.order_sequence_number),
+                });
+        }
+    }
+
+    Ok(trades.into_values().map(DexEvent::Trades).collect())
+}
+
+/*
+ * Function: parse_phoenix_markets
+ * 1. Get market accounts of markets with phoenix_market_address using rpc client
+ * 2. Parse market header and build market info with tree sizes, lot/tick sizes and taker fee
+ * Returns market infos with their initial books
+ */
+pub async fn parse_phoenix_markets(
+    rpc_client: &RpcClient,
+    markets: &[MarketConfig],
+) -> anyhow::Result<Vec<(PhoenixMarketInfo, PhoenixBook)>> {
+    let rpc_config = RpcAccountInfoConfig {
+        encoding: Some(UiAccountEncoding::Base64),
+        data_slice: None,
+        commitment: Some(CommitmentConfig::confirmed()),
+        min_context_slot: None,
+    };
+
+    let phoenix_markets = markets
+        .iter()
+        .filter(|x| x.info("phoenix_market_address").is_some())
+        .collect::<Vec<&MarketConfig>>();
+    let market_keys = phoenix_markets
+        .iter()
+        .filter_map(|x| x.info("phoenix_market_address"))
+        .map(|x| Pubkey::from_str(x))
+        .collect::<Result<Vec<Pubkey>, _>>()?;
+    let market_results = rpc_client
+        .get_multiple_accounts_with_config(&market_keys, rpc_config)
+        .await?
+        .value;
+
+    let mut market_infos: Vec<(PhoenixMarketInfo, PhoenixBook)> = Vec::new();
+    for (i, result) in market_results.iter().enumerate() {
+        let account = match result {
+            Some(account) => account,
+            None => continue,
+        };
+        let (header, fifo_market) = match (
+            PhoenixMarketHeader::load(&account.data),
+            PhoenixFifoMarket::load(&account.data),
+        ) {
+            (Some(header), Some(fifo_market)) => (header, fifo_market),
+            _ => continue,
+        };
+
+        let market = PhoenixMarketInfo {
+            name: phoenix_markets[i].slug.clone(),
+            address: market_keys[i],
+            base_decimals: header.base_decimals as u8,
+            quote_decimals: header.quote_decimals as u8,
+            base_lot_size: header.base_lot_size,
+            tick_size_in_quote_atoms_per_base_unit: header.tick_size_in_quote_atoms_per_base_unit,
+            raw_base_units_per_base_unit: header.raw_base_units_per_base_unit,
+            bids_size: header.bids_size as usize,
+            asks_size: header.asks_size as usize,
+            num_seats: header.num_seats as usize,
+            taker_fee_bps: fifo_market.taker_fee_bps,
+        };
+        let book = parse_phoenix_book(&account.data, &market).unwrap_or_default();
+        market_infos.push((market, book));
+    }
+
+    Ok(market_infos)
+}
+
+/*
+ * Phoenix venue adapter, fills are decoded from market events if transactions are subscribed
+ */
+#[derive(Default)]
+pub struct PhoenixAdapter {
+    pub markets: Vec<PhoenixMarketInfo>,
+    pub books: HashMap<String, PhoenixBook>,
+    pub decode_events: bool,
+}
+
+#[async_trait]
+impl DexAdapter for PhoenixAdapter {
+    fn name(&self) -> &'static str {
+        Venue::Phoenix.as_str()
+    }
+
+    async fn load_markets(
+        &mut self,
+        rpc_client: &RpcClient,
+        markets: &[MarketConfig],
+    ) -> anyhow::Result<()> {
+        for (market, book) in parse_phoenix_markets(rpc_client, markets).await? {
+            self.books.insert(market.address.to_string(), book);
+            self.markets.push(market);
+        }
+
+        Ok(())
+    }
+
+    fn account_filters(&self) -> AccountsFilterMap {
+        if self.markets.is_empty() {
+            return HashMap::new();
+        }
+
+        HashMap::from([(
+            "phoenix".to_string(),
+            SubscribeRequestFilterAccounts {
+                account: self.markets.iter().map(|x| x.address.to_string()).collect(),
+                owner: [PHOENIX_PROGRAM_ID.to_string()].into(),
+                filters: [].into(),
+            },
+        )])
+    }
+
+    async fn load_snapshot(
+        &mut self,
+        _rpc_client: &RpcClient,
+        _redis_conn: &mut Connection,
+    ) -> anyhow::Result<Vec<VenueSnapshot>> {
+        // Books are already loaded together with market headers
+        let snapshots = self
+            .markets
+            .iter()
+            .map(|market| {
+                let book = self
+                    .books
+                    .get(&market.address.to_string())
+                    .cloned()
+                    .unwrap_or_default();
+                VenueSnapshot {
+                    market: market.name.clone(),
+                    venue_market: VenueMarket {
+                        venue: Venue::Phoenix,
+                        address: market.address.to_string(),
+                        fee_rate_bps: market.taker_fee_bps,
+                    },
+                    orders: MarketOrders {
+                        asks: construct_phoenix_levels(&book.asks, market, 20),
+                        bids: construct_phoenix_levels(&book.bids, market, 20),
+                    },
+                }
+            })
+            .collect();
+
+        Ok(snapshots)
+    }
+
+    async fn process_account(
+        &mut self,
+        account: &mut Account,
+        _redis_conn: &mut (dyn ConnectionLike + Send),
+        _supabase_client: &(dyn DbSink + Sync),
+    ) -> anyhow::Result<Vec<DexEvent>> {
+        let market = match self
+            .markets
+            .iter()
+            .find(|x| x.is_valid_account(&account.pubkey))
+        {
+            Some(market) => market,
+            None => return Ok(vec![]),
+        };
+
+        let address = market.address.to_string();
+        let (book, events) = parse_phoenix_account(
+            market,
+            account,
+            self.books.get(&address),
+            self.decode_events,
+        )?;
+        self.books.insert(address, book);
+
+        Ok(events)
+    }
+
+    fn process_transaction(&mut self, transaction: &Transaction) -> anyhow::Result<Vec<DexEvent>> {
+        if !self.decode_events {
+            return Ok(vec![]);
+        }
+
+        parse_phoenix_transaction(&self.markets, transaction)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structs::{
+        fixtures::{phoenix_log_data, phoenix_market},
+        geyser::TxInstruction,
+    };
+
+    fn phoenix_instruction(index: u32, inner_index: Option<u32>, data: Vec<u8>) -> TxInstruction {
+        TxInstruction {
+            index,
+            inner_index,
+            program_id: Pubkey::from_str(PHOENIX_PROGRAM_ID).unwrap(),
+            accounts: vec![],
+            signers: vec![],
+            data,
+        }
+    }
+
+    #[test]
+    fn builds_trades_from_log_fills_with_block_time() {
+        let market = phoenix_market("SOL-USDC");
+        let other_market = Pubkey::new_unique();
+        let (signer, maker) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let log = phoenix_log_data(
+            &market.address,
+            &signer,
+            1_700_000_123,
+            &[(maker, !5, 99_000, 6)],
+        );
+        let transaction = Transaction {
+            slot: 10,
+            signature: "sig".to_string(),
+            index: 0,
+            fee_payer: signer,
+            is_failed: false,
+            instructions: vec![
+                // Swap instruction data carries no events, only its self-CPI log does
+                phoenix_instruction(1, None, log.clone()),
+                phoenix_instruction(1, Some(0), log),
+                phoenix_instruction(
+                    1,
+                    Some(1),
+                    phoenix_log_data(&other_market, &signer, 1_700_000_123, &[(maker, 6, 1, 1)]),
+                ),
+            ],
+            log_messages: vec![],
+        };
+
+        let events =
+            parse_phoenix_transaction(std::slice::from_ref(&market), &transaction).unwrap();
+        assert_eq!(events.len(), 1);
+        let trades = match &events[0] {
+            DexEvent::Trades(trades) => trades,
+            _ => panic!("expected trades event"),
+        };
+        assert_eq!(trades.len(), 1);
+        assert_eq!(trades[0].blocktime, 1_700_000_123);
+        assert_eq!(trades[0].avg_price, market.readable_price(99_000));
+        assert_eq!(trades[0].amount_lots, Decimal::from(6));
+        // Taker sold into a maker bid
+        assert_eq!(trades[0].market_buy, 0);
+        assert_eq!(trades[0].maker, Some(maker.to_string()));
+        assert_eq!(trades[0].taker, Some(signer.to_string()));
+        assert_eq!(trades[0].instruction_index, Some(1));
+        assert_eq!(trades[0].inner_instruction_index, Some(0));
+        assert_eq!(trades[0].attribution, Some(TradeAttribution::LogEvent));
+        assert_eq!(trades[0].transaction_signature, "sig");
+    }
+}
@@ -0,0 +1,220 @@
+use async_trait::async_trait;
+use postgrest::Postgrest;
+use redis::{Client, Connection, ConnectionLike, RedisResult, Value};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use yellowstone_grpc_proto::prelude::SubscribeRequestFilterAccounts;
+
+use crate::{
+    parser::{AmmAdapter, GdAdapter, ObAdapter, ObV2Adapter, PhoenixAdapter},
+    processor::{
+        alerts::evaluate_trade_alerts,
+        db::DbSink,
+        market::{publish_trades_data, update_trades},
+    },
+    structs::{
+        adapter::{DexEvent, VenueSnapshot},
+        alerts::AlertRulesCache,
+        geyser::{Account, Transaction},
+        makers::MakerQuotes,
+        market::{MarketConfig, MarketOrders, MarketTrade},
+        transactions::TxFillTracker,
+    },
+};
+
+pub type AccountsFilterMap = HashMap<String, SubscribeRequestFilterAccounts>;
+
+/*
+ * Venue integration plugged into subscribe_geyser
+ */
+#[async_trait]
+pub trait DexAdapter: Send + Sync {
+    // Name of the adapter for logging
+    fn name(&self) -> &'static str;
+
+    // Discover venue markets of market configs
+    async fn load_markets(
+        &mut self,
+        rpc_client: &RpcClient,
+        markets: &[MarketConfig],
+    ) -> anyhow::Result<()>;
+
+    // Geyser account filters covering every account of venue markets
+    fn account_filters(&self) -> AccountsFilterMap;
+
+    // Market address of every account of venue markets, used to map transaction instructions to markets
+    fn market_accounts(&self) -> HashMap<Pubkey, String> {
+        HashMap::new()
+    }
+
+    // Accounts read while loading markets and snapshots, stored in recording header so replay needs no rpc
+    fn startup_accounts(&self) -> Vec<Pubkey> {
+        vec![]
+    }
+
+    // Initial orderbook of every venue market
+    async fn load_snapshot(
+        &mut self,
+        rpc_client: &RpcClient,
+        redis_conn: &mut Connection,
+    ) -> anyhow::Result<Vec<VenueSnapshot>>;
+
+    // Decode account update into normalized events, empty if account isn't the venue's
+    async fn process_account(
+        &mut self,
+        account: &mut Account,
+        redis_conn: &mut (dyn ConnectionLike + Send),
+        supabase_client: &(dyn DbSink + Sync),
+    ) -> anyhow::Result<Vec<DexEvent>>;
+
+    // Decode subscribed transaction into normalized events, empty if venue doesn't decode transactions
+    fn process_transaction(&mut self, _transaction: &Transaction) -> anyhow::Result<Vec<DexEvent>> {
+        Ok(vec![])
+    }
+
+    // Publish venue updates held until their transaction is tracked, once ready
+    fn flush_pending(
+        &mut self,
+        _redis_conn: &mut (dyn ConnectionLike + Send),
+        _supabase_client: &(dyn DbSink + Sync),
+    ) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+/*
+ * Sized handle of redis connection passed to adapters, so redis commands run on it
+ */
+pub struct DynConnection<'a>(pub &'a mut (dyn ConnectionLike + Send));
+
+impl ConnectionLike for DynConnection<'_> {
+    fn req_packed_command(&mut self, cmd: &[u8]) -> RedisResult<Value> {
+        self.0.req_packed_command(cmd)
+    }
+
+    fn req_packed_commands(
+        &mut self,
+        cmd: &[u8],
+        offset: usize,
+        count: usize,
+    ) -> RedisResult<Vec<Value>> {
+        self.0.req_packed_commands(cmd, offset, count)
+    }
+
+    fn get_db(&self) -> i64 {
+        self.0.get_db()
+    }
+
+    fn check_connection(&mut self) -> bool {
+        self.0.check_connection()
+    }
+
+    fn is_open(&self) -> bool {
+        self.0.is_open()
+    }
+}
+
+/*
+ * Function: build_adapters
+ * 1. Build adapters of every supported venue, phoenix decodes fills from market events if transactions are subscribed
+ * 2. Gigadex holds balance transitions for instruction classification if transactions are subscribed
+ */
+pub fn build_adapters(subscribe_transactions: bool) -> Vec<Box<dyn DexAdapter>> {
+    vec![
+        Box::new(ObAdapter::default()),
+        Box::new(ObV2Adapter::default()),
+        Box::new(GdAdapter {
+            hold_balance_events: subscribe_transactions,
+            ..Default::default()
+        }),
+        Box::new(PhoenixAdapter {
+            decode_events: subscribe_transactions,
+            ..Default::default()
+        }),
+        Box::new(AmmAdapter::default()),
+    ]
+}
+
+/*
+ * Function: apply_dex_event
+ * 1. If book side event, then update local market state and maker quotes and publish venue orderbook
+ * 2. If trades event, then evaluate trade alerts and pass trades to trades handler,
+ *    or hold trades for transaction attribution if enabled
+ * Returns market name of updated orderbook
+ */
+pub fn apply_dex_event(
+    event: DexEvent,
+    handle_trades: &impl Fn(Vec<MarketTrade>),
+    redis_conn: &mut impl ConnectionLike,
+    market_orders: &mut HashMap<String, MarketOrders>,
+    maker_quotes: &mut HashMap<String, MakerQuotes>,
+    tx_tracker: &mut TxFillTracker,
+    alert_rules: &mut AlertRulesCache,
+    slot: u64,
+) -> anyhow::Result<Option<String>> {
+    match event {
+        DexEvent::BookSide {
+            market,
+            venue,
+            address,
+            is_bid,
+            levels,
+            quotes,
+        } => {
+            let market_state = match market_orders.get_mut(&address) {
+                Some(market_state) => market_state,
+                None => return Ok(None),
+            };
+
+            // Update local market state
+            let market_quotes = maker_quotes.entry(address).or_default();
+            if is_bid {
+                market_state.bids = levels;
+                market_quotes.bids = quotes;
+            } else {
+                market_state.asks = levels;
+                market_quotes.asks = quotes;
+            }
+
+            // Publish ask/bid updates to redis
+            publish_trades_data(&market, venue, market_state, redis_conn, slot, alert_rules)?;
+
+            Ok(Some(market))
+        }
+        DexEvent::Trades(trades) => {
+            // Match trade alert rules before trades are persisted
+            if let Err(e) = evaluate_trade_alerts(redis_conn, &trades, alert_rules) {
+                tracing::error!("Error evaluating trade alerts: {:?}", e);
+            }
+
+            if tx_tracker.enabled {
+                tx_tracker.pending_trades.push(trades);
+            } else {
+                handle_trades(trades);
+            }
+
+            Ok(None)
+        }
+    }
+}
+
+/*
+ * Helper function for calling update_trades in background
+ */
+pub fn spawn_update_trades(
+    api_url: &String,
+    redis_client: &Client,
+    supabase_client: &Postgrest,
+    trades: Vec<MarketTrade>,
+) {
+    tokio::spawn({
+        let supabase_clone = supabase_client.clone();
+        let redis_clone = redis_client.clone();
+        let url_clone = api_url.clone();
+
+        async move {
+            let _ = update_trades(url_clone, redis_clone, supabase_clone, trades).await;
+        }
+    });
+}
@@ -0,0 +1,378 @@
+use num_traits::ToPrimitive;
+use redis::{Commands, ConnectionLike};
+use sqlx::types::Decimal;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{
+    constants::{
+        ALERT_PRICE_WINDOW_SECS, ALERT_RULES_KEY, ALERT_RULES_TTL_SECS, SECONDS_PER_MINUTE,
+    },
+    structs::{
+        alerts::{Alert, AlertCondition, AlertPublishData, AlertRule, AlertRulesCache, AlertSink},
+        market::{MarketOrders, MarketTrade, Venue},
+    },
+    utils::generate_publish_data,
+};
+
+/*
+ * Function: evaluate_trade_alerts
+ * 1. Record trades' prices into redis's alert_prices window while market has price move rules
+ * 2. Match trade notional rules against every trade
+ * 3. Match price move rules against the oldest price inside the rule's window
+ * 4. Dispatch matched alerts into the rule's sinks once per cooldown
+ */
+pub fn evaluate_trade_alerts(
+    redis_conn: &mut impl ConnectionLike,
+    trades: &Vec<MarketTrade>,
+    alert_rules: &mut AlertRulesCache,
+) -> anyhow::Result<()> {
+    let last_trade = match trades.last() {
+        Some(trade) => trade,
+        None => return Ok(()),
+    };
+    let market_slug = last_trade.slug.clone();
+    let prices_key = format!("alert_prices:{}", market_slug);
+
+    let rules = load_alert_rules(redis_conn, &market_slug, alert_rules)?;
+    let has_price_rules = rules
+        .iter()
+        .any(|x| matches!(x.condition, AlertCondition::PriceMove { .. }));
+
+    // Record trade prices, price last so members stay unique per fill
+    if has_price_rules {
+        for trade in trades.iter() {
+            redis_conn.zadd(
+                &prices_key,
+                format!(
+                    "{}:{}:{}:{}:{}",
+                    trade.transaction_signature,
+                    trade.order_id.clone().unwrap_or_default(),
+                    trade.slot,
+                    trade.index,
+                    trade.avg_price
+                ),
+                trade.timestamp,
+            )?;
+        }
+        redis_conn.zrembyscore(
+            &prices_key,
+            0,
+            last_trade.timestamp.saturating_sub(ALERT_PRICE_WINDOW_SECS),
+        )?;
+    }
+
+    for rule in rules.iter() {
+        match rule.condition {
+            AlertCondition::TradeNotional { threshold } => {
+                for trade in trades.iter() {
+                    let price = Decimal::to_f64(&trade.avg_price).unwrap_or_default();
+                    let amount = Decimal::to_f64(&trade.amount).unwrap_or_default();
+                    let notional = price * amount;
+                    if notional < threshold {
+                        continue;
+                    }
+                    if !acquire_cooldown(redis_conn, rule, &market_slug)? {
+                        break;
+                    }
+
+                    let alert = Alert {
+                        rule_id: rule.id.clone(),
+                        slug: market_slug.clone(),
+                        venue: None,
+                        condition: rule.condition.as_str().to_string(),
+                        value: notional,
+                        threshold,
+                        message: format!(
+                            "{} {} {} at {} ({} notional)",
+                            market_slug,
+                            if trade.is_taker_buy { "buy" } else { "sell" },
+                            amount,
+                            price,
+                            notional
+                        ),
+                        slot: trade.slot,
+                        timestamp: trade.timestamp,
+                    };
+                    dispatch_alert(redis_conn, &rule.sinks, alert)?;
+                }
+            }
+            AlertCondition::PriceMove { percent, minutes } => {
+                let start_ts = last_trade
+                    .timestamp
+                    .saturating_sub(minutes * SECONDS_PER_MINUTE);
+                let window: Vec<String> =
+                    redis_conn.zrangebyscore_limit(&prices_key, start_ts, "+inf", 0, 1)?;
+                let start_price = match window.first().and_then(|x| x.rsplit(':').next()) {
+                    Some(price) => price.parse::<f64>().unwrap_or_default(),
+                    None => continue,
+                };
+                if start_price <= 0.0 {
+                    continue;
+                }
+
+                let last_price = Decimal::to_f64(&last_trade.avg_price).unwrap_or_default();
+                let move_percent = (last_price - start_price) / start_price * 100.0;
+                if move_percent.abs() < percent
+                    || !acquire_cooldown(redis_conn, rule, &market_slug)?
+                {
+                    continue;
+                }
+
+                let alert = Alert {
+                    rule_id: rule.id.clone(),
+                    slug: market_slug.clone(),
+                    venue: None,
+                    condition: rule.condition.as_str().to_string(),
+                    value: move_percent,
+                    threshold: percent,
+                    message: format!(
+                        "{} moved {:.2}% from {} to {} in {} minutes",
+                        market_slug, move_percent, start_price, last_price, minutes
+                    ),
+                    slot: last_trade.slot,
+                    timestamp: last_trade.timestamp,
+                };
+                dispatch_alert(redis_conn, &rule.sinks, alert)?;
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+/*
+ * Function: evaluate_book_alerts
+ * 1. Match spread rules against venue's best ask/bid
+ * 2. Match empty side rules against venue's ask/bid levels
+ * 3. Dispatch matched alerts into the rule's sinks once per cooldown
+ */
+pub fn evaluate_book_alerts(
+    market: &String,
+    venue: Venue,
+    market_state: &MarketOrders,
+    redis_conn: &mut impl ConnectionLike,
+    slot: u64,
+    alert_rules: &mut AlertRulesCache,
+) -> anyhow::Result<()> {
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+
+    let rules = load_alert_rules(redis_conn, market, alert_rules)?;
+    for rule in rules.iter() {
+        let (value, threshold, message) = match rule.condition {
+            AlertCondition::Spread { bps } => {
+                let (best_ask, best_bid) =
+                    match (market_state.asks.first(), market_state.bids.first()) {
+                        (Some(ask), Some(bid)) => (ask.price, bid.price),
+                        _ => continue,
+                    };
+                let mid = (best_ask + best_bid) / 2.0;
+                if mid <= 0.0 {
+                    continue;
+                }
+                let spread_bps = (best_ask - best_bid) / mid * 10_000.0;
+                if spread_bps < bps {
+                    continue;
+                }
+
+                (
+                    spread_bps,
+                    bps,
+                    format!(
+                        "{} {} spread widened to {:.2} bps ({} / {})",
+                        market,
+                        venue.as_str(),
+                        spread_bps,
+                        best_bid,
+                        best_ask
+                    ),
+                )
+            }
+            AlertCondition::EmptySide => {
+                let side = if market_state.asks.is_empty() {
+                    "asks"
+                } else if market_state.bids.is_empty() {
+                    "bids"
+                } else {
+                    continue;
+                };
+
+                (
+                    0.0,
+                    0.0,
+                    format!("{} {} {} side is empty", market, venue.as_str(), side),
+                )
+            }
+            _ => continue,
+        };
+
+        let cooldown_slug = format!("{}:{}", market, venue.as_str());
+        if !acquire_cooldown(redis_conn, rule, &cooldown_slug)? {
+            continue;
+        }
+
+        let alert = Alert {
+            rule_id: rule.id.clone(),
+            slug: market.clone(),
+            venue: Some(venue),
+            condition: rule.condition.as_str().to_string(),
+            value,
+            threshold,
+            message,
+            slot,
+            timestamp,
+        };
+        dispatch_alert(redis_conn, &rule.sinks, alert)?;
+    }
+
+    Ok(())
+}
+
+/*
+ * Helper function for load alert rules of market, reloading redis's rules once cached ones expire
+ */
+pub fn load_alert_rules(
+    redis_conn: &mut impl ConnectionLike,
+    market: &String,
+    alert_rules: &mut AlertRulesCache,
+) -> anyhow::Result<Vec<AlertRule>> {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    let expired = match alert_rules.loaded_ts {
+        Some(loaded_ts) => now >= loaded_ts + ALERT_RULES_TTL_SECS,
+        None => true,
+    };
+    if expired {
+        let data: Option<String> = redis_conn.get(ALERT_RULES_KEY)?;
+        let rules = match data {
+            Some(data) => match serde_json::from_str::<Vec<AlertRule>>(&data) {
+                Ok(rules) => rules,
+                Err(e) => {
+                    tracing::error!("Error parsing alert rules: {}", e);
+                    vec![]
+                }
+            },
+            None => vec![],
+        };
+        alert_rules.loaded_ts = Some(now);
+        alert_rules.rules = rules;
+    }
+
+    Ok(alert_rules
+        .rules
+        .iter()
+        .filter(|x| x.slug.is_none() || x.slug.as_ref() == Some(market))
+        .cloned()
+        .collect())
+}
+
+/*
+ * Helper function for hold rule's cooldown key in redis, returns false while cooling down
+ */
+fn acquire_cooldown(
+    redis_conn: &mut impl ConnectionLike,
+    rule: &AlertRule,
+    slug: &String,
+) -> anyhow::Result<bool> {
+    if rule.cooldown_secs == 0 {
+        return Ok(true);
+    }
+
+    let acquired: Option<String> = redis::cmd("SET")
+        .arg(format!("alert_cooldown:{}:{}", rule.id, slug))
+        .arg(1)
+        .arg("NX")
+        .arg("EX")
+        .arg(rule.cooldown_secs)
+        .query(redis_conn)?;
+
+    Ok(acquired.is_some())
+}
+
+/*
+ * Helper function for send alert into redis channel, webhook or log sinks
+ */
+fn dispatch_alert(
+    redis_conn: &mut impl ConnectionLike,
+    sinks: &Vec<AlertSink>,
+    alert: Alert,
+) -> anyhow::Result<()> {
+    for sink in sinks.iter() {
+        match sink {
+            AlertSink::Redis { channel } => {
+                let msg = generate_publish_data(
+                    &alert.slug,
+                    &AlertPublishData {
+                        alert: alert.clone(),
+                    },
+                    Some(alert.rule_id.clone()),
+                );
+                redis_conn.publish(channel, msg)?;
+            }
+            AlertSink::Webhook { url } => {
+                tokio::spawn({
+                    let url_clone = url.clone();
+                    let alert_clone = alert.clone();
+
+                    async move {
+                        let client = reqwest::Client::new();
+                        if let Err(e) = client.post(&url_clone).json(&alert_clone).send().await {
+                            tracing::error!("Error posting alert webhook: {}", e);
+                        }
+                    }
+                });
+            }
+            AlertSink::Log => {
+                tracing::warn!("Alert {}: {}", alert.rule_id, alert.message);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::structs::{fixtures::market_trade, replay::MemoryRedis};
+
+    use super::*;
+
+    #[test]
+    fn trade_notional_alerts_once_per_cooldown() {
+        let trades = vec![
+            market_trade("sol-usdc", 50.0, 4.0),
+            market_trade("sol-usdc", 50.0, 3.0),
+            market_trade("sol-usdc", 50.0, 1.0),
+        ];
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+
+        for (cooldown_secs, expected) in [(60, 1), (0, 4)] {
+            let mut alert_rules = AlertRulesCache {
+                loaded_ts: Some(now),
+                rules: vec![AlertRule {
+                    id: "large".to_string(),
+                    slug: Some("sol-usdc".to_string()),
+                    condition: AlertCondition::TradeNotional { threshold: 100.0 },
+                    sinks: vec![AlertSink::Redis {
+                        channel: "alerts".to_string(),
+                    }],
+                    cooldown_secs,
+                }],
+            };
+
+            // Cached rules are used without reloading, twice over the same trades
+            let mut redis = MemoryRedis::default();
+            for _ in 0..2 {
+                evaluate_trade_alerts(&mut redis, &trades, &mut alert_rules).unwrap();
+            }
+            let alerts = redis
+                .published
+                .iter()
+                .filter(|(channel, _)| channel == "alerts")
+                .count();
+            assert_eq!(alerts, expected);
+        }
+    }
+}
@@ -0,0 +1,206 @@
+use redis::{Commands, ConnectionLike};
+use std::{
+    collections::HashMap,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    constants::{ANALYTICS_CHANNEL_NAME, BOOK_METRICS_INTERVAL_SECS, DEPTH_BPS},
+    processor::db::DbSink,
+    structs::{
+        analytics::{BookMetrics, BookMetricsPublishData, DepthData, ExecutionData},
+        market::{MarketOrder, MarketOrders},
+    },
+    utils::generate_publish_data,
+};
+
+/*
+ * Function: publish_book_metrics
+ * 1. Compute mid, spread, depth and execution price metrics from market's orderbook
+ * 2. Update redis's book_metrics and publish metrics to analytics channel
+ * 3. Insert metrics into supabase once per interval as time series
+ */
+pub fn publish_book_metrics(
+    market: &String,
+    market_state: &MarketOrders,
+    notionals: &Vec<f64>,
+    prev_stored_ts: &mut HashMap<String, u64>,
+    redis_conn: &mut impl ConnectionLike,
+    supabase_client: &impl DbSink,
+    slot: u64,
+) -> anyhow::Result<()> {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    let metrics = match compute_book_metrics(market, market_state, notionals, slot, now) {
+        Some(metrics) => metrics,
+        None => return Ok(()),
+    };
+
+    let publish_data = BookMetricsPublishData { metrics };
+    redis_conn.set(
+        format!("book_metrics:{}", market),
+        serde_json::to_string(&publish_data)?,
+    )?;
+    redis_conn.publish(
+        ANALYTICS_CHANNEL_NAME,
+        generate_publish_data(market, &publish_data, None),
+    )?;
+
+    // Insert metrics into DB
+    let is_due = prev_stored_ts
+        .get(market)
+        .map_or(true, |ts| now >= ts + BOOK_METRICS_INTERVAL_SECS);
+    if is_due {
+        prev_stored_ts.insert(market.clone(), now);
+        supabase_client.store_book_metrics(vec![publish_data.metrics]);
+    }
+
+    Ok(())
+}
+
+/*
+ * Helper function for compute book metrics, None if either side of the book is empty
+ */
+pub fn compute_book_metrics(
+    market: &String,
+    market_state: &MarketOrders,
+    notionals: &Vec<f64>,
+    slot: u64,
+    timestamp: u64,
+) -> Option<BookMetrics> {
+    let best_ask = market_state.asks.first()?.price;
+    let best_bid = market_state.bids.first()?.price;
+    let mid = (best_ask + best_bid) / 2.0;
+    if mid <= 0.0 {
+        return None;
+    }
+
+    let spread = best_ask - best_bid;
+    let depth = DEPTH_BPS
+        .iter()
+        .map(|bps| {
+            let range = mid * (*bps as f64) / 10_000.0;
+            DepthData {
+                bps: *bps,
+                bid_amount: market_state
+                    .bids
+                    .iter()
+                    .filter(|x| x.price >= mid - range)
+                    .map(|x| x.amount)
+                    .sum(),
+                ask_amount: market_state
+                    .asks
+                    .iter()
+                    .filter(|x| x.price <= mid + range)
+                    .map(|x| x.amount)
+                    .sum(),
+            }
+        })
+        .collect();
+
+    let executions = notionals
+        .iter()
+        .map(|notional| {
+            let buy_price = execution_price(&market_state.asks, *notional);
+            let sell_price = execution_price(&market_state.bids, *notional);
+            ExecutionData {
+                notional: *notional,
+                buy_price,
+                sell_price,
+                buy_slippage_bps: buy_price.map(|x| (x - mid) / mid * 10_000.0),
+                sell_slippage_bps: sell_price.map(|x| (mid - x) / mid * 10_000.0),
+            }
+        })
+        .collect();
+
+    Some(BookMetrics {
+        slug: market.clone(),
+        mid,
+        spread,
+        spread_bps: spread / mid * 10_000.0,
+        depth,
+        executions,
+        slot,
+        timestamp,
+    })
+}
+
+/*
+ * Helper function for walk levels with quote notional and get average execution price
+ * Returns None if book depth can't fill the notional
+ */
+pub fn execution_price(levels: &Vec<MarketOrder>, notional: f64) -> Option<f64> {
+    let mut remaining = notional;
+    let mut filled_amount = 0.0;
+
+    for level in levels.iter() {
+        let level_notional = level.price * level.amount;
+        if level_notional >= remaining {
+            filled_amount += remaining / level.price;
+            remaining = 0.0;
+            break;
+        }
+        filled_amount += level.amount;
+        remaining -= level_notional;
+    }
+
+    if remaining > 0.0 || filled_amount == 0.0 {
+        return None;
+    }
+
+    Some(notional / filled_amount)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::structs::{fixtures::market_book, market::Venue};
+
+    use super::*;
+
+    #[test]
+    fn computes_book_metrics() {
+        let book = market_book(
+            Venue::Openbook,
+            &[(100.5, 1.0), (101.0, 2.0), (102.0, 5.0)],
+            &[(99.5, 1.0), (99.0, 2.0), (98.0, 5.0)],
+        );
+
+        let metrics =
+            compute_book_metrics(&"sol-usdc".to_string(), &book, &vec![302.5], 7, 1_000).unwrap();
+        assert_eq!(metrics.mid, 100.0);
+        assert_eq!(metrics.spread, 1.0);
+        assert_eq!(metrics.spread_bps, 100.0);
+
+        // 10bps holds no level, 50bps the best levels, 100bps the best two
+        let depth: Vec<(u64, f64, f64)> = metrics
+            .depth
+            .iter()
+            .map(|x| (x.bps, x.bid_amount, x.ask_amount))
+            .collect();
+        assert_eq!(depth, vec![(10, 0.0, 0.0), (50, 1.0, 1.0), (100, 3.0, 3.0)]);
+
+        // Buying 302.5 takes 1 at 100.5 and 2 at 101
+        let execution = &metrics.executions[0];
+        assert_eq!(execution.buy_price, Some(302.5 / 3.0));
+        assert!((execution.buy_slippage_bps.unwrap() - 250.0 / 3.0).abs() < 1e-9);
+        // Selling 302.5 takes 1 at 99.5, 2 at 99 and the rest at 98
+        let sell_price = 302.5 / (3.0 + 5.0 / 98.0);
+        assert!((execution.sell_price.unwrap() - sell_price).abs() < 1e-9);
+        assert!((execution.sell_slippage_bps.unwrap() - (100.0 - sell_price) * 100.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn skips_metrics_of_one_sided_book() {
+        let book = market_book(Venue::Openbook, &[(100.5, 1.0)], &[]);
+        assert!(compute_book_metrics(&"sol-usdc".to_string(), &book, &vec![], 7, 1_000).is_none());
+    }
+
+    #[test]
+    fn execution_price_needs_enough_depth() {
+        let book = market_book(Venue::Openbook, &[(100.0, 1.0), (200.0, 1.0)], &[]);
+
+        assert_eq!(execution_price(&book.asks, 50.0), Some(100.0));
+        assert_eq!(execution_price(&book.asks, 300.0), Some(150.0));
+        assert_eq!(execution_price(&book.asks, 300.1), None);
+        assert_eq!(execution_price(&vec![], 1.0), None);
+    }
+}
@@ -0,0 +1,262 @@
+use redis::{Commands, ConnectionLike};
+use std::{
+    collections::HashMap,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    constants::CHANNEL_NAME,
+    processor::db::DbSink,
+    structs::{
+        arbitrage::{ArbOpportunity, ArbPublishData},
+        market::{MarketOrders, Venue, VenueMarket},
+    },
+    utils::generate_publish_data,
+};
+
+/*
+ * Function: check_arbitrage
+ * 1. Compare best ask of each venue against best bid of every other venue of the market after fees
+ * 2. If books cross and opportunity changed, publish arbitrage event to redis clients
+ * 3. Insert new opportunities into supabase for later analysis
+ */
+pub fn check_arbitrage(
+    market: &String,
+    venues: &Vec<VenueMarket>,
+    market_orders: &HashMap<String, MarketOrders>,
+    prev_opportunities: &mut HashMap<String, ArbOpportunity>,
+    redis_conn: &mut impl ConnectionLike,
+    supabase_client: &impl DbSink,
+    slot: u64,
+) -> anyhow::Result<()> {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    let mut opportunities: Vec<ArbOpportunity> = Vec::new();
+
+    for buy in venues.iter() {
+        for sell in venues.iter() {
+            if buy.venue == sell.venue {
+                continue;
+            }
+
+            let (buy_book, sell_book) = match (
+                market_orders.get(&buy.address),
+                market_orders.get(&sell.address),
+            ) {
+                (Some(buy_book), Some(sell_book)) => (buy_book, sell_book),
+                _ => continue,
+            };
+
+            let key = format!("{}:{}:{}", market, buy.venue.as_str(), sell.venue.as_str());
+            let opportunity = detect_arbitrage(
+                market,
+                (buy.venue, buy_book, buy.fee_rate_bps),
+                (sell.venue, sell_book, sell.fee_rate_bps),
+                slot,
+                now,
+            );
+
+            match opportunity {
+                Some(opportunity) => {
+                    // Skip if same crossing already emitted
+                    let is_same = prev_opportunities.get(&key).is_some_and(|x| {
+                        x.best_ask == opportunity.best_ask
+                            && x.best_bid == opportunity.best_bid
+                            && x.size == opportunity.size
+                    });
+                    if is_same {
+                        continue;
+                    }
+
+                    tracing::info!(
+                        "Arbitrage: {} - buy {} sell {}, {}, {}",
+                        market,
+                        buy.venue.as_str(),
+                        sell.venue.as_str(),
+                        opportunity.size,
+                        opportunity.edge_bps
+                    );
+
+                    redis_conn.publish(
+                        CHANNEL_NAME,
+                        generate_publish_data(
+                            market,
+                            &ArbPublishData {
+                                arbitrage: opportunity.clone(),
+                            },
+                            None,
+                        ),
+                    )?;
+
+                    prev_opportunities.insert(key, opportunity.clone());
+                    opportunities.push(opportunity);
+                }
+                None => {
+                    prev_opportunities.remove(&key);
+                }
+            }
+        }
+    }
+
+    // Insert opportunities into DB
+    if !opportunities.is_empty() {
+        supabase_client.store_arb_opportunities(opportunities);
+    }
+
+    Ok(())
+}
+
+/*
+ * Helper function for walk buy venue's asks against sell venue's bids after fees
+ * Returns crossed size and expected edge in quote units, None if books don't cross
+ */
+pub fn detect_arbitrage(
+    market: &String,
+    buy: (Venue, &MarketOrders, u64),
+    sell: (Venue, &MarketOrders, u64),
+    slot: u64,
+    timestamp: u64,
+) -> Option<ArbOpportunity> {
+    let (buy_venue, buy_book, buy_fee_bps) = buy;
+    let (sell_venue, sell_book, sell_fee_bps) = sell;
+    let buy_fee = 1.0 + buy_fee_bps as f64 / 10_000.0;
+    let sell_fee = 1.0 - sell_fee_bps as f64 / 10_000.0;
+
+    let best_ask = buy_book.asks.first()?.price;
+    let best_bid = sell_book.bids.first()?.price;
+
+    let mut size = 0.0;
+    let mut edge = 0.0;
+    let mut cost = 0.0;
+
+    let mut asks = buy_book.asks.iter().map(|x| (x.price, x.amount));
+    let mut bids = sell_book.bids.iter().map(|x| (x.price, x.amount));
+    let mut ask = asks.next();
+    let mut bid = bids.next();
+    while let (Some((ask_price, ask_amount)), Some((bid_price, bid_amount))) = (ask, bid) {
+        let ask_net = ask_price * buy_fee;
+        let bid_net = bid_price * sell_fee;
+        if bid_net <= ask_net {
+            break;
+        }
+
+        let qty = f64::min(ask_amount, bid_amount);
+        size += qty;
+        edge += qty * (bid_net - ask_net);
+        cost += qty * ask_net;
+
+        ask = if ask_amount > qty {
+            Some((ask_price, ask_amount - qty))
+        } else {
+            asks.next()
+        };
+        bid = if bid_amount > qty {
+            Some((bid_price, bid_amount - qty))
+        } else {
+            bids.next()
+        };
+    }
+
+    if size == 0.0 {
+        return None;
+    }
+
+    Some(ArbOpportunity {
+        slug: market.clone(),
+        buy_venue,
+        sell_venue,
+        best_ask,
+        best_bid,
+        size,
+        edge,
+        edge_bps: if cost > 0.0 { edge / cost * 10_000.0 } else { 0.0 },
+        slot,
+        timestamp,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::structs::fixtures::market_book;
+
+    use super::*;
+
+    #[test]
+    fn detects_crossed_books() {
+        let market = "sol-usdc".to_string();
+        let buy_book = market_book(Venue::Openbook, &[(100.0, 1.0), (101.0, 2.0)], &[]);
+        let sell_book = market_book(Venue::Gigadex, &[], &[(102.0, 1.5), (100.5, 5.0)]);
+
+        let opportunity = detect_arbitrage(
+            &market,
+            (Venue::Openbook, &buy_book, 0),
+            (Venue::Gigadex, &sell_book, 0),
+            7,
+            1_000,
+        )
+        .unwrap();
+
+        // 1 at 100 -> 102, 0.5 at 101 -> 102, then 101 ask doesn't cross 100.5 bid
+        assert_eq!(opportunity.buy_venue, Venue::Openbook);
+        assert_eq!(opportunity.sell_venue, Venue::Gigadex);
+        assert_eq!(opportunity.best_ask, 100.0);
+        assert_eq!(opportunity.best_bid, 102.0);
+        assert_eq!(opportunity.size, 1.5);
+        assert_eq!(opportunity.edge, 2.5);
+        assert!((opportunity.edge_bps - 2.5 / 150.5 * 10_000.0).abs() < 1e-9);
+        assert_eq!(opportunity.slot, 7);
+    }
+
+    #[test]
+    fn ignores_uncrossed_and_empty_books() {
+        let market = "sol-usdc".to_string();
+        let buy_book = market_book(Venue::Openbook, &[(100.0, 1.0)], &[]);
+        let sell_book = market_book(Venue::Gigadex, &[], &[(99.9, 1.0)]);
+        let empty_book = market_book(Venue::Gigadex, &[], &[]);
+
+        assert!(detect_arbitrage(
+            &market,
+            (Venue::Openbook, &buy_book, 0),
+            (Venue::Gigadex, &sell_book, 0),
+            7,
+            1_000,
+        )
+        .is_none());
+        assert!(detect_arbitrage(
+            &market,
+            (Venue::Openbook, &buy_book, 0),
+            (Venue::Gigadex, &empty_book, 0),
+            7,
+            1_000,
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn nets_fees_of_both_venues() {
+        let market = "sol-usdc".to_string();
+        let buy_book = market_book(Venue::Openbook, &[(100.0, 1.0)], &[]);
+        let sell_book = market_book(Venue::Phoenix, &[], &[(100.5, 1.0)]);
+
+        // 50bps gross edge survives 10bps fees on both sides
+        let opportunity = detect_arbitrage(
+            &market,
+            (Venue::Openbook, &buy_book, 10),
+            (Venue::Phoenix, &sell_book, 10),
+            7,
+            1_000,
+        )
+        .unwrap();
+        let expected_edge = 100.5 * 0.999 - 100.0 * 1.001;
+        assert!((opportunity.edge - expected_edge).abs() < 1e-9);
+
+        // 30bps fees on both sides eat the whole edge
+        assert!(detect_arbitrage(
+            &market,
+            (Venue::Openbook, &buy_book, 30),
+            (Venue::Phoenix, &sell_book, 30),
+            7,
+            1_000,
+        )
+        .is_none());
+    }
+}
@@ -0,0 +1,299 @@
+use postgrest::Postgrest;
+use std::{
+    cmp::Ordering,
+    collections::HashMap,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    constants::SNAPSHOT_INTERVAL_SECS,
+    processor::db::{get_orderbook_deltas, get_orderbook_snapshot, DbSink},
+    structs::{
+        archive::{OrderbookDelta, OrderbookSnapshot},
+        market::{MarketOrder, MarketOrders, VenueMarket},
+    },
+};
+
+/*
+ * Function: archive_orderbook
+ * 1. Collect levels of market's venue books tagged with their venue
+ * 2. Diff it with previous archived orderbook and build level deltas
+ * 3. Insert deltas into supabase
+ * 4. Insert full orderbook snapshot once per interval
+ */
+pub fn archive_orderbook(
+    market: &String,
+    venues: &Vec<VenueMarket>,
+    market_orders: &HashMap<String, MarketOrders>,
+    prev_books: &mut HashMap<String, MarketOrders>,
+    prev_snapshot_ts: &mut HashMap<String, u64>,
+    supabase_client: &impl DbSink,
+    slot: u64,
+    write_version: u64,
+) -> anyhow::Result<()> {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    let books: Vec<&MarketOrders> = venues
+        .iter()
+        .filter_map(|x| market_orders.get(&x.address))
+        .collect();
+    let market_state = collect_venue_levels(&books);
+    let empty_book = MarketOrders {
+        asks: vec![],
+        bids: vec![],
+    };
+    let prev_book = prev_books.get(market).unwrap_or(&empty_book);
+
+    let mut deltas: Vec<OrderbookDelta> = Vec::new();
+    for (is_bid, prev_levels, cur_levels) in [
+        (false, &prev_book.asks, &market_state.asks),
+        (true, &prev_book.bids, &market_state.bids),
+    ] {
+        for level in diff_levels(prev_levels, cur_levels) {
+            deltas.push(OrderbookDelta {
+                slug: market.clone(),
+                slot,
+                write_version,
+                timestamp: now,
+                is_bid,
+                venue: level.venue,
+                price_lots: level.price_lots,
+                size_lots: level.size_lots,
+                price: level.price,
+                amount: level.amount,
+            });
+        }
+    }
+
+    let is_due = prev_snapshot_ts
+        .get(market)
+        .map_or(true, |ts| now >= ts + SNAPSHOT_INTERVAL_SECS);
+    let snapshot = if is_due {
+        prev_snapshot_ts.insert(market.clone(), now);
+        Some(OrderbookSnapshot {
+            slug: market.clone(),
+            slot,
+            write_version,
+            timestamp: now,
+            order_book: market_state.clone(),
+        })
+    } else {
+        None
+    };
+    prev_books.insert(market.clone(), market_state);
+
+    if deltas.is_empty() && snapshot.is_none() {
+        return Ok(());
+    }
+
+    // Insert deltas and snapshot into DB
+    supabase_client.store_orderbook_archive(deltas, snapshot);
+
+    Ok(())
+}
+
+/*
+ * Function: reconstruct_orderbook
+ * 1. Load latest snapshot of market at or before slot/timestamp from supabase
+ * 2. Load deltas written after snapshot up to slot/timestamp
+ * 3. Apply deltas on snapshot and return orderbook at that point
+ */
+pub async fn reconstruct_orderbook(
+    supabase_client: &Postgrest,
+    market: &String,
+    slot: Option<u64>,
+    timestamp: Option<u64>,
+) -> anyhow::Result<Option<MarketOrders>> {
+    let snapshot = match get_orderbook_snapshot(supabase_client, market, slot, timestamp).await? {
+        Some(snapshot) => snapshot,
+        None => return Ok(None),
+    };
+
+    let deltas = get_orderbook_deltas(
+        supabase_client,
+        market,
+        snapshot.write_version,
+        slot,
+        timestamp,
+    )
+    .await?;
+
+    let mut order_book = snapshot.order_book;
+    apply_deltas(&mut order_book, &deltas);
+
+    Ok(Some(order_book))
+}
+
+/*
+ * Helper function for collect every level of venue books tagged with their venue, sorted by price
+ * Levels aren't merged across venues, so deltas stay keyed by venue and price lots
+ */
+pub fn collect_venue_levels(books: &Vec<&MarketOrders>) -> MarketOrders {
+    let mut asks: Vec<MarketOrder> = books.iter().flat_map(|x| x.asks.clone()).collect();
+    let mut bids: Vec<MarketOrder> = books.iter().flat_map(|x| x.bids.clone()).collect();
+
+    asks.sort_by(|a, b| a.price.partial_cmp(&b.price).unwrap_or(Ordering::Equal));
+    bids.sort_by(|a, b| b.price.partial_cmp(&a.price).unwrap_or(Ordering::Equal));
+
+    MarketOrders { asks, bids }
+}
+
+/*
+ * Helper function for build changed levels between previous and current levels
+ * Removed levels are returned with zero size
+ */
+pub fn diff_levels(
+    prev_levels: &Vec<MarketOrder>,
+    cur_levels: &Vec<MarketOrder>,
+) -> Vec<MarketOrder> {
+    let mut changed: Vec<MarketOrder> = cur_levels
+        .iter()
+        .filter(|x| !prev_levels.contains(x))
+        .cloned()
+        .collect();
+
+    prev_levels
+        .iter()
+        .filter(|x| {
+            !cur_levels
+                .iter()
+                .any(|y| y.venue == x.venue && y.price_lots == x.price_lots)
+        })
+        .for_each(|x| {
+            changed.push(MarketOrder {
+                size_lots: 0,
+                amount: 0.0,
+                ..x.clone()
+            })
+        });
+
+    changed
+}
+
+/*
+ * Helper function for apply archived deltas on orderbook in write order
+ */
+pub fn apply_deltas(order_book: &mut MarketOrders, deltas: &Vec<OrderbookDelta>) {
+    for delta in deltas.iter() {
+        let levels = if delta.is_bid {
+            &mut order_book.bids
+        } else {
+            &mut order_book.asks
+        };
+
+        levels.retain(|x| !(x.venue == delta.venue && x.price_lots == delta.price_lots));
+        if delta.size_lots > 0 {
+            levels.push(MarketOrder {
+                price: delta.price,
+                amount: delta.amount,
+                price_lots: delta.price_lots,
+                size_lots: delta.size_lots,
+                venue: delta.venue,
+            });
+        }
+    }
+
+    order_book
+        .asks
+        .sort_by(|a, b| a.price.partial_cmp(&b.price).unwrap_or(Ordering::Equal));
+    order_book
+        .bids
+        .sort_by(|a, b| b.price.partial_cmp(&a.price).unwrap_or(Ordering::Equal));
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::structs::{fixtures::market_book, market::Venue};
+
+    use super::*;
+
+    /*
+     * Helper function for build archive deltas between two books as archive_orderbook does
+     */
+    fn book_deltas(
+        prev: &MarketOrders,
+        cur: &MarketOrders,
+        write_version: u64,
+    ) -> Vec<OrderbookDelta> {
+        let mut deltas = Vec::new();
+        for (is_bid, prev_levels, cur_levels) in [
+            (false, &prev.asks, &cur.asks),
+            (true, &prev.bids, &cur.bids),
+        ] {
+            for level in diff_levels(prev_levels, cur_levels) {
+                deltas.push(OrderbookDelta {
+                    slug: "SOL-USDC".to_string(),
+                    slot: write_version,
+                    write_version,
+                    timestamp: 0,
+                    is_bid,
+                    venue: level.venue,
+                    price_lots: level.price_lots,
+                    size_lots: level.size_lots,
+                    price: level.price,
+                    amount: level.amount,
+                });
+            }
+        }
+        deltas
+    }
+
+    #[test]
+    fn snapshot_with_deltas_rebuilds_every_book() {
+        let ob =
+            |asks: &[(f64, f64)], bids: &[(f64, f64)]| market_book(Venue::Openbook, asks, bids);
+        let gd = |asks: &[(f64, f64)], bids: &[(f64, f64)]| market_book(Venue::Gigadex, asks, bids);
+        let books = vec![
+            [
+                ob(&[(101.0, 1.0), (102.0, 2.0)], &[(99.0, 1.0)]),
+                gd(&[(101.0, 3.0)], &[(98.0, 1.0)]),
+            ],
+            // Resize, add and remove levels on both venues, with one price shared by both
+            [
+                ob(&[(101.0, 4.0)], &[(99.0, 1.0), (97.0, 5.0)]),
+                gd(&[(101.0, 3.0), (103.0, 1.0)], &[]),
+            ],
+            // Empty one venue
+            [ob(&[], &[]), gd(&[(103.0, 2.0)], &[(98.5, 1.0)])],
+        ];
+        let merged: Vec<MarketOrders> = books
+            .iter()
+            .map(|x| collect_venue_levels(&x.iter().collect()))
+            .collect();
+
+        let mut deltas = Vec::new();
+        for (i, pair) in merged.windows(2).enumerate() {
+            deltas.extend(book_deltas(&pair[0], &pair[1], i as u64 + 1));
+        }
+
+        for (i, expected) in merged.iter().enumerate().skip(1) {
+            let mut order_book = merged[0].clone();
+            let applied: Vec<OrderbookDelta> = deltas
+                .iter()
+                .filter(|x| x.write_version <= i as u64)
+                .cloned()
+                .collect();
+            apply_deltas(&mut order_book, &applied);
+
+            // Levels of different venues at one price may swap, so compare per venue
+            for venue in [Venue::Openbook, Venue::Gigadex] {
+                let venue_levels = |levels: &Vec<MarketOrder>| -> Vec<MarketOrder> {
+                    levels
+                        .iter()
+                        .filter(|x| x.venue == venue)
+                        .cloned()
+                        .collect()
+                };
+                assert_eq!(venue_levels(&order_book.asks), venue_levels(&expected.asks));
+                assert_eq!(venue_levels(&order_book.bids), venue_levels(&expected.bids));
+            }
+            assert_eq!(order_book.asks.len(), expected.asks.len());
+            assert_eq!(order_book.bids.len(), expected.bids.len());
+        }
+    }
+
+    #[test]
+    fn unchanged_book_has_no_deltas() {
+        let book = market_book(Venue::Openbook, &[(101.0, 1.0)], &[(99.0, 1.0)]);
+        assert!(book_deltas(&book, &book, 1).is_empty());
+    }
+}
@@ -0,0 +1,120 @@
+use anchor_lang::AnchorDeserialize;
+use num_traits::{FromPrimitive, ToPrimitive};
+use redis::{Commands, ConnectionLike};
+use sqlx::types::Decimal;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{
+    constants::{AUM_KEY, AUM_USD_DECIMALS, CHANNEL_NAME},
+    processor::db::DbSink,
+    structs::{
+        aum::{AumAccount, AumData, AumPublishData},
+        decode::{check_anchor_discriminator, read_bytes},
+        geyser::Account,
+    },
+    utils::{generate_publish_data, token_factor},
+};
+
+/*
+ * Function: update_aum
+ * 1. Decode aum account update into usd value
+ * 2. If aum changed, update redis's aum and publish aum updates to redis clients
+ * 3. Insert aum record into supabase's aum time series
+ */
+pub fn update_aum(
+    account: &Account,
+    prev_aum: &mut Option<u128>,
+    redis_conn: &mut impl ConnectionLike,
+    supabase_client: &impl DbSink,
+) -> anyhow::Result<()> {
+    let aum_account = parse_aum_account(&account.data)?;
+    if *prev_aum == Some(aum_account.aum_usd) {
+        return Ok(());
+    }
+    *prev_aum = Some(aum_account.aum_usd);
+
+    let aum = AumData {
+        address: account.pubkey.to_string(),
+        aum_usd_raw: aum_account.aum_usd.to_string(),
+        aum_usd: Decimal::from_u128(aum_account.aum_usd)
+            .and_then(|x| x.checked_div(token_factor(AUM_USD_DECIMALS)))
+            .and_then(|x| Decimal::to_f64(&x))
+            .unwrap_or_default(),
+        slot: account.slot,
+        timestamp: SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+    };
+
+    redis_conn.set(AUM_KEY, serde_json::to_string(&aum)?)?;
+    redis_conn.publish(
+        CHANNEL_NAME,
+        generate_publish_data(AUM_KEY, &AumPublishData { aum: aum.clone() }, None),
+    )?;
+
+    // Insert aum into DB
+    supabase_client.store_aum(aum);
+
+    Ok(())
+}
+
+/*
+ * Helper function for decode aum pool account data after its anchor discriminator
+ */
+pub fn parse_aum_account(data: &Vec<u8>) -> anyhow::Result<AumAccount> {
+    check_anchor_discriminator(data, "Pool", "aum pool")?;
+    let mut data_bytes = read_bytes(data, "aum pool", 8, data.len().saturating_sub(8))?;
+    let aum_account: AumAccount = AnchorDeserialize::deserialize(&mut data_bytes)?;
+
+    Ok(aum_account)
+}
+
+/*
+ * Function: get_current_aum
+ * 1. Get latest published aum from redis's aum
+ */
+pub fn get_current_aum(redis_conn: &mut impl ConnectionLike) -> anyhow::Result<Option<AumData>> {
+    let data: Option<String> = redis_conn.get(AUM_KEY)?;
+    let aum = match data {
+        Some(data) => Some(serde_json::from_str::<AumData>(&data)?),
+        None => None,
+    };
+
+    Ok(aum)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structs::decode::anchor_discriminator;
+    use anchor_lang::{prelude::Pubkey, AnchorSerialize};
+
+    fn pool_data(account_name: &str, pool: &AumAccount) -> Vec<u8> {
+        let mut data = anchor_discriminator(account_name).to_vec();
+        data.extend(pool.try_to_vec().unwrap());
+        // Remaining pool fields after aum_usd
+        data.extend([7u8; 64]);
+        data
+    }
+
+    #[test]
+    fn decodes_aum_after_discriminator_and_pool_fields() {
+        let pool = AumAccount {
+            name: "Pool".to_string(),
+            custodies: vec![Pubkey::new_unique(), Pubkey::new_unique()],
+            aum_usd: 1_234_567_890_123,
+        };
+
+        assert_eq!(parse_aum_account(&pool_data("Pool", &pool)).unwrap(), pool);
+    }
+
+    #[test]
+    fn rejects_account_of_other_type() {
+        let pool = AumAccount {
+            name: "Pool".to_string(),
+            custodies: vec![],
+            aum_usd: 1,
+        };
+
+        assert!(parse_aum_account(&pool_data("Custody", &pool)).is_err());
+        assert!(parse_aum_account(&vec![0u8; 4]).is_err());
+    }
+}
@@ -6,19 +6,177 @@ use sqlx::types::Decimal;
 
 use crate::{
     constants::{SECONDS_PER_DAY, SECONDS_PER_HOUR, SECONDS_PER_MINUTE},
-    structs::market::{CandleData, EventData, MarketTrade},
+    structs::{
+        analytics::BookMetrics,
+        arbitrage::ArbOpportunity,
+        archive::{OrderbookDelta, OrderbookSnapshot},
+        aum::AumData,
+        gigadex::{GdBalanceEvent, GdUidWallet},
+        indicators::IndicatorData,
+        makers::MakerStats,
+        market::{CandleData, EventData, MarketTrade},
+        replay::MemoryDb,
+    },
 };
 
+/*
+ * Destination of rows written while processing geyser updates, supabase live or memory on replay
+ */
+pub trait DbSink {
+    fn store_events(&self, events: Vec<EventData>);
+    fn store_balance_events(&self, events: Vec<GdBalanceEvent>);
+    fn store_uid_wallets(&self, uid_wallets: Vec<GdUidWallet>);
+    fn store_book_metrics(&self, metrics: Vec<BookMetrics>);
+    fn store_orderbook_archive(
+        &self,
+        deltas: Vec<OrderbookDelta>,
+        snapshot: Option<OrderbookSnapshot>,
+    );
+    fn store_arb_opportunities(&self, opportunities: Vec<ArbOpportunity>);
+    fn store_maker_stats(&self, stats: Vec<MakerStats>);
+    fn store_aum(&self, aum: AumData);
+}
+
+impl DbSink for Postgrest {
+    fn store_events(&self, events: Vec<EventData>) {
+        tokio::spawn({
+            let supabase_clone = self.clone();
+
+            async move {
+                let _ = insert_events(supabase_clone, events).await;
+            }
+        });
+    }
+
+    fn store_balance_events(&self, events: Vec<GdBalanceEvent>) {
+        tokio::spawn({
+            let supabase_clone = self.clone();
+
+            async move {
+                let _ = insert_balance_events(supabase_clone, events).await;
+            }
+        });
+    }
+
+    fn store_uid_wallets(&self, uid_wallets: Vec<GdUidWallet>) {
+        tokio::spawn({
+            let supabase_clone = self.clone();
+
+            async move {
+                let _ = insert_uid_wallets(supabase_clone, uid_wallets).await;
+            }
+        });
+    }
+
+    fn store_book_metrics(&self, metrics: Vec<BookMetrics>) {
+        tokio::spawn({
+            let supabase_clone = self.clone();
+
+            async move {
+                let _ = insert_book_metrics(supabase_clone, metrics).await;
+            }
+        });
+    }
+
+    fn store_orderbook_archive(
+        &self,
+        deltas: Vec<OrderbookDelta>,
+        snapshot: Option<OrderbookSnapshot>,
+    ) {
+        tokio::spawn({
+            let supabase_clone = self.clone();
+
+            async move {
+                if !deltas.is_empty() {
+                    let _ = insert_orderbook_deltas(supabase_clone.clone(), deltas).await;
+                }
+                if let Some(snapshot) = snapshot {
+                    let _ = insert_orderbook_snapshot(supabase_clone, snapshot).await;
+                }
+            }
+        });
+    }
+
+    fn store_arb_opportunities(&self, opportunities: Vec<ArbOpportunity>) {
+        tokio::spawn({
+            let supabase_clone = self.clone();
+
+            async move {
+                let _ = insert_arb_opportunities(supabase_clone, opportunities).await;
+            }
+        });
+    }
+
+    fn store_maker_stats(&self, stats: Vec<MakerStats>) {
+        tokio::spawn({
+            let supabase_clone = self.clone();
+
+            async move {
+                let _ = insert_maker_stats(supabase_clone, stats).await;
+            }
+        });
+    }
+
+    fn store_aum(&self, aum: AumData) {
+        tokio::spawn({
+            let supabase_clone = self.clone();
+
+            async move {
+                let _ = insert_aum(supabase_clone, aum).await;
+            }
+        });
+    }
+}
+
+impl DbSink for MemoryDb {
+    fn store_events(&self, events: Vec<EventData>) {
+        self.events.lock().unwrap().extend(events);
+    }
+
+    fn store_balance_events(&self, events: Vec<GdBalanceEvent>) {
+        self.balance_events.lock().unwrap().extend(events);
+    }
+
+    fn store_uid_wallets(&self, uid_wallets: Vec<GdUidWallet>) {
+        self.uid_wallets.lock().unwrap().extend(uid_wallets);
+    }
+
+    fn store_book_metrics(&self, metrics: Vec<BookMetrics>) {
+        self.book_metrics.lock().unwrap().extend(metrics);
+    }
+
+    fn store_orderbook_archive(
+        &self,
+        deltas: Vec<OrderbookDelta>,
+        snapshot: Option<OrderbookSnapshot>,
+    ) {
+        self.orderbook_deltas.lock().unwrap().extend(deltas);
+        self.orderbook_snapshots.lock().unwrap().extend(snapshot);
+    }
+
+    fn store_arb_opportunities(&self, opportunities: Vec<ArbOpportunity>) {
+        self.arb_opportunities.lock().unwrap().extend(opportunities);
+    }
+
+    fn store_maker_stats(&self, stats: Vec<MakerStats>) {
+        self.maker_stats.lock().unwrap().extend(stats);
+    }
+
+    fn store_aum(&self, aum: AumData) {
+        self.aums.lock().unwrap().push(aum);
+    }
+}
+
 /*
  * Function: insert_candles
  * 1. Build candle data based on unit and trades data
- * 2. Insert candle records into supabase
+ * 2. Insert candle records into supabase and return them
  */
 pub async fn insert_candles(
     supabase_client: Postgrest,
     trades: Vec<MarketTrade>,
     unit: &str,
-) -> anyhow::Result<()> {
+) -> anyhow::Result<Vec<CandleData>> {
     let market_slug = trades.first().unwrap().slug.clone();
     let blocktime = trades.first().unwrap().blocktime;
 
@@ -90,6 +248,9 @@ pub async fn insert_candles(
                 end_ts,
                 unit: unit.to_string(),
                 slug: trade.slug,
+                close_usd: trade.price_usd,
+                volume_usd: trade.notional_usd,
+                quote_usd_rate: trade.quote_usd_rate,
             };
             candle_set.insert(begin_ts, candle);
         } else {
@@ -98,6 +259,12 @@ pub async fn insert_candles(
             candle.high = f64::max(candle.high, price);
             candle.low = f64::min(candle.low, price);
             candle.close = price;
+            candle.close_usd = trade.price_usd;
+            candle.volume_usd = match (candle.volume_usd, trade.notional_usd) {
+                (Some(volume), Some(notional)) => Some(volume + notional),
+                (volume, notional) => volume.or(notional),
+            };
+            candle.quote_usd_rate = trade.quote_usd_rate;
         }
     }
 
@@ -110,7 +277,7 @@ pub async fn insert_candles(
         .execute()
         .await?;
 
-    Ok(())
+    Ok(candles_data)
 }
 
 /*
@@ -146,3 +313,305 @@ pub async fn insert_events(
 
     Ok(())
 }
+
+/*
+ * Function: insert_arb_opportunities
+ * 1. Insert cross-venue arbitrage opportunities into supabase
+ */
+pub async fn insert_arb_opportunities(
+    supabase_client: Postgrest,
+    opportunities: Vec<ArbOpportunity>,
+) -> anyhow::Result<()> {
+    supabase_client
+        .from("tb_arb_opportunities")
+        .insert(serde_json::to_string(&opportunities).unwrap())
+        .execute()
+        .await?;
+
+    Ok(())
+}
+
+/*
+ * Function: insert_book_metrics
+ * 1. Insert orderbook analytics metrics into supabase
+ */
+pub async fn insert_book_metrics(
+    supabase_client: Postgrest,
+    metrics: Vec<BookMetrics>,
+) -> anyhow::Result<()> {
+    supabase_client
+        .from("tb_book_metrics")
+        .insert(serde_json::to_string(&metrics).unwrap())
+        .execute()
+        .await?;
+
+    Ok(())
+}
+
+/*
+ * Function: insert_orderbook_snapshot
+ * 1. Insert full orderbook snapshot into supabase
+ */
+pub async fn insert_orderbook_snapshot(
+    supabase_client: Postgrest,
+    snapshot: OrderbookSnapshot,
+) -> anyhow::Result<()> {
+    supabase_client
+        .from("tb_orderbook_snapshots")
+        .insert(serde_json::to_string(&snapshot).unwrap())
+        .execute()
+        .await?;
+
+    Ok(())
+}
+
+/*
+ * Function: insert_orderbook_deltas
+ * 1. Insert orderbook level deltas into supabase
+ */
+pub async fn insert_orderbook_deltas(
+    supabase_client: Postgrest,
+    deltas: Vec<OrderbookDelta>,
+) -> anyhow::Result<()> {
+    supabase_client
+        .from("tb_orderbook_deltas")
+        .insert(serde_json::to_string(&deltas).unwrap())
+        .execute()
+        .await?;
+
+    Ok(())
+}
+
+/*
+ * Function: get_orderbook_snapshot
+ * 1. Get latest orderbook snapshot of market at or before slot/timestamp from supabase
+ */
+pub async fn get_orderbook_snapshot(
+    supabase_client: &Postgrest,
+    market: &String,
+    slot: Option<u64>,
+    timestamp: Option<u64>,
+) -> anyhow::Result<Option<OrderbookSnapshot>> {
+    let mut query = supabase_client
+        .from("tb_orderbook_snapshots")
+        .select("*")
+        .eq("slug", market);
+    if let Some(slot) = slot {
+        query = query.lte("slot", slot.to_string());
+    }
+    if let Some(timestamp) = timestamp {
+        query = query.lte("timestamp", timestamp.to_string());
+    }
+
+    let data = query
+        .order("write_version.desc")
+        .limit(1)
+        .execute()
+        .await?
+        .text()
+        .await?;
+    let snapshots = serde_json::from_str::<Vec<OrderbookSnapshot>>(&data)?;
+
+    Ok(snapshots.into_iter().next())
+}
+
+/*
+ * Function: get_orderbook_deltas
+ * 1. Get orderbook deltas of market written after write_version up to slot/timestamp from supabase
+ */
+pub async fn get_orderbook_deltas(
+    supabase_client: &Postgrest,
+    market: &String,
+    write_version: u64,
+    slot: Option<u64>,
+    timestamp: Option<u64>,
+) -> anyhow::Result<Vec<OrderbookDelta>> {
+    let mut query = supabase_client
+        .from("tb_orderbook_deltas")
+        .select("*")
+        .eq("slug", market)
+        .gt("write_version", write_version.to_string());
+    if let Some(slot) = slot {
+        query = query.lte("slot", slot.to_string());
+    }
+    if let Some(timestamp) = timestamp {
+        query = query.lte("timestamp", timestamp.to_string());
+    }
+
+    let data = query
+        .order("write_version.asc")
+        .execute()
+        .await?
+        .text()
+        .await?;
+    let deltas = serde_json::from_str::<Vec<OrderbookDelta>>(&data)?;
+
+    Ok(deltas)
+}
+
+/*
+ * Function: insert_balance_events
+ * 1. Insert gigadex balance transitions into supabase
+ */
+pub async fn insert_balance_events(
+    supabase_client: Postgrest,
+    events: Vec<GdBalanceEvent>,
+) -> anyhow::Result<()> {
+    supabase_client
+        .from("tb_balance_history")
+        .insert(serde_json::to_string(&events).unwrap())
+        .execute()
+        .await?;
+
+    Ok(())
+}
+
+/*
+ * Function: get_balance_history
+ * 1. Get latest balance transitions of uid in market from supabase
+ */
+pub async fn get_balance_history(
+    supabase_client: &Postgrest,
+    market: &String,
+    uid: u64,
+    limit: usize,
+) -> anyhow::Result<Vec<GdBalanceEvent>> {
+    let data = supabase_client
+        .from("tb_balance_history")
+        .select("*")
+        .eq("market", market)
+        .eq("uid", uid.to_string())
+        .order("slot.desc")
+        .limit(limit)
+        .execute()
+        .await?
+        .text()
+        .await?;
+    let events = serde_json::from_str::<Vec<GdBalanceEvent>>(&data)?;
+
+    Ok(events)
+}
+
+/*
+ * Function: insert_uid_wallets
+ * 1. Insert or update gigadex uid to wallet mapping into supabase
+ */
+pub async fn insert_uid_wallets(
+    supabase_client: Postgrest,
+    uid_wallets: Vec<GdUidWallet>,
+) -> anyhow::Result<()> {
+    supabase_client
+        .from("tb_uid_wallets")
+        .upsert(serde_json::to_string(&uid_wallets).unwrap())
+        .on_conflict("market, uid")
+        .execute()
+        .await?;
+
+    Ok(())
+}
+
+/*
+ * Function: insert_maker_stats
+ * 1. Insert or update daily maker stats into supabase
+ */
+pub async fn insert_maker_stats(
+    supabase_client: Postgrest,
+    stats: Vec<MakerStats>,
+) -> anyhow::Result<()> {
+    supabase_client
+        .from("tb_maker_stats")
+        .insert(serde_json::to_string(&stats).unwrap())
+        .on_conflict("day_ts, slug, maker")
+        .execute()
+        .await?;
+
+    Ok(())
+}
+
+/*
+ * Function: insert_indicators
+ * 1. Insert or update candle indicator records into supabase
+ */
+pub async fn insert_indicators(
+    supabase_client: Postgrest,
+    indicators: Vec<IndicatorData>,
+) -> anyhow::Result<()> {
+    if indicators.is_empty() {
+        return Ok(());
+    }
+
+    supabase_client
+        .from("tb_market_indicators")
+        .insert(serde_json::to_string(&indicators).unwrap())
+        .on_conflict("slug, begin_ts, unit")
+        .execute()
+        .await?;
+
+    Ok(())
+}
+
+/*
+ * Function: get_indicators
+ * 1. Get indicator history of market and unit between timestamps from supabase
+ */
+pub async fn get_indicators(
+    supabase_client: &Postgrest,
+    market: &String,
+    unit: &str,
+    from_ts: u64,
+    to_ts: u64,
+) -> anyhow::Result<Vec<IndicatorData>> {
+    let data = supabase_client
+        .from("tb_market_indicators")
+        .select("*")
+        .eq("slug", market)
+        .eq("unit", unit)
+        .gte("begin_ts", from_ts.to_string())
+        .lte("begin_ts", to_ts.to_string())
+        .order("begin_ts.asc")
+        .execute()
+        .await?
+        .text()
+        .await?;
+    let indicators = serde_json::from_str::<Vec<IndicatorData>>(&data)?;
+
+    Ok(indicators)
+}
+
+/*
+ * Function: insert_aum
+ * 1. Insert aum record into supabase
+ */
+pub async fn insert_aum(supabase_client: Postgrest, aum: AumData) -> anyhow::Result<()> {
+    supabase_client
+        .from("tb_aum")
+        .insert(serde_json::to_string(&vec![aum]).unwrap())
+        .execute()
+        .await?;
+
+    Ok(())
+}
+
+/*
+ * Function: get_aum_history
+ * 1. Get aum records between timestamps from supabase
+ */
+pub async fn get_aum_history(
+    supabase_client: &Postgrest,
+    from_ts: u64,
+    to_ts: u64,
+) -> anyhow::Result<Vec<AumData>> {
+    let data = supabase_client
+        .from("tb_aum")
+        .select("*")
+        .gte("timestamp", from_ts.to_string())
+        .lte("timestamp", to_ts.to_string())
+        .order("timestamp.asc")
+        .execute()
+        .await?
+        .text()
+        .await?;
+    let aum = serde_json::from_str::<Vec<AumData>>(&data)?;
+
+    Ok(aum)
+}
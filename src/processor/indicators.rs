@@ -0,0 +1,349 @@
+use postgrest::Postgrest;
+use redis::{Client, Commands, Connection};
+
+use crate::{
+    constants::{CHANNEL_NAME, INDICATOR_CONFIG_KEY},
+    processor::db::insert_indicators,
+    structs::{
+        indicators::{
+            BollingerBand, CandlePublishData, IndicatorConfig, IndicatorData, IndicatorState,
+        },
+        market::CandleData,
+    },
+    utils::generate_publish_data,
+};
+
+/*
+ * Function: update_indicators
+ * 1. Load indicator state of market and unit from redis's indicators
+ * 2. Close previous candle into state once a newer candle begins
+ * 3. Calculate sma/ema/rsi/bollinger of state with the open candle
+ * 4. Update redis's indicators in a WATCH/MULTI transaction, recalculating when a concurrent trades task updated it first
+ * 5. Publish candle updates with indicators to redis clients and insert indicator records into supabase
+ */
+pub async fn update_indicators(
+    redis_client: Client,
+    supabase_client: Postgrest,
+    mut candles: Vec<CandleData>,
+) -> anyhow::Result<()> {
+    let first_candle = match candles.first() {
+        Some(candle) => candle.clone(),
+        None => return Ok(()),
+    };
+    let mut redis_conn = redis_client.get_connection()?;
+    let config = load_indicator_config(&mut redis_conn)?;
+    let state_key = format!("indicators:{}:{}", first_candle.slug, first_candle.unit);
+    candles.sort_by_key(|x| x.begin_ts);
+
+    let updates: Vec<CandlePublishData> =
+        redis::transaction(&mut redis_conn, &[&state_key], |conn, pipe| {
+            let data: Option<String> = conn.get(&state_key)?;
+            let mut state = data
+                .and_then(|x| serde_json::from_str::<IndicatorState>(&x).ok())
+                .unwrap_or_default();
+            let updates = apply_candles(&mut state, &config, &candles);
+
+            let committed: Option<()> = pipe
+                .set(
+                    &state_key,
+                    serde_json::to_string(&state).unwrap_or_default(),
+                )
+                .ignore()
+                .query(conn)?;
+            Ok(committed.map(|_| updates))
+        })?;
+
+    let mut indicators_to_insert: Vec<IndicatorData> = Vec::new();
+    for update in updates.into_iter() {
+        redis_conn.publish(
+            CHANNEL_NAME,
+            generate_publish_data(&update.candle.slug, &update, None),
+        )?;
+        indicators_to_insert.push(update.indicators);
+    }
+    insert_indicators(supabase_client, indicators_to_insert).await?;
+
+    Ok(())
+}
+
+/*
+ * Helper function for apply candles sorted by begin_ts onto indicator state
+ * Returns candles with their indicators, skipping candles older than the open candle
+ */
+pub fn apply_candles(
+    state: &mut IndicatorState,
+    config: &IndicatorConfig,
+    candles: &Vec<CandleData>,
+) -> Vec<CandlePublishData> {
+    let mut updates: Vec<CandlePublishData> = Vec::new();
+    for candle in candles.iter() {
+        if candle.begin_ts < state.begin_ts {
+            continue;
+        }
+        if state.begin_ts > 0 && candle.begin_ts > state.begin_ts {
+            let close = state.close;
+            close_candle(state, config, close);
+        }
+        state.begin_ts = candle.begin_ts;
+        state.close = candle.close;
+
+        updates.push(CandlePublishData {
+            candle: candle.clone(),
+            indicators: calculate_indicators(state, config, candle),
+        });
+    }
+
+    updates
+}
+
+/*
+ * Helper function for load indicator periods from redis or default
+ */
+pub fn load_indicator_config(redis_conn: &mut Connection) -> anyhow::Result<IndicatorConfig> {
+    let data: Option<String> = redis_conn.get(INDICATOR_CONFIG_KEY)?;
+    let config = match data {
+        Some(data) => match serde_json::from_str::<IndicatorConfig>(&data) {
+            Ok(config) => config,
+            Err(e) => {
+                tracing::error!("Error parsing indicator config: {}", e);
+                IndicatorConfig::default()
+            }
+        },
+        None => IndicatorConfig::default(),
+    };
+
+    Ok(config)
+}
+
+/*
+ * Helper function for fold closed candle's close price into indicator state
+ */
+pub fn close_candle(state: &mut IndicatorState, config: &IndicatorConfig, close: f64) {
+    for period in config.ema_periods.iter() {
+        let ema = next_ema(state.emas.get(period).cloned(), *period, close);
+        state.emas.insert(*period, ema);
+    }
+
+    if let Some(prev_close) = state.prev_close {
+        let (avg_gain, avg_loss) = next_rsi_avgs(state, config.rsi_period, close - prev_close);
+        state.avg_gain = Some(avg_gain);
+        state.avg_loss = Some(avg_loss);
+    }
+    state.prev_close = Some(close);
+
+    let max_period = config
+        .sma_periods
+        .iter()
+        .cloned()
+        .chain([config.bollinger_period])
+        .max()
+        .unwrap_or_default();
+    state.closes.push_back(close);
+    while state.closes.len() > max_period {
+        state.closes.pop_front();
+    }
+}
+
+/*
+ * Helper function for calculate indicators of state with the open candle's close
+ */
+pub fn calculate_indicators(
+    state: &IndicatorState,
+    config: &IndicatorConfig,
+    candle: &CandleData,
+) -> IndicatorData {
+    let close = candle.close;
+    let window = |period: usize| -> Option<Vec<f64>> {
+        if period == 0 || state.closes.len() + 1 < period {
+            return None;
+        }
+        let mut values: Vec<f64> = state
+            .closes
+            .iter()
+            .skip(state.closes.len() + 1 - period)
+            .cloned()
+            .collect();
+        values.push(close);
+        Some(values)
+    };
+
+    let sma = config
+        .sma_periods
+        .iter()
+        .filter_map(|period| window(*period).map(|x| (*period, mean(&x))))
+        .collect();
+    let ema = config
+        .ema_periods
+        .iter()
+        .map(|period| {
+            (
+                *period,
+                next_ema(state.emas.get(period).cloned(), *period, close),
+            )
+        })
+        .collect();
+    let rsi = state.prev_close.map(|prev_close| {
+        let (avg_gain, avg_loss) = next_rsi_avgs(state, config.rsi_period, close - prev_close);
+        if avg_loss == 0.0 {
+            100.0
+        } else {
+            100.0 - 100.0 / (1.0 + avg_gain / avg_loss)
+        }
+    });
+    let bollinger = window(config.bollinger_period).map(|x| {
+        let middle = mean(&x);
+        let variance = x.iter().map(|v| (v - middle).powi(2)).sum::<f64>() / x.len() as f64;
+        let offset = config.bollinger_std_dev * variance.sqrt();
+        BollingerBand {
+            upper: middle + offset,
+            middle,
+            lower: middle - offset,
+        }
+    });
+
+    IndicatorData {
+        slug: candle.slug.clone(),
+        unit: candle.unit.clone(),
+        begin_ts: candle.begin_ts,
+        close,
+        sma,
+        ema,
+        rsi,
+        bollinger,
+    }
+}
+
+/*
+ * Helper function for ema seeded with the first close
+ */
+fn next_ema(prev_ema: Option<f64>, period: usize, close: f64) -> f64 {
+    match prev_ema {
+        Some(ema) => {
+            let alpha = 2.0 / (period as f64 + 1.0);
+            alpha * close + (1.0 - alpha) * ema
+        }
+        None => close,
+    }
+}
+
+/*
+ * Helper function for wilder smoothed average gain/loss seeded with the first change
+ */
+fn next_rsi_avgs(state: &IndicatorState, period: usize, change: f64) -> (f64, f64) {
+    let gain = f64::max(change, 0.0);
+    let loss = f64::max(-change, 0.0);
+    let period = period.max(1) as f64;
+
+    match (state.avg_gain, state.avg_loss) {
+        (Some(avg_gain), Some(avg_loss)) => (
+            (avg_gain * (period - 1.0) + gain) / period,
+            (avg_loss * (period - 1.0) + loss) / period,
+        ),
+        _ => (gain, loss),
+    }
+}
+
+fn mean(values: &Vec<f64>) -> f64 {
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candle(begin_ts: u64, close: f64) -> CandleData {
+        CandleData {
+            open: close,
+            high: close,
+            low: close,
+            close,
+            amount: 1.0,
+            begin_ts,
+            end_ts: begin_ts + 60,
+            unit: "1m".to_string(),
+            slug: "SOL-USDC".to_string(),
+            close_usd: None,
+            volume_usd: None,
+            quote_usd_rate: None,
+        }
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-6,
+            "expected {}, got {}",
+            expected,
+            actual
+        );
+    }
+
+    fn config() -> IndicatorConfig {
+        IndicatorConfig {
+            sma_periods: vec![3],
+            ema_periods: vec![3],
+            rsi_period: 3,
+            bollinger_period: 3,
+            bollinger_std_dev: 2.0,
+        }
+    }
+
+    #[test]
+    fn calculates_golden_indicators_of_closes() {
+        let mut state = IndicatorState::default();
+        let candles = vec![
+            candle(60, 10.0),
+            candle(120, 11.0),
+            candle(180, 12.0),
+            candle(240, 11.0),
+        ];
+        let updates = apply_candles(&mut state, &config(), &candles);
+        let indicators: Vec<&IndicatorData> = updates.iter().map(|x| &x.indicators).collect();
+
+        // Not enough closes for sma/bollinger, ema seeded with the first close
+        assert!(indicators[0].sma.is_empty());
+        assert_close(indicators[0].ema[&3], 10.0);
+        assert_eq!(indicators[0].rsi, None);
+        assert_eq!(indicators[0].bollinger, None);
+
+        assert_close(indicators[1].ema[&3], 10.5);
+        assert_close(indicators[1].rsi.unwrap(), 100.0);
+
+        assert_close(indicators[2].sma[&3], 11.0);
+        assert_close(indicators[2].ema[&3], 11.25);
+        let bollinger = indicators[2].bollinger.clone().unwrap();
+        assert_close(bollinger.middle, 11.0);
+        assert_close(bollinger.upper, 12.632993);
+        assert_close(bollinger.lower, 9.367007);
+
+        assert_close(indicators[3].sma[&3], 11.333333);
+        assert_close(indicators[3].ema[&3], 11.125);
+        assert_close(indicators[3].rsi.unwrap(), 66.666667);
+        let bollinger = indicators[3].bollinger.clone().unwrap();
+        assert_close(bollinger.upper, 12.276142);
+        assert_close(bollinger.lower, 10.390524);
+    }
+
+    #[test]
+    fn open_candle_updates_do_not_close_it() {
+        let mut state = IndicatorState::default();
+        apply_candles(
+            &mut state,
+            &config(),
+            &vec![candle(60, 10.0), candle(120, 11.0)],
+        );
+        let closed = state.clone();
+
+        let updates = apply_candles(
+            &mut state,
+            &config(),
+            &vec![candle(60, 9.0), candle(120, 13.0)],
+        );
+
+        // Stale candle is skipped and the open candle only moves its close
+        assert_eq!(updates.len(), 1);
+        assert_eq!(state.closes, closed.closes);
+        assert_eq!(state.emas, closed.emas);
+        assert_close(state.close, 13.0);
+        assert_close(updates[0].indicators.ema[&3], 11.5);
+    }
+}
@@ -0,0 +1,405 @@
+use num_traits::ToPrimitive;
+use redis::{Commands, ConnectionLike};
+use sqlx::types::Decimal;
+use std::{
+    cmp::Ordering,
+    collections::HashMap,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    constants::{CHANNEL_NAME, LEDGER_MARK_INTERVAL_SECS},
+    structs::{
+        ledger::{LedgerPublishData, TraderPosition},
+        market::{MarketOrders, MarketSendData, MarketTrade},
+    },
+    utils::{generate_publish_data, generate_publish_uid_data},
+};
+
+/*
+ * Function: update_ledger
+ * 1. Load positions of trades' makers, and takers once attributed to their wallet, from redis's ledger
+ * 2. Apply fills on positions and calculate realized pnl
+ * 3. Mark positions to market's mid price and calculate unrealized pnl
+ * 4. Update redis's ledger and publish position updates per trader
+ */
+pub fn update_ledger(
+    redis_conn: &mut impl ConnectionLike,
+    trades: &Vec<MarketTrade>,
+) -> anyhow::Result<()> {
+    let first_trade = match trades.first() {
+        Some(trade) => trade,
+        None => return Ok(()),
+    };
+    let market_slug = first_trade.slug.clone();
+    let ledger_key = format!("ledger:{}", market_slug);
+
+    let mut positions: HashMap<String, TraderPosition> = HashMap::new();
+    for trade in trades.iter() {
+        // Maker takes the opposite side of taker
+        let is_taker_buy = trade.is_taker_buy;
+        let sides = [
+            (trade.maker.as_ref(), !is_taker_buy),
+            (trade.taker.as_ref(), is_taker_buy),
+        ];
+
+        for (trader, is_buy) in sides {
+            let trader = match trader {
+                Some(trader) => trader,
+                None => continue,
+            };
+
+            if !positions.contains_key(trader) {
+                let data: Option<String> = redis_conn.hget(&ledger_key, trader)?;
+                let position = data
+                    .and_then(|x| serde_json::from_str::<TraderPosition>(&x).ok())
+                    .unwrap_or(TraderPosition {
+                        trader: trader.clone(),
+                        market: market_slug.clone(),
+                        ..Default::default()
+                    });
+                positions.insert(trader.clone(), position);
+            }
+
+            let position = positions.get_mut(trader).unwrap();
+            apply_fill(
+                position,
+                is_buy,
+                Decimal::to_f64(&trade.avg_price).unwrap_or_default(),
+                Decimal::to_f64(&trade.amount).unwrap_or_default(),
+            );
+            position.slot = trade.slot;
+            position.timestamp = trade.timestamp;
+        }
+    }
+
+    if positions.is_empty() {
+        return Ok(());
+    }
+
+    let last_price = Decimal::to_f64(&trades.last().unwrap().avg_price).unwrap_or_default();
+    let mark_price = get_mid_price(redis_conn, &market_slug).unwrap_or(last_price);
+
+    for position in positions.values_mut() {
+        mark_position(position, mark_price);
+        publish_position(redis_conn, &ledger_key, position)?;
+    }
+
+    Ok(())
+}
+
+/*
+ * Function: mark_ledger
+ * 1. Mark every open position of market to consolidated mid price once per interval
+ * 2. Update redis's ledger and publish position updates of re-marked traders
+ */
+pub fn mark_ledger(
+    market: &String,
+    order_book: &MarketOrders,
+    marked_ts: &mut HashMap<String, u64>,
+    redis_conn: &mut impl ConnectionLike,
+) -> anyhow::Result<()> {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    if marked_ts
+        .get(market)
+        .is_some_and(|ts| now < ts + LEDGER_MARK_INTERVAL_SECS)
+    {
+        return Ok(());
+    }
+
+    let mark_price = match (order_book.asks.first(), order_book.bids.first()) {
+        (Some(best_ask), Some(best_bid)) => (best_ask.price + best_bid.price) / 2.0,
+        _ => return Ok(()),
+    };
+    marked_ts.insert(market.clone(), now);
+
+    let ledger_key = format!("ledger:{}", market);
+    let ledger: HashMap<String, String> = redis_conn.hgetall(&ledger_key)?;
+    for data in ledger.values() {
+        let mut position = match serde_json::from_str::<TraderPosition>(data) {
+            Ok(position) => position,
+            Err(_) => continue,
+        };
+        if position.position == 0.0 || position.mark_price == mark_price {
+            continue;
+        }
+
+        mark_position(&mut position, mark_price);
+        publish_position(redis_conn, &ledger_key, &position)?;
+    }
+
+    Ok(())
+}
+
+/*
+ * Helper function for mark position to price and calculate unrealized pnl
+ */
+pub fn mark_position(position: &mut TraderPosition, mark_price: f64) {
+    position.mark_price = mark_price;
+    position.unrealized_pnl = position.position * (mark_price - position.avg_entry_price);
+}
+
+/*
+ * Helper function for update redis's ledger and publish position update of trader
+ * Gigadex traders are uids, openbook traders and attributed takers are wallet addresses
+ */
+fn publish_position(
+    redis_conn: &mut impl ConnectionLike,
+    ledger_key: &String,
+    position: &TraderPosition,
+) -> anyhow::Result<()> {
+    redis_conn.hset(
+        ledger_key,
+        &position.trader,
+        serde_json::to_string(position)?,
+    )?;
+
+    let publish_data = LedgerPublishData {
+        ledger: position.clone(),
+    };
+    let msg = match position.trader.parse::<u64>() {
+        Ok(uid) => {
+            let owner: Option<String> =
+                redis_conn.hget(format!("uid_wallets:{}", position.market), uid)?;
+            generate_publish_uid_data(&position.market, &publish_data, uid, owner)
+        }
+        Err(_) => generate_publish_data(
+            &position.market,
+            &publish_data,
+            Some(position.trader.clone()),
+        ),
+    };
+    redis_conn.publish(CHANNEL_NAME, msg)?;
+
+    Ok(())
+}
+
+/*
+ * Helper function for apply fill on position with average entry price
+ * Closing part of the fill is realized against average entry price
+ */
+pub fn apply_fill(position: &mut TraderPosition, is_buy: bool, price: f64, amount: f64) {
+    let delta = if is_buy { amount } else { -amount };
+    position.volume += amount * price;
+
+    if position.position == 0.0 || position.position.signum() == delta.signum() {
+        let size = position.position.abs();
+        position.avg_entry_price =
+            (size * position.avg_entry_price + amount * price) / (size + amount);
+        position.position += delta;
+        return;
+    }
+
+    let closing = f64::min(amount, position.position.abs());
+    position.realized_pnl +=
+        closing * (price - position.avg_entry_price) * position.position.signum();
+    position.position += delta;
+
+    if position.position == 0.0 {
+        position.avg_entry_price = 0.0;
+    } else if position.position.signum() == delta.signum() {
+        // Position flipped, remaining size is entered at fill price
+        position.avg_entry_price = price;
+    }
+}
+
+/*
+ * Helper function for get mid price from redis's consolidated orderbook
+ */
+pub fn get_mid_price(redis_conn: &mut impl ConnectionLike, market: &String) -> Option<f64> {
+    let data: String = redis_conn
+        .get(format!("compressed_orderbook:{}", market))
+        .ok()?;
+    let send_data = serde_json::from_str::<MarketSendData>(&data).ok()?;
+    let best_ask = send_data.order_book.asks.first()?.price;
+    let best_bid = send_data.order_book.bids.first()?.price;
+
+    Some((best_ask + best_bid) / 2.0)
+}
+
+/*
+ * Function: get_ledger_positions
+ * 1. Load positions of market's ledger, or only trader's position if set
+ * 2. Order positions by total pnl
+ */
+pub fn get_ledger_positions(
+    redis_conn: &mut impl ConnectionLike,
+    market: &String,
+    trader: Option<&String>,
+) -> anyhow::Result<Vec<TraderPosition>> {
+    let ledger_key = format!("ledger:{}", market);
+    let data: Vec<String> = match trader {
+        Some(trader) => {
+            let data: Option<String> = redis_conn.hget(&ledger_key, trader)?;
+            data.into_iter().collect()
+        }
+        None => {
+            let data: HashMap<String, String> = redis_conn.hgetall(&ledger_key)?;
+            data.into_values().collect()
+        }
+    };
+
+    let mut positions: Vec<TraderPosition> = data
+        .iter()
+        .map(|x| serde_json::from_str::<TraderPosition>(x))
+        .collect::<Result<_, _>>()?;
+    positions.sort_by(|a, b| {
+        (b.realized_pnl + b.unrealized_pnl)
+            .partial_cmp(&(a.realized_pnl + a.unrealized_pnl))
+            .unwrap_or(Ordering::Equal)
+    });
+
+    Ok(positions)
+}
+
+#[cfg(test)]
+mod tests {
+    use openbook_dex::matching::Side;
+    use std::collections::HashSet;
+
+    use crate::{
+        parser::{build_maker_trades, parse_openbook_account},
+        structs::{
+            adapter::DexEvent,
+            fixtures::{account, gd_market, market_book, ob_event_queue, ob_fill_event, ob_market},
+            gigadex::{FilledOrder, GdMarketOrderLog, GdPendingLog},
+            market::Venue,
+            replay::MemoryRedis,
+        },
+        utils::array_to_pubkey,
+    };
+
+    use super::*;
+
+    fn position(redis: &MemoryRedis, market: &str, trader: &str) -> TraderPosition {
+        let data = &redis.hashes[&format!("ledger:{}", market)][trader];
+        serde_json::from_str(data).unwrap()
+    }
+
+    #[test]
+    fn openbook_maker_bid_fill_is_long() {
+        let market = ob_market("sol-usdc");
+        // Maker bid bought 2 base for 100 quote
+        let data = ob_event_queue(
+            &[ob_fill_event(
+                Side::Bid,
+                true,
+                99_980_000,
+                2_000_000_000,
+                20_000,
+                7,
+                [1; 4],
+            )],
+            0,
+            4,
+        );
+        let events = parse_openbook_account(
+            &market,
+            &mut account(&market.event_queue, 10, data),
+            &mut HashSet::new(),
+        )
+        .unwrap();
+        let mut trades = match events.as_slice() {
+            [DexEvent::Trades(trades)] => trades.clone(),
+            _ => panic!("expected one trades event"),
+        };
+        trades[0].taker = Some("taker-wallet".to_string());
+
+        let mut redis = MemoryRedis::default();
+        update_ledger(&mut redis, &trades).unwrap();
+
+        let maker = array_to_pubkey([1; 4]).to_string();
+        assert_eq!(position(&redis, "sol-usdc", &maker).position, 2.0);
+        assert_eq!(position(&redis, "sol-usdc", "taker-wallet").position, -2.0);
+    }
+
+    #[test]
+    fn gigadex_maker_fill_of_market_buy_is_short() {
+        let market = gd_market("sol-usdc");
+        let log = GdPendingLog {
+            order: GdMarketOrderLog {
+                amount: 2_000_000_000,
+                total_value_lamports: 100_000_000,
+                counter: 1,
+            },
+            market_buy: true,
+            slot: 10,
+            txn_signature: String::new(),
+        };
+        let fills = vec![FilledOrder {
+            price: 50_000,
+            amount: 2_000_000_000,
+            uid: 3,
+        }];
+        let trades = build_maker_trades(&market, &log, &fills, 0);
+
+        let mut redis = MemoryRedis::default();
+        update_ledger(&mut redis, &trades).unwrap();
+
+        let maker = position(&redis, "sol-usdc", "3");
+        assert_eq!(maker.position, -2.0);
+        assert_eq!(maker.avg_entry_price, 50.0);
+    }
+
+    #[test]
+    fn realizes_closing_part_of_fill() {
+        let mut position = TraderPosition::default();
+        apply_fill(&mut position, true, 100.0, 2.0);
+        apply_fill(&mut position, true, 110.0, 2.0);
+        assert_eq!(position.avg_entry_price, 105.0);
+
+        // Sell 5 closes 4 at 120 and flips 1 short entered at 120
+        apply_fill(&mut position, false, 120.0, 5.0);
+        assert_eq!(position.realized_pnl, 60.0);
+        assert_eq!(position.position, -1.0);
+        assert_eq!(position.avg_entry_price, 120.0);
+    }
+
+    #[test]
+    fn marks_open_positions_to_mid() {
+        let mut redis = MemoryRedis::default();
+        for (trader, size) in [("1", 2.0), ("2", 0.0)] {
+            let position = TraderPosition {
+                trader: trader.to_string(),
+                market: "sol-usdc".to_string(),
+                position: size,
+                avg_entry_price: 100.0,
+                ..Default::default()
+            };
+            redis
+                .hset::<_, _, _, ()>(
+                    "ledger:sol-usdc",
+                    trader,
+                    serde_json::to_string(&position).unwrap(),
+                )
+                .unwrap();
+        }
+
+        let order_book = market_book(Venue::Openbook, &[(111.0, 1.0)], &[(109.0, 1.0)]);
+        let mut marked_ts = HashMap::new();
+        mark_ledger(
+            &"sol-usdc".to_string(),
+            &order_book,
+            &mut marked_ts,
+            &mut redis,
+        )
+        .unwrap();
+
+        let marked = position(&redis, "sol-usdc", "1");
+        assert_eq!(marked.mark_price, 110.0);
+        assert_eq!(marked.unrealized_pnl, 20.0);
+        assert_eq!(position(&redis, "sol-usdc", "2").mark_price, 0.0);
+        assert_eq!(redis.published.len(), 1);
+
+        // Within interval, positions aren't re-marked
+        let order_book = market_book(Venue::Openbook, &[(121.0, 1.0)], &[(119.0, 1.0)]);
+        mark_ledger(
+            &"sol-usdc".to_string(),
+            &order_book,
+            &mut marked_ts,
+            &mut redis,
+        )
+        .unwrap();
+        assert_eq!(position(&redis, "sol-usdc", "1").mark_price, 110.0);
+    }
+}
@@ -0,0 +1,325 @@
+use num_traits::ToPrimitive;
+use redis::{Commands, ConnectionLike};
+use sqlx::types::Decimal;
+use std::{
+    cmp::Ordering,
+    collections::HashMap,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    constants::{MAKER_QUOTE_BPS, MAKER_STATS_INTERVAL_SECS, SECONDS_PER_DAY},
+    processor::db::DbSink,
+    structs::{
+        makers::{MakerDayAccums, MakerQuotes, MakerStats, MakerTracker},
+        market::{MarketOrders, MarketTrade, VenueMarket},
+    },
+};
+
+/*
+ * Function: update_maker_stats
+ * 1. Attribute previous quoting state of market's makers for elapsed time
+ * 2. Sample every maker's quoted size within bps of mid on each side across venues
+ */
+pub fn update_maker_stats(
+    market: &String,
+    venues: &Vec<VenueMarket>,
+    maker_quotes: &HashMap<String, MakerQuotes>,
+    market_state: &MarketOrders,
+    tracker: &mut MakerTracker,
+) -> anyhow::Result<()> {
+    let now_ms = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as u64;
+
+    // Attribute previous quotes for elapsed time
+    if let Some(prev_ms) = tracker.sampled_ms.get(market) {
+        let elapsed = now_ms.saturating_sub(*prev_ms) as f64 / 1000.0;
+        let day_ts = (prev_ms / 1000 / SECONDS_PER_DAY) * SECONDS_PER_DAY;
+
+        *tracker
+            .observed_secs
+            .entry((day_ts, market.clone()))
+            .or_default() += elapsed;
+        for (maker, (bid_size, ask_size)) in tracker.quoted.get(market).into_iter().flatten() {
+            let accum = tracker
+                .accums
+                .entry((day_ts, market.clone(), maker.clone()))
+                .or_default();
+            accum.quoted_size_secs += (bid_size + ask_size) * elapsed;
+            if *bid_size > 0.0 {
+                accum.bid_secs += elapsed;
+            }
+            if *ask_size > 0.0 {
+                accum.ask_secs += elapsed;
+            }
+            if *bid_size > 0.0 && *ask_size > 0.0 {
+                accum.two_sided_secs += elapsed;
+            }
+        }
+    }
+
+    // Sample current quotes within bps of mid
+    let mut quoted: HashMap<String, (f64, f64)> = HashMap::new();
+    if let (Some(best_ask), Some(best_bid)) = (market_state.asks.first(), market_state.bids.first())
+    {
+        let mid = (best_ask.price + best_bid.price) / 2.0;
+        let range = mid * (MAKER_QUOTE_BPS as f64) / 10_000.0;
+
+        for quotes in venues.iter().filter_map(|x| maker_quotes.get(&x.address)) {
+            for quote in quotes.bids.iter().filter(|x| x.price >= mid - range) {
+                quoted.entry(quote.maker.clone()).or_default().0 += quote.amount;
+            }
+            for quote in quotes.asks.iter().filter(|x| x.price <= mid + range) {
+                quoted.entry(quote.maker.clone()).or_default().1 += quote.amount;
+            }
+        }
+    }
+
+    tracker.quoted.insert(market.clone(), quoted);
+    tracker.sampled_ms.insert(market.clone(), now_ms);
+
+    Ok(())
+}
+
+/*
+ * Function: flush_maker_stats
+ * 1. Build daily maker stats with uptime, average quoted size and fill share once per interval
+ * 2. Update redis's maker_leaderboard ordered by maker volume
+ * 3. Persist raw accumulators of each day so a restart resumes them instead of overwriting the day row
+ * 4. Insert or update daily stats into supabase and drop finished days
+ */
+pub fn flush_maker_stats(
+    tracker: &mut MakerTracker,
+    redis_conn: &mut impl ConnectionLike,
+    supabase_client: &impl DbSink,
+) -> anyhow::Result<()> {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    if now < tracker.flushed_ts + MAKER_STATS_INTERVAL_SECS {
+        return Ok(());
+    }
+    tracker.flushed_ts = now;
+    let cur_day_ts = (now / SECONDS_PER_DAY) * SECONDS_PER_DAY;
+
+    let mut rows: Vec<MakerStats> = Vec::new();
+    for ((day_ts, market), observed_secs) in tracker.observed_secs.iter() {
+        let volumes: HashMap<String, f64> =
+            redis_conn.hgetall(format!("maker_volume:{}:{}", market, day_ts))?;
+        let total_volume: f64 = volumes.values().sum();
+
+        let mut makers: Vec<String> = volumes.keys().cloned().collect();
+        for (accum_day_ts, accum_market, maker) in tracker.accums.keys() {
+            if accum_day_ts == day_ts && accum_market == market && !makers.contains(maker) {
+                makers.push(maker.clone());
+            }
+        }
+
+        let mut leaderboard: Vec<MakerStats> = makers
+            .into_iter()
+            .map(|maker| {
+                let accum = tracker
+                    .accums
+                    .get(&(*day_ts, market.clone(), maker.clone()))
+                    .cloned()
+                    .unwrap_or_default();
+                let maker_volume = volumes.get(&maker).cloned().unwrap_or_default();
+                let ratio = |secs: f64| {
+                    if *observed_secs > 0.0 {
+                        secs / observed_secs
+                    } else {
+                        0.0
+                    }
+                };
+
+                MakerStats {
+                    day_ts: *day_ts,
+                    slug: market.clone(),
+                    maker,
+                    quoted_size: ratio(accum.quoted_size_secs),
+                    bid_uptime: ratio(accum.bid_secs),
+                    ask_uptime: ratio(accum.ask_secs),
+                    two_sided_uptime: ratio(accum.two_sided_secs),
+                    maker_volume,
+                    fill_share: if total_volume > 0.0 {
+                        maker_volume / total_volume
+                    } else {
+                        0.0
+                    },
+                }
+            })
+            .collect();
+        leaderboard.sort_by(|a, b| {
+            b.maker_volume
+                .partial_cmp(&a.maker_volume)
+                .unwrap_or(Ordering::Equal)
+        });
+
+        redis_conn.set(
+            format!("maker_leaderboard:{}:{}", market, day_ts),
+            serde_json::to_string(&leaderboard)?,
+        )?;
+        rows.extend(leaderboard);
+    }
+    persist_maker_accums(tracker, redis_conn)?;
+
+    // Drop finished days after final flush
+    tracker
+        .observed_secs
+        .retain(|(day_ts, _), _| *day_ts >= cur_day_ts);
+    tracker
+        .accums
+        .retain(|(day_ts, _, _), _| *day_ts >= cur_day_ts);
+
+    // Insert stats into DB
+    if !rows.is_empty() {
+        supabase_client.store_maker_stats(rows);
+    }
+
+    Ok(())
+}
+
+/*
+ * Helper function for persisting raw accumulators of every tracked day and market
+ */
+fn persist_maker_accums(
+    tracker: &MakerTracker,
+    redis_conn: &mut impl ConnectionLike,
+) -> anyhow::Result<()> {
+    for ((day_ts, market), observed_secs) in tracker.observed_secs.iter() {
+        let day_accums = MakerDayAccums {
+            observed_secs: *observed_secs,
+            accums: tracker
+                .accums
+                .iter()
+                .filter(|((accum_day_ts, accum_market, _), _)| {
+                    accum_day_ts == day_ts && accum_market == market
+                })
+                .map(|((_, _, maker), accum)| (maker.clone(), *accum))
+                .collect(),
+        };
+        redis_conn.set(
+            format!("maker_accums:{}:{}", market, day_ts),
+            serde_json::to_string(&day_accums)?,
+        )?;
+    }
+
+    Ok(())
+}
+
+/*
+ * Function: load_maker_accums
+ * 1. Load today's persisted accumulators of markets into tracker, so restarts keep accumulating the day
+ */
+pub fn load_maker_accums<'a>(
+    tracker: &mut MakerTracker,
+    markets: impl Iterator<Item = &'a String>,
+    redis_conn: &mut impl ConnectionLike,
+) -> anyhow::Result<()> {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    let day_ts = (now / SECONDS_PER_DAY) * SECONDS_PER_DAY;
+
+    for market in markets {
+        let saved: Option<String> =
+            redis_conn.get(format!("maker_accums:{}:{}", market, day_ts))?;
+        let day_accums: MakerDayAccums = match saved {
+            Some(saved) => serde_json::from_str(&saved)?,
+            None => continue,
+        };
+
+        tracker
+            .observed_secs
+            .insert((day_ts, market.clone()), day_accums.observed_secs);
+        for (maker, accum) in day_accums.accums {
+            tracker
+                .accums
+                .insert((day_ts, market.clone(), maker), accum);
+        }
+    }
+
+    Ok(())
+}
+
+/*
+ * Function: update_maker_volume
+ * 1. Increase redis's daily maker_volume of trades' makers with trade notional
+ */
+pub fn update_maker_volume(
+    redis_conn: &mut impl ConnectionLike,
+    trades: &Vec<MarketTrade>,
+) -> anyhow::Result<()> {
+    for trade in trades.iter() {
+        let maker = match &trade.maker {
+            Some(maker) => maker,
+            None => continue,
+        };
+
+        let day_ts = (trade.timestamp / SECONDS_PER_DAY) * SECONDS_PER_DAY;
+        let notional = Decimal::to_f64(&(trade.avg_price * trade.amount)).unwrap_or_default();
+        redis_conn.hincr(
+            format!("maker_volume:{}:{}", trade.slug, day_ts),
+            maker,
+            notional,
+        )?;
+    }
+
+    Ok(())
+}
+
+/*
+ * Function: get_maker_leaderboard
+ * 1. Load market's maker leaderboard of day from redis, ordered by maker volume when flushed
+ */
+pub fn get_maker_leaderboard(
+    redis_conn: &mut impl ConnectionLike,
+    market: &String,
+    day_ts: u64,
+) -> anyhow::Result<Vec<MakerStats>> {
+    let data: Option<String> =
+        redis_conn.get(format!("maker_leaderboard:{}:{}", market, day_ts))?;
+    let leaderboard = match data {
+        Some(data) => serde_json::from_str::<Vec<MakerStats>>(&data)?,
+        None => vec![],
+    };
+
+    Ok(leaderboard)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structs::{makers::MakerAccum, replay::MemoryRedis};
+
+    #[test]
+    fn restart_resumes_persisted_day_accumulators() {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let day_ts = (now / SECONDS_PER_DAY) * SECONDS_PER_DAY;
+        let market = "SOL-USDC".to_string();
+        let accum = MakerAccum {
+            quoted_size_secs: 1200.0,
+            bid_secs: 60.0,
+            ask_secs: 30.0,
+            two_sided_secs: 30.0,
+        };
+
+        let mut tracker = MakerTracker::default();
+        tracker.observed_secs.insert((day_ts, market.clone()), 90.0);
+        tracker
+            .accums
+            .insert((day_ts, market.clone(), "maker".to_string()), accum);
+        let mut redis = MemoryRedis::default();
+        persist_maker_accums(&tracker, &mut redis).unwrap();
+
+        let mut restarted = MakerTracker::default();
+        load_maker_accums(&mut restarted, [market.clone()].iter(), &mut redis).unwrap();
+
+        assert_eq!(
+            restarted.observed_secs.get(&(day_ts, market.clone())),
+            Some(&90.0)
+        );
+        assert_eq!(
+            restarted.accums.get(&(day_ts, market, "maker".to_string())),
+            Some(&accum)
+        );
+    }
+}
@@ -1,38 +1,65 @@
-use std::num::NonZeroUsize;
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, HashMap},
+    num::NonZeroUsize,
+};
 
 use num_traits::ToPrimitive;
 use postgrest::Postgrest;
-use redis::{Client, Commands, Connection, RedisError};
+use redis::{Client, Commands, ConnectionLike, RedisError};
 use sqlx::types::Decimal;
 
 use crate::{
     api::get_summary,
-    constants::{CHANNEL_NAME, PRICES_KEY, SUMMARY_KEY},
+    constants::{
+        CHANNEL_NAME, CONSOLIDATED_LOT_DECIMALS, CONSOLIDATED_ORDER_DEPTH, PRICES_KEY,
+        SOL_ORACLE_KEY, SUMMARY_KEY,
+    },
     insert_candles, insert_trades,
-    structs::market::{
-        LastTradeData, MarketOrders, MarketPricesData, MarketSendData, MarketTrade, PriceData,
-        SummaryPublishData, TradeData, TradePublishData, TradesPublishData,
+    processor::{
+        alerts::evaluate_book_alerts,
+        indicators::update_indicators,
+        ledger::update_ledger,
+        makers::update_maker_volume,
+        oracle::{apply_usd_rate, get_quote_usd_rate},
+    },
+    structs::{
+        alerts::AlertRulesCache,
+        market::{
+            LastTradeData, MarketOrder, MarketOrders, MarketPricesData, MarketSendData,
+            MarketTrade, PriceData, SummaryPublishData, TradeData, TradePublishData,
+            TradesPublishData, Venue, VenueMarket,
+        },
+        oracle::OraclePrice,
     },
     utils::generate_publish_data,
 };
 
 /*
  * Function: update_trades
- * 1. Update redis's last_trade_data with provided trades
+ * 1. Convert trades into usd with market's quote rate
+ *    Update redis's last_trade_data with provided trades
  * 2. Extend redis's recent_trades with current trades
  * 3. Publish trade updates to redis clients
- * 4. Insert trades data into supabase's trade table
- * 5. Publish price updates using gigadexV2 api
- * 6. Insert candle data based on trade data
+ * 4. Update makers' positions and pnl in redis's ledger and makers' daily volume
+ *    Insert trades data into supabase's trade table
+ * 5. Publish price updates using gigadexV2 api, with sol price from oracle when subscribed
+ *    Summary is skipped when the api fails
+ * 6. Insert candle data based on trade data and publish candles with their indicators
  */
 pub async fn update_trades(
     api_url: String,
     redis_client: Client,
     supabase_client: Postgrest,
-    trades: Vec<MarketTrade>,
+    mut trades: Vec<MarketTrade>,
 ) -> anyhow::Result<()> {
     let mut redis_conn = redis_client.get_connection().unwrap();
 
+    // Convert trades into usd with market's quote rate
+    let quote_usd_rate =
+        get_quote_usd_rate(&mut redis_conn, &trades.first().unwrap().slug).unwrap_or_default();
+    apply_usd_rate(&mut trades, quote_usd_rate);
+
     let first_trade = trades.first().unwrap();
     let market_slug = first_trade.slug.clone();
     let market_address = first_trade.market_address.clone();
@@ -96,6 +123,9 @@ pub async fn update_trades(
             amount_lots: Decimal::to_f64(&x.amount_lots).unwrap_or_default(),
             market_buy: x.market_buy == 1,
             timestamp: x.timestamp,
+            price_usd: x.price_usd,
+            signature: x.transaction_signature.clone(),
+            taker: x.taker.clone(),
         })
         .collect();
     redis_conn.publish(
@@ -109,19 +139,50 @@ pub async fn update_trades(
         ),
     )?;
 
+    // Update makers' ledger and daily volume, whether or not the trade insert succeeds
+    if let Err(e) = update_ledger(&mut redis_conn, &trades) {
+        tracing::error!("Failed to update ledger of {}: {:?}", market_slug, e);
+    }
+    if let Err(e) = update_maker_volume(&mut redis_conn, &trades) {
+        tracing::error!("Failed to update maker volume of {}: {:?}", market_slug, e);
+    }
+
     // Insert trade record
     insert_trades(supabase_client.clone(), trades.clone()).await?;
 
-    // Publish summary data
-    let summary = get_summary(&api_url, &market_slug).await.unwrap();
-    redis_conn.set(
-        format!("{}:{}", SUMMARY_KEY, market_slug),
-        &serde_json::to_string(&SummaryPublishData { summary }).unwrap(),
-    )?;
-    redis_conn.publish(
-        CHANNEL_NAME,
-        generate_publish_data(&market_slug, &SummaryPublishData { summary }, None),
-    )?;
+    // Publish summary data with sol price from oracle
+    let summary = match get_summary(&api_url, &market_slug)
+        .await
+        .map_err(|e| e.to_string())
+    {
+        Ok(mut summary) => {
+            let sol_oracle: Option<String> =
+                redis_conn.get(format!("oracle_price:{}", SOL_ORACLE_KEY))?;
+            if let Some(oracle_price) =
+                sol_oracle.and_then(|x| serde_json::from_str::<OraclePrice>(&x).ok())
+            {
+                summary.sol_price = oracle_price.price;
+            }
+            if let Some(rate) = quote_usd_rate {
+                summary.price_usd = Some(summary.price * rate);
+                summary.volume_24h_usd = Some(summary.volume_24h * rate);
+                summary.quote_usd_rate = Some(rate);
+            }
+            redis_conn.set(
+                format!("{}:{}", SUMMARY_KEY, market_slug),
+                &serde_json::to_string(&SummaryPublishData { summary }).unwrap(),
+            )?;
+            redis_conn.publish(
+                CHANNEL_NAME,
+                generate_publish_data(&market_slug, &SummaryPublishData { summary }, None),
+            )?;
+            Some(summary)
+        }
+        Err(e) => {
+            tracing::error!("Failed to get summary of {}: {}", market_slug, e);
+            None
+        }
+    };
 
     // Publish price data
     let prices_str: String = redis_conn.get(PRICES_KEY)?;
@@ -130,7 +191,11 @@ pub async fn update_trades(
         Some(market_price) => {
             market_price.price = last_trade.price;
             market_price.market_buy = last_trade.market_buy;
-            market_price.change_24h = summary.change_24h;
+            if let Some(summary) = summary {
+                market_price.change_24h = summary.change_24h;
+            }
+            market_price.price_usd = quote_usd_rate.map(|x| last_trade.price * x);
+            market_price.quote_usd_rate = quote_usd_rate;
         }
         None => {
             prices_data.market_prices.insert(
@@ -138,7 +203,9 @@ pub async fn update_trades(
                 PriceData {
                     price: last_trade.price,
                     market_buy: last_trade.market_buy,
-                    change_24h: summary.change_24h,
+                    change_24h: summary.map(|x| x.change_24h).unwrap_or_default(),
+                    price_usd: quote_usd_rate.map(|x| last_trade.price * x),
+                    quote_usd_rate,
                 },
             );
         }
@@ -151,14 +218,19 @@ pub async fn update_trades(
         generate_publish_data("general", &prices_data, None),
     )?;
 
-    // Insert candles
+    // Insert candles and update their indicators
     for unit in ["1m", "15m", "4h", "1d"] {
         tokio::spawn({
+            let redis_clone = redis_client.clone();
             let supabase_clone = supabase_client.clone();
             let trades_clone = trades.clone();
 
             async move {
-                let _ = insert_candles(supabase_clone, trades_clone, unit).await;
+                if let Ok(candles) =
+                    insert_candles(supabase_clone.clone(), trades_clone, unit).await
+                {
+                    let _ = update_indicators(redis_clone, supabase_clone, candles).await;
+                }
             }
         });
     }
@@ -166,16 +238,61 @@ pub async fn update_trades(
     Ok(())
 }
 
+/*
+ * Function: publish_trades_data
+ * 1. Update redis's per-venue compressed_orderbook with provided market state
+ * 2. Publish per-venue orderbook updates to redis clients
+ * 3. Evaluate book alert rules on the venue's orderbook
+ */
 pub fn publish_trades_data(
     market: &String,
+    venue: Venue,
     market_state: &MarketOrders,
-    redis_conn: &mut Connection,
+    redis_conn: &mut impl ConnectionLike,
     slot: u64,
+    alert_rules: &mut AlertRulesCache,
 ) -> anyhow::Result<()> {
     let send_data = MarketSendData {
         order_book: market_state.clone(),
         slot,
     };
+    let venue_market = format!("{}:{}", market, venue.as_str());
+
+    redis_conn.set(
+        format!("compressed_orderbook:{}", venue_market),
+        serde_json::to_string(&send_data)?,
+    )?;
+
+    let publish_string = generate_publish_data(&venue_market, &send_data, None);
+    redis_conn.publish(CHANNEL_NAME, publish_string)?;
+
+    // Match spread/empty side alert rules
+    evaluate_book_alerts(market, venue, market_state, redis_conn, slot, alert_rules)?;
+
+    Ok(())
+}
+
+/*
+ * Function: publish_consolidated_data
+ * 1. Merge every venue's orderbook of the market into one consolidated book
+ * 2. Update redis's compressed_orderbook and publish consolidated updates to redis clients
+ * Returns published consolidated book for analytics, so metrics match the published levels
+ */
+pub fn publish_consolidated_data(
+    market: &String,
+    venues: &Vec<VenueMarket>,
+    market_orders: &HashMap<String, MarketOrders>,
+    redis_conn: &mut impl ConnectionLike,
+    slot: u64,
+) -> anyhow::Result<MarketOrders> {
+    let books: Vec<&MarketOrders> = venues
+        .iter()
+        .filter_map(|x| market_orders.get(&x.address))
+        .collect();
+    let send_data = MarketSendData {
+        order_book: consolidate_orders(&books, CONSOLIDATED_ORDER_DEPTH),
+        slot,
+    };
 
     redis_conn.set(
         format!("compressed_orderbook:{}", market),
@@ -185,5 +302,146 @@ pub fn publish_trades_data(
     let publish_string = generate_publish_data(&market, &send_data, None);
     redis_conn.publish(CHANNEL_NAME, publish_string)?;
 
-    Ok(())
+    Ok(send_data.order_book)
+}
+
+/*
+ * Helper function for merge venue orderbooks into one book in depth
+ * Levels of every venue at the same price are merged into one level
+ */
+pub fn consolidate_orders(books: &Vec<&MarketOrders>, depth: usize) -> MarketOrders {
+    MarketOrders {
+        asks: merge_levels(books.iter().flat_map(|x| x.asks.iter()), false, depth),
+        bids: merge_levels(books.iter().flat_map(|x| x.bids.iter()), true, depth),
+    }
+}
+
+/*
+ * Helper function for merge levels of equal price into consolidated levels in depth
+ * Venue lots differ per venue, so lots are rescaled into fixed point of readable price/amount
+ * of CONSOLIDATED_LOT_DECIMALS, which also keys levels of equal price
+ * Merged level is tagged with the venue quoting most of its amount
+ */
+fn merge_levels<'a>(
+    levels: impl Iterator<Item = &'a MarketOrder>,
+    is_bid: bool,
+    depth: usize,
+) -> Vec<MarketOrder> {
+    let lot_factor = 10f64.powi(CONSOLIDATED_LOT_DECIMALS as i32);
+    let mut price_levels: BTreeMap<u64, Vec<&MarketOrder>> = BTreeMap::new();
+    for level in levels {
+        let price_lots = (level.price * lot_factor).round() as u64;
+        price_levels.entry(price_lots).or_default().push(level);
+    }
+
+    let merged = price_levels.into_iter().map(|(price_lots, levels)| {
+        let amount: f64 = levels.iter().map(|x| x.amount).sum();
+        let top_level = levels
+            .iter()
+            .max_by(|a, b| a.amount.partial_cmp(&b.amount).unwrap_or(Ordering::Equal))
+            .unwrap();
+
+        MarketOrder {
+            price: top_level.price,
+            amount,
+            price_lots,
+            size_lots: (amount * lot_factor).round() as u64,
+            venue: top_level.venue,
+        }
+    });
+
+    if is_bid {
+        merged.rev().take(depth).collect()
+    } else {
+        merged.take(depth).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::structs::{fixtures::market_book, replay::MemoryRedis};
+
+    use super::*;
+
+    #[test]
+    fn consolidates_equal_prices_across_venues() {
+        // Same price converted from different venue lots may differ in float rounding
+        let ob = market_book(
+            Venue::Openbook,
+            &[(100.0, 1.0), (101.0, 2.0)],
+            &[(99.0, 1.0)],
+        );
+        let gd = market_book(
+            Venue::Gigadex,
+            &[(100.0 + 1e-12, 3.0), (100.5, 1.0)],
+            &[(99.0, 0.5), (98.0, 1.0)],
+        );
+
+        let order_book = consolidate_orders(&vec![&ob, &gd], 2);
+        let levels = |levels: &Vec<MarketOrder>| -> Vec<(u64, u64, f64, Venue)> {
+            levels
+                .iter()
+                .map(|x| (x.price_lots, x.size_lots, x.amount, x.venue))
+                .collect()
+        };
+        assert_eq!(
+            levels(&order_book.asks),
+            vec![
+                (100_000_000_000, 4_000_000_000, 4.0, Venue::Gigadex),
+                (100_500_000_000, 1_000_000_000, 1.0, Venue::Gigadex),
+            ]
+        );
+        assert_eq!(
+            levels(&order_book.bids),
+            vec![
+                (99_000_000_000, 1_500_000_000, 1.5, Venue::Openbook),
+                (98_000_000_000, 1_000_000_000, 1.0, Venue::Gigadex),
+            ]
+        );
+    }
+
+    #[test]
+    fn consolidated_book_is_cut_to_depth() {
+        let asks: Vec<(f64, f64)> = (0..30).map(|i| (100.0 + i as f64, 1.0)).collect();
+        let mut market_orders = HashMap::new();
+        market_orders.insert(
+            "ob".to_string(),
+            market_book(Venue::Openbook, &asks[..15], &[(99.0, 1.0)]),
+        );
+        market_orders.insert(
+            "gd".to_string(),
+            market_book(Venue::Gigadex, &asks[15..], &[(98.0, 1.0)]),
+        );
+        let venues = vec![
+            VenueMarket {
+                venue: Venue::Openbook,
+                address: "ob".to_string(),
+                fee_rate_bps: 0,
+            },
+            VenueMarket {
+                venue: Venue::Gigadex,
+                address: "gd".to_string(),
+                fee_rate_bps: 0,
+            },
+        ];
+
+        let mut redis = MemoryRedis::default();
+        let order_book = publish_consolidated_data(
+            &"sol-usdc".to_string(),
+            &venues,
+            &market_orders,
+            &mut redis,
+            7,
+        )
+        .unwrap();
+
+        // Metrics are computed on the same bounded book as published
+        assert_eq!(order_book.asks.len(), CONSOLIDATED_ORDER_DEPTH);
+        assert_eq!(order_book.bids.len(), 2);
+        assert_eq!(order_book.asks[CONSOLIDATED_ORDER_DEPTH - 1].price, 119.0);
+
+        let stored: MarketSendData =
+            serde_json::from_str(&redis.strings["compressed_orderbook:sol-usdc"]).unwrap();
+        assert_eq!(stored.order_book, order_book);
+    }
 }
@@ -0,0 +1,440 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::{net::TcpListener, sync::mpsc};
+use tokio_stream::wrappers::{ReceiverStream, TcpListenerStream};
+use yellowstone_grpc_client::GeyserGrpcClient;
+use yellowstone_grpc_proto::{
+    prelude::{
+        geyser_server::{Geyser, GeyserServer},
+        GetBlockHeightRequest, GetBlockHeightResponse, GetLatestBlockhashRequest,
+        GetLatestBlockhashResponse, GetSlotRequest, GetSlotResponse, GetVersionRequest,
+        GetVersionResponse, IsBlockhashValidRequest, IsBlockhashValidResponse, PingRequest,
+        PongResponse, SubscribeRequest, SubscribeUpdate,
+    },
+    tonic::{self, service::Interceptor, transport::Server, Request, Response, Status, Streaming},
+};
+
+/*
+ * In-process geyser serving scripted updates, one script per subscribe call
+ */
+#[derive(Clone, Default)]
+pub struct MockGeyser {
+    scripts: Arc<Mutex<VecDeque<Vec<SubscribeUpdate>>>>,
+    pub requests: Arc<Mutex<Vec<SubscribeRequest>>>,
+}
+
+#[tonic::async_trait]
+impl Geyser for MockGeyser {
+    type SubscribeStream = ReceiverStream<Result<SubscribeUpdate, Status>>;
+
+    /*
+     * Function: subscribe
+     * 1. Take next script, an exhausted script list fails subscribe so subscribe_geyser's loop returns
+     * 2. Record subscribe request sent by client, then stream script and end the stream
+     */
+    async fn subscribe(
+        &self,
+        request: Request<Streaming<SubscribeRequest>>,
+    ) -> Result<Response<Self::SubscribeStream>, Status> {
+        let mut stream = request.into_inner();
+        let script = match self.scripts.lock().unwrap().pop_front() {
+            Some(script) => script,
+            None => return Err(Status::unavailable("mock geyser scripts exhausted")),
+        };
+        let (tx, rx) = mpsc::channel(script.len().max(1));
+
+        tokio::spawn({
+            let requests = self.requests.clone();
+
+            async move {
+                match stream.message().await {
+                    Ok(Some(request)) => requests.lock().unwrap().push(request),
+                    _ => return,
+                }
+                for update in script {
+                    if tx.send(Ok(update)).await.is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+
+    async fn ping(&self, _: Request<PingRequest>) -> Result<Response<PongResponse>, Status> {
+        Err(Status::unimplemented("mock geyser"))
+    }
+
+    async fn get_latest_blockhash(
+        &self,
+        _: Request<GetLatestBlockhashRequest>,
+    ) -> Result<Response<GetLatestBlockhashResponse>, Status> {
+        Err(Status::unimplemented("mock geyser"))
+    }
+
+    async fn get_block_height(
+        &self,
+        _: Request<GetBlockHeightRequest>,
+    ) -> Result<Response<GetBlockHeightResponse>, Status> {
+        Err(Status::unimplemented("mock geyser"))
+    }
+
+    async fn get_slot(
+        &self,
+        _: Request<GetSlotRequest>,
+    ) -> Result<Response<GetSlotResponse>, Status> {
+        Err(Status::unimplemented("mock geyser"))
+    }
+
+    async fn is_blockhash_valid(
+        &self,
+        _: Request<IsBlockhashValidRequest>,
+    ) -> Result<Response<IsBlockhashValidResponse>, Status> {
+        Err(Status::unimplemented("mock geyser"))
+    }
+
+    async fn get_version(
+        &self,
+        _: Request<GetVersionRequest>,
+    ) -> Result<Response<GetVersionResponse>, Status> {
+        Err(Status::unimplemented("mock geyser"))
+    }
+}
+
+/*
+ * Function: spawn_mock_geyser
+ * 1. Bind local port and serve mock geyser with scripts in background
+ * Returns endpoint for geyser client and mock holding recorded requests
+ */
+pub async fn spawn_mock_geyser(scripts: Vec<Vec<SubscribeUpdate>>) -> (String, MockGeyser) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let endpoint = format!("http://{}", listener.local_addr().unwrap());
+    let mock = MockGeyser {
+        scripts: Arc::new(Mutex::new(scripts.into())),
+        ..Default::default()
+    };
+
+    tokio::spawn({
+        let service = GeyserServer::new(mock.clone());
+
+        async move {
+            let _ = Server::builder()
+                .add_service(service)
+                .serve_with_incoming(TcpListenerStream::new(listener))
+                .await;
+        }
+    });
+
+    (endpoint, mock)
+}
+
+/*
+ * Helper function for connecting geyser client to mock endpoint
+ */
+pub async fn connect_mock_geyser(endpoint: String) -> GeyserGrpcClient<impl Interceptor> {
+    GeyserGrpcClient::connect_with_timeout(
+        endpoint,
+        None::<String>,
+        None,
+        Some(Duration::from_secs(5)),
+        Some(Duration::from_secs(5)),
+        false,
+    )
+    .await
+    .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::{sink::SinkExt, stream::StreamExt};
+    use solana_sdk::pubkey::Pubkey;
+    use std::{
+        collections::{HashMap, HashSet},
+        str::FromStr,
+    };
+    use yellowstone_grpc_proto::prelude::{subscribe_update::UpdateOneof, CommitmentLevel};
+
+    use crate::{
+        constants::{CHANNEL_NAME, OPENBOOK_PROGRAM_ID},
+        parser::{parse_openbook_account, ObAdapter},
+        processor::{
+            adapter::DexAdapter,
+            market::publish_trades_data,
+            subscribe::{build_subscribe_request, subscribe_geyser},
+        },
+        structs::{
+            adapter::{DexEvent, VenueSnapshot},
+            alerts::AlertRulesCache,
+            fixtures::{account_update, ob_leaf, ob_market, ob_slab},
+            geyser::Account,
+            market::{
+                MarketOrders, MarketSendData, MarketTrade, PublishAllData, Venue, VenueMarket,
+            },
+            oracle::OracleInfo,
+            replay::{MemoryDb, MemoryRedis},
+            subscribe::SubscribeOptions,
+        },
+    };
+
+    use super::*;
+
+    /*
+     * Helper function for subscribing like subscribe_geyser and collecting updates until stream ends
+     */
+    async fn collect_updates(
+        geyser_client: &mut GeyserGrpcClient<impl Interceptor>,
+        request: &SubscribeRequest,
+    ) -> Vec<SubscribeUpdate> {
+        let (mut subscribe_tx, stream) = geyser_client.subscribe().await.unwrap();
+        subscribe_tx.send(request.clone()).await.unwrap();
+
+        stream.map(|x| x.unwrap()).collect().await
+    }
+
+    fn update_slot(update: &SubscribeUpdate) -> Option<u64> {
+        match &update.update_oneof {
+            Some(UpdateOneof::Account(account)) => Some(account.slot),
+            _ => None,
+        }
+    }
+
+    #[tokio::test]
+    async fn sends_adapter_filters() {
+        let market = ob_market("sol-usdc");
+        let adapter = ObAdapter {
+            markets: vec![market.clone()],
+            filled_order_ids: HashSet::new(),
+        };
+        let oracle = OracleInfo {
+            key: "sol-usdc".to_string(),
+            address: Pubkey::new_unique(),
+        };
+        let aum_account = Pubkey::new_unique();
+        let request = build_subscribe_request(
+            adapter.account_filters(),
+            &vec![oracle.clone()],
+            Some(aum_account),
+            false,
+        );
+
+        let (endpoint, mock) = spawn_mock_geyser(vec![vec![]]).await;
+        let mut geyser_client = connect_mock_geyser(endpoint).await;
+        collect_updates(&mut geyser_client, &request).await;
+
+        let requests = mock.requests.lock().unwrap();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0], request);
+        assert_eq!(
+            requests[0].commitment,
+            Some(CommitmentLevel::Confirmed as i32)
+        );
+        assert!(requests[0].transactions.is_empty());
+
+        let openbook = &requests[0].accounts["openbook"];
+        assert_eq!(openbook.owner, vec![OPENBOOK_PROGRAM_ID.to_string()]);
+        for account in [market.asks, market.bids, market.event_queue] {
+            assert!(openbook.account.contains(&account.to_string()));
+        }
+        assert_eq!(
+            requests[0].accounts["oracles"].account,
+            vec![oracle.address.to_string()]
+        );
+        assert_eq!(
+            requests[0].accounts["aum"].account,
+            vec![aum_account.to_string()]
+        );
+    }
+
+    #[tokio::test]
+    async fn resubscribes_after_stream_ends() {
+        let market = ob_market("sol-usdc");
+        let owner = Pubkey::from_str(OPENBOOK_PROGRAM_ID).unwrap();
+        let scripts = vec![
+            vec![
+                account_update(&market.bids, &owner, 1, ob_slab(&[], true)),
+                account_update(&market.asks, &owner, 2, ob_slab(&[], false)),
+            ],
+            vec![account_update(&market.bids, &owner, 3, ob_slab(&[], true))],
+        ];
+        let request = build_subscribe_request(HashMap::new(), &vec![], None, true);
+
+        let (endpoint, mock) = spawn_mock_geyser(scripts).await;
+        let mut geyser_client = connect_mock_geyser(endpoint).await;
+
+        // Same client subscribes again once stream ends, as subscribe_geyser's loop does
+        let first = collect_updates(&mut geyser_client, &request).await;
+        let second = collect_updates(&mut geyser_client, &request).await;
+
+        assert_eq!(
+            first.iter().filter_map(update_slot).collect::<Vec<u64>>(),
+            vec![1, 2]
+        );
+        assert_eq!(
+            second.iter().filter_map(update_slot).collect::<Vec<u64>>(),
+            vec![3]
+        );
+
+        let requests = mock.requests.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0], requests[1]);
+        assert!(!requests[0].transactions.is_empty());
+    }
+
+    #[tokio::test]
+    async fn publishes_streamed_book_side() {
+        let market = ob_market("sol-usdc");
+        let owner = Pubkey::from_str(OPENBOOK_PROGRAM_ID).unwrap();
+        let bids = ob_slab(
+            &[
+                ob_leaf(50_000, 1_000, 1, [1; 4]),
+                ob_leaf(49_000, 2_000, 2, [2; 4]),
+                ob_leaf(50_000, 500, 3, [3; 4]),
+            ],
+            true,
+        );
+        let scripts = vec![vec![account_update(&market.bids, &owner, 7, bids)]];
+
+        let (endpoint, _) = spawn_mock_geyser(scripts).await;
+        let mut geyser_client = connect_mock_geyser(endpoint).await;
+        let updates = collect_updates(&mut geyser_client, &SubscribeRequest::default()).await;
+
+        let mut redis = MemoryRedis::default();
+        let mut market_state = MarketOrders {
+            asks: vec![],
+            bids: vec![],
+        };
+        let mut filled_order_ids = HashSet::new();
+        for update in updates {
+            let mut account: Account = match update.update_oneof {
+                Some(UpdateOneof::Account(account)) => Account::try_from(account).unwrap(),
+                _ => continue,
+            };
+            let events =
+                parse_openbook_account(&market, &mut account, &mut filled_order_ids).unwrap();
+            for event in events {
+                if let DexEvent::BookSide { is_bid, levels, .. } = event {
+                    assert!(is_bid);
+                    market_state.bids = levels;
+                    publish_trades_data(
+                        &market.name,
+                        Venue::Openbook,
+                        &market_state,
+                        &mut redis,
+                        account.slot,
+                        &mut AlertRulesCache::default(),
+                    )
+                    .unwrap();
+                }
+            }
+        }
+
+        let stored: MarketSendData =
+            serde_json::from_str(&redis.strings["compressed_orderbook:sol-usdc:openbook"]).unwrap();
+        assert_eq!(stored.slot, 7);
+        let levels: Vec<(u64, u64)> = stored
+            .order_book
+            .bids
+            .iter()
+            .map(|x| (x.price_lots, x.size_lots))
+            .collect();
+        assert_eq!(levels, vec![(50_000, 1_500), (49_000, 2_000)]);
+        assert_eq!(stored.order_book.bids[0].price, 50.0);
+        assert_eq!(stored.order_book.bids[0].amount, 1.5);
+
+        assert_eq!(redis.published.len(), 1);
+        assert_eq!(redis.published[0].0, CHANNEL_NAME);
+        assert!(redis.published[0].1.contains("sol-usdc:openbook"));
+    }
+
+    #[tokio::test]
+    async fn runs_subscribe_geyser_on_memory_stores() {
+        let market = ob_market("sol-usdc");
+        let owner = Pubkey::from_str(OPENBOOK_PROGRAM_ID).unwrap();
+        let bids = ob_slab(&[ob_leaf(50_000, 1_000, 1, [1; 4])], true);
+        let asks = ob_slab(&[ob_leaf(51_000, 2_000, 2, [2; 4])], false);
+        let scripts = vec![
+            vec![account_update(&market.bids, &owner, 7, bids)],
+            vec![account_update(&market.asks, &owner, 8, asks)],
+        ];
+
+        let mut adapters: Vec<Box<dyn DexAdapter>> = vec![Box::new(ObAdapter {
+            markets: vec![market.clone()],
+            filled_order_ids: HashSet::new(),
+        })];
+        let snapshots = vec![VenueSnapshot {
+            market: market.name.clone(),
+            venue_market: VenueMarket {
+                venue: Venue::Openbook,
+                address: market.address.to_string(),
+                fee_rate_bps: market.fee_rate_bps,
+            },
+            orders: MarketOrders {
+                asks: vec![],
+                bids: vec![],
+            },
+        }];
+        let mut redis = MemoryRedis::default();
+        let db = MemoryDb::default();
+
+        let (endpoint, mock) = spawn_mock_geyser(scripts).await;
+        let mut geyser_client = connect_mock_geyser(endpoint).await;
+
+        // Returns once mock runs out of scripts, after resubscribing for the second one
+        let ret = subscribe_geyser(
+            &SubscribeOptions::default(),
+            &[],
+            &mut adapters,
+            snapshots,
+            None,
+            &mut redis,
+            &db,
+            &|_: Vec<MarketTrade>| {},
+            &mut geyser_client,
+        )
+        .await;
+        assert!(ret.is_err());
+
+        let requests = mock.requests.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0], requests[1]);
+        assert_eq!(
+            requests[0],
+            build_subscribe_request(adapters[0].account_filters(), &vec![], None, false)
+        );
+
+        let venue: MarketSendData =
+            serde_json::from_str(&redis.strings["compressed_orderbook:sol-usdc:openbook"]).unwrap();
+        assert_eq!(venue.slot, 8);
+        let consolidated: MarketSendData =
+            serde_json::from_str(&redis.strings["compressed_orderbook:sol-usdc"]).unwrap();
+        assert_eq!(consolidated.slot, 8);
+        assert_eq!(consolidated.order_book.bids[0].price, 50.0);
+        assert_eq!(consolidated.order_book.asks[0].price, 51.0);
+        assert_eq!(consolidated.order_book.asks[0].amount, 2.0);
+
+        // Initial books and both streamed updates are published per venue and consolidated
+        for market in ["sol-usdc:openbook", "sol-usdc"] {
+            let slots: Vec<u64> = redis
+                .published
+                .iter()
+                .filter(|(channel, _)| channel == CHANNEL_NAME)
+                .filter_map(|(_, msg)| {
+                    serde_json::from_str::<PublishAllData<MarketSendData>>(msg).ok()
+                })
+                .filter(|x| x.market == market)
+                .map(|x| x.data.slot)
+                .collect();
+            assert_eq!(slots, vec![0, 7, 8]);
+        }
+
+        // Book metrics and archive rows go to injected db sink
+        assert_eq!(db.book_metrics.lock().unwrap().len(), 1);
+        let snapshots = db.orderbook_snapshots.lock().unwrap();
+        assert_eq!(snapshots.len(), 1);
+        assert_eq!(snapshots[0].slot, 7);
+    }
+}
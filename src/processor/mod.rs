@@ -1,6 +1,21 @@
 pub mod subscribe;
 pub mod market;
 pub mod db;
+pub mod arbitrage;
+pub mod adapter;
+pub mod alerts;
+pub mod analytics;
+pub mod archive;
+pub mod aum;
+pub mod indicators;
+pub mod ledger;
+pub mod makers;
+#[cfg(test)]
+pub mod mock_geyser;
+pub mod oracle;
+pub mod query;
+pub mod replay;
+pub mod transactions;
 
 pub use subscribe::*;
 pub use market::*;
@@ -0,0 +1,317 @@
+use num_traits::ToPrimitive;
+use redis::{Commands, ConnectionLike};
+use sqlx::types::Decimal;
+use std::{
+    collections::HashMap,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    constants::{ANALYTICS_CHANNEL_NAME, CHANNEL_NAME, SOL_PRICE_REFRESH_SECS, SUMMARY_KEY},
+    parser::oracle::parse_oracle_account,
+    structs::{
+        geyser::Account,
+        market::{LastTradeData, MarketOrders, MarketTrade, SummaryPublishData},
+        oracle::{
+            OracleDeviation, OracleDeviationPublishData, OracleInfo, OraclePrice,
+            OraclePricePublishData, UsdSource,
+        },
+    },
+    utils::generate_publish_data,
+};
+
+/*
+ * Function: update_oracle_price
+ * 1. Decode oracle account update into oracle price
+ * 2. Update local oracle prices and redis's oracle_price
+ * 3. Publish oracle price updates to redis clients
+ */
+pub fn update_oracle_price(
+    oracle: &OracleInfo,
+    account: &Account,
+    oracle_prices: &mut HashMap<String, OraclePrice>,
+    redis_conn: &mut impl ConnectionLike,
+) -> anyhow::Result<Option<OraclePrice>> {
+    let oracle_price = match parse_oracle_account(oracle, account) {
+        Some(price) => price,
+        None => return Ok(None),
+    };
+
+    redis_conn.set(
+        format!("oracle_price:{}", oracle.key),
+        serde_json::to_string(&oracle_price)?,
+    )?;
+    redis_conn.publish(
+        CHANNEL_NAME,
+        generate_publish_data(
+            &oracle.key,
+            &OraclePricePublishData {
+                oracle_price: oracle_price.clone(),
+            },
+            None,
+        ),
+    )?;
+    oracle_prices.insert(oracle.key.clone(), oracle_price.clone());
+
+    Ok(Some(oracle_price))
+}
+
+/*
+ * Function: publish_oracle_deviation
+ * 1. Calculate deviation of consolidated orderbook's mid price against market's oracle price
+ * 2. Update redis's oracle_deviation and publish deviation to analytics channel
+ */
+pub fn publish_oracle_deviation(
+    market: &String,
+    market_state: &MarketOrders,
+    oracle_prices: &HashMap<String, OraclePrice>,
+    redis_conn: &mut impl ConnectionLike,
+    slot: u64,
+) -> anyhow::Result<()> {
+    let oracle_price = match oracle_prices.get(market) {
+        Some(price) if price.price > 0.0 => price.price,
+        _ => return Ok(()),
+    };
+    let dex_price = match (market_state.asks.first(), market_state.bids.first()) {
+        (Some(ask), Some(bid)) => (ask.price + bid.price) / 2.0,
+        _ => return Ok(()),
+    };
+
+    let deviation = OracleDeviation {
+        slug: market.clone(),
+        oracle_price,
+        dex_price,
+        deviation_bps: (dex_price - oracle_price) / oracle_price * 10_000.0,
+        slot,
+        timestamp: SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+    };
+
+    redis_conn.set(
+        format!("oracle_deviation:{}", market),
+        serde_json::to_string(&deviation)?,
+    )?;
+    redis_conn.publish(
+        ANALYTICS_CHANNEL_NAME,
+        generate_publish_data(market, &OracleDeviationPublishData { deviation }, None),
+    )?;
+
+    Ok(())
+}
+
+/*
+ * Function: refresh_sol_price
+ * 1. Once per interval, load every market's stored summary from redis
+ * 2. Update summary's sol price with sol oracle price, store and publish it to redis clients
+ * Markets without stored summary are skipped until their first trades publish one
+ */
+pub fn refresh_sol_price<'a>(
+    markets: impl Iterator<Item = &'a String>,
+    oracle_price: &OraclePrice,
+    refreshed_ts: &mut u64,
+    redis_conn: &mut impl ConnectionLike,
+) -> anyhow::Result<()> {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    if now < *refreshed_ts + SOL_PRICE_REFRESH_SECS {
+        return Ok(());
+    }
+    *refreshed_ts = now;
+
+    for market in markets {
+        let summary_key = format!("{}:{}", SUMMARY_KEY, market);
+        let data: Option<String> = redis_conn.get(&summary_key)?;
+        let mut summary =
+            match data.and_then(|x| serde_json::from_str::<SummaryPublishData>(&x).ok()) {
+                Some(data) => data.summary,
+                None => continue,
+            };
+        if summary.sol_price == oracle_price.price {
+            continue;
+        }
+        summary.sol_price = oracle_price.price;
+
+        redis_conn.set(
+            summary_key,
+            serde_json::to_string(&SummaryPublishData { summary })?,
+        )?;
+        redis_conn.publish(
+            CHANNEL_NAME,
+            generate_publish_data(market, &SummaryPublishData { summary }, None),
+        )?;
+    }
+
+    Ok(())
+}
+
+/*
+ * Function: get_quote_usd_rate
+ * 1. Load market's quote_usd_source from redis's market_info
+ * 2. Resolve rate from oracle price, stablecoin market's last trade or fixed rate
+ */
+pub fn get_quote_usd_rate(
+    redis_conn: &mut impl ConnectionLike,
+    market: &String,
+) -> anyhow::Result<Option<f64>> {
+    let source: Option<String> =
+        redis_conn.hget(format!("market_info:{}", market), "quote_usd_source")?;
+    let source = match source.as_deref().and_then(UsdSource::parse) {
+        Some(source) => source,
+        None => return Ok(None),
+    };
+
+    let rate = match source {
+        UsdSource::Oracle(key) => {
+            let data: Option<String> = redis_conn.get(format!("oracle_price:{}", key))?;
+            data.and_then(|x| serde_json::from_str::<OraclePrice>(&x).ok())
+                .map(|x| x.price)
+        }
+        UsdSource::Market(slug) => {
+            let data: Option<String> = redis_conn.get(format!("last_trade_data:{}", slug))?;
+            data.and_then(|x| serde_json::from_str::<LastTradeData>(&x).ok())
+                .map(|x| x.price)
+        }
+        UsdSource::Fixed(rate) => Some(rate),
+    };
+
+    Ok(rate.filter(|x| *x > 0.0))
+}
+
+/*
+ * Helper function for fill trades' usd price and notional with quote rate
+ */
+pub fn apply_usd_rate(trades: &mut Vec<MarketTrade>, quote_usd_rate: Option<f64>) {
+    let rate = match quote_usd_rate {
+        Some(rate) => rate,
+        None => return,
+    };
+
+    for trade in trades.iter_mut() {
+        let price = Decimal::to_f64(&trade.avg_price).unwrap_or_default();
+        let amount = Decimal::to_f64(&trade.amount).unwrap_or_default();
+        trade.price_usd = Some(price * rate);
+        trade.notional_usd = Some(price * amount * rate);
+        trade.quote_usd_rate = Some(rate);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structs::{
+        fixtures::{market_trade, summary_data},
+        oracle::OracleType,
+        replay::MemoryRedis,
+    };
+
+    fn sol_oracle_price(price: f64) -> OraclePrice {
+        OraclePrice {
+            key: "sol".to_string(),
+            address: "oracle".to_string(),
+            oracle_type: OracleType::Pyth,
+            price,
+            confidence: 0.1,
+            slot: 10,
+        }
+    }
+
+    fn with_usd_source(redis: &mut MemoryRedis, market: &str, source: &str) {
+        redis.hashes.insert(
+            format!("market_info:{}", market),
+            HashMap::from([("quote_usd_source".to_string(), source.to_string())]),
+        );
+    }
+
+    #[test]
+    fn quote_usd_rate_resolves_each_source() {
+        let mut redis = MemoryRedis::default();
+        redis.strings.insert(
+            "oracle_price:sol".to_string(),
+            serde_json::to_string(&sol_oracle_price(150.0)).unwrap(),
+        );
+        redis.strings.insert(
+            "last_trade_data:usdt-usdc".to_string(),
+            serde_json::to_string(&LastTradeData {
+                price: 0.999,
+                amount: 10.0,
+                market_buy: true,
+                timestamp: 1000,
+            })
+            .unwrap(),
+        );
+        with_usd_source(&mut redis, "bonk-sol", "oracle:sol");
+        with_usd_source(&mut redis, "bonk-usdt", "market:usdt-usdc");
+        with_usd_source(&mut redis, "bonk-usdc", "fixed:1");
+        with_usd_source(&mut redis, "bonk-eth", "oracle:eth");
+
+        let rate = |redis: &mut MemoryRedis, market: &str| {
+            get_quote_usd_rate(redis, &market.to_string()).unwrap()
+        };
+        assert_eq!(rate(&mut redis, "bonk-sol"), Some(150.0));
+        assert_eq!(rate(&mut redis, "bonk-usdt"), Some(0.999));
+        assert_eq!(rate(&mut redis, "bonk-usdc"), Some(1.0));
+        // Source without a price yet and market without a source have no rate
+        assert_eq!(rate(&mut redis, "bonk-eth"), None);
+        assert_eq!(rate(&mut redis, "sol-usdc"), None);
+    }
+
+    #[test]
+    fn malformed_quote_usd_source_has_no_rate() {
+        let mut redis = MemoryRedis::default();
+        for source in ["sol", "oracle:", "fixed:one", "fixed:-1", "fixed:0"] {
+            with_usd_source(&mut redis, "bonk-sol", source);
+
+            let rate = get_quote_usd_rate(&mut redis, &"bonk-sol".to_string()).unwrap();
+            assert_eq!(rate, None, "{}", source);
+        }
+    }
+
+    #[test]
+    fn usd_rate_fills_trades_price_and_notional() {
+        let mut trades = vec![market_trade("bonk-sol", 2.5, 4.0)];
+        apply_usd_rate(&mut trades, None);
+        assert_eq!(trades[0].price_usd, None);
+
+        apply_usd_rate(&mut trades, Some(150.0));
+        assert_eq!(trades[0].price_usd, Some(375.0));
+        assert_eq!(trades[0].notional_usd, Some(1500.0));
+        assert_eq!(trades[0].quote_usd_rate, Some(150.0));
+    }
+
+    #[test]
+    fn sol_oracle_refreshes_stored_summaries_once_per_interval() {
+        let mut redis = MemoryRedis::default();
+        let stored = |redis: &MemoryRedis, market: &str| {
+            serde_json::from_str::<SummaryPublishData>(
+                &redis.strings[&format!("summary:{}", market)],
+            )
+            .unwrap()
+            .summary
+        };
+        redis.strings.insert(
+            "summary:sol-usdc".to_string(),
+            serde_json::to_string(&SummaryPublishData {
+                summary: summary_data(120.0, 118.0),
+            })
+            .unwrap(),
+        );
+        let markets = vec!["sol-usdc".to_string(), "bonk-sol".to_string()];
+        let mut oracle_price = sol_oracle_price(121.5);
+        let mut refreshed_ts = 0;
+
+        refresh_sol_price(markets.iter(), &oracle_price, &mut refreshed_ts, &mut redis).unwrap();
+        assert_eq!(stored(&redis, "sol-usdc"), summary_data(120.0, 121.5));
+        // Market without a stored summary isn't published
+        assert_eq!(redis.published.len(), 1);
+        assert!(!redis.strings.contains_key("summary:bonk-sol"));
+
+        // Within the interval oracle updates don't republish summaries
+        oracle_price.price = 122.0;
+        refresh_sol_price(markets.iter(), &oracle_price, &mut refreshed_ts, &mut redis).unwrap();
+        assert_eq!(stored(&redis, "sol-usdc").sol_price, 121.5);
+        assert_eq!(redis.published.len(), 1);
+
+        refreshed_ts -= SOL_PRICE_REFRESH_SECS;
+        refresh_sol_price(markets.iter(), &oracle_price, &mut refreshed_ts, &mut redis).unwrap();
+        assert_eq!(stored(&redis, "sol-usdc").sol_price, 122.0);
+        assert_eq!(redis.published.len(), 2);
+    }
+}
@@ -0,0 +1,423 @@
+use axum::{
+    extract::{Path, RawQuery, State},
+    http::{header, HeaderName, StatusCode},
+    routing::get,
+    Router,
+};
+use postgrest::Postgrest;
+use redis::ConnectionLike;
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    constants::{QUERY_DEFAULT_LIMIT, SECONDS_PER_DAY},
+    parser::get_uid_wallets,
+    processor::{
+        archive::reconstruct_orderbook,
+        aum::get_current_aum,
+        db::{get_aum_history, get_balance_history, get_indicators},
+        ledger::get_ledger_positions,
+        makers::get_maker_leaderboard,
+    },
+    structs::query::{Query, QueryState},
+};
+
+/*
+ * Function: serve_queries
+ * 1. Listen http on port, answering GET /query/{kind}/{args..}?{key=value..} with query result as json
+ * 2. Respond bad request on invalid query and internal error on failed query
+ */
+pub async fn serve_queries(port: u16, state: QueryState) -> anyhow::Result<()> {
+    let app = Router::new()
+        .route("/query/*spec", get(handle_query))
+        .with_state(state);
+    let addr = SocketAddr::from(([0, 0, 0, 0], port));
+
+    tracing::info!("Serving queries on {}", addr);
+    axum::Server::bind(&addr)
+        .serve(app.into_make_service())
+        .await?;
+
+    Ok(())
+}
+
+/*
+ * Helper function for answering query request of path and parameters
+ */
+async fn handle_query(
+    State(state): State<QueryState>,
+    Path(path): Path<String>,
+    RawQuery(params): RawQuery,
+) -> Result<([(HeaderName, &'static str); 1], String), (StatusCode, String)> {
+    let query = parse_query(&query_spec(&path, params.as_deref()))
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    let mut redis_conn = state
+        .redis_client
+        .get_connection()
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let result = run_query(&query, &state.supabase_client, &mut redis_conn)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(([(header::CONTENT_TYPE, "application/json")], result))
+}
+
+/*
+ * Helper function for query spec of request path segments followed by key=value parameters
+ */
+pub fn query_spec(path: &str, params: Option<&str>) -> String {
+    path.split('/')
+        .chain(params.unwrap_or_default().split('&'))
+        .filter(|x| !x.is_empty())
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+/*
+ * Function: parse_query
+ * 1. Split query spec into query kind, market and key=value parameters
+ * 2. Build query of that kind, failing on unknown kind or invalid parameter
+ */
+pub fn parse_query(spec: &str) -> anyhow::Result<Query> {
+    let mut tokens = spec.split_whitespace();
+    let kind = tokens
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("Empty query"))?;
+
+    let mut args: Vec<&str> = Vec::new();
+    let mut params: HashMap<&str, &str> = HashMap::new();
+    for token in tokens {
+        match token.split_once('=') {
+            Some((key, value)) => {
+                params.insert(key, value);
+            }
+            None => args.push(token),
+        }
+    }
+
+    match kind {
+        "orderbook" => Ok(Query::Orderbook {
+            market: query_arg(&args, 0, "market")?,
+            slot: query_param(&params, "slot")?,
+            timestamp: query_param(&params, "timestamp")?,
+        }),
+        "balances" => Ok(Query::Balances {
+            market: query_arg(&args, 0, "market")?,
+            uid: query_arg(&args, 1, "uid")?
+                .parse::<u64>()
+                .map_err(|e| anyhow::anyhow!("Invalid query argument uid: {}", e))?,
+            limit: query_param(&params, "limit")?.unwrap_or(QUERY_DEFAULT_LIMIT),
+        }),
+        "indicators" => Ok(Query::Indicators {
+            market: query_arg(&args, 0, "market")?,
+            unit: query_arg(&args, 1, "unit")?,
+            from_ts: query_param(&params, "from")?.unwrap_or_default(),
+            to_ts: query_param(&params, "to")?,
+        }),
+        "aum" => Ok(Query::Aum),
+        "aum_history" => Ok(Query::AumHistory {
+            from_ts: query_param(&params, "from")?.unwrap_or_default(),
+            to_ts: query_param(&params, "to")?,
+        }),
+        "ledger" => Ok(Query::Ledger {
+            market: query_arg(&args, 0, "market")?,
+            trader: args.get(1).map(|x| x.to_string()),
+        }),
+        "uid_wallets" => Ok(Query::UidWallets {
+            market: query_arg(&args, 0, "market")?,
+            uid: args
+                .get(1)
+                .map(|x| x.parse::<u64>())
+                .transpose()
+                .map_err(|e| anyhow::anyhow!("Invalid query argument uid: {}", e))?,
+        }),
+        "makers" => Ok(Query::Makers {
+            market: query_arg(&args, 0, "market")?,
+            day_ts: query_param(&params, "day")?,
+        }),
+        _ => Err(anyhow::anyhow!("Unknown query: {}", kind)),
+    }
+}
+
+/*
+ * Function: run_query
+ * 1. Load query result from supabase, or redis for current aum, ledger, uid wallets and makers
+ * 2. Return result as json
+ */
+pub async fn run_query(
+    query: &Query,
+    supabase_client: &Postgrest,
+    redis_conn: &mut impl ConnectionLike,
+) -> anyhow::Result<String> {
+    match query {
+        Query::Orderbook {
+            market,
+            slot,
+            timestamp,
+        } => {
+            let order_book =
+                reconstruct_orderbook(supabase_client, market, *slot, *timestamp).await?;
+            Ok(serde_json::to_string(&order_book)?)
+        }
+        Query::Balances { market, uid, limit } => {
+            let events =
+                get_balance_history(supabase_client, market, *uid, *limit as usize).await?;
+            Ok(serde_json::to_string(&events)?)
+        }
+        Query::Indicators {
+            market,
+            unit,
+            from_ts,
+            to_ts,
+        } => {
+            let to_ts = query_to_ts(*to_ts)?;
+            let indicators = get_indicators(supabase_client, market, unit, *from_ts, to_ts).await?;
+            Ok(serde_json::to_string(&indicators)?)
+        }
+        Query::Aum => {
+            let aum = get_current_aum(redis_conn)?;
+            Ok(serde_json::to_string(&aum)?)
+        }
+        Query::AumHistory { from_ts, to_ts } => {
+            let aum = get_aum_history(supabase_client, *from_ts, query_to_ts(*to_ts)?).await?;
+            Ok(serde_json::to_string(&aum)?)
+        }
+        Query::Ledger { market, trader } => {
+            let positions = get_ledger_positions(redis_conn, market, trader.as_ref())?;
+            Ok(serde_json::to_string(&positions)?)
+        }
+        Query::UidWallets { market, uid } => {
+            let wallets = get_uid_wallets(redis_conn, market, *uid)?;
+            Ok(serde_json::to_string(&wallets)?)
+        }
+        Query::Makers { market, day_ts } => {
+            let day_ts = (query_to_ts(*day_ts)? / SECONDS_PER_DAY) * SECONDS_PER_DAY;
+            let leaderboard = get_maker_leaderboard(redis_conn, market, day_ts)?;
+            Ok(serde_json::to_string(&leaderboard)?)
+        }
+    }
+}
+
+/*
+ * Helper function for positional argument of query
+ */
+fn query_arg(args: &Vec<&str>, index: usize, name: &str) -> anyhow::Result<String> {
+    args.get(index)
+        .map(|x| x.to_string())
+        .ok_or_else(|| anyhow::anyhow!("Missing query argument: {}", name))
+}
+
+/*
+ * Helper function for end timestamp of query, now if not set
+ */
+fn query_to_ts(to_ts: Option<u64>) -> anyhow::Result<u64> {
+    match to_ts {
+        Some(to_ts) => Ok(to_ts),
+        None => Ok(SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs()),
+    }
+}
+
+/*
+ * Helper function for optional numeric key=value parameter of query
+ */
+fn query_param(params: &HashMap<&str, &str>, key: &str) -> anyhow::Result<Option<u64>> {
+    params
+        .get(key)
+        .map(|x| x.parse::<u64>())
+        .transpose()
+        .map_err(|e| anyhow::anyhow!("Invalid query parameter {}: {}", key, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::structs::{ledger::TraderPosition, replay::MemoryRedis};
+
+    use super::*;
+
+    #[test]
+    fn parses_orderbook_query() {
+        assert_eq!(
+            parse_query("orderbook SOL-USDC slot=250").unwrap(),
+            Query::Orderbook {
+                market: "SOL-USDC".to_string(),
+                slot: Some(250),
+                timestamp: None,
+            }
+        );
+        assert_eq!(
+            parse_query("orderbook SOL-USDC timestamp=1700000000").unwrap(),
+            Query::Orderbook {
+                market: "SOL-USDC".to_string(),
+                slot: None,
+                timestamp: Some(1700000000),
+            }
+        );
+    }
+
+    #[test]
+    fn parses_balances_query() {
+        assert_eq!(
+            parse_query("balances SOL-USDC 42 limit=10").unwrap(),
+            Query::Balances {
+                market: "SOL-USDC".to_string(),
+                uid: 42,
+                limit: 10,
+            }
+        );
+        assert_eq!(
+            parse_query("balances SOL-USDC 42").unwrap(),
+            Query::Balances {
+                market: "SOL-USDC".to_string(),
+                uid: 42,
+                limit: QUERY_DEFAULT_LIMIT,
+            }
+        );
+    }
+
+    #[test]
+    fn parses_indicators_query() {
+        assert_eq!(
+            parse_query("indicators SOL-USDC 15m from=1700000000").unwrap(),
+            Query::Indicators {
+                market: "SOL-USDC".to_string(),
+                unit: "15m".to_string(),
+                from_ts: 1700000000,
+                to_ts: None,
+            }
+        );
+    }
+
+    #[test]
+    fn parses_aum_queries() {
+        assert_eq!(parse_query("aum").unwrap(), Query::Aum);
+        assert_eq!(
+            parse_query("aum_history from=1700000000 to=1700086400").unwrap(),
+            Query::AumHistory {
+                from_ts: 1700000000,
+                to_ts: Some(1700086400),
+            }
+        );
+    }
+
+    #[test]
+    fn parses_redis_queries() {
+        assert_eq!(
+            parse_query("ledger SOL-USDC").unwrap(),
+            Query::Ledger {
+                market: "SOL-USDC".to_string(),
+                trader: None,
+            }
+        );
+        assert_eq!(
+            parse_query("ledger SOL-USDC alice").unwrap(),
+            Query::Ledger {
+                market: "SOL-USDC".to_string(),
+                trader: Some("alice".to_string()),
+            }
+        );
+        assert_eq!(
+            parse_query("uid_wallets SOL-USDC 42").unwrap(),
+            Query::UidWallets {
+                market: "SOL-USDC".to_string(),
+                uid: Some(42),
+            }
+        );
+        assert_eq!(
+            parse_query("makers SOL-USDC day=1700000000").unwrap(),
+            Query::Makers {
+                market: "SOL-USDC".to_string(),
+                day_ts: Some(1700000000),
+            }
+        );
+    }
+
+    #[test]
+    fn builds_spec_of_request_path() {
+        assert_eq!(
+            query_spec("orderbook/SOL-USDC", Some("slot=250&timestamp=1700000000")),
+            "orderbook SOL-USDC slot=250 timestamp=1700000000"
+        );
+        assert_eq!(query_spec("aum/", None), "aum");
+        assert_eq!(
+            parse_query(&query_spec("balances/SOL-USDC/42", Some("limit=10"))).unwrap(),
+            Query::Balances {
+                market: "SOL-USDC".to_string(),
+                uid: 42,
+                limit: 10,
+            }
+        );
+    }
+
+    #[test]
+    fn answers_redis_queries() {
+        let supabase_client = Postgrest::new("http://localhost");
+        let mut redis = MemoryRedis::default();
+        let position = |trader: &str, realized_pnl: f64| TraderPosition {
+            trader: trader.to_string(),
+            market: "sol-usdc".to_string(),
+            realized_pnl,
+            ..Default::default()
+        };
+        redis.hashes.insert(
+            "ledger:sol-usdc".to_string(),
+            HashMap::from([
+                (
+                    "alice".to_string(),
+                    serde_json::to_string(&position("alice", 1.0)).unwrap(),
+                ),
+                (
+                    "bob".to_string(),
+                    serde_json::to_string(&position("bob", 5.0)).unwrap(),
+                ),
+            ]),
+        );
+        redis.hashes.insert(
+            "uid_wallets:sol-usdc".to_string(),
+            HashMap::from([("7".to_string(), "wallet".to_string())]),
+        );
+        redis.strings.insert(
+            "maker_leaderboard:sol-usdc:1699920000".to_string(),
+            "[]".to_string(),
+        );
+
+        let mut run = |spec: &str| {
+            let query = parse_query(spec).unwrap();
+            futures::executor::block_on(run_query(&query, &supabase_client, &mut redis)).unwrap()
+        };
+
+        // Positions are ordered by total pnl
+        let positions: Vec<TraderPosition> = serde_json::from_str(&run("ledger sol-usdc")).unwrap();
+        assert_eq!(
+            positions,
+            vec![position("bob", 5.0), position("alice", 1.0)]
+        );
+        let positions: Vec<TraderPosition> =
+            serde_json::from_str(&run("ledger sol-usdc alice")).unwrap();
+        assert_eq!(positions, vec![position("alice", 1.0)]);
+
+        assert_eq!(run("uid_wallets sol-usdc"), r#"{"7":"wallet"}"#);
+        assert_eq!(run("uid_wallets sol-usdc 8"), "{}");
+
+        // Day is floored to the leaderboard's day
+        assert_eq!(run("makers sol-usdc day=1700000000"), "[]");
+        assert_eq!(run("makers eth-usdc day=1700000000"), "[]");
+    }
+
+    #[test]
+    fn rejects_invalid_query() {
+        assert!(parse_query("").is_err());
+        assert!(parse_query("candles SOL-USDC").is_err());
+        assert!(parse_query("orderbook").is_err());
+        assert!(parse_query("orderbook SOL-USDC slot=latest").is_err());
+        assert!(parse_query("balances SOL-USDC").is_err());
+        assert!(parse_query("balances SOL-USDC alice").is_err());
+        assert!(parse_query("indicators SOL-USDC").is_err());
+        assert!(parse_query("aum_history from=yesterday").is_err());
+        assert!(parse_query("ledger").is_err());
+        assert!(parse_query("uid_wallets SOL-USDC alice").is_err());
+        assert!(parse_query("makers SOL-USDC day=today").is_err());
+    }
+}
@@ -0,0 +1,516 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter, ErrorKind, Read, Write},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+use tokio::time::sleep;
+
+use crate::{
+    constants::{RECORD_FLUSH_INTERVAL_MS, RECORD_FLUSH_RECORDS},
+    parser::{
+        build_gd_markets, build_ob_markets, parse_gigadex_account, parse_openbook_account,
+        publish_balance_events, GdAdapter, ObAdapter,
+    },
+    processor::adapter::DexAdapter,
+    structs::{
+        adapter::DexEvent,
+        geyser::Account,
+        market::MarketConfig,
+        replay::{MemoryDb, MemoryRedis, RecordedAccount, RecordingHeader},
+    },
+    utils::fetch_accounts,
+};
+
+/*
+ * Writes recording header and geyser account updates to recording log
+ */
+pub struct Recorder {
+    writer: BufWriter<File>,
+    unflushed: usize,
+    flushed_at: Instant,
+}
+
+impl Recorder {
+    /*
+     * Function: create
+     * 1. Create recording log, replacing earlier recording of other markets and startup accounts
+     * 2. Write recording header as first record
+     */
+    pub fn create(path: &str, header: &RecordingHeader) -> anyhow::Result<Self> {
+        let mut recorder = Self {
+            writer: BufWriter::new(File::create(path)?),
+            unflushed: 0,
+            flushed_at: Instant::now(),
+        };
+        recorder.write_record(&header.try_to_vec()?)?;
+        recorder.flush()?;
+
+        Ok(recorder)
+    }
+
+    /*
+     * Function: record
+     * 1. Serialize account update with receive time as borsh record
+     * 2. Write record prefixed by its u32 length, flushing every RECORD_FLUSH_RECORDS records or RECORD_FLUSH_INTERVAL_MS
+     */
+    pub fn record(&mut self, account: &Account) -> anyhow::Result<()> {
+        let received_at_ms = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as u64;
+        let record = RecordedAccount::new(account, received_at_ms).try_to_vec()?;
+        self.write_record(&record)?;
+        self.unflushed += 1;
+
+        if self.unflushed >= RECORD_FLUSH_RECORDS {
+            self.flush()?;
+        } else {
+            self.flush_if_due()?;
+        }
+
+        Ok(())
+    }
+
+    /*
+     * Flush buffered records once RECORD_FLUSH_INTERVAL_MS passed since last flush, so killed process loses at most the interval
+     */
+    pub fn flush_if_due(&mut self) -> anyhow::Result<()> {
+        if self.unflushed > 0
+            && self.flushed_at.elapsed() >= Duration::from_millis(RECORD_FLUSH_INTERVAL_MS)
+        {
+            self.flush()?;
+        }
+
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> anyhow::Result<()> {
+        self.writer.flush()?;
+        self.unflushed = 0;
+        self.flushed_at = Instant::now();
+
+        Ok(())
+    }
+
+    /*
+     * Helper function for writing record prefixed by its u32 length
+     */
+    fn write_record(&mut self, record: &[u8]) -> anyhow::Result<()> {
+        self.writer
+            .write_all(&(record.len() as u32).to_le_bytes())?;
+        self.writer.write_all(record)?;
+
+        Ok(())
+    }
+}
+
+/*
+ * Function: build_recording_header
+ * 1. Get accounts every adapter read at startup using rpc client
+ * 2. Build recording header of market configs and fetched accounts
+ */
+pub async fn build_recording_header(
+    rpc_client: &RpcClient,
+    markets: &[MarketConfig],
+    adapters: &[Box<dyn DexAdapter>],
+) -> anyhow::Result<RecordingHeader> {
+    let keys: Vec<Pubkey> = adapters.iter().flat_map(|x| x.startup_accounts()).collect();
+    let accounts = fetch_accounts(rpc_client, &keys).await?;
+    let recorded_at_ms = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as u64;
+
+    RecordingHeader::new(markets, accounts, recorded_at_ms)
+}
+
+/*
+ * Function: read_recording
+ * 1. Read recording header of first record
+ * 2. Read length prefixed account records, stopping at truncated tail record written by interrupted recorder
+ */
+pub fn read_recording(path: &str) -> anyhow::Result<(RecordingHeader, Vec<RecordedAccount>)> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let header = match read_record(&mut reader, path)? {
+        Some(record) => RecordingHeader::try_from_slice(&record)?,
+        None => return Err(anyhow::anyhow!("Recording {} has no header", path)),
+    };
+
+    let mut records: Vec<RecordedAccount> = Vec::new();
+    while let Some(record) = read_record(&mut reader, path)? {
+        records.push(RecordedAccount::try_from_slice(&record)?);
+    }
+
+    Ok((header, records))
+}
+
+/*
+ * Helper function for reading length prefixed record, None at end of recording or its truncated tail
+ */
+fn read_record(reader: &mut impl Read, path: &str) -> anyhow::Result<Option<Vec<u8>>> {
+    let mut len_bytes = [0u8; 4];
+    match reader.read_exact(&mut len_bytes) {
+        Ok(_) => {}
+        Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e.into()),
+    }
+
+    let mut record = vec![0u8; u32::from_le_bytes(len_bytes) as usize];
+    match reader.read_exact(&mut record) {
+        Ok(_) => Ok(Some(record)),
+        Err(e) if e.kind() == ErrorKind::UnexpectedEof => {
+            tracing::warn!("Truncated record at end of recording {}", path);
+            Ok(None)
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/*
+ * Openbook/gigadex parser states and in-memory sinks of a replay
+ */
+#[derive(Default)]
+pub struct ReplayState {
+    pub ob: ObAdapter,
+    pub gd: GdAdapter,
+    pub redis: MemoryRedis,
+    pub db: MemoryDb,
+    pub events: Vec<DexEvent>,
+}
+
+impl ReplayState {
+    /*
+     * Function: from_header
+     * 1. Build openbook/gigadex markets from recorded market configs and market/mint accounts
+     * 2. Seed gigadex orders, delta logs and balances from recorded snapshot accounts, as subscribe does at startup
+     */
+    pub fn from_header(header: &RecordingHeader) -> anyhow::Result<Self> {
+        let markets = header.market_configs()?;
+        let accounts = header.account_map();
+
+        let ob = ObAdapter {
+            markets: build_ob_markets(&markets, &accounts),
+            ..Default::default()
+        };
+        let mut gd = GdAdapter {
+            markets: build_gd_markets(&markets, &accounts),
+            ..Default::default()
+        };
+        gd.build_snapshot(&accounts)?;
+
+        Ok(Self {
+            ob,
+            gd,
+            ..Default::default()
+        })
+    }
+
+    /*
+     * Function: apply
+     * 1. Pass account to parse_openbook_account or parse_gigadex_account of its market
+     * 2. Keep emitted book side and trades events, recording balance transitions into sinks
+     */
+    pub fn apply(&mut self, mut account: Account) -> anyhow::Result<()> {
+        if let Some(market) = self
+            .ob
+            .markets
+            .iter()
+            .find(|x| x.is_valid_account(&account.pubkey))
+        {
+            let events =
+                parse_openbook_account(market, &mut account, &mut self.ob.filled_order_ids)?;
+            self.events.extend(events);
+        } else if let Some(market) = self
+            .gd
+            .markets
+            .iter()
+            .find(|x| x.is_valid_account(&account.pubkey))
+        {
+            let mut balance_events = Vec::new();
+            let events = parse_gigadex_account(
+                &self.db,
+                market,
+                &mut account,
+                &mut self.redis,
+                &mut self.gd.uid_asks,
+                &mut self.gd.uid_bids,
+                &mut self.gd.balances,
+                &mut self.gd.fill_states,
+                &mut self.gd.tree_deltas,
+                &self.gd.uid_wallets,
+                &mut balance_events,
+            )?;
+            if !balance_events.is_empty() {
+                publish_balance_events(&self.db, &mut self.redis, balance_events)?;
+            }
+            self.events.extend(events);
+        }
+
+        Ok(())
+    }
+}
+
+/*
+ * Function: replay_accounts
+ * 1. Wait between records by their recorded receive gap divided by speed, no wait if speed is 0
+ * 2. Apply every record to replay state, logging records failed to parse
+ * Returns number of failed records
+ */
+pub async fn replay_accounts(
+    records: Vec<RecordedAccount>,
+    state: &mut ReplayState,
+    speed: f64,
+) -> usize {
+    let mut failed = 0;
+    let mut prev_received_at: Option<u64> = None;
+
+    for record in records {
+        if let Some(prev) = prev_received_at {
+            let gap_ms = record.received_at_ms.saturating_sub(prev);
+            if speed > 0.0 && gap_ms > 0 {
+                sleep(Duration::from_millis((gap_ms as f64 / speed) as u64)).await;
+            }
+        }
+        prev_received_at = Some(record.received_at_ms);
+
+        let (slot, pubkey) = (record.slot, record.pubkey);
+        if let Err(e) = state.apply(record.into()) {
+            tracing::error!(
+                "Replay error at slot {} of {}: {:?}",
+                slot,
+                bs58::encode(pubkey).into_string(),
+                e
+            );
+            failed += 1;
+        }
+    }
+
+    failed
+}
+
+/*
+ * Function: replay_recording
+ * 1. Read recording log and build markets and startup state from its header, no redis/rpc needed
+ * 2. Replay account records into in-memory redis/db sinks
+ * 3. Log summary of replayed events and sink contents
+ */
+pub async fn replay_recording(path: &str, speed: f64) -> anyhow::Result<ReplayState> {
+    let (header, records) = read_recording(path)?;
+    tracing::info!("Replaying {} account updates of {}", records.len(), path);
+
+    let mut state = ReplayState::from_header(&header)?;
+    let failed = replay_accounts(records, &mut state, speed).await;
+
+    let trades: usize = state
+        .events
+        .iter()
+        .map(|x| match x {
+            DexEvent::Trades(trades) => trades.len(),
+            _ => 0,
+        })
+        .sum();
+    tracing::info!(
+        "Replay done: {} events, {} trades, {} publishes, {} db events, {} failed",
+        state.events.len(),
+        trades,
+        state.redis.published.len(),
+        state.db.events.lock().unwrap().len(),
+        failed
+    );
+
+    Ok(state)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, fs::OpenOptions};
+
+    use crate::structs::{
+        fixtures::{
+            account, gd_market, gd_market_data, gd_order_tree, gd_user_balances, market_config,
+            mint_data, ob_leaf, ob_market, ob_market_data, ob_slab,
+        },
+        gigadex::{GdMarketOrder, NodeDeltaLog},
+        market::Venue,
+    };
+
+    use super::*;
+
+    /*
+     * Helper function for unique recording path in temp dir
+     */
+    fn recording_path() -> String {
+        std::env::temp_dir()
+            .join(format!("recording-{}.log", Pubkey::new_unique()))
+            .to_string_lossy()
+            .to_string()
+    }
+
+    fn insert(uid: u64, price: u64, amount: u64) -> NodeDeltaLog {
+        NodeDeltaLog {
+            key: 0,
+            is_delete: 0,
+            is_insert: 1,
+            is_delta: 0,
+            amount,
+            uid,
+            price,
+        }
+    }
+
+    fn order(uid: u64, price_lots: u64, amount_lots: u64) -> GdMarketOrder {
+        GdMarketOrder {
+            uid,
+            price_lots,
+            amount_lots,
+        }
+    }
+
+    #[test]
+    fn reads_back_recorded_header_and_accounts() {
+        let path = recording_path();
+        let market = Pubkey::new_unique();
+        let header = RecordingHeader::new(
+            &[market_config(
+                "sol-usdc",
+                &[("gd_market_address", market.to_string())],
+            )],
+            HashMap::from([(market, vec![1, 2, 3])]),
+            1_000,
+        )
+        .unwrap();
+        let updates = [
+            account(&Pubkey::new_unique(), 10, vec![4; 16]),
+            account(&Pubkey::new_unique(), 11, vec![]),
+        ];
+
+        let mut recorder = Recorder::create(&path, &header).unwrap();
+        for update in updates.iter() {
+            recorder.record(update).unwrap();
+        }
+        drop(recorder);
+
+        // Record cut short by interrupted recorder is dropped
+        OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap()
+            .write_all(&[64, 0, 0, 0, 1])
+            .unwrap();
+
+        let (read_header, records) = read_recording(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(read_header, header);
+        assert_eq!(read_header.market_configs().unwrap()[0].slug, "sol-usdc");
+        assert_eq!(read_header.account_map()[&market], vec![1, 2, 3]);
+        assert_eq!(records.len(), 2);
+        for (record, update) in records.into_iter().zip(updates.iter()) {
+            let replayed: Account = record.into();
+            assert_eq!(
+                (replayed.slot, replayed.pubkey, &replayed.data),
+                (update.slot, update.pubkey, &update.data)
+            );
+            assert_eq!(replayed.write_version, update.write_version);
+        }
+    }
+
+    #[tokio::test]
+    async fn replays_recorded_fixture_from_startup_state() {
+        let ob = ob_market("sol-usdc");
+        let gd = gd_market("sol-usdc");
+        let markets = vec![market_config(
+            "sol-usdc",
+            &[
+                ("ob_market_address", ob.address.to_string()),
+                ("gd_market_address", gd.address.to_string()),
+            ],
+        )];
+
+        // Uid 1 rests an ask and holds a balance before recording starts
+        let startup_asks = gd_order_tree(&[order(1, 50_000, 100)], &[], &[insert(1, 50_000, 100)]);
+        let accounts = HashMap::from([
+            (ob.address, ob_market_data(&ob)),
+            (ob.base_mint, mint_data(9)),
+            (ob.quote_mint, mint_data(6)),
+            (gd.address, gd_market_data(&gd)),
+            (gd.asks, startup_asks.clone()),
+            (gd.bids, gd_order_tree(&[], &[], &[])),
+            (gd.balances, gd_user_balances(&[(1, 1_000_000, 0)])),
+        ]);
+        let header = RecordingHeader::new(&markets, accounts, 1_000).unwrap();
+
+        // Recorded updates: uid 2 places an ask, uid 1 is credited, openbook asks and an unknown account
+        let path = recording_path();
+        let mut recorder = Recorder::create(&path, &header).unwrap();
+        for update in [
+            account(&gd.asks, 10, startup_asks.clone()),
+            account(
+                &gd.asks,
+                11,
+                gd_order_tree(
+                    &[order(1, 50_000, 100), order(2, 50_100, 200)],
+                    &[],
+                    &[insert(2, 50_100, 200)],
+                ),
+            ),
+            account(&gd.balances, 12, gd_user_balances(&[(1, 2_000_000, 0)])),
+            account(&ob.asks, 13, ob_slab(&[ob_leaf(100, 5, 1, [0; 4])], false)),
+            account(&Pubkey::new_unique(), 14, vec![0; 8]),
+        ] {
+            recorder.record(&update).unwrap();
+        }
+        drop(recorder);
+
+        let (header, records) = read_recording(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let mut state = ReplayState::from_header(&header).unwrap();
+        let failed = replay_accounts(records, &mut state, 0.0).await;
+        assert_eq!(failed, 0);
+
+        // Markets are rebuilt from recorded market and mint accounts
+        assert_eq!(state.ob.markets.len(), 1);
+        assert_eq!(state.ob.markets[0].asks, ob.asks);
+        assert_eq!(
+            (
+                state.ob.markets[0].base_decimals,
+                state.ob.markets[0].quote_decimals
+            ),
+            (9, 6)
+        );
+        assert_eq!(state.gd.markets.len(), 1);
+        assert_eq!(state.gd.markets[0].balances, gd.balances);
+
+        // Seeded orders and delta log keep uid 1 from being republished
+        let book_uids: Vec<u64> = state
+            .redis
+            .published
+            .iter()
+            .map(|(_, msg)| serde_json::from_str::<serde_json::Value>(msg).unwrap())
+            .filter(|x| x["data"].get("uidAsks").is_some() || x["data"].get("orderEvent").is_some())
+            .map(|x| x["type"].as_u64().unwrap())
+            .collect();
+        assert_eq!(book_uids, vec![2, 2]);
+
+        // Seeded balances record only the credit of uid 1
+        let balance_events = state.db.balance_events.lock().unwrap();
+        assert_eq!(balance_events.len(), 1);
+        assert_eq!(balance_events[0].uid, 1);
+        assert_eq!(balance_events[0].lamports_delta, 1.0);
+
+        // Both venues emit book sides of the recorded book accounts
+        let book_sides: Vec<(Venue, bool)> = state
+            .events
+            .iter()
+            .filter_map(|x| match x {
+                DexEvent::BookSide { venue, is_bid, .. } => Some((*venue, *is_bid)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            book_sides,
+            vec![
+                (Venue::Gigadex, false),
+                (Venue::Gigadex, false),
+                (Venue::Openbook, false)
+            ]
+        );
+    }
+}
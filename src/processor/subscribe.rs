@@ -1,13 +1,9 @@
 use futures::{sink::SinkExt, stream::StreamExt};
 use postgrest::Postgrest;
-use redis::{Client, Commands};
+use redis::{Client, Commands, Connection, ConnectionLike};
 use solana_client::nonblocking::rpc_client::RpcClient;
-use std::{
-    collections::{HashMap, HashSet},
-    error::Error,
-    time::Duration,
-    vec,
-};
+use solana_sdk::pubkey::Pubkey;
+use std::{collections::HashMap, error::Error, str::FromStr, time::Duration, vec};
 use tokio::time::sleep;
 use yellowstone_grpc_client::{GeyserGrpcClient, GeyserGrpcClientError};
 use yellowstone_grpc_proto::{
@@ -19,170 +15,220 @@ use yellowstone_grpc_proto::{
 };
 
 use crate::{
-    constants::{DELAY_MILISEC, GD_ORDER_DEPTH, GIGADEX_PROGRAM_ID, OPENBOOK_PROGRAM_ID},
-    parser::{
-        parse_gd_markets, parse_gd_orders, parse_gigadex_account, parse_ob_markets,
-        parse_ob_orders, parse_openbook_account, sort_orders,
+    constants::{CONSOLIDATED_ORDER_DEPTH, DELAY_MILISEC, SOL_ORACLE_KEY},
+    processor::{
+        adapter::{
+            apply_dex_event, build_adapters, spawn_update_trades, AccountsFilterMap, DexAdapter,
+        },
+        analytics::publish_book_metrics,
+        arbitrage::check_arbitrage,
+        archive::archive_orderbook,
+        aum::update_aum,
+        db::DbSink,
+        ledger::mark_ledger,
+        makers::{flush_maker_stats, load_maker_accums, update_maker_stats},
+        market::{consolidate_orders, publish_consolidated_data, publish_trades_data},
+        oracle::{publish_oracle_deviation, refresh_sol_price, update_oracle_price},
+        replay::{build_recording_header, Recorder},
+        transactions::{build_transactions_filter, drain_ready_trades, track_transaction},
     },
-    processor::market::publish_trades_data,
     structs::{
-        geyser::Account,
-        gigadex::{GdBalance, GdMarketOrder},
-        market::{MarketConfig, MarketOrders},
+        adapter::VenueSnapshot,
+        alerts::AlertRulesCache,
+        arbitrage::ArbOpportunity,
+        decode::DecodeError,
+        geyser::{Account, Transaction},
+        makers::{MakerQuotes, MakerTracker},
+        market::{MarketConfig, MarketOrders, MarketTrade, VenueMarket},
+        oracle::{OracleInfo, OraclePrice},
+        subscribe::SubscribeOptions,
+        transactions::TxFillTracker,
     },
 };
 
-type AccountsFilterMap = HashMap<String, SubscribeRequestFilterAccounts>;
-
 /*
- * Function: subscribe_geyser
- * 1. Get active markets from redis as markets key
- * 2. Parse openbook and gigadex's ask/bid/fill accounts
- * 3. Build accounts list for subscribe and initial market states
- * 4. Loop subscribe geyser and if account update matched, process account update using OB or GD parser
+ * Function: run_subscribe
+ * 1. Load market configs of active markets from redis
+ * 2. Load venue markets and initial market states through each venue's DexAdapter
+ * 3. If record path set, start recording log with market configs and accounts adapters read at startup
+ * 4. Subscribe geyser with redis connection and supabase, calling update_trades in background
  */
-pub async fn subscribe_geyser(
+pub async fn run_subscribe(
     api_url: String,
+    options: &SubscribeOptions,
+    record_path: Option<String>,
     redis_client: &Client,
     supabase_client: &Postgrest,
     rpc_client: &RpcClient,
     geyser_client: &mut GeyserGrpcClient<impl Interceptor>,
 ) -> Result<(), Box<dyn Error>> {
     tracing::info!("Subscribe geyser...");
-    let mut redis_conn = redis_client
-        .get_connection()
-        .expect("Failed to get redis connection");
-
-    let mut accounts: Vec<String> = Vec::new();
+    let mut redis_conn = redis_client.get_connection()?;
 
     // Load markets
-    let market_keys: Vec<String> = redis_conn.smembers("markets").expect("Get markets failed");
+    let markets = load_market_configs(&mut redis_conn)?;
 
-    let mut markets: Vec<MarketConfig> = Vec::new();
-    for market in market_keys {
-        let market_info: HashMap<String, String> =
-            redis_conn.hgetall(format!("market_info:{market}"))?;
-        if !market_info.contains_key("name") {
-            continue;
+    // Prepare venue adapters and initial market states
+    let mut adapters = build_adapters(options.subscribe_transactions);
+    let snapshots = load_snapshots(&mut adapters, &markets, &mut redis_conn, rpc_client).await?;
+
+    // Start recording log with market configs and accounts adapters read at startup
+    let recorder = match &record_path {
+        Some(path) => {
+            let header = build_recording_header(rpc_client, &markets, &adapters).await?;
+            Some(Recorder::create(path, &header)?)
         }
+        None => None,
+    };
 
-        let base_decimals = market_info.get("base_decimals").unwrap();
-        let quote_decimals = market_info.get("quote_decimals").unwrap();
-
-        markets.push(MarketConfig {
-            gd_market_address: market_info.get("gd_market_address").cloned(),
-            ob_market_address: market_info.get("ob_market_address").cloned(),
-            name: market_info.get("name").unwrap().to_string(),
-            slug: market_info.get("slug").unwrap().to_string(),
-            status: market_info.get("status").unwrap().to_string(),
-            base_decimals: u8::from_str_radix(&base_decimals, 10)?,
-            quote_decimals: u8::from_str_radix(&quote_decimals, 10)?,
-        });
+    subscribe_geyser(
+        options,
+        &markets,
+        &mut adapters,
+        snapshots,
+        recorder,
+        &mut redis_conn,
+        supabase_client,
+        &|trades| spawn_update_trades(&api_url, redis_client, supabase_client, trades),
+        geyser_client,
+    )
+    .await
+}
+
+/*
+ * Function: load_snapshots
+ * 1. Load venue markets of market configs through each adapter, invalid markets are skipped by adapters
+ * 2. Load initial orderbooks of every adapter's venue markets
+ * 3. Return error if markets can't be fetched so subscribe task retries
+ */
+pub async fn load_snapshots(
+    adapters: &mut [Box<dyn DexAdapter>],
+    markets: &[MarketConfig],
+    redis_conn: &mut Connection,
+    rpc_client: &RpcClient,
+) -> anyhow::Result<Vec<VenueSnapshot>> {
+    let mut snapshots: Vec<VenueSnapshot> = Vec::new();
+    for adapter in adapters.iter_mut() {
+        adapter
+            .load_markets(rpc_client, markets)
+            .await
+            .map_err(|e| anyhow::anyhow!("Load {} markets failed: {}", adapter.name(), e))?;
+
+        snapshots.extend(adapter.load_snapshot(rpc_client, redis_conn).await?);
     }
 
+    Ok(snapshots)
+}
+
+/*
+ * Function: subscribe_geyser
+ * 1. Publish initial orderbooks of loaded venue markets
+ * 2. Build accounts filters for subscribe from adapters' account filters
+ * 3. Loop subscribe geyser and pass account update to adapters, apply their book side and trades events
+ * 4. If ask/bid account updated, publish consolidated orderbook of the market across venues
+ * 5. Publish analytics metrics of consolidated orderbook
+ * 6. Archive consolidated orderbook snapshots and deltas keyed by slot
+ * 7. Check consolidated venues for crossed books and emit arbitrage opportunities
+ * 8. If oracle account updated, publish oracle price and market's deviation against oracle, refresh summaries' sol price on sol oracle
+ * 9. If aum account updated, publish aum and store aum time series
+ * 10. If transactions subscribed, hold trades until their taker instructions are tracked for exact signature/taker/fee payer
+ *     Adapters flush venue updates they held for their transactions once ready
+ * 11. If recorder set, write every account update to recording log for replay
+ * 12. Skip malformed updates and account data, counting them per adapter instead of ending the stream
+ * Redis connection, db sink and trades handler are injected, so tests run it against mock geyser and memory stores
+ */
+pub async fn subscribe_geyser(
+    options: &SubscribeOptions,
+    markets: &[MarketConfig],
+    adapters: &mut [Box<dyn DexAdapter>],
+    snapshots: Vec<VenueSnapshot>,
+    mut recorder: Option<Recorder>,
+    redis_conn: &mut (impl ConnectionLike + Send),
+    supabase_client: &(impl DbSink + Sync),
+    handle_trades: &(impl Fn(Vec<MarketTrade>) + Sync),
+    geyser_client: &mut GeyserGrpcClient<impl Interceptor>,
+) -> Result<(), Box<dyn Error>> {
     let mut market_orders: HashMap<String, MarketOrders> = HashMap::new();
+    let mut market_venues: HashMap<String, Vec<VenueMarket>> = HashMap::new();
+    let mut arb_opportunities: HashMap<String, ArbOpportunity> = HashMap::new();
+    let mut metrics_stored_ts: HashMap<String, u64> = HashMap::new();
+    let mut ledger_marked_ts: HashMap<String, u64> = HashMap::new();
+    let mut archived_books: HashMap<String, MarketOrders> = HashMap::new();
+    let mut snapshot_stored_ts: HashMap<String, u64> = HashMap::new();
+    let mut maker_quotes: HashMap<String, MakerQuotes> = HashMap::new();
+    let mut maker_tracker = MakerTracker::default();
+    let mut oracle_prices: HashMap<String, OraclePrice> = HashMap::new();
+    let mut sol_price_refreshed_ts: u64 = 0;
+    let mut prev_aum: Option<u128> = None;
+    let mut malformed_updates: HashMap<&'static str, u64> = HashMap::new();
+    let mut alert_rules = AlertRulesCache::default();
+    let mut tx_tracker = TxFillTracker {
+        enabled: options.subscribe_transactions,
+        ..Default::default()
+    };
 
-    // Prepare openbook accounts
-    let mut ob_order_ids: HashSet<u128> = HashSet::new();
-    let ob_markets = parse_ob_markets(rpc_client, markets.clone())
-        .await
-        .expect("Load openbook markets failed");
-
-    for market in ob_markets.clone() {
-        let market_key = market.address.clone();
-        let asks_key = market.asks.clone();
-        let bids_key = market.bids.clone();
-        accounts.push(asks_key.to_string());
-        accounts.push(bids_key.to_string());
-        accounts.push(market.event_queue.to_string());
-
-        let asks = match parse_ob_orders(rpc_client, asks_key, false, market.clone()).await {
-            Ok(asks) => asks,
-            Err(_) => Vec::new(),
-        };
-        let bids = match parse_ob_orders(rpc_client, bids_key, true, market.clone()).await {
-            Ok(asks) => asks,
-            Err(_) => Vec::new(),
-        };
-
-        // Publish initial orderbook data
-        let market_order = MarketOrders { asks, bids };
-        publish_trades_data(&market.name, &market_order, &mut redis_conn, 0)?;
-
-        market_orders.insert(market_key.to_string(), market_order);
+    // Prepare oracle accounts
+    let mut oracles: Vec<OracleInfo> = Vec::new();
+    if let Some(address) = &options.sol_oracle_address {
+        oracles.push(OracleInfo {
+            key: SOL_ORACLE_KEY.to_string(),
+            address: Pubkey::from_str(address)?,
+        });
+    }
+    for market in markets.iter() {
+        if let Some(address) = &market.oracle_address {
+            oracles.push(OracleInfo {
+                key: market.slug.clone(),
+                address: Pubkey::from_str(address)?,
+            });
+        }
     }
 
-    // Prepare gigadex accounts
-    let mut gd_balances: HashMap<String, HashMap<u64, GdBalance>> = HashMap::new();
-    let mut gd_uid_asks: HashMap<String, HashMap<u64, Vec<GdMarketOrder>>> = HashMap::new();
-    let mut gd_uid_bids: HashMap<String, HashMap<u64, Vec<GdMarketOrder>>> = HashMap::new();
-    let gd_markets = parse_gd_markets(rpc_client, &markets)
-        .await
-        .expect("Load gigadex markets failed");
-    for market in gd_markets.iter() {
-        let market_key = market.address.clone();
-        accounts.push(market.asks.to_string());
-        accounts.push(market.bids.to_string());
-        accounts.push(market.balances.to_string());
-        accounts.push(market.buy_order_log.to_string());
-        accounts.push(market.sell_order_log.to_string());
-
-        let asks = parse_gd_orders(rpc_client, market.asks).await?;
-        let bids = parse_gd_orders(rpc_client, market.bids).await?;
-
-        // Build initial orderbook data
-        let market_order = MarketOrders {
-            asks: sort_orders(&asks, market, GD_ORDER_DEPTH, false),
-            bids: sort_orders(&bids, market, GD_ORDER_DEPTH, true),
-        };
-        publish_trades_data(&market.name, &market_order, &mut redis_conn, 0)?;
-
-        // Build initial uid orders
-        gd_uid_asks.insert(market.name.clone(), HashMap::new());
-        let uid_asks = gd_uid_asks.get_mut(&market.name).unwrap();
-        asks.iter().for_each(|x| {
-            let uid = x.uid;
-            if !uid_asks.contains_key(&uid) {
-                uid_asks.insert(uid, vec![]);
-            }
-            let orders = uid_asks.get_mut(&uid).unwrap();
-            orders.push(x.clone());
-        });
+    // Publish initial orderbook data
+    for snapshot in snapshots {
+        publish_trades_data(
+            &snapshot.market,
+            snapshot.venue_market.venue,
+            &snapshot.orders,
+            redis_conn,
+            0,
+            &mut alert_rules,
+        )?;
 
-        gd_uid_bids.insert(market.name.clone(), HashMap::new());
-        let uid_bids = gd_uid_bids.get_mut(&market.name).unwrap();
-        bids.iter().for_each(|x| {
-            let uid = x.uid;
-            if !uid_bids.contains_key(&uid) {
-                uid_bids.insert(uid, vec![]);
-            }
-            let orders = uid_bids.get_mut(&uid).unwrap();
-            orders.push(x.clone());
-        });
+        market_orders.insert(snapshot.venue_market.address.clone(), snapshot.orders);
+        market_venues
+            .entry(snapshot.market)
+            .or_default()
+            .push(snapshot.venue_market);
+    }
 
-        // Build initial balances data
-        gd_balances.insert(market.name.clone(), HashMap::new());
+    let mut accounts_filter: AccountsFilterMap = HashMap::new();
+    for adapter in adapters.iter() {
+        accounts_filter.extend(adapter.account_filters());
+        tx_tracker.market_accounts.extend(adapter.market_accounts());
+    }
 
-        market_orders.insert(market_key.to_string(), market_order);
+    // Publish initial consolidated orderbook data
+    for (market, venues) in market_venues.iter() {
+        publish_consolidated_data(market, venues, &market_orders, redis_conn, 0)?;
     }
 
+    // Resume today's maker accumulators
+    load_maker_accums(&mut maker_tracker, market_venues.keys(), redis_conn)?;
+
+    // Prepare aum account
+    let aum_account = match &options.aum_account_address {
+        Some(address) => Some(Pubkey::from_str(address)?),
+        None => None,
+    };
+
     // Prepare geyser client
-    let mut request = SubscribeRequest::default();
-    request.set_commitment(CommitmentLevel::Confirmed);
-    let mut accounts_filter: AccountsFilterMap = HashMap::new();
-    accounts_filter.insert(
-        "client".to_string(),
-        SubscribeRequestFilterAccounts {
-            account: accounts,
-            owner: [
-                OPENBOOK_PROGRAM_ID.to_string(),
-                GIGADEX_PROGRAM_ID.to_string(),
-            ]
-            .into(),
-            filters: [].into(),
-        },
+    let request = build_subscribe_request(
+        accounts_filter,
+        &oracles,
+        aum_account,
+        options.subscribe_transactions,
     );
-    request.accounts = accounts_filter;
 
     // Subscribe geyser events
     loop {
@@ -196,55 +242,253 @@ pub async fn subscribe_geyser(
         while let Some(message) = stream.next().await {
             match message {
                 Ok(msg) => {
-                    #[allow(clippy::single_match)]
                     #[allow(clippy::multiple_unsafe_ops_per_block)]
                     match msg.update_oneof {
                         Some(UpdateOneof::Account(account)) => {
-                            let mut account: Account = account.into();
+                            let mut account = match Account::try_from(account) {
+                                Ok(account) => account,
+                                Err(e) => {
+                                    count_malformed(&mut malformed_updates, "geyser", None, &e);
+                                    continue;
+                                }
+                            };
+                            if let Some(recorder) = recorder.as_mut() {
+                                if let Err(e) = recorder.record(&account) {
+                                    tracing::error!("Record account error: {:?}", e);
+                                }
+                            }
                             let account_address = account.pubkey;
+                            tx_tracker.last_slot = tx_tracker.last_slot.max(account.slot);
+                            let mut book_market: Option<String> = None;
 
-                            // Parse OB market order
-                            let ob_market = ob_markets
-                                .iter()
-                                .find(|x| x.is_valid_account(&account_address));
-                            if ob_market.is_some() {
-                                let market = ob_market.unwrap();
-                                let _ = parse_openbook_account(
-                                    api_url.clone(),
-                                    redis_client.clone(),
-                                    supabase_client.clone(),
-                                    market.clone(),
-                                    &mut account,
-                                    &mut redis_conn,
-                                    &mut market_orders,
-                                    &mut ob_order_ids,
-                                )
-                                .await;
+                            // Process account update of venue adapters
+                            for adapter in adapters.iter_mut() {
+                                let events = match adapter
+                                    .process_account(&mut account, redis_conn, supabase_client)
+                                    .await
+                                {
+                                    Ok(events) => events,
+                                    Err(e) => {
+                                        match e.downcast_ref::<DecodeError>() {
+                                            Some(e) => count_malformed(
+                                                &mut malformed_updates,
+                                                adapter.name(),
+                                                Some(&account_address),
+                                                e,
+                                            ),
+                                            None => tracing::error!(
+                                                "Error processing {} account: {:?}",
+                                                adapter.name(),
+                                                e
+                                            ),
+                                        }
+                                        continue;
+                                    }
+                                };
+
+                                for event in events {
+                                    match apply_dex_event(
+                                        event,
+                                        handle_trades,
+                                        redis_conn,
+                                        &mut market_orders,
+                                        &mut maker_quotes,
+                                        &mut tx_tracker,
+                                        &mut alert_rules,
+                                        account.slot,
+                                    ) {
+                                        Ok(Some(market_name)) => book_market = Some(market_name),
+                                        Ok(None) => {}
+                                        Err(e) => tracing::error!(
+                                            "Error applying {} event: {:?}",
+                                            adapter.name(),
+                                            e
+                                        ),
+                                    }
+                                }
+                            }
+
+                            // Update oracle prices and market's deviation
+                            for oracle in oracles.iter().filter(|x| x.address.eq(&account_address))
+                            {
+                                let updated = update_oracle_price(
+                                    oracle,
+                                    &account,
+                                    &mut oracle_prices,
+                                    redis_conn,
+                                );
+                                if let (Ok(Some(price)), true) =
+                                    (&updated, oracle.key.eq(SOL_ORACLE_KEY))
+                                {
+                                    if let Err(e) = refresh_sol_price(
+                                        market_venues.keys(),
+                                        price,
+                                        &mut sol_price_refreshed_ts,
+                                        redis_conn,
+                                    ) {
+                                        tracing::error!("Failed to refresh sol price: {:?}", e);
+                                    }
+                                }
+                                if let (Ok(Some(_)), Some(venues)) =
+                                    (updated, market_venues.get(&oracle.key))
+                                {
+                                    let books: Vec<&MarketOrders> = venues
+                                        .iter()
+                                        .filter_map(|x| market_orders.get(&x.address))
+                                        .collect();
+                                    let _ = publish_oracle_deviation(
+                                        &oracle.key,
+                                        &consolidate_orders(&books, CONSOLIDATED_ORDER_DEPTH),
+                                        &oracle_prices,
+                                        redis_conn,
+                                        account.slot,
+                                    );
+                                }
+                            }
+
+                            // Update aum
+                            if aum_account.eq(&Some(account_address)) {
+                                let _ = update_aum(
+                                    &account,
+                                    &mut prev_aum,
+                                    redis_conn,
+                                    supabase_client,
+                                );
+                            }
+
+                            // Process consolidated orderbook of updated market
+                            if let Some(market_name) = book_market {
+                                let venues = market_venues.get(&market_name).unwrap();
+                                if let Ok(order_book) = publish_consolidated_data(
+                                    &market_name,
+                                    venues,
+                                    &market_orders,
+                                    redis_conn,
+                                    account.slot,
+                                ) {
+                                    let _ = publish_book_metrics(
+                                        &market_name,
+                                        &order_book,
+                                        &options.analytics_notionals,
+                                        &mut metrics_stored_ts,
+                                        redis_conn,
+                                        supabase_client,
+                                        account.slot,
+                                    );
+                                    let _ = mark_ledger(
+                                        &market_name,
+                                        &order_book,
+                                        &mut ledger_marked_ts,
+                                        redis_conn,
+                                    );
+                                    let _ = archive_orderbook(
+                                        &market_name,
+                                        venues,
+                                        &market_orders,
+                                        &mut archived_books,
+                                        &mut snapshot_stored_ts,
+                                        supabase_client,
+                                        account.slot,
+                                        account.write_version,
+                                    );
+                                    let _ = publish_oracle_deviation(
+                                        &market_name,
+                                        &order_book,
+                                        &oracle_prices,
+                                        redis_conn,
+                                        account.slot,
+                                    );
+                                    let _ = update_maker_stats(
+                                        &market_name,
+                                        venues,
+                                        &maker_quotes,
+                                        &order_book,
+                                        &mut maker_tracker,
+                                    );
+                                    let _ = flush_maker_stats(
+                                        &mut maker_tracker,
+                                        redis_conn,
+                                        supabase_client,
+                                    );
+                                }
+                                let _ = check_arbitrage(
+                                    &market_name,
+                                    venues,
+                                    &market_orders,
+                                    &mut arb_opportunities,
+                                    redis_conn,
+                                    supabase_client,
+                                    account.slot,
+                                );
+                            }
+                        }
+                        Some(UpdateOneof::Transaction(transaction)) => {
+                            let transaction = match Transaction::try_from(transaction) {
+                                Ok(transaction) => transaction,
+                                Err(e) => {
+                                    count_malformed(
+                                        &mut malformed_updates,
+                                        "geyser transaction",
+                                        None,
+                                        &e,
+                                    );
+                                    continue;
+                                }
                             };
+                            track_transaction(&transaction, &mut tx_tracker);
+
+                            // Process transaction of venue adapters decoding market events
+                            for adapter in adapters.iter_mut() {
+                                let events = match adapter.process_transaction(&transaction) {
+                                    Ok(events) => events,
+                                    Err(e) => {
+                                        tracing::error!(
+                                            "Error processing {} transaction: {:?}",
+                                            adapter.name(),
+                                            e
+                                        );
+                                        continue;
+                                    }
+                                };
 
-                            // Parse GD market order
-                            let gd_market = gd_markets
-                                .iter()
-                                .find(|x| x.is_valid_account(&account_address));
-                            if gd_market.is_some() {
-                                let market = gd_market.unwrap();
-                                let _ = parse_gigadex_account(
-                                    api_url.clone(),
-                                    redis_client.clone(),
-                                    supabase_client.clone(),
-                                    market.clone(),
-                                    &mut account,
-                                    &mut redis_conn,
-                                    &mut market_orders,
-                                    &mut gd_uid_asks,
-                                    &mut gd_uid_bids,
-                                    &mut gd_balances,
-                                )
-                                .await;
+                                for event in events {
+                                    if let Err(e) = apply_dex_event(
+                                        event,
+                                        handle_trades,
+                                        redis_conn,
+                                        &mut market_orders,
+                                        &mut maker_quotes,
+                                        &mut tx_tracker,
+                                        &mut alert_rules,
+                                        transaction.slot,
+                                    ) {
+                                        tracing::error!(
+                                            "Error applying {} event: {:?}",
+                                            adapter.name(),
+                                            e
+                                        );
+                                    }
+                                }
                             }
                         }
                         _ => {}
                     }
+
+                    if let Some(recorder) = recorder.as_mut() {
+                        if let Err(e) = recorder.flush_if_due() {
+                            tracing::error!("Flush recording error: {:?}", e);
+                        }
+                    }
+
+                    // Process trades attributed to their transactions and venue updates held for them
+                    for trades in drain_ready_trades(&mut tx_tracker) {
+                        handle_trades(trades);
+                    }
+                    for adapter in adapters.iter_mut() {
+                        if let Err(e) = adapter.flush_pending(redis_conn, supabase_client) {
+                            tracing::error!("Error flushing {} updates: {:?}", adapter.name(), e);
+                        }
+                    }
                 }
                 Err(e) => {
                     tracing::error!("Error geyser streaming: {:?}", e);
@@ -254,3 +498,124 @@ pub async fn subscribe_geyser(
         }
     }
 }
+
+/*
+ * Helper function for counting malformed update of a source and logging its running count
+ */
+fn count_malformed(
+    malformed_updates: &mut HashMap<&'static str, u64>,
+    source: &'static str,
+    address: Option<&Pubkey>,
+    e: &DecodeError,
+) {
+    let count = malformed_updates.entry(source).or_default();
+    *count += 1;
+    tracing::warn!(
+        "Skipped malformed {} update {}: {} ({} total)",
+        source,
+        address.map(|x| x.to_string()).unwrap_or_default(),
+        e,
+        count
+    );
+}
+
+/*
+ * Function: load_market_configs
+ * 1. Get active markets from redis as markets key
+ * 2. Build market config of each market from its market_info hash, keeping the hash for adapters' venue keys
+ * 3. Skip markets with missing or invalid market_info fields
+ */
+pub fn load_market_configs(
+    redis_conn: &mut Connection,
+) -> Result<Vec<MarketConfig>, Box<dyn Error>> {
+    let market_keys: Vec<String> = redis_conn.smembers("markets")?;
+
+    let mut markets: Vec<MarketConfig> = Vec::new();
+    for market in market_keys {
+        let market_info: HashMap<String, String> =
+            redis_conn.hgetall(format!("market_info:{market}"))?;
+        if !market_info.contains_key("name") {
+            continue;
+        }
+
+        match build_market_config(market_info) {
+            Ok(market_config) => markets.push(market_config),
+            Err(e) => tracing::warn!("Skipped market {}: {}", market, e),
+        }
+    }
+
+    Ok(markets)
+}
+
+/*
+ * Helper function for building market config from its market_info hash
+ */
+fn build_market_config(market_info: HashMap<String, String>) -> anyhow::Result<MarketConfig> {
+    let base_decimals = market_info_field(&market_info, "base_decimals")?;
+    let quote_decimals = market_info_field(&market_info, "quote_decimals")?;
+
+    Ok(MarketConfig {
+        name: market_info_field(&market_info, "name")?,
+        slug: market_info_field(&market_info, "slug")?,
+        status: market_info_field(&market_info, "status")?,
+        base_decimals: u8::from_str_radix(&base_decimals, 10)
+            .map_err(|e| anyhow::anyhow!("Invalid market_info base_decimals: {}", e))?,
+        quote_decimals: u8::from_str_radix(&quote_decimals, 10)
+            .map_err(|e| anyhow::anyhow!("Invalid market_info quote_decimals: {}", e))?,
+        oracle_address: market_info.get("oracle_address").cloned(),
+        market_info,
+    })
+}
+
+/*
+ * Helper function for field of market_info hash
+ */
+fn market_info_field(market_info: &HashMap<String, String>, name: &str) -> anyhow::Result<String> {
+    market_info
+        .get(name)
+        .cloned()
+        .ok_or_else(|| anyhow::anyhow!("Missing market_info field: {}", name))
+}
+
+/*
+ * Function: build_subscribe_request
+ * 1. Build confirmed subscribe request of venue adapters' accounts filters
+ * 2. Add oracle and aum accounts filters
+ * 3. If transactions subscribed, add transactions filter for exact fill attribution
+ */
+pub fn build_subscribe_request(
+    mut accounts_filter: AccountsFilterMap,
+    oracles: &Vec<OracleInfo>,
+    aum_account: Option<Pubkey>,
+    subscribe_transactions: bool,
+) -> SubscribeRequest {
+    let mut request = SubscribeRequest::default();
+    request.set_commitment(CommitmentLevel::Confirmed);
+    if !oracles.is_empty() {
+        accounts_filter.insert(
+            "oracles".to_string(),
+            SubscribeRequestFilterAccounts {
+                account: oracles.iter().map(|x| x.address.to_string()).collect(),
+                owner: [].into(),
+                filters: [].into(),
+            },
+        );
+    }
+    if let Some(address) = aum_account {
+        accounts_filter.insert(
+            "aum".to_string(),
+            SubscribeRequestFilterAccounts {
+                account: [address.to_string()].into(),
+                owner: [].into(),
+                filters: [].into(),
+            },
+        );
+    }
+    request.accounts = accounts_filter;
+
+    if subscribe_transactions {
+        request.transactions = build_transactions_filter();
+    }
+
+    request
+}
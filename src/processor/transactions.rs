@@ -0,0 +1,550 @@
+use std::collections::HashMap;
+use yellowstone_grpc_proto::prelude::SubscribeRequestFilterTransactions;
+
+use crate::{
+    constants::{
+        GD_NON_FILL_INSTRUCTIONS, GIGADEX_PROGRAM_ID, OB_NEW_ORDER_V3_TAG, OB_SEND_TAKE_TAG,
+        OB_V2_TAKER_INSTRUCTIONS, OPENBOOK_PROGRAM_ID, OPENBOOK_V2_PROGRAM_ID, PHOENIX_PROGRAM_ID,
+        TX_FILL_KEEP_SLOTS, TX_FILL_WAIT_SLOTS,
+    },
+    structs::{
+        geyser::{Transaction, TxInstruction},
+        market::{MarketTrade, TradeAttribution},
+        transactions::{TxFill, TxFillTracker},
+    },
+};
+
+/*
+ * Function: build_transactions_filter
+ * 1. Build geyser transactions filter of successful non-vote transactions touching openbook v1/v2, gigadex or phoenix
+ */
+pub fn build_transactions_filter() -> HashMap<String, SubscribeRequestFilterTransactions> {
+    HashMap::from([(
+        "fills".to_string(),
+        SubscribeRequestFilterTransactions {
+            vote: Some(false),
+            failed: Some(false),
+            signature: None,
+            account_include: vec![
+                OPENBOOK_PROGRAM_ID.to_string(),
+                OPENBOOK_V2_PROGRAM_ID.to_string(),
+                GIGADEX_PROGRAM_ID.to_string(),
+                PHOENIX_PROGRAM_ID.to_string(),
+            ],
+            account_exclude: vec![],
+            account_required: vec![],
+        },
+    )])
+}
+
+/*
+ * Function: track_transaction
+ * 1. Resolve anchor instruction names of invocations from inner logs
+ * 2. Record every openbook v1/v2 and gigadex taker instruction with its market, taker wallet and fee payer
+ * 3. Drop tracked transactions older than keep window
+ */
+pub fn track_transaction(tx: &Transaction, tracker: &mut TxFillTracker) {
+    tracker.last_slot = tracker.last_slot.max(tx.slot);
+    tracker
+        .seen_signatures
+        .insert(tx.signature.clone(), tx.slot);
+
+    if !tx.is_failed {
+        let names = instruction_names(&tx.log_messages);
+
+        for ix in tx.instructions.iter() {
+            let market = match ix
+                .accounts
+                .iter()
+                .find_map(|x| tracker.market_accounts.get(x))
+            {
+                Some(market) => market.clone(),
+                None => continue,
+            };
+            let taker = match decode_taker(ix, &names) {
+                Some(taker) => taker,
+                None => continue,
+            };
+
+            tracker
+                .tx_fills
+                .entry(market.clone())
+                .or_default()
+                .push(TxFill {
+                    slot: tx.slot,
+                    signature: tx.signature.clone(),
+                    tx_index: tx.index,
+                    instruction_index: ix.index,
+                    inner_instruction_index: ix.inner_index,
+                    market,
+                    accounts: ix.accounts.iter().map(|x| x.to_string()).collect(),
+                    taker: taker.or(Some(tx.fee_payer.to_string())),
+                    fee_payer: tx.fee_payer.to_string(),
+                });
+        }
+    }
+
+    // Prune old transactions
+    let min_slot = tracker.last_slot.saturating_sub(TX_FILL_KEEP_SLOTS);
+    tracker.seen_signatures.retain(|_, slot| *slot >= min_slot);
+    tracker.tx_fills.retain(|_, fills| {
+        fills.retain(|x| x.slot >= min_slot);
+        !fills.is_empty()
+    });
+}
+
+/*
+ * Function: drain_ready_trades
+ * 1. Trades are ready once their last writer transaction is tracked or TX_FILL_WAIT_SLOTS passed,
+ *    so attributed trades reach redis/postgres up to that many slots late
+ * 2. Attribute ready trades to their taker instructions
+ */
+pub fn drain_ready_trades(tracker: &mut TxFillTracker) -> Vec<Vec<MarketTrade>> {
+    let pending = std::mem::take(&mut tracker.pending_trades);
+    let mut ready: Vec<Vec<MarketTrade>> = Vec::new();
+    for mut trades in pending {
+        let is_ready = trades.iter().all(|x| {
+            tracker
+                .seen_signatures
+                .contains_key(&x.transaction_signature)
+                || tracker.last_slot > x.slot + TX_FILL_WAIT_SLOTS
+        });
+        if !is_ready {
+            tracker.pending_trades.push(trades);
+            continue;
+        }
+
+        attribute_trades(&mut trades, tracker);
+        ready.push(trades);
+    }
+
+    ready
+}
+
+/*
+ * Function: attribute_trades
+ * 1. Skip trades already attributed by their venue's market events
+ * 2. Match trade to taker instruction of same slot holding trade's taker account (openbook v1/v2 open orders)
+ * 3. Otherwise fall back to last taker instruction of trade's last writer transaction, marked as such
+ *    (gigadex order tree holds last instruction fills, openbook send take has no open orders)
+ * 4. Set exact signature, instruction/inner instruction index, taker wallet and fee payer of matched instruction
+ * Fills themselves are still decoded from account state, instructions only attribute the taker side,
+ * so a trade spanning several instructions of one writer transaction gets the last one
+ */
+pub fn attribute_trades(trades: &mut Vec<MarketTrade>, tracker: &TxFillTracker) {
+    for trade in trades.iter_mut().filter(|x| x.attribution.is_none()) {
+        let tx_fills = match tracker.tx_fills.get(&trade.market_address) {
+            Some(tx_fills) => tx_fills,
+            None => continue,
+        };
+
+        let by_taker = trade.taker_account.as_ref().and_then(|taker_account| {
+            tx_fills
+                .iter()
+                .rev()
+                .find(|x| x.slot == trade.slot && x.accounts.contains(taker_account))
+        });
+        let (tx_fill, attribution) = match by_taker {
+            Some(tx_fill) => (tx_fill, TradeAttribution::TakerAccount),
+            None => match tx_fills
+                .iter()
+                .rev()
+                .find(|x| x.signature == trade.transaction_signature)
+            {
+                Some(tx_fill) => (tx_fill, TradeAttribution::LastWriter),
+                None => continue,
+            },
+        };
+
+        trade.transaction_signature = tx_fill.signature.clone();
+        trade.instruction_index = Some(tx_fill.instruction_index);
+        trade.inner_instruction_index = tx_fill.inner_instruction_index;
+        trade.taker = tx_fill.taker.clone();
+        trade.fee_payer = Some(tx_fill.fee_payer.clone());
+        trade.attribution = Some(attribution);
+    }
+}
+
+/*
+ * Helper function for taker wallet of openbook new order/send take, openbook v2 place order and gigadex order instructions
+ * Returns None if instruction can't fill, Some(None) if taker is unknown
+ */
+fn decode_taker(
+    ix: &TxInstruction,
+    names: &HashMap<(u32, Option<u32>), (String, String)>,
+) -> Option<Option<String>> {
+    let program_id = ix.program_id.to_string();
+    if program_id == OPENBOOK_PROGRAM_ID {
+        // Instruction data is version byte followed by u32 tag, owner/signer is 8th account of both
+        let tag = u32::from_le_bytes(ix.data.get(1..5)?.try_into().ok()?);
+        if tag != OB_NEW_ORDER_V3_TAG && tag != OB_SEND_TAKE_TAG {
+            return None;
+        }
+        return Some(ix.accounts.get(7).map(|x| x.to_string()));
+    }
+
+    if program_id == OPENBOOK_V2_PROGRAM_ID {
+        // Anchor place order instructions take, signer is the taker's owner
+        let (_, name) = names.get(&(ix.index, ix.inner_index))?;
+        if !OB_V2_TAKER_INSTRUCTIONS.contains(&name.as_str()) {
+            return None;
+        }
+        return Some(ix.signers.first().map(|x| x.to_string()));
+    }
+
+    if program_id == GIGADEX_PROGRAM_ID {
+        if let Some((_, name)) = names.get(&(ix.index, ix.inner_index)) {
+            if GD_NON_FILL_INSTRUCTIONS.iter().any(|x| name.contains(x)) {
+                return None;
+            }
+        }
+        return Some(ix.signers.first().map(|x| x.to_string()));
+    }
+
+    None
+}
+
+/*
+ * Helper function for invoked program and anchor instruction name per instruction
+ * Instructions are keyed by top level index and inner ordinal, every invoke deeper than 1 is the next inner instruction
+ */
+pub fn instruction_names(logs: &Vec<String>) -> HashMap<(u32, Option<u32>), (String, String)> {
+    let mut names: HashMap<(u32, Option<u32>), (String, String)> = HashMap::new();
+    // Invoked program of every instruction on the invoke stack
+    let mut stack: Vec<((u32, Option<u32>), String)> = Vec::new();
+    let mut index: Option<u32> = None;
+    let mut inner_count: u32 = 0;
+
+    for log in logs {
+        let parts: Vec<&str> = log.split_whitespace().collect();
+        match parts.as_slice() {
+            ["Program", program, "invoke", depth] => {
+                let key = if *depth == "[1]" {
+                    index = Some(index.map_or(0, |x| x + 1));
+                    inner_count = 0;
+                    (index.unwrap_or_default(), None)
+                } else {
+                    inner_count += 1;
+                    (index.unwrap_or_default(), Some(inner_count - 1))
+                };
+                stack.push((key, program.to_string()));
+            }
+            ["Program", _, "success"] | ["Program", _, "failed:", ..] => {
+                stack.pop();
+            }
+            ["Program", "log:", "Instruction:", name] => {
+                if let Some((key, program)) = stack.last() {
+                    names
+                        .entry(*key)
+                        .or_insert((program.clone(), name.to_string()));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::pubkey::Pubkey;
+    use sqlx::types::Decimal;
+    use std::str::FromStr;
+
+    fn instruction(
+        program: &str,
+        index: u32,
+        inner_index: Option<u32>,
+        accounts: Vec<Pubkey>,
+        signers: Vec<Pubkey>,
+        data: Vec<u8>,
+    ) -> TxInstruction {
+        TxInstruction {
+            index,
+            inner_index,
+            program_id: Pubkey::from_str(program).unwrap_or_default(),
+            accounts,
+            signers,
+            data,
+        }
+    }
+
+    // Version byte followed by u32 instruction tag
+    fn ob_data(tag: u32) -> Vec<u8> {
+        let mut data = vec![0u8];
+        data.extend_from_slice(&tag.to_le_bytes());
+        data
+    }
+
+    fn tx_fill(signature: &str, instruction_index: u32, open_orders: &str, taker: &str) -> TxFill {
+        TxFill {
+            slot: 5,
+            signature: signature.to_string(),
+            tx_index: 0,
+            instruction_index,
+            inner_instruction_index: Some(instruction_index + 1),
+            market: "market".to_string(),
+            accounts: vec![open_orders.to_string()],
+            taker: Some(taker.to_string()),
+            fee_payer: format!("{}-payer", taker),
+        }
+    }
+
+    fn trade(signature: &str, taker_account: Option<&str>) -> MarketTrade {
+        MarketTrade {
+            slug: "SOL-USDC".to_string(),
+            order_id: None,
+            market_buy: 1,
+            avg_price: Decimal::ONE,
+            amount: Decimal::ONE,
+            timestamp: 0,
+            market_address: "market".to_string(),
+            blocktime: 0,
+            index: 0,
+            avg_price_lots: Decimal::ONE,
+            amount_lots: Decimal::ONE,
+            slot: 5,
+            transaction_signature: signature.to_string(),
+            maker: None,
+            price_usd: None,
+            notional_usd: None,
+            quote_usd_rate: None,
+            instruction_index: None,
+            inner_instruction_index: None,
+            taker: None,
+            fee_payer: None,
+            attribution: None,
+            taker_account: taker_account.map(|x| x.to_string()),
+            is_taker_buy: true,
+        }
+    }
+
+    fn logs(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(|x| x.to_string()).collect()
+    }
+
+    #[test]
+    fn names_instructions_by_top_level_index_and_inner_ordinal() {
+        let names = instruction_names(&logs(&[
+            "Program Budget invoke [1]",
+            "Program Budget success",
+            "Program Router invoke [1]",
+            "Program log: Instruction: Route",
+            "Program Gigadex invoke [2]",
+            "Program log: Instruction: MarketBuy",
+            "Program Token invoke [3]",
+            "Program log: Instruction: Transfer",
+            "Program Token success",
+            "Program Gigadex success",
+            "Program Gigadex invoke [2]",
+            "Program log: Instruction: CancelOrder",
+            "Program Gigadex success",
+            "Program Router success",
+            "Program Gigadex invoke [1]",
+            "Program log: Instruction: ClaimLots",
+            "Program Gigadex failed: custom program error: 0x1",
+        ]));
+
+        let name = |key: (u32, Option<u32>)| {
+            names
+                .get(&key)
+                .map(|(program, name)| (program.as_str(), name.as_str()))
+        };
+        assert_eq!(names.len(), 5);
+        assert_eq!(name((0, None)), None);
+        assert_eq!(name((1, None)), Some(("Router", "Route")));
+        assert_eq!(name((1, Some(0))), Some(("Gigadex", "MarketBuy")));
+        assert_eq!(name((1, Some(1))), Some(("Token", "Transfer")));
+        assert_eq!(name((1, Some(2))), Some(("Gigadex", "CancelOrder")));
+        assert_eq!(name((2, None)), Some(("Gigadex", "ClaimLots")));
+    }
+
+    #[test]
+    fn decodes_takers_of_fill_instructions() {
+        let accounts: Vec<Pubkey> = (0..10).map(|_| Pubkey::new_unique()).collect();
+        let owner = Some(accounts[7].to_string());
+        let signer = Pubkey::new_unique();
+        let names = HashMap::from([
+            (
+                (1, Some(0)),
+                (GIGADEX_PROGRAM_ID.to_string(), "MarketBuy".to_string()),
+            ),
+            (
+                (1, Some(1)),
+                (GIGADEX_PROGRAM_ID.to_string(), "CancelOrder".to_string()),
+            ),
+            (
+                (2, None),
+                (
+                    OPENBOOK_V2_PROGRAM_ID.to_string(),
+                    "PlaceTakeOrder".to_string(),
+                ),
+            ),
+            (
+                (3, None),
+                (
+                    OPENBOOK_V2_PROGRAM_ID.to_string(),
+                    "CancelOrder".to_string(),
+                ),
+            ),
+        ]);
+        let ob = |tag: u32| {
+            instruction(
+                OPENBOOK_PROGRAM_ID,
+                0,
+                None,
+                accounts.clone(),
+                vec![],
+                ob_data(tag),
+            )
+        };
+        let gd = |inner_index: u32, signers: Vec<Pubkey>| {
+            instruction(
+                GIGADEX_PROGRAM_ID,
+                1,
+                Some(inner_index),
+                accounts.clone(),
+                signers,
+                vec![],
+            )
+        };
+
+        assert_eq!(
+            decode_taker(&ob(OB_NEW_ORDER_V3_TAG), &names),
+            Some(owner.clone())
+        );
+        assert_eq!(decode_taker(&ob(OB_SEND_TAKE_TAG), &names), Some(owner));
+        // Settle funds isn't a taker instruction
+        assert_eq!(decode_taker(&ob(5), &names), None);
+        assert_eq!(
+            decode_taker(&gd(0, vec![signer]), &names),
+            Some(Some(signer.to_string()))
+        );
+        assert_eq!(decode_taker(&gd(0, vec![]), &names), Some(None));
+        assert_eq!(decode_taker(&gd(1, vec![signer]), &names), None);
+        let ob_v2 = |index: u32| {
+            instruction(
+                OPENBOOK_V2_PROGRAM_ID,
+                index,
+                None,
+                accounts.clone(),
+                vec![signer],
+                vec![],
+            )
+        };
+        assert_eq!(
+            decode_taker(&ob_v2(2), &names),
+            Some(Some(signer.to_string()))
+        );
+        assert_eq!(decode_taker(&ob_v2(3), &names), None);
+        // Unnamed v2 instruction can't be told apart from a cancel
+        assert_eq!(decode_taker(&ob_v2(4), &names), None);
+        let other = instruction(
+            PHOENIX_PROGRAM_ID,
+            0,
+            None,
+            accounts.clone(),
+            vec![],
+            ob_data(10),
+        );
+        assert_eq!(decode_taker(&other, &names), None);
+    }
+
+    #[test]
+    fn tracks_inner_taker_instruction_with_its_ordinal() {
+        let market = Pubkey::new_unique();
+        let accounts: Vec<Pubkey> = (0..10)
+            .map(|i| if i == 0 { market } else { Pubkey::new_unique() })
+            .collect();
+        let fee_payer = Pubkey::new_unique();
+        let mut tracker = TxFillTracker {
+            enabled: true,
+            market_accounts: HashMap::from([(market, "market".to_string())]),
+            ..Default::default()
+        };
+        let tx = Transaction {
+            slot: 5,
+            signature: "routed".to_string(),
+            index: 3,
+            fee_payer,
+            is_failed: false,
+            instructions: vec![
+                instruction(
+                    PHOENIX_PROGRAM_ID,
+                    0,
+                    None,
+                    vec![market],
+                    vec![fee_payer],
+                    vec![],
+                ),
+                instruction(GIGADEX_PROGRAM_ID, 0, Some(0), vec![], vec![], vec![]),
+                instruction(
+                    OPENBOOK_PROGRAM_ID,
+                    0,
+                    Some(1),
+                    accounts.clone(),
+                    vec![],
+                    ob_data(OB_NEW_ORDER_V3_TAG),
+                ),
+            ],
+            log_messages: vec![],
+        };
+
+        track_transaction(&tx, &mut tracker);
+        let fills = tracker.tx_fills.get("market").unwrap();
+        assert_eq!(fills.len(), 1);
+        assert_eq!(fills[0].instruction_index, 0);
+        assert_eq!(fills[0].inner_instruction_index, Some(1));
+        assert_eq!(fills[0].taker, Some(accounts[7].to_string()));
+        assert_eq!(fills[0].fee_payer, fee_payer.to_string());
+        assert!(tracker.seen_signatures.contains_key("routed"));
+    }
+
+    #[test]
+    fn attributes_trades_by_taker_account_then_marked_last_writer() {
+        let tracker = TxFillTracker {
+            enabled: true,
+            tx_fills: HashMap::from([(
+                "market".to_string(),
+                vec![
+                    tx_fill("tx-a", 0, "oo-alice", "alice"),
+                    tx_fill("tx-b", 1, "oo-bob", "bob"),
+                ],
+            )]),
+            ..Default::default()
+        };
+        let mut logged = trade("tx-logged", None);
+        logged.attribution = Some(TradeAttribution::LogEvent);
+        let mut trades = vec![
+            // Account update written last by tx-a holds bob's fill of tx-b
+            trade("tx-a", Some("oo-bob")),
+            // Send take fill has no taker open orders
+            trade("tx-a", None),
+            trade("tx-unknown", Some("oo-carol")),
+            logged,
+        ];
+
+        attribute_trades(&mut trades, &tracker);
+
+        assert_eq!(trades[0].transaction_signature, "tx-b");
+        assert_eq!(trades[0].instruction_index, Some(1));
+        assert_eq!(trades[0].inner_instruction_index, Some(2));
+        assert_eq!(trades[0].taker, Some("bob".to_string()));
+        assert_eq!(trades[0].fee_payer, Some("bob-payer".to_string()));
+        assert_eq!(trades[0].attribution, Some(TradeAttribution::TakerAccount));
+
+        assert_eq!(trades[1].transaction_signature, "tx-a");
+        assert_eq!(trades[1].taker, Some("alice".to_string()));
+        assert_eq!(trades[1].attribution, Some(TradeAttribution::LastWriter));
+
+        assert_eq!(trades[2].transaction_signature, "tx-unknown");
+        assert_eq!(trades[2].instruction_index, None);
+        assert_eq!(trades[2].attribution, None);
+
+        assert_eq!(trades[3].transaction_signature, "tx-logged");
+        assert_eq!(trades[3].taker, None);
+        assert_eq!(trades[3].attribution, Some(TradeAttribution::LogEvent));
+    }
+}
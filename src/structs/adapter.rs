@@ -0,0 +1,30 @@
+use super::{
+    makers::MakerQuote,
+    market::{MarketOrder, MarketOrders, MarketTrade, Venue, VenueMarket},
+};
+
+/*
+ * Venue-agnostic event decoded from an account update
+ */
+#[derive(Debug, Clone)]
+pub enum DexEvent {
+    BookSide {
+        market: String,
+        venue: Venue,
+        address: String,
+        is_bid: bool,
+        levels: Vec<MarketOrder>,
+        quotes: Vec<MakerQuote>,
+    },
+    Trades(Vec<MarketTrade>),
+}
+
+/*
+ * Initial orderbook of a venue market
+ */
+#[derive(Debug, Clone)]
+pub struct VenueSnapshot {
+    pub market: String,
+    pub venue_market: VenueMarket,
+    pub orders: MarketOrders,
+}
@@ -0,0 +1,89 @@
+use serde_derive::{Deserialize, Serialize};
+
+use crate::constants::{ALERT_CHANNEL_NAME, ALERT_COOLDOWN_SECS};
+
+use super::market::Venue;
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AlertCondition {
+    TradeNotional { threshold: f64 },
+    PriceMove { percent: f64, minutes: u64 },
+    Spread { bps: f64 },
+    EmptySide,
+}
+
+impl AlertCondition {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AlertCondition::TradeNotional { .. } => "trade_notional",
+            AlertCondition::PriceMove { .. } => "price_move",
+            AlertCondition::Spread { .. } => "spread",
+            AlertCondition::EmptySide => "empty_side",
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AlertSink {
+    Redis {
+        #[serde(default = "default_alert_channel")]
+        channel: String,
+    },
+    Webhook {
+        url: String,
+    },
+    Log,
+}
+
+/*
+ * Alert rule stored in redis's alert_rules, slug None matches every market
+ */
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct AlertRule {
+    pub id: String,
+    pub slug: Option<String>,
+    pub condition: AlertCondition,
+    pub sinks: Vec<AlertSink>,
+
+    #[serde(rename = "cooldownSecs", default = "default_alert_cooldown")]
+    pub cooldown_secs: u64,
+}
+
+/*
+ * Alert rules loaded from redis's alert_rules, owned by the subscribe loop and reloaded once expired
+ */
+#[derive(Debug, Clone, Default)]
+pub struct AlertRulesCache {
+    pub loaded_ts: Option<u64>,
+    pub rules: Vec<AlertRule>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct Alert {
+    #[serde(rename = "ruleId")]
+    pub rule_id: String,
+
+    pub slug: String,
+    pub venue: Option<Venue>,
+    pub condition: String,
+    pub value: f64,
+    pub threshold: f64,
+    pub message: String,
+    pub slot: u64,
+    pub timestamp: u64,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct AlertPublishData {
+    pub alert: Alert,
+}
+
+fn default_alert_channel() -> String {
+    ALERT_CHANNEL_NAME.to_string()
+}
+
+fn default_alert_cooldown() -> u64 {
+    ALERT_COOLDOWN_SECS
+}
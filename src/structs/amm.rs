@@ -0,0 +1,623 @@
+use bytemuck::{try_from_bytes, Pod, Zeroable};
+use solana_sdk::pubkey::Pubkey;
+use std::{
+    collections::{HashMap, HashSet},
+    mem::size_of,
+    str::FromStr,
+};
+
+use crate::constants::AMM_DEFAULT_FEE_BPS;
+
+use super::market::{MarketConfig, MarketOrder, Venue};
+
+const ANCHOR_DISCRIMINATOR_LEN: usize = 8;
+const TOKEN_ACCOUNT_AMOUNT_OFFSET: usize = 64;
+const MINT_SUPPLY_OFFSET: usize = 36;
+// Meteora dynamic pool: lp_mint, token_a_mint, token_b_mint, a_vault, b_vault, a_vault_lp, b_vault_lp after discriminator
+const METEORA_POOL_VAULTS_OFFSET: usize = 104;
+// Meteora vault: enabled, bumps, total_amount, token_vault, fee_vault, token_mint, lp_mint, 30 strategies, base, admin, operator, locked profit tracker
+const METEORA_VAULT_TOTAL_AMOUNT_OFFSET: usize = 11;
+const METEORA_VAULT_LP_MINT_OFFSET: usize = 115;
+const METEORA_VAULT_LOCKED_PROFIT_OFFSET: usize = 1203;
+const METEORA_LOCKED_PROFIT_DEGRADATION_DENOMINATOR: u128 = 1_000_000_000_000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AmmKind {
+    // Reserves held in base/quote token vaults
+    ConstantProduct,
+    // Meteora dynamic pool, reserves are pool's share of lp of Meteora vaults holding the tokens
+    MeteoraVault,
+    // Orca whirlpool, active liquidity and sqrt price in pool account
+    Whirlpool,
+}
+
+/*
+ * Accounts of Meteora vaults backing a dynamic pool, token a/b as in pool account
+ */
+#[derive(Debug, Clone, Default)]
+pub struct MeteoraVaults {
+    pub a_vault: Pubkey,
+    pub b_vault: Pubkey,
+    pub a_vault_lp: Pubkey,
+    pub b_vault_lp: Pubkey,
+    pub a_lp_mint: Pubkey,
+    pub b_lp_mint: Pubkey,
+}
+
+#[derive(Debug, Clone)]
+pub struct AmmPoolInfo {
+    pub name: String,
+    pub venue: Venue,
+    pub kind: AmmKind,
+    pub address: Pubkey,
+    pub base_vault: Pubkey,
+    pub quote_vault: Pubkey,
+    pub meteora: MeteoraVaults,
+    pub inverted: bool,
+    pub base_decimals: u8,
+    pub quote_decimals: u8,
+    pub fee_rate_bps: u64,
+}
+impl AmmPoolInfo {
+    /*
+     * Parse pool of market_info amm_pools entry, inverted if token a of pool is the quote
+     * raydium:{pool}:{base_vault}:{quote_vault}[:{fee_bps}]
+     * meteora:{pool}[:{fee_bps}][:inverted], vaults are discovered from pool account
+     * orca:{whirlpool}[:inverted]
+     */
+    pub fn from_config(market: &MarketConfig, config: &str) -> anyhow::Result<Self> {
+        let parts: Vec<&str> = config.trim().split(':').collect();
+        let address = Pubkey::from_str(parts.get(1).unwrap_or(&""))?;
+        let mut pool = AmmPoolInfo {
+            name: market.slug.clone(),
+            venue: Venue::Orca,
+            kind: AmmKind::Whirlpool,
+            address,
+            base_vault: address,
+            quote_vault: address,
+            meteora: MeteoraVaults::default(),
+            inverted: false,
+            base_decimals: market.base_decimals,
+            quote_decimals: market.quote_decimals,
+            fee_rate_bps: AMM_DEFAULT_FEE_BPS,
+        };
+
+        match parts[0] {
+            "raydium" => {
+                if parts.len() < 4 {
+                    anyhow::bail!("Missing vaults of amm pool: {}", config);
+                }
+                pool.venue = Venue::Raydium;
+                pool.kind = AmmKind::ConstantProduct;
+                pool.base_vault = Pubkey::from_str(parts[2])?;
+                pool.quote_vault = Pubkey::from_str(parts[3])?;
+                if let Some(fee_rate_bps) = parts.get(4) {
+                    pool.fee_rate_bps = fee_rate_bps.parse::<u64>()?;
+                }
+            }
+            "meteora" => {
+                pool.venue = Venue::Meteora;
+                pool.kind = AmmKind::MeteoraVault;
+                for part in parts.iter().skip(2) {
+                    if *part == "inverted" {
+                        pool.inverted = true;
+                    } else {
+                        pool.fee_rate_bps = part.parse::<u64>()?;
+                    }
+                }
+            }
+            "orca" => {
+                pool.inverted = parts.get(2).eq(&Some(&"inverted"));
+            }
+            kind => anyhow::bail!("Unknown amm pool kind: {}", kind),
+        }
+
+        Ok(pool)
+    }
+
+    pub fn accounts(&self) -> Vec<Pubkey> {
+        match self.kind {
+            AmmKind::ConstantProduct => vec![self.base_vault, self.quote_vault],
+            AmmKind::MeteoraVault => vec![
+                self.meteora.a_vault,
+                self.meteora.b_vault,
+                self.meteora.a_vault_lp,
+                self.meteora.b_vault_lp,
+                self.meteora.a_lp_mint,
+                self.meteora.b_lp_mint,
+            ],
+            AmmKind::Whirlpool => vec![self.address],
+        }
+    }
+
+    pub fn is_valid_account(&self, account: &Pubkey) -> bool {
+        self.accounts().contains(account)
+    }
+
+    /*
+     * Decode amount of vault pool account, token amount of token accounts, unlocked amount of Meteora vaults and supply of lp mints
+     */
+    pub fn account_amount(&self, account: &Pubkey, data: &[u8], now_ts: u64) -> Option<u64> {
+        if self.kind == AmmKind::MeteoraVault {
+            if account.eq(&self.meteora.a_vault) || account.eq(&self.meteora.b_vault) {
+                return meteora_vault_unlocked_amount(data, now_ts);
+            }
+            if account.eq(&self.meteora.a_lp_mint) || account.eq(&self.meteora.b_lp_mint) {
+                return read_u64(data, MINT_SUPPLY_OFFSET);
+            }
+        }
+        token_account_amount(data)
+    }
+
+    /*
+     * Reserves of vault pool from latest amounts of its accounts, None until every account is known
+     * Meteora reserve is vault's unlocked amount times pool's share of vault lp supply
+     */
+    pub fn vault_reserves(&self, amounts: &HashMap<Pubkey, u64>) -> Option<AmmReserves> {
+        let (base_atoms, quote_atoms) = match self.kind {
+            AmmKind::ConstantProduct => (
+                *amounts.get(&self.base_vault)? as f64,
+                *amounts.get(&self.quote_vault)? as f64,
+            ),
+            AmmKind::MeteoraVault => {
+                let share = |vault: &Pubkey, vault_lp: &Pubkey, lp_mint: &Pubkey| -> Option<f64> {
+                    let supply = *amounts.get(lp_mint)?;
+                    if supply == 0 {
+                        return Some(0.0);
+                    }
+                    Some(
+                        *amounts.get(vault)? as f64 * *amounts.get(vault_lp)? as f64
+                            / supply as f64,
+                    )
+                };
+                let a_atoms = share(
+                    &self.meteora.a_vault,
+                    &self.meteora.a_vault_lp,
+                    &self.meteora.a_lp_mint,
+                )?;
+                let b_atoms = share(
+                    &self.meteora.b_vault,
+                    &self.meteora.b_vault_lp,
+                    &self.meteora.b_lp_mint,
+                )?;
+                if self.inverted {
+                    (b_atoms, a_atoms)
+                } else {
+                    (a_atoms, b_atoms)
+                }
+            }
+            AmmKind::Whirlpool => return None,
+        };
+
+        Some(AmmReserves {
+            base: base_atoms / 10f64.powi(self.base_decimals as i32),
+            quote: quote_atoms / 10f64.powi(self.quote_decimals as i32),
+            price_range: None,
+        })
+    }
+}
+
+/*
+ * Readable base/quote reserves of pool, virtual reserves of active liquidity for whirlpool
+ */
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct AmmReserves {
+    pub base: f64,
+    pub quote: f64,
+    // Readable price range where reserves hold, None if curve holds at any price
+    pub price_range: Option<(f64, f64)>,
+}
+impl AmmReserves {
+    pub fn price(&self) -> Option<f64> {
+        if self.base <= 0.0 || self.quote <= 0.0 {
+            return None;
+        }
+        Some(self.quote / self.base)
+    }
+}
+
+/*
+ * Pool state between account updates
+ */
+#[derive(Debug, Clone, Default)]
+pub struct AmmState {
+    pub reserves: AmmReserves,
+    pub liquidity: u128,
+    // Latest amount and write version of every vault pool account
+    pub amounts: HashMap<Pubkey, u64>,
+    pub write_versions: HashMap<Pubkey, u64>,
+    // Slot, signature and accounts of transaction whose writes are being collected
+    pub pending_slot: u64,
+    pub pending_signature: Option<String>,
+    pub pending_accounts: HashSet<Pubkey>,
+    // Last reserves after every write of a transaction
+    pub settled: Option<AmmReserves>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AmmSwap {
+    pub is_buy: bool,
+    pub price: f64,
+    pub amount: f64,
+}
+
+/*
+ * Orca whirlpool account, fields up to token mint b
+ */
+#[derive(Copy, Clone)]
+#[repr(packed)]
+#[allow(dead_code)]
+pub struct WhirlpoolState {
+    pub whirlpools_config: [u8; 32],
+    pub whirlpool_bump: u8,
+    pub tick_spacing: u16,
+    pub tick_spacing_seed: [u8; 2],
+    pub fee_rate: u16,
+    pub protocol_fee_rate: u16,
+    pub liquidity: u128,
+    pub sqrt_price: u128,
+    pub tick_current_index: i32,
+    pub protocol_fee_owed_a: u64,
+    pub protocol_fee_owed_b: u64,
+    pub token_mint_a: [u8; 32],
+    pub token_vault_a: [u8; 32],
+    pub fee_growth_global_a: u128,
+    pub token_mint_b: [u8; 32],
+}
+unsafe impl Zeroable for WhirlpoolState {}
+unsafe impl Pod for WhirlpoolState {}
+
+impl WhirlpoolState {
+    pub fn load(data: &[u8]) -> Option<&Self> {
+        let start = ANCHOR_DISCRIMINATOR_LEN;
+        try_from_bytes(data.get(start..start + size_of::<Self>())?).ok()
+    }
+
+    /*
+     * Virtual reserves of active liquidity, x = L / sqrt(P) and y = L * sqrt(P) in readable units
+     * Liquidity only changes at initialized ticks, which are multiples of tick spacing, so reserves hold within current spacing range
+     */
+    pub fn reserves(&self, pool: &AmmPoolInfo) -> AmmReserves {
+        let liquidity = self.liquidity as f64;
+        let sqrt_price = self.sqrt_price as f64 / 2f64.powi(64);
+        if sqrt_price <= 0.0 {
+            return AmmReserves::default();
+        }
+
+        let (a_atoms, b_atoms) = (liquidity / sqrt_price, liquidity * sqrt_price);
+        let (base_atoms, quote_atoms) = if pool.inverted {
+            (b_atoms, a_atoms)
+        } else {
+            (a_atoms, b_atoms)
+        };
+
+        let tick_spacing = (self.tick_spacing as i32).max(1);
+        let lower_tick = self.tick_current_index.div_euclid(tick_spacing) * tick_spacing;
+        let readable_price = |tick: i32| {
+            let atoms_price = 1.0001f64.powi(if pool.inverted { -tick } else { tick });
+            atoms_price * 10f64.powi(pool.base_decimals as i32 - pool.quote_decimals as i32)
+        };
+        let (lower_price, upper_price) = (
+            readable_price(lower_tick),
+            readable_price(lower_tick + tick_spacing),
+        );
+
+        AmmReserves {
+            base: base_atoms / 10f64.powi(pool.base_decimals as i32),
+            quote: quote_atoms / 10f64.powi(pool.quote_decimals as i32),
+            price_range: Some((lower_price.min(upper_price), lower_price.max(upper_price))),
+        }
+    }
+
+    // Fee rate is in hundredths of a bps
+    pub fn fee_rate_bps(&self) -> u64 {
+        self.fee_rate as u64 / 100
+    }
+}
+
+/*
+ * Helper function for reading little endian u64 at offset
+ */
+fn read_u64(data: &[u8], offset: usize) -> Option<u64> {
+    let bytes = data.get(offset..offset + 8)?;
+    Some(u64::from_le_bytes(bytes.try_into().ok()?))
+}
+
+/*
+ * Helper function for reading pubkey at offset
+ */
+fn read_pubkey(data: &[u8], offset: usize) -> Option<Pubkey> {
+    Pubkey::try_from(data.get(offset..offset + 32)?).ok()
+}
+
+/*
+ * Helper function for reading amount of spl token account
+ */
+pub fn token_account_amount(data: &[u8]) -> Option<u64> {
+    read_u64(data, TOKEN_ACCOUNT_AMOUNT_OFFSET)
+}
+
+/*
+ * Helper function for reading a_vault, b_vault, a_vault_lp and b_vault_lp of Meteora dynamic pool account
+ */
+pub fn meteora_pool_vaults(data: &[u8]) -> Option<[Pubkey; 4]> {
+    let key = |i: usize| read_pubkey(data, METEORA_POOL_VAULTS_OFFSET + i * 32);
+    Some([key(0)?, key(1)?, key(2)?, key(3)?])
+}
+
+/*
+ * Helper function for reading lp mint of Meteora vault account
+ */
+pub fn meteora_vault_lp_mint(data: &[u8]) -> Option<Pubkey> {
+    read_pubkey(data, METEORA_VAULT_LP_MINT_OFFSET)
+}
+
+/*
+ * Helper function for unlocked amount of Meteora vault, total amount less profit still locked at now
+ * Locked profit degrades linearly since last report by degradation per second over 1e12
+ */
+pub fn meteora_vault_unlocked_amount(data: &[u8], now_ts: u64) -> Option<u64> {
+    let total_amount = read_u64(data, METEORA_VAULT_TOTAL_AMOUNT_OFFSET)?;
+    let last_updated_locked_profit = read_u64(data, METEORA_VAULT_LOCKED_PROFIT_OFFSET)? as u128;
+    let last_report = read_u64(data, METEORA_VAULT_LOCKED_PROFIT_OFFSET + 8)?;
+    let degradation = read_u64(data, METEORA_VAULT_LOCKED_PROFIT_OFFSET + 16)? as u128;
+
+    let locked_ratio = now_ts.saturating_sub(last_report) as u128 * degradation;
+    let locked_profit = if locked_ratio > METEORA_LOCKED_PROFIT_DEGRADATION_DENOMINATOR {
+        0
+    } else {
+        last_updated_locked_profit * (METEORA_LOCKED_PROFIT_DEGRADATION_DENOMINATOR - locked_ratio)
+            / METEORA_LOCKED_PROFIT_DEGRADATION_DENOMINATOR
+    };
+
+    Some(total_amount.saturating_sub(locked_profit as u64))
+}
+
+/*
+ * Function: construct_amm_levels
+ * 1. Step price away from pool price by step bps per level, clamping the last level to reserves' price range
+ * 2. Base amount of level is reserve change of constant product curve between neighbouring prices
+ * Lots are quote atoms per base and base atoms since pools have no lot sizes
+ */
+pub fn construct_amm_levels(
+    reserves: &AmmReserves,
+    pool: &AmmPoolInfo,
+    is_bid: bool,
+    depth: usize,
+    step_bps: u64,
+) -> Vec<MarketOrder> {
+    let price = match reserves.price() {
+        Some(price) => price,
+        None => return vec![],
+    };
+    let k = reserves.base * reserves.quote;
+    let base_at = |price: f64| (k / price).sqrt();
+    let step = step_bps as f64 / 10_000.0;
+
+    let mut levels: Vec<MarketOrder> = Vec::new();
+    let mut prev_price = price;
+    for i in 1..=depth {
+        let mut level_price = if is_bid {
+            price * (1.0 - step * i as f64)
+        } else {
+            price * (1.0 + step * i as f64)
+        };
+        if let Some((min_price, max_price)) = reserves.price_range {
+            level_price = level_price.clamp(min_price, max_price);
+        }
+        if level_price <= 0.0 || level_price == prev_price {
+            break;
+        }
+
+        let amount = (base_at(level_price) - base_at(prev_price)).abs();
+        prev_price = level_price;
+        levels.push(MarketOrder {
+            price: level_price,
+            amount,
+            price_lots: (level_price * 10f64.powi(pool.quote_decimals as i32)) as u64,
+            size_lots: (amount * 10f64.powi(pool.base_decimals as i32)) as u64,
+            venue: pool.venue,
+        });
+    }
+
+    levels
+}
+
+/*
+ * Function: detect_amm_swap
+ * 1. Swap moves base and quote reserves in opposite directions, liquidity changes move both the same way
+ * 2. Base flowing into pool is a sell, base flowing out is a buy, price is quote change over base change
+ */
+pub fn detect_amm_swap(prev: &AmmReserves, next: &AmmReserves) -> Option<AmmSwap> {
+    let base_delta = next.base - prev.base;
+    let quote_delta = next.quote - prev.quote;
+    if base_delta == 0.0 || quote_delta == 0.0 || base_delta.signum() == quote_delta.signum() {
+        return None;
+    }
+
+    Some(AmmSwap {
+        is_buy: base_delta < 0.0,
+        price: quote_delta.abs() / base_delta.abs(),
+        amount: base_delta.abs(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structs::fixtures::amm_pool;
+
+    fn reserves(base: f64, quote: f64) -> AmmReserves {
+        AmmReserves {
+            base,
+            quote,
+            price_range: None,
+        }
+    }
+
+    fn assert_close(a: f64, b: f64) {
+        assert!((a - b).abs() < 1e-9 * b.abs().max(1.0), "{} != {}", a, b);
+    }
+
+    #[test]
+    fn constructs_levels_along_constant_product_curve() {
+        let pool = amm_pool("SOL-USDC");
+        let pool_reserves = reserves(100.0, 10_000.0);
+
+        let bids = construct_amm_levels(&pool_reserves, &pool, true, 3, 100);
+        let prices: Vec<f64> = bids.iter().map(|x| x.price).collect();
+        assert_close(prices[0], 99.0);
+        assert_close(prices[1], 98.0);
+        assert_close(prices[2], 97.0);
+        // Selling into the pool down to the last level takes exactly the curve's base change
+        let base_at = |price: f64| (1_000_000.0f64 / price).sqrt();
+        let total: f64 = bids.iter().map(|x| x.amount).sum();
+        assert_close(total, base_at(97.0) - 100.0);
+        assert_eq!(bids[0].price_lots, 99_000_000);
+        assert_eq!(bids[0].size_lots, (bids[0].amount * 1e9) as u64);
+
+        let asks = construct_amm_levels(&pool_reserves, &pool, false, 3, 100);
+        assert_close(asks[0].price, 101.0);
+        assert_close(asks[0].amount, 100.0 - base_at(101.0));
+
+        // Empty pool has no price
+        assert!(construct_amm_levels(&reserves(0.0, 10.0), &pool, true, 3, 100).is_empty());
+    }
+
+    #[test]
+    fn clamps_levels_to_price_range() {
+        let pool = amm_pool("SOL-USDC");
+        let pool_reserves = AmmReserves {
+            price_range: Some((99.5, 100.5)),
+            ..reserves(100.0, 10_000.0)
+        };
+
+        let asks = construct_amm_levels(&pool_reserves, &pool, false, 5, 20);
+        let prices: Vec<f64> = asks.iter().map(|x| x.price).collect();
+        assert_eq!(prices.len(), 3);
+        assert_close(prices[1], 100.4);
+        assert_close(prices[2], 100.5);
+
+        let bids = construct_amm_levels(&pool_reserves, &pool, true, 5, 20);
+        assert_eq!(bids.len(), 3);
+        assert_close(bids[2].price, 99.5);
+    }
+
+    #[test]
+    fn detects_swaps_from_opposite_reserve_moves() {
+        let prev = reserves(100.0, 10_000.0);
+
+        // Base out of pool is a buy
+        let buy = detect_amm_swap(&prev, &reserves(99.0, 10_101.0)).unwrap();
+        assert!(buy.is_buy);
+        assert_close(buy.amount, 1.0);
+        assert_close(buy.price, 101.0);
+
+        let sell = detect_amm_swap(&prev, &reserves(102.0, 9_802.0)).unwrap();
+        assert!(!sell.is_buy);
+        assert_close(sell.amount, 2.0);
+        assert_close(sell.price, 99.0);
+
+        // Deposits/withdrawals and one sided changes aren't swaps
+        assert!(detect_amm_swap(&prev, &reserves(110.0, 11_000.0)).is_none());
+        assert!(detect_amm_swap(&prev, &reserves(90.0, 9_000.0)).is_none());
+        assert!(detect_amm_swap(&prev, &reserves(101.0, 10_000.0)).is_none());
+    }
+
+    #[test]
+    fn restricts_whirlpool_reserves_to_current_tick_spacing() {
+        let mut pool = amm_pool("SOL-USDC");
+        pool.kind = AmmKind::Whirlpool;
+
+        // 100 USDC per SOL is 0.1 quote atoms per base atom
+        let atoms_price = 0.1f64;
+        let mut whirlpool = WhirlpoolState::zeroed();
+        whirlpool.tick_spacing = 64;
+        whirlpool.liquidity = 1_000_000_000;
+        whirlpool.sqrt_price = (atoms_price.sqrt() * 2f64.powi(64)) as u128;
+        whirlpool.tick_current_index = (atoms_price.ln() / 1.0001f64.ln()).floor() as i32;
+
+        let pool_reserves = whirlpool.reserves(&pool);
+        let price = pool_reserves.price().unwrap();
+        assert_close(price, 100.0);
+        let (min_price, max_price) = pool_reserves.price_range.unwrap();
+        assert!(min_price <= price && price < max_price);
+        // Spacing of 64 ticks is about 0.64% wide
+        assert_close(max_price / min_price, 1.0001f64.powi(64));
+
+        // Inverted pool quotes token a per token b
+        pool.inverted = true;
+        pool.base_decimals = 6;
+        pool.quote_decimals = 9;
+        let inverted = whirlpool.reserves(&pool);
+        let (min_price, max_price) = inverted.price_range.unwrap();
+        let price = inverted.price().unwrap();
+        assert_close(price, 0.01);
+        assert!(min_price < price && price <= max_price);
+    }
+
+    #[test]
+    fn computes_meteora_reserves_from_vault_lp_share() {
+        let mut pool = amm_pool("SOL-USDC");
+        pool.kind = AmmKind::MeteoraVault;
+        pool.meteora = MeteoraVaults {
+            a_vault: Pubkey::new_unique(),
+            b_vault: Pubkey::new_unique(),
+            a_vault_lp: Pubkey::new_unique(),
+            b_vault_lp: Pubkey::new_unique(),
+            a_lp_mint: Pubkey::new_unique(),
+            b_lp_mint: Pubkey::new_unique(),
+        };
+
+        // Vault holding 400 SOL with 1000 lp supply of which pool owns 250
+        let mut vault = vec![0u8; 1227];
+        vault[11..19].copy_from_slice(&400_000_000_000u64.to_le_bytes());
+        let mut mint = vec![0u8; 82];
+        mint[36..44].copy_from_slice(&1_000u64.to_le_bytes());
+        let mut amounts = HashMap::from([
+            (
+                pool.meteora.a_vault,
+                pool.account_amount(&pool.meteora.a_vault, &vault, 0)
+                    .unwrap(),
+            ),
+            (
+                pool.meteora.a_lp_mint,
+                pool.account_amount(&pool.meteora.a_lp_mint, &mint, 0)
+                    .unwrap(),
+            ),
+            (pool.meteora.a_vault_lp, 250),
+            (pool.meteora.b_vault, 20_000_000_000),
+            (pool.meteora.b_lp_mint, 2_000),
+            (pool.meteora.b_vault_lp, 1_000),
+        ]);
+        let pool_reserves = pool.vault_reserves(&amounts).unwrap();
+        assert_close(pool_reserves.base, 100.0);
+        assert_close(pool_reserves.quote, 10_000.0);
+
+        pool.inverted = true;
+        pool.base_decimals = 6;
+        pool.quote_decimals = 9;
+        let inverted = pool.vault_reserves(&amounts).unwrap();
+        assert_close(inverted.base, 10_000.0);
+        assert_close(inverted.quote, 100.0);
+
+        // Unknown account amount means no reserves yet
+        amounts.remove(&pool.meteora.b_lp_mint);
+        assert!(pool.vault_reserves(&amounts).is_none());
+    }
+
+    #[test]
+    fn excludes_locked_profit_from_meteora_vault_amount() {
+        let mut vault = vec![0u8; 1227];
+        vault[11..19].copy_from_slice(&1_000u64.to_le_bytes());
+        // 100 locked profit reported at 1000, fully unlocked after 1e12 / 1e9 = 1000 seconds
+        vault[1203..1211].copy_from_slice(&100u64.to_le_bytes());
+        vault[1211..1219].copy_from_slice(&1_000u64.to_le_bytes());
+        vault[1219..1227].copy_from_slice(&1_000_000_000u64.to_le_bytes());
+
+        assert_eq!(meteora_vault_unlocked_amount(&vault, 1_000), Some(900));
+        assert_eq!(meteora_vault_unlocked_amount(&vault, 1_500), Some(950));
+        assert_eq!(meteora_vault_unlocked_amount(&vault, 3_000), Some(1_000));
+        assert_eq!(meteora_vault_unlocked_amount(&vault[..1_220], 1_000), None);
+    }
+}
@@ -0,0 +1,49 @@
+use serde_derive::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct DepthData {
+    pub bps: u64,
+
+    #[serde(rename = "bidAmount")]
+    pub bid_amount: f64,
+
+    #[serde(rename = "askAmount")]
+    pub ask_amount: f64,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct ExecutionData {
+    pub notional: f64,
+
+    #[serde(rename = "buyPrice")]
+    pub buy_price: Option<f64>,
+
+    #[serde(rename = "sellPrice")]
+    pub sell_price: Option<f64>,
+
+    #[serde(rename = "buySlippageBps")]
+    pub buy_slippage_bps: Option<f64>,
+
+    #[serde(rename = "sellSlippageBps")]
+    pub sell_slippage_bps: Option<f64>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct BookMetrics {
+    pub slug: String,
+    pub mid: f64,
+    pub spread: f64,
+
+    #[serde(rename = "spreadBps")]
+    pub spread_bps: f64,
+
+    pub depth: Vec<DepthData>,
+    pub executions: Vec<ExecutionData>,
+    pub slot: u64,
+    pub timestamp: u64,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct BookMetricsPublishData {
+    pub metrics: BookMetrics,
+}
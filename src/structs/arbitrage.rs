@@ -0,0 +1,36 @@
+use serde_derive::{Deserialize, Serialize};
+
+use super::market::Venue;
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct ArbOpportunity {
+    pub slug: String,
+
+    #[serde(rename = "buyVenue")]
+    pub buy_venue: Venue,
+
+    #[serde(rename = "sellVenue")]
+    pub sell_venue: Venue,
+
+    #[serde(rename = "bestAsk")]
+    pub best_ask: f64,
+
+    #[serde(rename = "bestBid")]
+    pub best_bid: f64,
+
+    pub size: f64,
+
+    pub edge: f64,
+
+    #[serde(rename = "edgeBps")]
+    pub edge_bps: f64,
+
+    pub slot: u64,
+
+    pub timestamp: u64,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct ArbPublishData {
+    pub arbitrage: ArbOpportunity,
+}
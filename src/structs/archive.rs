@@ -0,0 +1,26 @@
+use serde_derive::{Deserialize, Serialize};
+
+use super::market::{MarketOrders, Venue};
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct OrderbookSnapshot {
+    pub slug: String,
+    pub slot: u64,
+    pub write_version: u64,
+    pub timestamp: u64,
+    pub order_book: MarketOrders,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct OrderbookDelta {
+    pub slug: String,
+    pub slot: u64,
+    pub write_version: u64,
+    pub timestamp: u64,
+    pub is_bid: bool,
+    pub venue: Venue,
+    pub price_lots: u64,
+    pub size_lots: u64,
+    pub price: f64,
+    pub amount: f64,
+}
@@ -0,0 +1,33 @@
+use anchor_lang::{prelude::Pubkey, AnchorDeserialize, AnchorSerialize};
+use serde_derive::{Deserialize, Serialize};
+
+/*
+ * Jupiter Perpetuals Pool account (JLP pool 5BUwFW4nRbftYTDMbgxykoFWqWHPzahFSNAaaaJtVKsq), decoded up to aum_usd
+ * Borsh fields after the anchor "Pool" discriminator follow the program's IDL (jup-ag perpetuals Pool):
+ * name, custodies, aum_usd, then limit/fees/pool_apr/... which aren't needed
+ */
+#[derive(Clone, Debug, PartialEq, AnchorDeserialize, AnchorSerialize)]
+pub struct AumAccount {
+    pub name: String,
+    pub custodies: Vec<Pubkey>,
+    pub aum_usd: u128,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct AumData {
+    pub address: String,
+
+    #[serde(rename = "aumUsdRaw")]
+    pub aum_usd_raw: String,
+
+    #[serde(rename = "aumUsd")]
+    pub aum_usd: f64,
+
+    pub slot: u64,
+    pub timestamp: u64,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct AumPublishData {
+    pub aum: AumData,
+}
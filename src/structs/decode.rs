@@ -0,0 +1,154 @@
+use anchor_lang::solana_program::hash::hash;
+use bytemuck::{try_from_bytes, Pod};
+use std::{fmt, mem::size_of};
+
+const ANCHOR_DISCRIMINATOR_LEN: usize = 8;
+
+/*
+ * Reason an account update couldn't be decoded, malformed accounts are counted and skipped
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    // Account data length doesn't match the account layout
+    InvalidLength {
+        account: &'static str,
+        expected: usize,
+        actual: usize,
+    },
+    // Anchor discriminator doesn't match the account type
+    InvalidDiscriminator {
+        account: &'static str,
+    },
+    // Serum head/tail padding is missing around account data
+    InvalidPadding {
+        account: &'static str,
+    },
+    // Account flags don't match the account type
+    InvalidFlags {
+        account: &'static str,
+        flags: u64,
+    },
+    // Index read from account data points outside of its array
+    OutOfBounds {
+        account: &'static str,
+        index: usize,
+        len: usize,
+    },
+    // Account layout is valid but its content is inconsistent
+    Corrupted {
+        account: &'static str,
+        reason: &'static str,
+    },
+    // Field of geyser update is missing or invalid
+    InvalidUpdate {
+        field: &'static str,
+    },
+    // Account read at startup doesn't exist
+    MissingAccount {
+        account: &'static str,
+    },
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::InvalidLength {
+                account,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "invalid {} account length: expected {}, got {}",
+                account, expected, actual
+            ),
+            DecodeError::InvalidDiscriminator { account } => {
+                write!(f, "invalid {} account discriminator", account)
+            }
+            DecodeError::InvalidPadding { account } => {
+                write!(f, "invalid {} account padding", account)
+            }
+            DecodeError::InvalidFlags { account, flags } => {
+                write!(f, "invalid {} account flags: {:#x}", account, flags)
+            }
+            DecodeError::OutOfBounds {
+                account,
+                index,
+                len,
+            } => write!(
+                f,
+                "{} account index {} out of bounds of {}",
+                account, index, len
+            ),
+            DecodeError::Corrupted { account, reason } => {
+                write!(f, "corrupted {} account: {}", account, reason)
+            }
+            DecodeError::InvalidUpdate { field } => {
+                write!(f, "invalid geyser update field: {}", field)
+            }
+            DecodeError::MissingAccount { account } => {
+                write!(f, "missing {} account", account)
+            }
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/*
+ * Helper function for slicing account data of given length starting at offset
+ */
+pub fn read_bytes<'a>(
+    data: &'a [u8],
+    account: &'static str,
+    start: usize,
+    len: usize,
+) -> Result<&'a [u8], DecodeError> {
+    data.get(start..start + len)
+        .ok_or(DecodeError::InvalidLength {
+            account,
+            expected: start + len,
+            actual: data.len(),
+        })
+}
+
+/*
+ * Helper function for casting account data at offset into packed layout T
+ */
+pub fn read_pod<'a, T: Pod>(
+    data: &'a [u8],
+    account: &'static str,
+    start: usize,
+) -> Result<&'a T, DecodeError> {
+    let bytes = read_bytes(data, account, start, size_of::<T>())?;
+    try_from_bytes(bytes).map_err(|_| DecodeError::Corrupted {
+        account,
+        reason: "unaligned layout",
+    })
+}
+
+/*
+ * Helper function for anchor discriminator of account named account_name, sha256("account:{name}")[..8]
+ */
+pub fn anchor_discriminator(account_name: &str) -> [u8; ANCHOR_DISCRIMINATOR_LEN] {
+    let preimage = format!("account:{}", account_name);
+    let mut discriminator = [0u8; ANCHOR_DISCRIMINATOR_LEN];
+    discriminator
+        .copy_from_slice(&hash(preimage.as_bytes()).to_bytes()[..ANCHOR_DISCRIMINATOR_LEN]);
+    discriminator
+}
+
+/*
+ * Helper function for checking anchor discriminator of account named account_name
+ */
+pub fn check_anchor_discriminator(
+    data: &[u8],
+    account_name: &str,
+    account: &'static str,
+) -> Result<(), DecodeError> {
+    if read_bytes(data, account, 0, ANCHOR_DISCRIMINATOR_LEN)? != anchor_discriminator(account_name)
+    {
+        return Err(DecodeError::InvalidDiscriminator { account });
+    }
+
+    Ok(())
+}
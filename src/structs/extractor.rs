@@ -1,202 +1,263 @@
-
-use openbook_dex::{
-    matching::Side,
-    state::{
-        strip_header, 
-        Event,
-        EventQueueHeader, 
-        EventView, 
-        Queue
-    },
-};
-use redis::{
-    Client, 
-    Connection
-};
+use openbook_dex::{matching::Side, state::EventView};
+use solana_sdk::pubkey::Pubkey;
 use sqlx::types::Decimal;
-use std::{
-    collections::{HashMap, HashSet},
-    error::Error,
-    str::FromStr,
-    time::Duration,
-    vec,
-};
-
-use anchor_lang::AnchorDeserialize;
-use solana_account_decoder::UiAccountEncoding;
-use solana_client::{
-    nonblocking::rpc_client::RpcClient, 
-    rpc_config::RpcAccountInfoConfig
-};
-use solana_sdk::{
-    account_info::AccountInfo, 
-    commitment_config::CommitmentConfig, 
-    program_pack::Pack, pubkey::Pubkey
-};
 
 use crate::{
-    processor::market::{
-        publish_trades_data, 
-        update_trades
-    },
-    structs::{
-        geyser::Account,
-        market::{MarketConfig, MarketOrder},
-        mint::Mint,
-        openbook::{ObMarketInfo, ObMarketState},
-        slab::{construct_levels, Slab},
-    },
+    structs::{openbook::ObMarketInfo, openbook_v2::ObV2FillEvent},
     utils::{array_to_pubkey, token_factor},
 };
 
-use yellowstone_grpc_client::{
-    GeyserGrpcClient, 
-    GeyserGrpcClientError
-};
-use yellowstone_grpc_proto::{
-    prelude::{
-        subscribe_update::UpdateOneof, 
-        SubscribeRequest,
-        SubscribeRequestFilterAccounts,
-    },
-    tonic::service::Interceptor,
-};
-
+/*
+ * Decoded fill of openbook event queue in readable and lot units
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fill {
+    pub order_id: u128,
+    pub owner: Pubkey,
+    pub maker: bool,
+    pub is_buy: bool,
+    pub price: Decimal,
+    pub size: Decimal,
+    pub price_lots: Decimal,
+    pub size_lots: Decimal,
+}
 
-pub async fn extractor (
-    api_url: String,
-    redis_client: &Client,
-    rpc_client: &RpcClient,
-    market_address: String,
-) -> Result<(), Box<dyn Error>> {
+/*
+ * Function: extract_fill
+ * 1. Calculate price before fees from paid/received quote amount and fee or rebate
+ * 2. Calculate readable price/size and their lots based on market's decimals and lot sizes
+ * Size lots follow openbook trades' published amount_lots, size scaled by quote decimals and quote lot size
+ * Returns None for out events and fills without base quantity
+ */
+pub fn extract_fill(event: EventView, market: &ObMarketInfo) -> Option<Fill> {
+    let (side, maker, native_qty_paid, native_qty_received, native_fee_or_rebate, order_id, owner) =
+        match event {
+            EventView::Fill {
+                side,
+                maker,
+                native_qty_paid,
+                native_qty_received,
+                native_fee_or_rebate,
+                order_id,
+                owner,
+                ..
+            } => (
+                side,
+                maker,
+                native_qty_paid,
+                native_qty_received,
+                native_fee_or_rebate,
+                order_id,
+                owner,
+            ),
+            _ => return None,
+        };
 
-    tracing::info!("Extracting data...");
+    let base_factor = token_factor(market.base_decimals);
+    let quote_factor = token_factor(market.quote_decimals);
 
-    let market_state = market_orders
-      .get_mut(&market.address.to_string())
-      .unwrap();
+    let (native_base_qty, price_before_fees) = match side {
+        Side::Bid => (
+            native_qty_received,
+            Decimal::from(native_qty_paid) + Decimal::from(native_fee_or_rebate),
+        ),
+        Side::Ask => (
+            native_qty_paid,
+            Decimal::from(native_qty_received) - Decimal::from(native_fee_or_rebate),
+        ),
+    };
+    if native_base_qty == 0 {
+        return None;
+    }
 
-    let mut api_url_conn = api_url
-      .get_connection()
-      .expect("Failed to get api url connection");
+    let price = (price_before_fees * base_factor)
+        .checked_div(quote_factor * Decimal::from(native_base_qty))?;
+    let price_lots = price
+        .checked_mul(quote_factor)
+        .unwrap_or_default()
+        .checked_mul(Decimal::from(market.base_lot_size))
+        .unwrap_or_default()
+        .checked_div(base_factor)
+        .unwrap_or_default()
+        .checked_div(Decimal::from(market.quote_lot_size))
+        .unwrap_or_default()
+        .round();
 
-    let mut redis_conn = redis_client
-     .get_connection()
-     .expect("Failed to get redis connection");
+    let size = Decimal::from(native_base_qty)
+        .checked_div(base_factor)
+        .unwrap_or_default();
+    let size_lots = size
+        .checked_mul(quote_factor)
+        .unwrap_or_default()
+        .checked_div(Decimal::from(market.quote_lot_size))
+        .unwrap_or_default();
 
-     let mut rcp_client_conn = rcp_client
-     .get_connection()
-     .expect("Failed to get rcp client connection");
+    Some(Fill {
+        order_id,
+        owner: array_to_pubkey(owner),
+        maker,
+        is_buy: match side {
+            Side::Bid => true,
+            Side::Ask => false,
+        },
+        price,
+        size,
+        price_lots,
+        size_lots,
+    })
+}
 
-     .get_connection()
-     .expect("Failed to get geyser client connection");
+/*
+ * Function: extract_v2_fill
+ * 1. Calculate readable price/size from openbook v2 fill's price/quantity lots
+ * 2. Take maker side as opposite of taker side, market sequence number as order id
+ * Returns None for fills without quantity
+ */
+pub fn extract_v2_fill(event: &ObV2FillEvent, market: &ObMarketInfo) -> Option<Fill> {
+    let (price_lots, quantity) = (event.price, event.quantity);
+    if quantity <= 0 || price_lots <= 0 {
+        return None;
+    }
 
-     let mut api_url_conn = api_url_conn.clone();
-     let mut redis_conn = redis_conn.clone();
-     let mut rcp_client_conn = rcp_client_conn.clone();
+    let base_factor = token_factor(market.base_decimals);
+    let quote_factor = token_factor(market.quote_decimals);
+    let price_lots = Decimal::from(price_lots);
+    let size_lots = Decimal::from(quantity);
 
-     let mut accounts: Vec<String> = Vec::new();
-     let market_keys: Vec<String> = redis_conn
-      .smembers("markets")
-      .expect("Get markets failed");
+    let price = (price_lots * Decimal::from(market.quote_lot_size) * base_factor)
+        .checked_div(Decimal::from(market.base_lot_size) * quote_factor)?;
+    let size = (size_lots * Decimal::from(market.base_lot_size))
+        .checked_div(base_factor)
+        .unwrap_or_default();
 
-    let mut markets: Vec<MarketConfig> = Vec::new();
+    Some(Fill {
+        order_id: event.market_seq_num as u128,
+        owner: Pubkey::new_from_array(event.maker),
+        maker: true,
+        is_buy: event.taker_side == 1,
+        price,
+        size,
+        price_lots,
+        size_lots,
+    })
+}
 
-    let rpc_config = RpcAccountInfoConfig {
-        encoding: Some(UiAccountEncoding::Base64),
-        data_slice: None,
-        commitment: Some(CommitmentConfig::confirmed()),
-        min_context_slot: None,
-    };
+#[cfg(test)]
+mod tests {
+    use openbook_dex::fees::FeeTier;
+    use std::str::FromStr;
 
-    let market_keys = markets
-        .iter()
-        .filter(|x| x.ob_market_address.is_some())
-        .map(|x| Pubkey::from_str(&x.ob_market_address.clone().unwrap()).unwrap())
-        .collect::<Vec<Pubkey>>();
-        
-    let mut market_results = rpc_client
-        .get_multiple_accounts_with_config(&market_keys, rpc_config.clone())
-        .await?
-        .value;
-
-    let mut mint_key_map = HashMap::new();
-
-    let mut market_infos = market_results
-        .iter_mut()
-        .map(|r| {
-            let get_account_result = r.as_mut().unwrap();
-
-            let mut market_bytes: &[u8] = &mut get_account_result.data[5..];
-            let raw_market: ObMarketState =
-                AnchorDeserialize::deserialize(&mut market_bytes).unwrap();
-
-            let market_address = array_to_pubkey(raw_market.own_address);
-            let bids_key = array_to_pubkey(raw_market.bids);
-            let asks_key = array_to_pubkey(raw_market.asks);
-            let event_queue_key = array_to_pubkey(raw_market.event_q);
-            let base_mint_key = array_to_pubkey(raw_market.coin_mint);
-            let quote_mint_key = array_to_pubkey(raw_market.pc_mint);
-            mint_key_map.insert(base_mint_key, 0);
-            mint_key_map.insert(quote_mint_key, 0);
-
-            let market_name = markets
-                .iter()
-                .find(|x| {
-                    x.ob_market_address.clone().unwrap_or_default() == market_address.to_string()
-                })
-                .unwrap()
-                .slug
-                .clone();
-        })
-        .collect::<Vec<ObMarketInfo>>();
-
-    if market_infos .is_empty() {
-        let base_factor = token_factor(market.base_decimals);
-        let quote_factor = token_factor(market.quote_decimals);
-
-        let price_before_fees = Decimal::from(match side {
-            Side::Bid => native_qty_paid + native_fee_or_rebate,
-            Side::Ask => native_qty_received - native_fee_or_rebate,
-        });
-
-        let price = match side {
-            Side::Bid => {
-                (price_before_fees * base_factor)
-                    / (quote_factor * Decimal::from(native_qty_received))
-            }
-            Side::Ask => {
-                (price_before_fees * base_factor)
-                    / (quote_factor * Decimal::from(native_qty_paid))
-            }
-        };
+    use super::*;
+    use crate::structs::fixtures::ob_v2_fill_event;
 
-        let amount = match side {
-            Side::Bid => Decimal::from(native_qty_received),
-            Side::Ask => Decimal::from(native_qty_paid),
+    fn market() -> ObMarketInfo {
+        ObMarketInfo {
+            name: "sol-usdc".to_string(),
+            address: Pubkey::default(),
+            base_mint: Pubkey::default(),
+            quote_mint: Pubkey::default(),
+            bids: Pubkey::default(),
+            asks: Pubkey::default(),
+            event_queue: Pubkey::default(),
+            base_decimals: 9,
+            quote_decimals: 6,
+            base_lot_size: 1_000_000,
+            quote_lot_size: 1,
+            fee_rate_bps: 0,
         }
-        .checked_div(base_factor)
-        .unwrap_or_default();
+    }
 
-        let is_buy = match side {
-            Side::Bid => true,
-            Side::Ask => false,
+    fn fill_event(
+        side: Side,
+        native_qty_paid: u64,
+        native_qty_received: u64,
+        native_fee_or_rebate: u64,
+    ) -> EventView {
+        EventView::Fill {
+            side,
+            maker: true,
+            native_qty_paid,
+            native_qty_received,
+            native_fee_or_rebate,
+            order_id: 7,
+            owner: [1, 2, 3, 4],
+            owner_slot: 0,
+            fee_tier: FeeTier::Base,
+            client_order_id: None,
         }
+    }
 
-        tracing::info!(
-            "OpenBook market fills: {}, {}, {}",
-            price,
-            amount,
-            is_buy
-        );
-        let output = (
-            price,
-            amount,
-            is_buy
+    #[test]
+    fn extracts_bid_fill() {
+        // Maker bid paid 100 quote with 0.02 rebate for 2 base
+        let fill = extract_fill(
+            fill_event(Side::Bid, 99_980_000, 2_000_000_000, 20_000),
+            &market(),
+        )
+        .unwrap();
+
+        assert!(fill.is_buy);
+        assert!(fill.maker);
+        assert_eq!(fill.order_id, 7);
+        assert_eq!(fill.owner, array_to_pubkey([1, 2, 3, 4]));
+        assert_eq!(fill.price, Decimal::from(50));
+        assert_eq!(fill.size, Decimal::from(2));
+        assert_eq!(fill.price_lots, Decimal::from(50_000));
+        assert_eq!(fill.size_lots, Decimal::from(2_000_000));
+    }
+
+    #[test]
+    fn extracts_ask_fill() {
+        // Maker ask sold 0.5 base for 25.01 quote including 0.01 fee
+        let fill = extract_fill(
+            fill_event(Side::Ask, 500_000_000, 25_010_000, 10_000),
+            &market(),
+        )
+        .unwrap();
+
+        assert!(!fill.is_buy);
+        assert_eq!(fill.price, Decimal::from(50));
+        assert_eq!(fill.size, Decimal::from_str("0.5").unwrap());
+        assert_eq!(fill.price_lots, Decimal::from(50_000));
+        assert_eq!(fill.size_lots, Decimal::from(500_000));
+    }
+
+    #[test]
+    fn skips_empty_fill() {
+        assert_eq!(
+            extract_fill(fill_event(Side::Bid, 0, 0, 0), &market()),
+            None
         );
-        OK(output)
     }
 
-}
+    #[test]
+    fn extracts_v2_fill_with_maker_side() {
+        // Taker ask of 2 base at 50 fills a maker bid
+        let fill = extract_v2_fill(&ob_v2_fill_event(9, 50_000, 2_000, 1), &market()).unwrap();
 
+        assert!(fill.is_buy);
+        assert!(fill.maker);
+        assert_eq!(fill.order_id, 9);
+        assert_eq!(fill.owner, Pubkey::new_from_array([1; 32]));
+        assert_eq!(fill.price, Decimal::from(50));
+        assert_eq!(fill.size, Decimal::from(2));
+        assert_eq!(fill.price_lots, Decimal::from(50_000));
+        assert_eq!(fill.size_lots, Decimal::from(2_000));
 
+        // Taker bid fills a maker ask
+        let fill = extract_v2_fill(&ob_v2_fill_event(10, 50_000, 2_000, 0), &market()).unwrap();
+        assert!(!fill.is_buy);
+    }
+
+    #[test]
+    fn skips_empty_v2_fill() {
+        assert_eq!(
+            extract_v2_fill(&ob_v2_fill_event(9, 50_000, 0, 1), &market()),
+            None
+        );
+        assert_eq!(
+            extract_v2_fill(&ob_v2_fill_event(9, 0, 2_000, 1), &market()),
+            None
+        );
+    }
+}
@@ -0,0 +1,884 @@
+use anchor_lang::AnchorSerialize;
+use bytemuck::Zeroable;
+use num_traits::FromPrimitive;
+use openbook_dex::{
+    fees::FeeTier,
+    matching::Side,
+    state::{Event, EventView},
+};
+use solana_sdk::{program_pack::Pack, pubkey::Pubkey};
+use sqlx::types::Decimal;
+use std::{collections::HashMap, mem::size_of};
+use yellowstone_grpc_proto::prelude::{
+    subscribe_update::UpdateOneof, SubscribeUpdate, SubscribeUpdateAccount,
+    SubscribeUpdateAccountInfo,
+};
+
+use super::{
+    amm::{AmmKind, AmmPoolInfo, MeteoraVaults},
+    decode::anchor_discriminator,
+    geyser::Account,
+    gigadex::{
+        FilledOrder, GdMarketInfo, GdMarketOrder, GdMarketOrderLog, GdMarketState, GdUserAccount,
+        Node, NodeDeltaLog, OrderTree, UserBalances, GD_MARKET_ACCOUNT, GD_ORDER_LOG_ACCOUNT,
+        GD_ORDER_TREE_ACCOUNT, GD_USER_ACCOUNT, GD_USER_BALANCES_ACCOUNT,
+    },
+    market::{MarketConfig, MarketOrder, MarketOrders, MarketTrade, SummaryData, Venue},
+    mint::Mint,
+    openbook::ObMarketInfo,
+    openbook_v2::{ObV2FillEvent, ObV2LeafNode},
+    phoenix::{
+        PhoenixFifoMarket, PhoenixMarketHeader, PhoenixMarketInfo, PhoenixOrder, PhoenixTrader,
+    },
+    slab::LeafNode,
+};
+use crate::constants::PHOENIX_LOG_INSTRUCTION_TAG;
+
+const OB_ACCOUNT_HEAD: &[u8; 5] = b"serum";
+const OB_ACCOUNT_TAIL: &[u8; 7] = b"padding";
+const OB_NODE_SIZE: usize = 72;
+const OB_MARKET_WORDS: usize = 47;
+const PYTH_PRICE_ACCOUNT_SIZE: usize = 3312;
+const SWITCHBOARD_AGGREGATOR_ACCOUNT_SIZE: usize = 3851;
+// Initialized | EventQueue/Bids/Asks account flags
+const OB_EVENT_QUEUE_FLAGS: u64 = 1 | 1 << 4;
+const OB_BIDS_FLAGS: u64 = 1 | 1 << 5;
+const OB_ASKS_FLAGS: u64 = 1 | 1 << 6;
+const OB_V2_NODE_SIZE: usize = 88;
+const OB_V2_BOOK_SIDE_HEADER_SIZE: usize = 832;
+const OB_V2_EVENT_NODE_SIZE: usize = 152;
+const PHOENIX_TREE_HEADER_SIZE: usize = 32;
+const PHOENIX_ORDER_NODE_SIZE: usize = 64;
+const PHOENIX_TRADER_NODE_SIZE: usize = 144;
+const GD_DISCRIMINATOR_LEN: usize = 8;
+const GD_BALANCE_ENTRY_SIZE: usize = 16;
+
+/*
+ * Linear congruential generator for reproducible property test inputs
+ */
+pub struct Lcg(pub u64);
+
+impl Lcg {
+    pub fn next(&mut self) -> u64 {
+        self.0 = self
+            .0
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        self.0 >> 33
+    }
+
+    /*
+     * Next value in [low, high)
+     */
+    pub fn range(&mut self, low: u64, high: u64) -> u64 {
+        low + self.next() % (high - low)
+    }
+}
+
+/*
+ * Fixture openbook market, 9 decimals base in 0.001 lots and 6 decimals quote in 1e-6 lots
+ */
+pub fn ob_market(name: &str) -> ObMarketInfo {
+    ObMarketInfo {
+        name: name.to_string(),
+        address: Pubkey::new_unique(),
+        base_mint: Pubkey::new_unique(),
+        quote_mint: Pubkey::new_unique(),
+        bids: Pubkey::new_unique(),
+        asks: Pubkey::new_unique(),
+        event_queue: Pubkey::new_unique(),
+        base_decimals: 9,
+        quote_decimals: 6,
+        base_lot_size: 1_000_000,
+        quote_lot_size: 1,
+        fee_rate_bps: 0,
+    }
+}
+
+/*
+ * Fixture openbook market account of market, serum padded state of its address, mints, book accounts and lot sizes
+ */
+pub fn ob_market_data(market: &ObMarketInfo) -> Vec<u8> {
+    let mut state = vec![0u8; OB_MARKET_WORDS * 8];
+    let mut write = |word: usize, bytes: &[u8]| {
+        state[word * 8..word * 8 + bytes.len()].copy_from_slice(bytes);
+    };
+    write(1, &market.address.to_bytes());
+    write(6, &market.base_mint.to_bytes());
+    write(10, &market.quote_mint.to_bytes());
+    write(31, &market.event_queue.to_bytes());
+    write(35, &market.bids.to_bytes());
+    write(39, &market.asks.to_bytes());
+    write(43, &market.base_lot_size.to_le_bytes());
+    write(44, &market.quote_lot_size.to_le_bytes());
+    write(45, &market.fee_rate_bps.to_le_bytes());
+
+    [
+        OB_ACCOUNT_HEAD.as_slice(),
+        &state,
+        OB_ACCOUNT_TAIL.as_slice(),
+    ]
+    .concat()
+}
+
+/*
+ * Fixture initialized mint account of decimals
+ */
+pub fn mint_data(decimals: u8) -> Vec<u8> {
+    let mut data = vec![0u8; Mint::LEN];
+    data[44] = decimals;
+    data[45] = 1;
+    data
+}
+
+/*
+ * Fixture openbook leaf of price/quantity lots, seq_num keeps keys of same price unique
+ */
+pub fn ob_leaf(price_lots: u64, quantity: u64, seq_num: u64, owner: [u64; 4]) -> LeafNode {
+    LeafNode {
+        tag: 2,
+        owner_slot: 0,
+        fee_tier: 0,
+        padding: [0; 2],
+        key: ((price_lots as u128) << 64) | seq_num as u128,
+        owner,
+        quantity,
+        client_order_id: 0,
+    }
+}
+
+/*
+ * Function: ob_slab
+ * 1. Sort leaves by key and place them at node 0..n
+ * 2. Chain inner nodes n..2n-1 whose lower child is a leaf and upper child is the next inner node
+ * 3. Wrap slab header and nodes with openbook account head, bids/asks flags and tail padding
+ */
+pub fn ob_slab(leaves: &[LeafNode], is_bid: bool) -> Vec<u8> {
+    let mut leaves = leaves.to_vec();
+    leaves.sort_by_key(|x| x.key);
+    let num_leaves = leaves.len() as u32;
+
+    let mut nodes: Vec<u8> = Vec::new();
+    for leaf in leaves.iter() {
+        nodes.extend_from_slice(bytemuck::bytes_of(leaf));
+    }
+    for i in 0..num_leaves.saturating_sub(1) {
+        let upper = if i + 2 == num_leaves {
+            num_leaves - 1
+        } else {
+            num_leaves + i + 1
+        };
+        let mut node = vec![0u8; OB_NODE_SIZE];
+        node[0..4].copy_from_slice(&1u32.to_le_bytes());
+        node[8..24].copy_from_slice(&leaves[i as usize].key.to_le_bytes());
+        node[24..28].copy_from_slice(&i.to_le_bytes());
+        node[28..32].copy_from_slice(&upper.to_le_bytes());
+        nodes.extend(node);
+    }
+    let num_nodes = (nodes.len() / OB_NODE_SIZE) as u64;
+    let root = if num_leaves > 1 { num_leaves } else { 0 };
+
+    let flags = if is_bid { OB_BIDS_FLAGS } else { OB_ASKS_FLAGS };
+
+    let mut data: Vec<u8> = OB_ACCOUNT_HEAD.to_vec();
+    data.extend_from_slice(&flags.to_le_bytes());
+    data.extend_from_slice(&num_nodes.to_le_bytes());
+    data.extend_from_slice(&0u64.to_le_bytes());
+    data.extend_from_slice(&0u32.to_le_bytes());
+    data.extend_from_slice(&root.to_le_bytes());
+    data.extend_from_slice(&(num_leaves as u64).to_le_bytes());
+    data.extend(nodes);
+    data.extend_from_slice(OB_ACCOUNT_TAIL);
+    data
+}
+
+/*
+ * Fixture openbook maker or taker fill event, paid/received are native quote/base amounts by side
+ */
+pub fn ob_fill_event(
+    side: Side,
+    maker: bool,
+    native_qty_paid: u64,
+    native_qty_received: u64,
+    native_fee_or_rebate: u64,
+    order_id: u128,
+    owner: [u64; 4],
+) -> Event {
+    Event::new(EventView::Fill {
+        side,
+        maker,
+        native_qty_paid,
+        native_qty_received,
+        native_fee_or_rebate,
+        order_id,
+        owner,
+        owner_slot: 0,
+        fee_tier: FeeTier::Base,
+        client_order_id: None,
+    })
+}
+
+/*
+ * Function: ob_event_queue
+ * 1. Place events into ring buffer of capacity starting at head
+ * 2. Wrap event queue header and buffer with openbook account head and tail padding
+ */
+pub fn ob_event_queue(events: &[Event], head: u64, capacity: usize) -> Vec<u8> {
+    let mut buf = vec![Event::zeroed(); capacity];
+    for (i, event) in events.iter().enumerate() {
+        buf[(head as usize + i) % capacity] = *event;
+    }
+
+    let mut data: Vec<u8> = OB_ACCOUNT_HEAD.to_vec();
+    data.extend_from_slice(&OB_EVENT_QUEUE_FLAGS.to_le_bytes());
+    data.extend_from_slice(&head.to_le_bytes());
+    data.extend_from_slice(&(events.len() as u64).to_le_bytes());
+    data.extend_from_slice(&(events.len() as u64).to_le_bytes());
+    for event in buf.iter() {
+        data.extend_from_slice(bytemuck::bytes_of(event));
+    }
+    data.extend_from_slice(OB_ACCOUNT_TAIL);
+    data
+}
+
+/*
+ * Fixture openbook v2 fixed price leaf of price/quantity lots placed at timestamp
+ */
+pub fn ob_v2_leaf(
+    price_lots: u64,
+    quantity: i64,
+    time_in_force: u16,
+    timestamp: u64,
+    seq_num: u64,
+) -> ObV2LeafNode {
+    ObV2LeafNode {
+        tag: 2,
+        owner_slot: 0,
+        time_in_force,
+        padding: [0; 4],
+        key: ((price_lots as u128) << 64) | seq_num as u128,
+        owner: [1; 32],
+        quantity,
+        timestamp,
+        peg_limit: -1,
+        client_order_id: 0,
+    }
+}
+
+/*
+ * Fixture openbook v2 oracle pegged leaf, price offset from oracle is shifted by 2^63 in its key
+ */
+pub fn ob_v2_pegged_leaf(
+    price_offset_lots: i64,
+    quantity: i64,
+    peg_limit: i64,
+    seq_num: u64,
+) -> ObV2LeafNode {
+    let price_data = (price_offset_lots as u64).wrapping_add(1 << 63);
+
+    ObV2LeafNode {
+        peg_limit,
+        key: ((price_data as u128) << 64) | seq_num as u128,
+        ..ob_v2_leaf(0, quantity, 0, 0, seq_num)
+    }
+}
+
+/*
+ * Helper function for appending tree of leaves sorted by key, chained like ob_slab, returns its root handle
+ */
+fn push_ob_v2_tree(nodes: &mut Vec<u8>, leaves: &[ObV2LeafNode]) -> u32 {
+    let mut leaves = leaves.to_vec();
+    leaves.sort_by_key(|x| x.key);
+    let first = (nodes.len() / OB_V2_NODE_SIZE) as u32;
+    let num_leaves = leaves.len() as u32;
+
+    for leaf in leaves.iter() {
+        nodes.extend_from_slice(bytemuck::bytes_of(leaf));
+    }
+    for i in 0..num_leaves.saturating_sub(1) {
+        let lower = first + i;
+        let upper = if i + 2 == num_leaves {
+            first + num_leaves - 1
+        } else {
+            first + num_leaves + i + 1
+        };
+        let mut node = vec![0u8; OB_V2_NODE_SIZE];
+        node[0] = 1;
+        node[8..24].copy_from_slice(&leaves[i as usize].key.to_le_bytes());
+        node[24..28].copy_from_slice(&lower.to_le_bytes());
+        node[28..32].copy_from_slice(&upper.to_le_bytes());
+        nodes.extend(node);
+    }
+
+    if num_leaves > 1 {
+        first + num_leaves
+    } else {
+        first
+    }
+}
+
+/*
+ * Function: ob_v2_book_side
+ * 1. Append fixed and oracle pegged trees into shared nodes
+ * 2. Write their roots and leaf counts into bookside header after anchor discriminator
+ */
+pub fn ob_v2_book_side(fixed: &[ObV2LeafNode], pegged: &[ObV2LeafNode]) -> Vec<u8> {
+    let mut nodes: Vec<u8> = Vec::new();
+    let fixed_root = push_ob_v2_tree(&mut nodes, fixed);
+    let pegged_root = push_ob_v2_tree(&mut nodes, pegged);
+
+    let mut header = vec![0u8; OB_V2_BOOK_SIDE_HEADER_SIZE];
+    header[0..4].copy_from_slice(&fixed_root.to_le_bytes());
+    header[4..8].copy_from_slice(&(fixed.len() as u32).to_le_bytes());
+    header[8..12].copy_from_slice(&pegged_root.to_le_bytes());
+    header[12..16].copy_from_slice(&(pegged.len() as u32).to_le_bytes());
+
+    let mut data = anchor_discriminator("BookSide").to_vec();
+    data.extend(header);
+    data.extend(nodes);
+    data
+}
+
+/*
+ * Fixture openbook v2 fill of price/quantity lots, taker side 0 is bid and 1 is ask
+ */
+pub fn ob_v2_fill_event(
+    market_seq_num: u64,
+    price_lots: i64,
+    quantity: i64,
+    taker_side: u8,
+) -> ObV2FillEvent {
+    ObV2FillEvent {
+        event_type: 0,
+        taker_side,
+        maker_out: 0,
+        maker_slot: 0,
+        padding: [0; 4],
+        timestamp: 1_700_000_000,
+        market_seq_num,
+        maker: [1; 32],
+        maker_timestamp: 0,
+        taker: [2; 32],
+        taker_client_order_id: 0,
+        price: price_lots,
+        peg_limit: -1,
+        quantity,
+        maker_client_order_id: 0,
+        reserved: [0; 8],
+    }
+}
+
+/*
+ * Function: ob_v2_event_heap
+ * 1. Write fills as used list nodes 0..n linked in order
+ * 2. Prefix heap header and anchor discriminator
+ */
+pub fn ob_v2_event_heap(fills: &[ObV2FillEvent]) -> Vec<u8> {
+    let mut data = anchor_discriminator("EventHeap").to_vec();
+    data.extend_from_slice(&(fills.len() as u16).to_le_bytes());
+    data.extend_from_slice(&0u16.to_le_bytes());
+    data.extend_from_slice(&(fills.len() as u16).to_le_bytes());
+    data.extend_from_slice(&0u16.to_le_bytes());
+    data.extend_from_slice(&(fills.len() as u64).to_le_bytes());
+
+    for (i, fill) in fills.iter().enumerate() {
+        let mut node = vec![0u8; OB_V2_EVENT_NODE_SIZE];
+        node[0..2].copy_from_slice(&(i as u16 + 1).to_le_bytes());
+        node[2..4].copy_from_slice(&(i as u16).wrapping_sub(1).to_le_bytes());
+        node[8..].copy_from_slice(bytemuck::bytes_of(fill));
+        data.extend(node);
+    }
+
+    data
+}
+
+/*
+ * Fixture phoenix market, 9 decimals base in 0.001 lots and 6 decimals quote in 0.001 ticks, room for 4 orders per side and 2 seats
+ */
+pub fn phoenix_market(name: &str) -> PhoenixMarketInfo {
+    PhoenixMarketInfo {
+        name: name.to_string(),
+        address: Pubkey::new_unique(),
+        base_decimals: 9,
+        quote_decimals: 6,
+        base_lot_size: 1_000_000,
+        tick_size_in_quote_atoms_per_base_unit: 1_000,
+        raw_base_units_per_base_unit: 1,
+        bids_size: 4,
+        asks_size: 4,
+        num_seats: 2,
+        taker_fee_bps: 2,
+    }
+}
+
+/*
+ * Helper function for appending sokoban tree of node payloads given in key order
+ * Nodes are stored in key order from index 1 and linked as balanced tree, so the walk must follow pointers from root
+ */
+fn push_phoenix_tree(data: &mut Vec<u8>, payloads: &[Vec<u8>], node_size: usize, capacity: usize) {
+    // Root and left/right children of node range, nodes addressed from 1
+    fn link(links: &mut Vec<(u32, u32)>, low: usize, high: usize) -> u32 {
+        if low >= high {
+            return 0;
+        }
+        let mid = (low + high) / 2;
+        let left = link(links, low, mid);
+        let right = link(links, mid + 1, high);
+        links[mid] = (left, right);
+        mid as u32 + 1
+    }
+    let mut links: Vec<(u32, u32)> = vec![(0, 0); payloads.len()];
+    let root = link(&mut links, 0, payloads.len());
+
+    let mut header = vec![0u8; PHOENIX_TREE_HEADER_SIZE];
+    header[0..8].copy_from_slice(&(root as u64).to_le_bytes());
+    header[16..24].copy_from_slice(&(payloads.len() as u64).to_le_bytes());
+    header[24..28].copy_from_slice(&(payloads.len() as u32).to_le_bytes());
+    data.extend(header);
+
+    for i in 0..capacity {
+        let mut node = vec![0u8; node_size];
+        if let (Some(payload), Some((left, right))) = (payloads.get(i), links.get(i)) {
+            node[0..4].copy_from_slice(&left.to_le_bytes());
+            node[4..8].copy_from_slice(&right.to_le_bytes());
+            node[16..16 + payload.len()].copy_from_slice(payload);
+        }
+        data.extend(node);
+    }
+}
+
+/*
+ * Function: phoenix_market_data
+ * 1. Write market header with sequence number and zeroed fifo market fields
+ * 2. Append bids, asks and traders trees, trader index of a seat is its position from 1
+ */
+pub fn phoenix_market_data(
+    market: &PhoenixMarketInfo,
+    sequence_number: u64,
+    bids: &[PhoenixOrder],
+    asks: &[PhoenixOrder],
+    traders: &[PhoenixTrader],
+) -> Vec<u8> {
+    let mut header = PhoenixMarketHeader::zeroed();
+    header.market_sequence_number = sequence_number;
+    let mut data = bytemuck::bytes_of(&header).to_vec();
+    data.extend_from_slice(bytemuck::bytes_of(&PhoenixFifoMarket::zeroed()));
+
+    let order_payload = |x: &PhoenixOrder| {
+        [
+            x.price_in_ticks,
+            x.order_sequence_number,
+            x.trader_index,
+            x.base_lots,
+            0,
+            0,
+        ]
+        .iter()
+        .flat_map(|x| x.to_le_bytes())
+        .collect::<Vec<u8>>()
+    };
+    let bids: Vec<Vec<u8>> = bids.iter().map(order_payload).collect();
+    let asks: Vec<Vec<u8>> = asks.iter().map(order_payload).collect();
+    let traders: Vec<Vec<u8>> = traders
+        .iter()
+        .map(|x| {
+            let mut payload = x.owner.to_bytes().to_vec();
+            for lots in [
+                x.quote_lots_locked,
+                x.quote_lots_free,
+                x.base_lots_locked,
+                x.base_lots_free,
+            ] {
+                payload.extend_from_slice(&lots.to_le_bytes());
+            }
+            payload
+        })
+        .collect();
+    push_phoenix_tree(&mut data, &bids, PHOENIX_ORDER_NODE_SIZE, market.bids_size);
+    push_phoenix_tree(&mut data, &asks, PHOENIX_ORDER_NODE_SIZE, market.asks_size);
+    push_phoenix_tree(
+        &mut data,
+        &traders,
+        PHOENIX_TRADER_NODE_SIZE,
+        market.num_seats,
+    );
+
+    data
+}
+
+/*
+ * Fixture phoenix self-CPI log instruction data of audit header followed by fill events of maker orders
+ * Each fill is (maker, order sequence number, price in ticks, base lots filled), a place event is mixed in before fills
+ */
+pub fn phoenix_log_data(
+    market: &Pubkey,
+    signer: &Pubkey,
+    timestamp: i64,
+    fills: &[(Pubkey, u64, u64, u64)],
+) -> Vec<u8> {
+    let mut data = vec![PHOENIX_LOG_INSTRUCTION_TAG];
+    // Header event: instruction, sequence number, timestamp, slot, market, signer, total events
+    data.push(1);
+    data.push(0);
+    data.extend_from_slice(&7u64.to_le_bytes());
+    data.extend_from_slice(&timestamp.to_le_bytes());
+    data.extend_from_slice(&1u64.to_le_bytes());
+    data.extend_from_slice(&market.to_bytes());
+    data.extend_from_slice(&signer.to_bytes());
+    data.extend_from_slice(&(fills.len() as u16 + 1).to_le_bytes());
+
+    // Place event: index, order sequence number, client order id, price in ticks, base lots placed
+    data.push(3);
+    data.extend_from_slice(&0u16.to_le_bytes());
+    data.extend_from_slice(&99u64.to_le_bytes());
+    data.extend_from_slice(&0u128.to_le_bytes());
+    data.extend_from_slice(&1u64.to_le_bytes());
+    data.extend_from_slice(&1u64.to_le_bytes());
+
+    // Fill events: index, maker, order sequence number, price in ticks, base lots filled/remaining
+    for (i, (maker, order_sequence_number, price_in_ticks, base_lots)) in fills.iter().enumerate() {
+        data.push(2);
+        data.extend_from_slice(&(i as u16 + 1).to_le_bytes());
+        data.extend_from_slice(&maker.to_bytes());
+        data.extend_from_slice(&order_sequence_number.to_le_bytes());
+        data.extend_from_slice(&price_in_ticks.to_le_bytes());
+        data.extend_from_slice(&base_lots.to_le_bytes());
+        data.extend_from_slice(&0u64.to_le_bytes());
+    }
+
+    data
+}
+
+/*
+ * Fixture spl token account data holding amount
+ */
+pub fn token_account_data(amount: u64) -> Vec<u8> {
+    let mut data = vec![0u8; 165];
+    data[64..72].copy_from_slice(&amount.to_le_bytes());
+    data
+}
+
+/*
+ * Fixture constant product pool of 9 decimals base and 6 decimals quote vaults
+ */
+pub fn amm_pool(name: &str) -> AmmPoolInfo {
+    AmmPoolInfo {
+        name: name.to_string(),
+        venue: Venue::Raydium,
+        kind: AmmKind::ConstantProduct,
+        address: Pubkey::new_unique(),
+        base_vault: Pubkey::new_unique(),
+        quote_vault: Pubkey::new_unique(),
+        meteora: MeteoraVaults::default(),
+        inverted: false,
+        base_decimals: 9,
+        quote_decimals: 6,
+        fee_rate_bps: 25,
+    }
+}
+
+/*
+ * Fixture account write of transaction signature with write version
+ */
+pub fn account_write(
+    pubkey: &Pubkey,
+    slot: u64,
+    write_version: u64,
+    signature: &str,
+    data: Vec<u8>,
+) -> Account {
+    Account {
+        write_version,
+        txn_signature: signature.to_string(),
+        ..account(pubkey, slot, data)
+    }
+}
+
+/*
+ * Fixture gigadex market, price lots are 1e-3 quote per base and amount lots are base atoms
+ */
+pub fn gd_market(name: &str) -> GdMarketInfo {
+    GdMarketInfo {
+        name: name.to_string(),
+        address: Pubkey::new_unique(),
+        bids: Pubkey::new_unique(),
+        asks: Pubkey::new_unique(),
+        balances: Pubkey::new_unique(),
+        buy_order_log: Pubkey::new_unique(),
+        sell_order_log: Pubkey::new_unique(),
+        base_decimals: 9,
+        quote_decimals: 6,
+        multiplier: 1_000_000,
+        fee_rate_bps: 0,
+    }
+}
+
+/*
+ * Fixture gigadex market account pointing at market's trees and balances, prefixed by Market discriminator
+ */
+pub fn gd_market_data(market: &GdMarketInfo) -> Vec<u8> {
+    let state = GdMarketState {
+        mint: Pubkey::new_unique(),
+        balances: market.balances,
+        wsol_vault: Pubkey::new_unique(),
+        lot_vault: Pubkey::new_unique(),
+        asks: market.asks,
+        bids: market.bids,
+    };
+
+    [
+        anchor_discriminator(GD_MARKET_ACCOUNT).to_vec(),
+        bytemuck::bytes_of(&state).to_vec(),
+    ]
+    .concat()
+}
+
+/*
+ * Function: gd_order_tree
+ * 1. Write orders as tree nodes in given order, fills and delta log of last instruction
+ * 2. Prefix OrderTree account discriminator
+ */
+pub fn gd_order_tree(
+    orders: &[GdMarketOrder],
+    fills: &[FilledOrder],
+    deltas: &[NodeDeltaLog],
+) -> Vec<u8> {
+    let mut data = vec![0u8; GD_DISCRIMINATOR_LEN + size_of::<OrderTree>()];
+    data[..GD_DISCRIMINATOR_LEN].copy_from_slice(&anchor_discriminator(GD_ORDER_TREE_ACCOUNT));
+    let tree = bytemuck::from_bytes_mut::<OrderTree>(&mut data[GD_DISCRIMINATOR_LEN..]);
+
+    // Fields of packed tree are copied out and back instead of borrowed
+    let mut nodes = tree.nodes;
+    for (i, order) in orders.iter().enumerate() {
+        nodes[i] = Node {
+            price: order.price_lots,
+            amount: order.amount_lots,
+            uid: order.uid,
+            left: 0,
+            right: 0,
+            next: 0,
+            height: 1,
+        };
+    }
+    tree.nodes = nodes;
+    tree.num_orders = orders.len() as u64;
+
+    let mut tree_fills = tree.fills;
+    tree_fills[..fills.len()].copy_from_slice(fills);
+    tree.fills = tree_fills;
+    tree.num_fills = fills.len() as u64;
+
+    let mut node_delta = tree.node_delta;
+    node_delta[..deltas.len()].copy_from_slice(deltas);
+    tree.node_delta = node_delta;
+    tree.num_deltas = deltas.len() as u64;
+
+    data
+}
+
+/*
+ * Function: gd_user_balances
+ * 1. Write (uid, lamports, lots) entries at their uid index, num_users is the largest uid
+ * 2. Prefix UserBalances account discriminator
+ */
+pub fn gd_user_balances(entries: &[(u64, u64, u64)]) -> Vec<u8> {
+    let mut data = vec![0u8; GD_DISCRIMINATOR_LEN + size_of::<UserBalances>()];
+    data[..GD_DISCRIMINATOR_LEN].copy_from_slice(&anchor_discriminator(GD_USER_BALANCES_ACCOUNT));
+    let num_users = entries.iter().map(|x| x.0).max().unwrap_or_default();
+    data[GD_DISCRIMINATOR_LEN..GD_DISCRIMINATOR_LEN + 8].copy_from_slice(&num_users.to_le_bytes());
+
+    for (uid, lamports, lots) in entries.iter() {
+        let start = GD_DISCRIMINATOR_LEN + 8 + *uid as usize * GD_BALANCE_ENTRY_SIZE;
+        data[start..start + 8].copy_from_slice(&lamports.to_le_bytes());
+        data[start + 8..start + 16].copy_from_slice(&lots.to_le_bytes());
+    }
+
+    data
+}
+
+/*
+ * Fixture gigadex order log account of order, prefixed by its account discriminator
+ */
+pub fn gd_order_log(order: &GdMarketOrderLog) -> Vec<u8> {
+    [
+        anchor_discriminator(GD_ORDER_LOG_ACCOUNT).to_vec(),
+        order.try_to_vec().unwrap(),
+    ]
+    .concat()
+}
+
+/*
+ * Fixture gigadex user account registering owner to uid of market, prefixed by its account discriminator
+ */
+pub fn gd_user_account(user: &GdUserAccount) -> Vec<u8> {
+    [
+        anchor_discriminator(GD_USER_ACCOUNT).to_vec(),
+        user.try_to_vec().unwrap(),
+    ]
+    .concat()
+}
+
+/*
+ * Fixture api summary of market at price, with sol price as of its last trades
+ */
+pub fn summary_data(price: f64, sol_price: f64) -> SummaryData {
+    SummaryData {
+        change_24h: 0.0,
+        price_24h: price,
+        high_24h: price,
+        low_24h: price,
+        volume_24h: 0.0,
+        price,
+        sol_price,
+        nft_pool: None,
+        lot_supply: None,
+        market_buy: None,
+        price_usd: None,
+        volume_24h_usd: None,
+        quote_usd_rate: None,
+    }
+}
+
+/*
+ * Fixture taker buy trade of market at price and amount, on slot 10 at timestamp 1000
+ */
+pub fn market_trade(slug: &str, price: f64, amount: f64) -> MarketTrade {
+    MarketTrade {
+        slug: slug.to_string(),
+        order_id: None,
+        market_buy: 1,
+        avg_price: Decimal::from_f64(price).unwrap(),
+        amount: Decimal::from_f64(amount).unwrap(),
+        timestamp: 1000,
+        market_address: slug.to_string(),
+        blocktime: 1000,
+        index: 0,
+        avg_price_lots: Decimal::from_f64(price).unwrap(),
+        amount_lots: Decimal::from_f64(amount).unwrap(),
+        slot: 10,
+        transaction_signature: String::new(),
+        maker: None,
+        price_usd: None,
+        notional_usd: None,
+        quote_usd_rate: None,
+        instruction_index: None,
+        inner_instruction_index: None,
+        taker: None,
+        fee_payer: None,
+        attribution: None,
+        taker_account: None,
+        is_taker_buy: true,
+    }
+}
+
+/*
+ * Fixture venue orderbook of (price, amount) asks and bids, in 0.01 price lots and 0.001 size lots
+ */
+pub fn market_book(venue: Venue, asks: &[(f64, f64)], bids: &[(f64, f64)]) -> MarketOrders {
+    let levels = |levels: &[(f64, f64)]| {
+        levels
+            .iter()
+            .map(|(price, amount)| MarketOrder {
+                price: *price,
+                amount: *amount,
+                price_lots: (price * 100.0).round() as u64,
+                size_lots: (amount * 1000.0).round() as u64,
+                venue,
+            })
+            .collect()
+    };
+
+    MarketOrders {
+        asks: levels(asks),
+        bids: levels(bids),
+    }
+}
+
+/*
+ * Fixture market config of slug with market_info venue keys
+ */
+pub fn market_config(slug: &str, info: &[(&str, String)]) -> MarketConfig {
+    MarketConfig {
+        name: slug.to_uppercase(),
+        slug: slug.to_string(),
+        base_decimals: 9,
+        quote_decimals: 6,
+        status: "active".to_string(),
+        oracle_address: None,
+        market_info: info
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.clone()))
+            .collect::<HashMap<String, String>>(),
+    }
+}
+
+/*
+ * Fixture decoded account update of account data at slot
+ */
+pub fn account(pubkey: &Pubkey, slot: u64, data: Vec<u8>) -> Account {
+    Account {
+        is_startup: false,
+        slot,
+        pubkey: *pubkey,
+        lamports: 1,
+        owner: Pubkey::default(),
+        executable: false,
+        rent_epoch: 0,
+        data,
+        write_version: slot,
+        txn_signature: String::new(),
+    }
+}
+
+/*
+ * Fixture geyser account update of account data at slot
+ */
+pub fn account_update(
+    pubkey: &Pubkey,
+    owner: &Pubkey,
+    slot: u64,
+    data: Vec<u8>,
+) -> SubscribeUpdate {
+    SubscribeUpdate {
+        filters: vec![],
+        update_oneof: Some(UpdateOneof::Account(SubscribeUpdateAccount {
+            account: Some(SubscribeUpdateAccountInfo {
+                pubkey: pubkey.to_bytes().to_vec(),
+                lamports: 1,
+                owner: owner.to_bytes().to_vec(),
+                executable: false,
+                rent_epoch: 0,
+                data,
+                write_version: slot,
+                txn_signature: Some(vec![1; 64]),
+            }),
+            slot,
+            is_startup: false,
+        })),
+    }
+}
+
+/*
+ * Fixture pyth v2 price account of exponent, aggregate price/confidence and status at their layout offsets
+ */
+pub fn pyth_price_data(expo: i32, price: i64, conf: u64, status: u32) -> Vec<u8> {
+    let mut data = vec![0u8; PYTH_PRICE_ACCOUNT_SIZE];
+    data[0..4].copy_from_slice(&0xa1b2c3d4u32.to_le_bytes());
+    data[20..24].copy_from_slice(&expo.to_le_bytes());
+    data[208..216].copy_from_slice(&price.to_le_bytes());
+    data[216..224].copy_from_slice(&conf.to_le_bytes());
+    data[224..228].copy_from_slice(&status.to_le_bytes());
+    data
+}
+
+/*
+ * Fixture switchboard v2 aggregator account of latest confirmed result and std deviation as mantissa/scale decimals
+ */
+pub fn switchboard_aggregator_data(result: (i128, u32), std_deviation: (i128, u32)) -> Vec<u8> {
+    let mut data = vec![0u8; SWITCHBOARD_AGGREGATOR_ACCOUNT_SIZE];
+    for (offset, (mantissa, scale)) in [(366, result), (386, std_deviation)] {
+        data[offset..offset + 16].copy_from_slice(&mantissa.to_le_bytes());
+        data[offset + 16..offset + 20].copy_from_slice(&scale.to_le_bytes());
+    }
+    data
+}
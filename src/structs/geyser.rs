@@ -1,5 +1,7 @@
 use solana_sdk::pubkey::Pubkey;
-use yellowstone_grpc_proto::prelude::SubscribeUpdateAccount;
+use yellowstone_grpc_proto::prelude::{SubscribeUpdateAccount, SubscribeUpdateTransaction};
+
+use super::decode::DecodeError;
 
 #[derive(Debug)]
 #[allow(dead_code)]
@@ -16,26 +18,144 @@ pub struct Account {
     pub txn_signature: String,
 }
 
-impl From<SubscribeUpdateAccount> for Account {
-    fn from(
+impl TryFrom<SubscribeUpdateAccount> for Account {
+    type Error = DecodeError;
+
+    fn try_from(
         SubscribeUpdateAccount {
             is_startup,
             slot,
             account,
         }: SubscribeUpdateAccount,
-    ) -> Self {
-        let account = account.expect("should be defined");
-        Self {
+    ) -> Result<Self, Self::Error> {
+        let account = account.ok_or(DecodeError::InvalidUpdate { field: "account" })?;
+        Ok(Self {
             is_startup,
             slot,
-            pubkey: Pubkey::try_from(account.pubkey).expect("valid pubkey"),
+            pubkey: Pubkey::try_from(account.pubkey)
+                .map_err(|_| DecodeError::InvalidUpdate { field: "pubkey" })?,
             lamports: account.lamports,
-            owner: Pubkey::try_from(account.owner).expect("valid pubkey"),
+            owner: Pubkey::try_from(account.owner)
+                .map_err(|_| DecodeError::InvalidUpdate { field: "owner" })?,
             executable: account.executable,
             rent_epoch: account.rent_epoch,
             data: account.data,
             write_version: account.write_version,
             txn_signature: bs58::encode(account.txn_signature.unwrap_or_default()).into_string(),
+        })
+    }
+}
+
+/*
+ * Top level or inner instruction of a transaction with resolved accounts
+ */
+#[derive(Debug, Clone)]
+pub struct TxInstruction {
+    // Index of top level instruction, inner instructions share their parent's
+    pub index: u32,
+    // Ordinal among inner instructions of top level instruction, None for top level
+    pub inner_index: Option<u32>,
+    pub program_id: Pubkey,
+    pub accounts: Vec<Pubkey>,
+    pub signers: Vec<Pubkey>,
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug)]
+#[allow(dead_code)]
+pub struct Transaction {
+    pub slot: u64,
+    pub signature: String,
+    pub index: u64,
+    pub fee_payer: Pubkey,
+    pub is_failed: bool,
+    pub instructions: Vec<TxInstruction>,
+    pub log_messages: Vec<String>,
+}
+
+impl TryFrom<SubscribeUpdateTransaction> for Transaction {
+    type Error = DecodeError;
+
+    fn try_from(
+        SubscribeUpdateTransaction { transaction, slot }: SubscribeUpdateTransaction,
+    ) -> Result<Self, Self::Error> {
+        let info = transaction.ok_or(DecodeError::InvalidUpdate {
+            field: "transaction",
+        })?;
+        let message = info.transaction.and_then(|x| x.message).unwrap_or_default();
+        let meta = info.meta.unwrap_or_default();
+
+        // Static keys followed by writable and readonly keys loaded from lookup tables
+        let account_keys: Vec<Pubkey> = message
+            .account_keys
+            .into_iter()
+            .chain(meta.loaded_writable_addresses.into_iter())
+            .chain(meta.loaded_readonly_addresses.into_iter())
+            .map(|x| Pubkey::try_from(x).unwrap_or_default())
+            .collect();
+        let num_signers = message
+            .header
+            .map(|x| x.num_required_signatures as usize)
+            .unwrap_or(1);
+        let resolve = |index: u32,
+                       inner_index: Option<u32>,
+                       program_id_index: u32,
+                       accounts: &Vec<u8>,
+                       data: Vec<u8>| {
+            TxInstruction {
+                index,
+                inner_index,
+                program_id: account_keys
+                    .get(program_id_index as usize)
+                    .cloned()
+                    .unwrap_or_default(),
+                accounts: accounts
+                    .iter()
+                    .map(|x| account_keys.get(*x as usize).cloned().unwrap_or_default())
+                    .collect(),
+                signers: accounts
+                    .iter()
+                    .filter(|x| (**x as usize) < num_signers)
+                    .filter_map(|x| account_keys.get(*x as usize).cloned())
+                    .collect(),
+                data,
+            }
+        };
+
+        let mut instructions: Vec<TxInstruction> = Vec::new();
+        for (index, ix) in message.instructions.into_iter().enumerate() {
+            instructions.push(resolve(
+                index as u32,
+                None,
+                ix.program_id_index,
+                &ix.accounts,
+                ix.data,
+            ));
+            for (inner_index, ix) in meta
+                .inner_instructions
+                .iter()
+                .filter(|x| x.index == index as u32)
+                .flat_map(|x| x.instructions.iter())
+                .enumerate()
+            {
+                instructions.push(resolve(
+                    index as u32,
+                    Some(inner_index as u32),
+                    ix.program_id_index,
+                    &ix.accounts,
+                    ix.data.clone(),
+                ));
+            }
         }
+
+        Ok(Self {
+            slot,
+            signature: bs58::encode(info.signature).into_string(),
+            index: info.index,
+            fee_payer: account_keys.first().cloned().unwrap_or_default(),
+            is_failed: meta.err.is_some(),
+            instructions,
+            log_messages: meta.log_messages,
+        })
     }
 }
@@ -3,10 +3,17 @@ use anchor_lang::{AnchorDeserialize, AnchorSerialize};
 use bytemuck::{Pod, Zeroable};
 use serde_derive::{Deserialize, Serialize};
 use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
 
 pub const ORDERBOOK_DEPTH: usize = 1000; // this is before any compression
 pub const MAX_FILLS_PER_MARKET_ORDER: usize = 64;
 pub const USERS_PER_MARKET: usize = 10_000;
+// Anchor account names hashed into discriminators of gigadex accounts
+pub const GD_MARKET_ACCOUNT: &str = "Market";
+pub const GD_ORDER_TREE_ACCOUNT: &str = "OrderTree";
+pub const GD_USER_BALANCES_ACCOUNT: &str = "UserBalances";
+pub const GD_ORDER_LOG_ACCOUNT: &str = "MarketOrderLog";
+pub const GD_USER_ACCOUNT: &str = "UserAccount";
 
 #[derive(Debug, Clone, Default)]
 pub struct GdMarketInfo {
@@ -20,6 +27,7 @@ pub struct GdMarketInfo {
     pub base_decimals: u8,
     pub quote_decimals: u8,
     pub multiplier: u64,
+    pub fee_rate_bps: u64,
 }
 impl GdMarketInfo {
     pub fn is_valid_account(&self, account: &Pubkey) -> bool {
@@ -31,7 +39,8 @@ impl GdMarketInfo {
     }
 }
 
-#[derive(AnchorDeserialize, AnchorSerialize, Debug, Clone)]
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
 pub struct GdMarketState {
     pub mint: Pubkey,
     pub balances: Pubkey,
@@ -40,6 +49,25 @@ pub struct GdMarketState {
     pub asks: Pubkey,
     pub bids: Pubkey,
 }
+unsafe impl Zeroable for GdMarketState {}
+unsafe impl Pod for GdMarketState {}
+
+/*
+ * Anchor account registering a wallet to a uid of the market's UserBalances entries
+ */
+#[derive(AnchorDeserialize, AnchorSerialize, Debug, Clone)]
+pub struct GdUserAccount {
+    pub market: Pubkey,
+    pub owner: Pubkey,
+    pub uid: u64,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct GdUidWallet {
+    pub market: String,
+    pub uid: u64,
+    pub owner: String,
+}
 
 #[derive(Deserialize, Serialize, Default, Debug, Clone, PartialEq)]
 pub struct GdMarketOrder {
@@ -48,7 +76,7 @@ pub struct GdMarketOrder {
     pub amount_lots: u64,
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct FilledOrder {
     pub price: u64,
     pub amount: u64,
@@ -73,7 +101,7 @@ pub struct Node {
     pub height: u64,
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct NodeDeltaLog {
     pub key: u64,
     pub is_delete: u64,
@@ -125,7 +153,7 @@ pub struct UserBalances {
 unsafe impl Zeroable for UserBalances {}
 unsafe impl Pod for UserBalances {}
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct GdBalance {
     pub lamports: f64,
     pub lots: f64,
@@ -138,6 +166,86 @@ pub struct GdMarketOrderLog {
     pub counter: u64,
 }
 
+#[derive(Debug, Clone)]
+pub struct GdPendingLog {
+    pub order: GdMarketOrderLog,
+    pub market_buy: bool,
+    pub slot: u64,
+    pub txn_signature: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct GdTreeFills {
+    pub slot: u64,
+    pub write_version: u64,
+    pub txn_signature: String,
+    pub fills: Vec<FilledOrder>,
+}
+
+/*
+ * Order log updates waiting for the maker fills of the matched tree, deduplicated by counter
+ * Tree fills queue in write order, so logs of one slot pair with them in counter order
+ * Logs left unpaired once their slot passes are counted and dropped
+ */
+#[derive(Debug, Clone, Default)]
+pub struct GdFillState {
+    pub buy_counter: Option<u64>,
+    pub sell_counter: Option<u64>,
+    pub pending_logs: Vec<GdPendingLog>,
+    pub ask_fills: Vec<GdTreeFills>,
+    pub bid_fills: Vec<GdTreeFills>,
+    pub unmatched_logs: u64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GdTreeDeltas {
+    pub deltas: Vec<NodeDeltaLog>,
+    pub fills: Vec<FilledOrder>,
+    pub amount_cancelled: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GdOrderEventKind {
+    Placed,
+    PartiallyFilled,
+    Filled,
+    Cancelled,
+    Amended,
+}
+impl GdOrderEventKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GdOrderEventKind::Placed => "placed",
+            GdOrderEventKind::PartiallyFilled => "partially_filled",
+            GdOrderEventKind::Filled => "filled",
+            GdOrderEventKind::Cancelled => "cancelled",
+            GdOrderEventKind::Amended => "amended",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GdOrderEvent {
+    pub kind: GdOrderEventKind,
+    pub uid: u64,
+    #[serde(rename = "isBid")]
+    pub is_bid: bool,
+    pub amount: f64,
+    pub price: f64,
+    #[serde(rename = "priceLots")]
+    pub price_lots: u64,
+    #[serde(rename = "amountLots")]
+    pub amount_lots: u64,
+    pub slot: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GdOrderEventData {
+    #[serde(rename = "orderEvent")]
+    pub order_event: GdOrderEvent,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GdOrderData {
     pub amount: f64,
@@ -162,6 +270,55 @@ pub struct GdBidsData {
     pub slot: u64,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GdBalanceEventKind {
+    FillCredit,
+    Claim,
+    Withdraw,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GdBalanceEvent {
+    pub market: String,
+    pub uid: u64,
+    pub kind: GdBalanceEventKind,
+    pub lamports_delta: f64,
+    pub lots_delta: f64,
+    pub lamports: f64,
+    pub lots: f64,
+    pub slot: u64,
+    pub signature: String,
+    pub timestamp: u64,
+}
+
+/*
+ * Balance transition with uid's wallet, waiting for instruction names of its transaction
+ */
+#[derive(Debug, Clone)]
+pub struct GdPendingBalanceEvent {
+    pub balance_event: GdBalanceEvent,
+    pub owner: Option<String>,
+}
+
+/*
+ * Balance transitions held until their transaction is tracked
+ * Gigadex instruction names of tracked transactions classify them, kept for the tracking window
+ */
+#[derive(Debug, Clone, Default)]
+pub struct GdBalanceTracker {
+    pub last_slot: u64,
+    // Slot and gigadex instruction names of tracked transaction signatures
+    pub instructions: HashMap<String, (u64, Vec<String>)>,
+    pub pending: Vec<GdPendingBalanceEvent>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GdBalanceEventData {
+    #[serde(rename = "balanceEvent")]
+    pub balance_event: GdBalanceEvent,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GdBalanceData {
     #[serde(rename = "claimableBalance")]
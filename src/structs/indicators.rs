@@ -0,0 +1,78 @@
+use serde_derive::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+
+use crate::constants::{BOLLINGER_PERIOD, BOLLINGER_STD_DEV, EMA_PERIODS, RSI_PERIOD, SMA_PERIODS};
+
+use super::market::CandleData;
+
+/*
+ * Indicator periods stored in redis's indicator_config, falls back to constants
+ */
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct IndicatorConfig {
+    #[serde(rename = "smaPeriods")]
+    pub sma_periods: Vec<usize>,
+
+    #[serde(rename = "emaPeriods")]
+    pub ema_periods: Vec<usize>,
+
+    #[serde(rename = "rsiPeriod")]
+    pub rsi_period: usize,
+
+    #[serde(rename = "bollingerPeriod")]
+    pub bollinger_period: usize,
+
+    #[serde(rename = "bollingerStdDev")]
+    pub bollinger_std_dev: f64,
+}
+
+impl Default for IndicatorConfig {
+    fn default() -> Self {
+        IndicatorConfig {
+            sma_periods: SMA_PERIODS.to_vec(),
+            ema_periods: EMA_PERIODS.to_vec(),
+            rsi_period: RSI_PERIOD,
+            bollinger_period: BOLLINGER_PERIOD,
+            bollinger_std_dev: BOLLINGER_STD_DEV,
+        }
+    }
+}
+
+/*
+ * Indicator state of closed candles, the open candle is applied on top of it
+ */
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Default)]
+pub struct IndicatorState {
+    pub begin_ts: u64,
+    pub close: f64,
+    pub closes: VecDeque<f64>,
+    pub emas: BTreeMap<usize, f64>,
+    pub prev_close: Option<f64>,
+    pub avg_gain: Option<f64>,
+    pub avg_loss: Option<f64>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct BollingerBand {
+    pub upper: f64,
+    pub middle: f64,
+    pub lower: f64,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct IndicatorData {
+    pub slug: String,
+    pub unit: String,
+    pub begin_ts: u64,
+    pub close: f64,
+    pub sma: BTreeMap<usize, f64>,
+    pub ema: BTreeMap<usize, f64>,
+    pub rsi: Option<f64>,
+    pub bollinger: Option<BollingerBand>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct CandlePublishData {
+    pub candle: CandleData,
+    pub indicators: IndicatorData,
+}
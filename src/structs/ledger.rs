@@ -0,0 +1,29 @@
+use serde_derive::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Default)]
+pub struct TraderPosition {
+    pub trader: String,
+    pub market: String,
+    pub position: f64,
+
+    #[serde(rename = "avgEntryPrice")]
+    pub avg_entry_price: f64,
+
+    #[serde(rename = "realizedPnl")]
+    pub realized_pnl: f64,
+
+    #[serde(rename = "unrealizedPnl")]
+    pub unrealized_pnl: f64,
+
+    #[serde(rename = "markPrice")]
+    pub mark_price: f64,
+
+    pub volume: f64,
+    pub slot: u64,
+    pub timestamp: u64,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct LedgerPublishData {
+    pub ledger: TraderPosition,
+}
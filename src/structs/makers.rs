@@ -0,0 +1,69 @@
+use serde_derive::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct MakerQuote {
+    pub maker: String,
+    pub price: f64,
+    pub amount: f64,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Default)]
+pub struct MakerQuotes {
+    pub asks: Vec<MakerQuote>,
+    pub bids: Vec<MakerQuote>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Default)]
+pub struct MakerAccum {
+    pub quoted_size_secs: f64,
+    pub bid_secs: f64,
+    pub ask_secs: f64,
+    pub two_sided_secs: f64,
+}
+
+/*
+ * Time-weighted maker quoting state, accumulated per (day_ts, market, maker)
+ */
+#[derive(Debug, Clone, Default)]
+pub struct MakerTracker {
+    pub sampled_ms: HashMap<String, u64>,
+    pub quoted: HashMap<String, HashMap<String, (f64, f64)>>,
+    pub observed_secs: HashMap<(u64, String), f64>,
+    pub accums: HashMap<(u64, String, String), MakerAccum>,
+    pub flushed_ts: u64,
+}
+
+/*
+ * Day's raw accumulators of a market, persisted on flush so a restart resumes them
+ */
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Default)]
+pub struct MakerDayAccums {
+    pub observed_secs: f64,
+    pub accums: HashMap<String, MakerAccum>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct MakerStats {
+    pub day_ts: u64,
+    pub slug: String,
+    pub maker: String,
+
+    #[serde(rename = "quotedSize")]
+    pub quoted_size: f64,
+
+    #[serde(rename = "bidUptime")]
+    pub bid_uptime: f64,
+
+    #[serde(rename = "askUptime")]
+    pub ask_uptime: f64,
+
+    #[serde(rename = "twoSidedUptime")]
+    pub two_sided_uptime: f64,
+
+    #[serde(rename = "makerVolume")]
+    pub maker_volume: f64,
+
+    #[serde(rename = "fillShare")]
+    pub fill_share: f64,
+}
@@ -62,6 +62,15 @@ pub struct SummaryData {
 
     #[serde(rename = "marketBuy")]
     pub market_buy: Option<bool>,
+
+    #[serde(rename = "priceUsd")]
+    pub price_usd: Option<f64>,
+
+    #[serde(rename = "volume24HUsd")]
+    pub volume_24h_usd: Option<f64>,
+
+    #[serde(rename = "quoteUsdRate")]
+    pub quote_usd_rate: Option<f64>,
 }
 
 #[derive(Deserialize, Serialize, Debug, PartialEq, Clone)]
@@ -79,6 +88,12 @@ pub struct PriceData {
 
     #[serde(rename = "change24H")]
     pub change_24h: f64,
+
+    #[serde(rename = "priceUsd")]
+    pub price_usd: Option<f64>,
+
+    #[serde(rename = "quoteUsdRate")]
+    pub quote_usd_rate: Option<f64>,
 }
 
 #[derive(Deserialize, Serialize, Debug)]
@@ -121,23 +136,45 @@ pub struct PublishUidData<F> {
     pub market: String,
 
     pub data: F,
+
+    pub owner: Option<String>,
 }
 
+/*
+ * Market config of market_info:{market}, venue keys are parsed by each adapter from market_info
+ */
 #[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 pub struct MarketConfig {
     pub name: String,
     pub slug: String,
-    pub ob_market_address: Option<String>,
-    pub gd_market_address: Option<String>,
     pub base_decimals: u8,
     pub quote_decimals: u8,
     pub status: String,
+    pub oracle_address: Option<String>,
+    pub market_info: HashMap<String, String>,
+}
+impl MarketConfig {
+    /*
+     * Raw market_info value of key, empty value counts as unset
+     */
+    pub fn info(&self, key: &str) -> Option<&String> {
+        self.market_info.get(key).filter(|x| !x.is_empty())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct VenueMarket {
+    pub venue: Venue,
+    pub address: String,
+    pub fee_rate_bps: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MarketTrade {
     pub slug: String,
     pub order_id: Option<String>,
+    // 1 if the taker bought, maker took the other side
+    // Openbook keeps its published convention of 1 when the maker's order was a bid
     pub market_buy: u8,
     pub avg_price: Decimal,
     pub amount: Decimal,
@@ -149,6 +186,24 @@ pub struct MarketTrade {
     pub amount_lots: Decimal,
     pub slot: u64,
     pub transaction_signature: String,
+    pub maker: Option<String>,
+    pub price_usd: Option<f64>,
+    pub notional_usd: Option<f64>,
+    pub quote_usd_rate: Option<f64>,
+    pub instruction_index: Option<u32>,
+    // Ordinal of CPI'd taker instruction within top level instruction_index
+    pub inner_instruction_index: Option<u32>,
+    pub taker: Option<String>,
+    pub fee_payer: Option<String>,
+    // How signature/instruction/taker were resolved, None if trade keeps its account update signature
+    pub attribution: Option<TradeAttribution>,
+
+    // Taker's account from fill events for matching taker instruction, not stored
+    #[serde(skip)]
+    pub taker_account: Option<String>,
+    // Taker side on every venue for ledger and alerts, not stored
+    #[serde(skip)]
+    pub is_taker_buy: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -173,6 +228,10 @@ pub struct TradePublishData {
     #[serde(rename = "marketBuy")]
     pub market_buy: bool,
     pub timestamp: u64,
+    #[serde(rename = "priceUsd")]
+    pub price_usd: Option<f64>,
+    pub signature: String,
+    pub taker: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -191,6 +250,49 @@ pub struct CandleData {
     pub end_ts: u64,
     pub unit: String,
     pub slug: String,
+    pub close_usd: Option<f64>,
+    pub volume_usd: Option<f64>,
+    pub quote_usd_rate: Option<f64>,
+}
+
+/*
+ * Source of a trade's transaction attribution
+ */
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TradeAttribution {
+    // Market event logged by the taker instruction itself
+    LogEvent,
+    // Taker instruction of same slot holding trade's taker account
+    TakerAccount,
+    // Last taker instruction of account update's writer transaction, ambiguous if it has several
+    LastWriter,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum Venue {
+    Openbook,
+    #[serde(rename = "openbook_v2")]
+    OpenbookV2,
+    Gigadex,
+    Phoenix,
+    Raydium,
+    Orca,
+    Meteora,
+}
+impl Venue {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Venue::Openbook => "openbook",
+            Venue::OpenbookV2 => "openbook_v2",
+            Venue::Gigadex => "gigadex",
+            Venue::Phoenix => "phoenix",
+            Venue::Raydium => "raydium",
+            Venue::Orca => "orca",
+            Venue::Meteora => "meteora",
+        }
+    }
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
@@ -203,6 +305,8 @@ pub struct MarketOrder {
 
     #[serde(rename = "sizeLots")]
     pub size_lots: u64,
+
+    pub venue: Venue,
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
@@ -1,8 +1,27 @@
+pub mod adapter;
+pub mod alerts;
+pub mod amm;
+pub mod analytics;
+pub mod arbitrage;
+pub mod archive;
+pub mod decode;
 pub mod geyser;
 pub mod gigadex;
+pub mod indicators;
+pub mod ledger;
+pub mod makers;
+pub mod oracle;
 pub mod market;
 pub mod openbook;
+pub mod openbook_v2;
+pub mod phoenix;
+pub mod replay;
 pub mod slab;
+pub mod transactions;
 pub mod mint;
 pub mod extractor;
-pub mod getAUM;
+#[cfg(test)]
+pub mod fixtures;
+pub mod aum;
+pub mod query;
+pub mod subscribe;
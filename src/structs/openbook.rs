@@ -14,11 +14,16 @@ pub struct ObMarketInfo {
     pub quote_decimals: u8,
     pub base_lot_size: u64,
     pub quote_lot_size: u64,
+    pub fee_rate_bps: u64,
 }
 impl ObMarketInfo {
     pub fn is_valid_account(&self, account: &Pubkey) -> bool {
         account.eq(&self.bids) || account.eq(&self.asks) || account.eq(&self.event_queue)
     }
+
+    pub fn is_book_account(&self, account: &Pubkey) -> bool {
+        account.eq(&self.bids) || account.eq(&self.asks)
+    }
 }
 
 #[derive(Copy, Clone, AnchorDeserialize)]
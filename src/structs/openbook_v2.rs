@@ -0,0 +1,561 @@
+use bytemuck::{cast_ref, try_from_bytes, Pod, Zeroable};
+use num_enum::{IntoPrimitive, TryFromPrimitive};
+use solana_sdk::pubkey::Pubkey;
+use std::{convert::TryFrom, mem::size_of};
+
+use super::{
+    decode::{check_anchor_discriminator, read_pod, DecodeError},
+    makers::MakerQuote,
+    market::{MarketOrder, Venue},
+    openbook::ObMarketInfo,
+    slab::{readable_price, readable_quantity},
+};
+
+pub type ObV2NodeHandle = u32;
+
+const ANCHOR_DISCRIMINATOR_LEN: usize = 8;
+const EVENT_HEAP_LEN: usize = 600;
+
+#[derive(IntoPrimitive, TryFromPrimitive, Debug)]
+#[repr(u8)]
+enum ObV2NodeTag {
+    Uninitialized = 0,
+    InnerNode = 1,
+    LeafNode = 2,
+    FreeNode = 3,
+    LastFreeNode = 4,
+}
+
+#[derive(IntoPrimitive, TryFromPrimitive, Debug, PartialEq)]
+#[repr(u8)]
+pub enum ObV2EventType {
+    Fill = 0,
+    Out = 1,
+}
+
+/*
+ * Openbook v2 market account, fields up to quote mint
+ */
+#[derive(Copy, Clone)]
+#[repr(packed)]
+#[allow(dead_code)]
+pub struct ObV2MarketState {
+    pub bump: u8,
+    pub base_decimals: u8,
+    pub quote_decimals: u8,
+    pub padding1: [u8; 5],
+    pub market_authority: [u8; 32],
+    pub time_expiry: i64,
+    pub collect_fee_admin: [u8; 32],
+    pub open_orders_admin: [u8; 32],
+    pub consume_events_admin: [u8; 32],
+    pub close_market_admin: [u8; 32],
+    pub name: [u8; 16],
+    pub bids: [u8; 32],
+    pub asks: [u8; 32],
+    pub event_heap: [u8; 32],
+    pub oracle_a: [u8; 32],
+    pub oracle_b: [u8; 32],
+    pub oracle_config: [u8; 88],
+    pub stable_price_model: [u8; 288],
+    pub quote_lot_size: i64,
+    pub base_lot_size: i64,
+    pub seq_num: u64,
+    pub registration_time: i64,
+    pub maker_fee: i64,
+    pub taker_fee: i64,
+    pub fees_accrued: u128,
+    pub fees_to_referrers: u128,
+    pub referrer_rebates_accrued: u64,
+    pub fees_available: u64,
+    pub maker_volume: u128,
+    pub taker_volume_wo_oo: u128,
+    pub base_mint: [u8; 32],
+    pub quote_mint: [u8; 32],
+}
+unsafe impl Zeroable for ObV2MarketState {}
+unsafe impl Pod for ObV2MarketState {}
+
+impl ObV2MarketState {
+    pub fn load(data: &[u8]) -> Result<&Self, DecodeError> {
+        check_anchor_discriminator(data, "Market", "openbook v2 market")?;
+        read_pod(data, "openbook v2 market", ANCHOR_DISCRIMINATOR_LEN)
+    }
+}
+
+#[derive(Copy, Clone)]
+#[repr(packed)]
+#[allow(dead_code)]
+struct ObV2InnerNode {
+    tag: u8,
+    padding: [u8; 3],
+    prefix_len: u32,
+    key: u128,
+    children: [u32; 2],
+    child_earliest_expiry: [u64; 2],
+    reserved: [u8; 40],
+}
+unsafe impl Zeroable for ObV2InnerNode {}
+unsafe impl Pod for ObV2InnerNode {}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[repr(packed)]
+pub struct ObV2LeafNode {
+    pub tag: u8,
+    pub owner_slot: u8,
+    pub time_in_force: u16,
+    pub padding: [u8; 4],
+    pub key: u128,
+    pub owner: [u8; 32],
+    pub quantity: i64,
+    pub timestamp: u64,
+    pub peg_limit: i64,
+    pub client_order_id: u64,
+}
+unsafe impl Zeroable for ObV2LeafNode {}
+unsafe impl Pod for ObV2LeafNode {}
+
+impl ObV2LeafNode {
+    #[inline]
+    pub fn price(&self) -> u64 {
+        (self.key >> 64) as u64
+    }
+
+    #[inline]
+    pub fn quantity(&self) -> u64 {
+        self.quantity.max(0) as u64
+    }
+
+    #[inline]
+    pub fn owner(&self) -> Pubkey {
+        Pubkey::new_from_array(self.owner)
+    }
+
+    // Order with time in force is expired once it passed, 0 never expires
+    pub fn is_expired(&self, now_ts: u64) -> bool {
+        self.time_in_force > 0 && now_ts >= self.timestamp + self.time_in_force as u64
+    }
+
+    // Price of oracle pegged order whose key stores its offset from oracle price shifted by 2^63
+    // None if price isn't positive or crosses the order's peg limit, -1 peg limit is unlimited
+    pub fn pegged_price(&self, oracle_price_lots: i64, is_bid: bool) -> Option<u64> {
+        let offset = self.price().wrapping_sub(1 << 63) as i64;
+        let price_lots = oracle_price_lots.checked_add(offset)?;
+        let peg_limit = self.peg_limit;
+        let within_limit = peg_limit == -1
+            || if is_bid {
+                price_lots <= peg_limit
+            } else {
+                price_lots >= peg_limit
+            };
+
+        (price_lots >= 1 && within_limit).then_some(price_lots as u64)
+    }
+}
+
+/*
+ * Valid order of openbook v2 bookside at its effective price lots
+ */
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ObV2Order<'a> {
+    pub leaf: &'a ObV2LeafNode,
+    pub price_lots: u64,
+}
+
+#[derive(Copy, Clone, Debug)]
+#[repr(packed)]
+#[allow(dead_code)]
+struct ObV2AnyNode {
+    tag: u8,
+    data: [u8; 87],
+}
+unsafe impl Zeroable for ObV2AnyNode {}
+unsafe impl Pod for ObV2AnyNode {}
+
+#[derive(Copy, Clone)]
+#[repr(packed)]
+#[allow(dead_code)]
+struct ObV2BookSideHeader {
+    fixed_root: ObV2NodeHandle,
+    fixed_leaf_count: u32,
+    oracle_pegged_root: ObV2NodeHandle,
+    oracle_pegged_leaf_count: u32,
+    reserved_roots: [u64; 4],
+    reserved: [u8; 256],
+    order_tree_type: u8,
+    padding: [u8; 3],
+    bump_index: u32,
+    free_list_len: u32,
+    free_list_head: u32,
+    reserved_nodes: [u8; 512],
+}
+unsafe impl Zeroable for ObV2BookSideHeader {}
+unsafe impl Pod for ObV2BookSideHeader {}
+
+/*
+ * Openbook v2 bookside account, fixed price and oracle pegged order trees sharing nodes
+ */
+pub struct ObV2BookSide<'a> {
+    header: &'a ObV2BookSideHeader,
+    nodes: &'a [u8],
+}
+
+impl<'a> ObV2BookSide<'a> {
+    pub fn new(data: &'a [u8]) -> Result<Self, DecodeError> {
+        check_anchor_discriminator(data, "BookSide", "openbook v2 bookside")?;
+        Ok(Self {
+            header: read_pod(data, "openbook v2 bookside", ANCHOR_DISCRIMINATOR_LEN)?,
+            nodes: &data[ANCHOR_DISCRIMINATOR_LEN + size_of::<ObV2BookSideHeader>()..],
+        })
+    }
+
+    fn node(&self, handle: ObV2NodeHandle) -> Option<&'a ObV2AnyNode> {
+        let start = handle as usize * size_of::<ObV2AnyNode>();
+        let end = start + size_of::<ObV2AnyNode>();
+        try_from_bytes(self.nodes.get(start..end)?).ok()
+    }
+
+    // Valid orders of fixed and oracle pegged trees, best price first
+    // Expired orders are skipped, pegged orders are skipped without oracle price or beyond their peg limit
+    pub fn orders(
+        &self,
+        is_bid: bool,
+        now_ts: u64,
+        oracle_price_lots: Option<i64>,
+    ) -> Vec<ObV2Order<'a>> {
+        let header = self.header;
+        let live = |leaf: &&ObV2LeafNode| !leaf.is_expired(now_ts);
+
+        let mut orders: Vec<ObV2Order<'a>> = self
+            .traverse(header.fixed_root, header.fixed_leaf_count, is_bid)
+            .into_iter()
+            .filter(live)
+            .map(|leaf| ObV2Order {
+                leaf,
+                price_lots: leaf.price(),
+            })
+            .collect();
+
+        if let Some(oracle_price_lots) = oracle_price_lots {
+            let pegged = self
+                .traverse(
+                    header.oracle_pegged_root,
+                    header.oracle_pegged_leaf_count,
+                    is_bid,
+                )
+                .into_iter()
+                .filter(live);
+            for leaf in pegged {
+                if let Some(price_lots) = leaf.pegged_price(oracle_price_lots, is_bid) {
+                    orders.push(ObV2Order { leaf, price_lots });
+                }
+            }
+
+            // Merge pegged orders into fixed orders by price, stable for same price
+            if is_bid {
+                orders.sort_by(|a, b| b.price_lots.cmp(&a.price_lots));
+            } else {
+                orders.sort_by_key(|x| x.price_lots);
+            }
+        }
+
+        orders
+    }
+
+    // Leaves of tree at root in key order, best price first, stopping at handles outside of nodes or cycles
+    fn traverse(
+        &self,
+        root: ObV2NodeHandle,
+        leaf_count: u32,
+        is_bid: bool,
+    ) -> Vec<&'a ObV2LeafNode> {
+        let num_nodes = self.nodes.len() / size_of::<ObV2AnyNode>();
+        let mut buf = Vec::with_capacity((leaf_count as usize).min(num_nodes));
+        if leaf_count == 0 {
+            return buf;
+        }
+
+        let mut stack: Vec<ObV2NodeHandle> = vec![root];
+        let mut visited = 0;
+        while let Some(handle) = stack.pop() {
+            visited += 1;
+            if visited > num_nodes {
+                break;
+            }
+            let node = match self.node(handle) {
+                Some(node) => node,
+                None => break,
+            };
+            match ObV2NodeTag::try_from(node.tag) {
+                Ok(ObV2NodeTag::LeafNode) => buf.push(cast_ref::<ObV2AnyNode, ObV2LeafNode>(node)),
+                Ok(ObV2NodeTag::InnerNode) => {
+                    let inner: &ObV2InnerNode = cast_ref(node);
+                    let children = inner.children;
+                    // Bids walk higher keys first, asks walk lower keys first
+                    if is_bid {
+                        stack.push(children[0]);
+                        stack.push(children[1]);
+                    } else {
+                        stack.push(children[1]);
+                        stack.push(children[0]);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        buf
+    }
+}
+
+#[derive(Copy, Clone)]
+#[repr(packed)]
+#[allow(dead_code)]
+struct ObV2EventHeapHeader {
+    free_head: u16,
+    used_head: u16,
+    count: u16,
+    padding: u16,
+    seq_num: u64,
+}
+unsafe impl Zeroable for ObV2EventHeapHeader {}
+unsafe impl Pod for ObV2EventHeapHeader {}
+
+#[derive(Copy, Clone)]
+#[repr(packed)]
+#[allow(dead_code)]
+struct ObV2EventNode {
+    next: u16,
+    prev: u16,
+    padding: [u8; 4],
+    event: [u8; 144],
+}
+unsafe impl Zeroable for ObV2EventNode {}
+unsafe impl Pod for ObV2EventNode {}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[repr(packed)]
+pub struct ObV2FillEvent {
+    pub event_type: u8,
+    pub taker_side: u8,
+    pub maker_out: u8,
+    pub maker_slot: u8,
+    pub padding: [u8; 4],
+    pub timestamp: u64,
+    pub market_seq_num: u64,
+    pub maker: [u8; 32],
+    pub maker_timestamp: u64,
+    pub taker: [u8; 32],
+    pub taker_client_order_id: u64,
+    pub price: i64,
+    pub peg_limit: i64,
+    pub quantity: i64,
+    pub maker_client_order_id: u64,
+    pub reserved: [u8; 8],
+}
+unsafe impl Zeroable for ObV2FillEvent {}
+unsafe impl Pod for ObV2FillEvent {}
+
+/*
+ * Helper function for reading fill events of openbook v2 event heap in used list order
+ */
+pub fn parse_event_heap(data: &[u8]) -> Result<Vec<ObV2FillEvent>, DecodeError> {
+    check_anchor_discriminator(data, "EventHeap", "openbook v2 event heap")?;
+    let header_start = ANCHOR_DISCRIMINATOR_LEN;
+    let nodes_start = header_start + size_of::<ObV2EventHeapHeader>();
+    let header: &ObV2EventHeapHeader = read_pod(data, "openbook v2 event heap", header_start)?;
+
+    let mut fills: Vec<ObV2FillEvent> = Vec::new();
+    let mut index = header.used_head as usize;
+    for _ in 0..(header.count as usize).min(EVENT_HEAP_LEN) {
+        let start = nodes_start + index * size_of::<ObV2EventNode>();
+        let node: &ObV2EventNode = match data
+            .get(start..start + size_of::<ObV2EventNode>())
+            .and_then(|x| try_from_bytes(x).ok())
+        {
+            Some(node) => node,
+            None => break,
+        };
+
+        if let Ok(ObV2EventType::Fill) = ObV2EventType::try_from(node.event[0]) {
+            if let Ok(fill) = try_from_bytes::<ObV2FillEvent>(&node.event) {
+                fills.push(*fill);
+            }
+        }
+        index = node.next as usize;
+    }
+
+    Ok(fills)
+}
+
+/*
+ * Helper function for market's oracle price in openbook v2 price lots
+ */
+pub fn oracle_price_lots(price: f64, market: &ObMarketInfo) -> Option<i64> {
+    let lots = price * market.base_lot_size as f64 * 10f64.powi(market.quote_decimals as i32)
+        / (market.quote_lot_size as f64 * 10f64.powi(market.base_decimals as i32));
+
+    (lots.is_finite() && lots >= 1.0).then(|| lots.round() as i64)
+}
+
+pub fn construct_v2_levels(
+    orders: &Vec<ObV2Order>,
+    market: &ObMarketInfo,
+    depth: usize,
+) -> Vec<MarketOrder> {
+    let mut levels: Vec<(u64, u64)> = vec![];
+    for x in orders {
+        let len = levels.len();
+        if len > 0 && levels[len - 1].0 == x.price_lots {
+            levels[len - 1].1 += x.leaf.quantity();
+        } else if len == depth {
+            break;
+        } else {
+            levels.push((x.price_lots, x.leaf.quantity()));
+        }
+    }
+    levels
+        .into_iter()
+        .map(|x| MarketOrder {
+            price: readable_price(x.0, market),
+            amount: readable_quantity(x.1, market),
+            price_lots: x.0,
+            size_lots: x.1,
+            venue: Venue::OpenbookV2,
+        })
+        .collect()
+}
+
+pub fn construct_v2_quotes(orders: &Vec<ObV2Order>, market: &ObMarketInfo) -> Vec<MakerQuote> {
+    orders
+        .iter()
+        .map(|x| MakerQuote {
+            maker: x.leaf.owner().to_string(),
+            price: readable_price(x.price_lots, market),
+            amount: readable_quantity(x.leaf.quantity(), market),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structs::{
+        decode::anchor_discriminator,
+        fixtures::{
+            ob_market, ob_v2_book_side, ob_v2_event_heap, ob_v2_fill_event, ob_v2_leaf,
+            ob_v2_pegged_leaf,
+        },
+    };
+
+    const NOW_TS: u64 = 1_700_000_000;
+
+    fn prices(orders: &Vec<ObV2Order>) -> Vec<u64> {
+        orders.iter().map(|x| x.price_lots).collect()
+    }
+
+    #[test]
+    fn walks_fixed_tree_best_price_first_skipping_expired() {
+        let leaves = [
+            ob_v2_leaf(103, 1, 0, NOW_TS, 1),
+            ob_v2_leaf(101, 2, 0, NOW_TS, 2),
+            ob_v2_leaf(102, 3, 60, NOW_TS - 60, 3),
+            ob_v2_leaf(104, 4, 60, NOW_TS - 30, 4),
+        ];
+        let asks = ob_v2_book_side(&leaves, &[]);
+        let bids = ob_v2_book_side(&leaves, &[]);
+
+        let ask_orders = ObV2BookSide::new(&asks)
+            .unwrap()
+            .orders(false, NOW_TS, None);
+        let bid_orders = ObV2BookSide::new(&bids).unwrap().orders(true, NOW_TS, None);
+
+        // 102 expired at NOW_TS, 104 is still in force
+        assert_eq!(prices(&ask_orders), vec![101, 103, 104]);
+        assert_eq!(prices(&bid_orders), vec![104, 103, 101]);
+    }
+
+    #[test]
+    fn prices_pegged_orders_from_oracle_within_peg_limit() {
+        let fixed = [ob_v2_leaf(98, 1, 0, NOW_TS, 1)];
+        let pegged = [
+            ob_v2_pegged_leaf(-1, 2, -1, 2),
+            ob_v2_pegged_leaf(-5, 3, -1, 3),
+            // Pegged 1 above oracle but limited to 97
+            ob_v2_pegged_leaf(1, 4, 97, 4),
+        ];
+        let data = ob_v2_book_side(&fixed, &pegged);
+        let book_side = ObV2BookSide::new(&data).unwrap();
+
+        assert_eq!(
+            prices(&book_side.orders(true, NOW_TS, Some(100))),
+            vec![99, 98, 95]
+        );
+        assert_eq!(prices(&book_side.orders(true, NOW_TS, None)), vec![98]);
+    }
+
+    #[test]
+    fn aggregates_levels_of_same_price_up_to_depth() {
+        let market = ob_market("SOL-USDC");
+        let leaves = [
+            ob_v2_leaf(20_000, 1_000, 0, NOW_TS, 1),
+            ob_v2_leaf(20_000, 500, 0, NOW_TS, 2),
+            ob_v2_leaf(20_010, 2_000, 0, NOW_TS, 3),
+            ob_v2_leaf(20_020, 3_000, 0, NOW_TS, 4),
+        ];
+        let data = ob_v2_book_side(&leaves, &[]);
+        let orders = ObV2BookSide::new(&data)
+            .unwrap()
+            .orders(false, NOW_TS, None);
+
+        let levels = construct_v2_levels(&orders, &market, 2);
+        assert_eq!(
+            levels
+                .iter()
+                .map(|x| (x.price_lots, x.size_lots))
+                .collect::<Vec<(u64, u64)>>(),
+            vec![(20_000, 1_500), (20_010, 2_000)]
+        );
+        assert_eq!(levels[0].price, 20.0);
+        assert_eq!(levels[0].amount, 1.5);
+        assert_eq!(construct_v2_quotes(&orders, &market).len(), 4);
+    }
+
+    #[test]
+    fn converts_oracle_price_into_price_lots() {
+        let market = ob_market("SOL-USDC");
+
+        assert_eq!(oracle_price_lots(20.0, &market), Some(20_000));
+        assert_eq!(oracle_price_lots(0.0, &market), None);
+    }
+
+    #[test]
+    fn reads_fills_of_event_heap_in_used_order() {
+        let fills = [
+            ob_v2_fill_event(7, 20_000, 5, 1),
+            ob_v2_fill_event(8, 20_010, 6, 0),
+        ];
+
+        assert_eq!(
+            parse_event_heap(&ob_v2_event_heap(&fills)).unwrap(),
+            fills.to_vec()
+        );
+    }
+
+    #[test]
+    fn rejects_accounts_of_other_types() {
+        let mut market = anchor_discriminator("Market").to_vec();
+        market.extend(vec![0u8; size_of::<ObV2MarketState>()]);
+        let mut book_side = ob_v2_book_side(&[], &[]);
+        book_side[..8].copy_from_slice(&anchor_discriminator("EventHeap"));
+
+        assert!(ObV2MarketState::load(&market).is_ok());
+        assert_eq!(
+            ObV2MarketState::load(&market[1..]).err(),
+            Some(DecodeError::InvalidDiscriminator {
+                account: "openbook v2 market"
+            })
+        );
+        assert!(ObV2BookSide::new(&book_side).is_err());
+    }
+}
@@ -0,0 +1,134 @@
+use serde_derive::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+
+use crate::constants::{PYTH_PROGRAM_ID, SWITCHBOARD_PROGRAM_ID};
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum OracleType {
+    Pyth,
+    Switchboard,
+}
+impl OracleType {
+    pub fn from_owner(owner: &Pubkey) -> Option<OracleType> {
+        if owner.eq(&Pubkey::from_str(PYTH_PROGRAM_ID).unwrap()) {
+            Some(OracleType::Pyth)
+        } else if owner.eq(&Pubkey::from_str(SWITCHBOARD_PROGRAM_ID).unwrap()) {
+            Some(OracleType::Switchboard)
+        } else {
+            None
+        }
+    }
+}
+
+/*
+ * Oracle account subscribed for key, which is market's name or SOL_ORACLE_KEY
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct OracleInfo {
+    pub key: String,
+    pub address: Pubkey,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct OraclePrice {
+    pub key: String,
+    pub address: String,
+
+    #[serde(rename = "oracleType")]
+    pub oracle_type: OracleType,
+
+    pub price: f64,
+    pub confidence: f64,
+    pub slot: u64,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct OraclePricePublishData {
+    #[serde(rename = "oraclePrice")]
+    pub oracle_price: OraclePrice,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct OracleDeviation {
+    pub slug: String,
+
+    #[serde(rename = "oraclePrice")]
+    pub oracle_price: f64,
+
+    #[serde(rename = "dexPrice")]
+    pub dex_price: f64,
+
+    #[serde(rename = "deviationBps")]
+    pub deviation_bps: f64,
+
+    pub slot: u64,
+    pub timestamp: u64,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct OracleDeviationPublishData {
+    pub deviation: OracleDeviation,
+}
+
+/*
+ * Quote to USD source of market_info's quote_usd_source,
+ * formatted as oracle:{key}, market:{slug} or fixed:{rate}
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum UsdSource {
+    Oracle(String),
+    Market(String),
+    Fixed(f64),
+}
+impl UsdSource {
+    pub fn parse(source: &str) -> Option<UsdSource> {
+        let (kind, value) = source.split_once(':')?;
+        if value.is_empty() {
+            return None;
+        }
+        match kind {
+            "oracle" => Some(UsdSource::Oracle(value.to_string())),
+            "market" => Some(UsdSource::Market(value.to_string())),
+            "fixed" => value.parse::<f64>().ok().map(UsdSource::Fixed),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_usd_sources() {
+        assert_eq!(
+            UsdSource::parse("oracle:sol"),
+            Some(UsdSource::Oracle("sol".to_string()))
+        );
+        assert_eq!(
+            UsdSource::parse("market:usdt-usdc"),
+            Some(UsdSource::Market("usdt-usdc".to_string()))
+        );
+        assert_eq!(
+            UsdSource::parse("fixed:0.998"),
+            Some(UsdSource::Fixed(0.998))
+        );
+    }
+
+    #[test]
+    fn malformed_usd_source_is_none() {
+        for source in [
+            "",
+            "sol",
+            "oracle:",
+            "market:",
+            "fixed:",
+            "fixed:one",
+            "pyth:sol",
+        ] {
+            assert_eq!(UsdSource::parse(source), None, "{}", source);
+        }
+    }
+}
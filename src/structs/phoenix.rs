@@ -0,0 +1,799 @@
+use anchor_lang::AnchorDeserialize;
+use bytemuck::{try_from_bytes, Pod, Zeroable};
+use num_traits::ToPrimitive;
+use solana_sdk::pubkey::Pubkey;
+use sqlx::types::Decimal;
+use std::{collections::HashMap, mem::size_of};
+
+use crate::{constants::PHOENIX_LOG_INSTRUCTION_TAG, utils::token_factor};
+
+use super::{
+    makers::MakerQuote,
+    market::{MarketOrder, Venue},
+};
+
+const SENTINEL: u32 = 0;
+
+#[derive(Debug, Clone, Default)]
+pub struct PhoenixMarketInfo {
+    pub name: String,
+    pub address: Pubkey,
+    pub base_decimals: u8,
+    pub quote_decimals: u8,
+    pub base_lot_size: u64,
+    pub tick_size_in_quote_atoms_per_base_unit: u64,
+    pub raw_base_units_per_base_unit: u32,
+    pub bids_size: usize,
+    pub asks_size: usize,
+    pub num_seats: usize,
+    pub taker_fee_bps: u64,
+}
+impl PhoenixMarketInfo {
+    pub fn is_valid_account(&self, account: &Pubkey) -> bool {
+        account.eq(&self.address)
+    }
+
+    pub fn readable_price(&self, price_in_ticks: u64) -> Decimal {
+        let quote_factor = token_factor(self.quote_decimals);
+        let raw_base_units = Decimal::from(self.raw_base_units_per_base_unit.max(1));
+        (Decimal::from(price_in_ticks) * Decimal::from(self.tick_size_in_quote_atoms_per_base_unit))
+            .checked_div(quote_factor * raw_base_units)
+            .unwrap_or_default()
+    }
+
+    pub fn readable_quantity(&self, base_lots: u64) -> Decimal {
+        (Decimal::from(base_lots) * Decimal::from(self.base_lot_size))
+            .checked_div(token_factor(self.base_decimals))
+            .unwrap_or_default()
+    }
+}
+
+/*
+ * Phoenix market header, first 576 bytes of market account
+ */
+#[derive(Copy, Clone)]
+#[repr(packed)]
+#[allow(dead_code)]
+pub struct PhoenixMarketHeader {
+    pub discriminant: u64,
+    pub status: u64,
+    pub bids_size: u64,
+    pub asks_size: u64,
+    pub num_seats: u64,
+    pub base_decimals: u32,
+    pub base_vault_bump: u32,
+    pub base_mint: [u8; 32],
+    pub base_vault: [u8; 32],
+    pub base_lot_size: u64,
+    pub quote_decimals: u32,
+    pub quote_vault_bump: u32,
+    pub quote_mint: [u8; 32],
+    pub quote_vault: [u8; 32],
+    pub quote_lot_size: u64,
+    pub tick_size_in_quote_atoms_per_base_unit: u64,
+    pub authority: [u8; 32],
+    pub fee_recipient: [u8; 32],
+    pub market_sequence_number: u64,
+    pub successor: [u8; 32],
+    pub raw_base_units_per_base_unit: u32,
+    pub padding1: u32,
+    pub padding2: [u64; 32],
+}
+unsafe impl Zeroable for PhoenixMarketHeader {}
+unsafe impl Pod for PhoenixMarketHeader {}
+
+impl PhoenixMarketHeader {
+    pub fn load(data: &[u8]) -> Option<&Self> {
+        try_from_bytes(data.get(..size_of::<Self>())?).ok()
+    }
+}
+
+/*
+ * Phoenix fifo market fields between header and order trees
+ */
+#[derive(Copy, Clone)]
+#[repr(packed)]
+#[allow(dead_code)]
+pub struct PhoenixFifoMarket {
+    pub padding: [u64; 32],
+    pub base_lots_per_base_unit: u64,
+    pub tick_size_in_quote_lots_per_base_unit: u64,
+    pub order_sequence_number: u64,
+    pub taker_fee_bps: u64,
+    pub collected_quote_lot_fees: u64,
+    pub unclaimed_quote_lot_fees: u64,
+}
+unsafe impl Zeroable for PhoenixFifoMarket {}
+unsafe impl Pod for PhoenixFifoMarket {}
+
+impl PhoenixFifoMarket {
+    pub fn load(data: &[u8]) -> Option<&Self> {
+        let start = size_of::<PhoenixMarketHeader>();
+        try_from_bytes(data.get(start..start + size_of::<Self>())?).ok()
+    }
+}
+
+/*
+ * Sokoban red black tree header with node allocator header
+ */
+#[derive(Copy, Clone)]
+#[repr(packed)]
+#[allow(dead_code)]
+struct PhoenixTreeHeader {
+    root: u64,
+    padding: u64,
+    size: u64,
+    bump_index: u32,
+    free_list_head: u32,
+}
+unsafe impl Zeroable for PhoenixTreeHeader {}
+unsafe impl Pod for PhoenixTreeHeader {}
+
+#[derive(Copy, Clone)]
+#[repr(packed)]
+#[allow(dead_code)]
+struct PhoenixOrderNode {
+    left: u32,
+    right: u32,
+    parent: u32,
+    color: u32,
+    price_in_ticks: u64,
+    order_sequence_number: u64,
+    trader_index: u64,
+    num_base_lots: u64,
+    last_valid_slot: u64,
+    last_valid_unix_timestamp_in_seconds: u64,
+}
+unsafe impl Zeroable for PhoenixOrderNode {}
+unsafe impl Pod for PhoenixOrderNode {}
+
+#[derive(Copy, Clone)]
+#[repr(packed)]
+#[allow(dead_code)]
+struct PhoenixTraderNode {
+    left: u32,
+    right: u32,
+    parent: u32,
+    color: u32,
+    trader: [u8; 32],
+    quote_lots_locked: u64,
+    quote_lots_free: u64,
+    base_lots_locked: u64,
+    base_lots_free: u64,
+    padding: [u64; 8],
+}
+unsafe impl Zeroable for PhoenixTraderNode {}
+unsafe impl Pod for PhoenixTraderNode {}
+
+trait TreeNode: Pod {
+    fn left(&self) -> u32;
+    fn right(&self) -> u32;
+}
+impl TreeNode for PhoenixOrderNode {
+    fn left(&self) -> u32 {
+        self.left
+    }
+    fn right(&self) -> u32 {
+        self.right
+    }
+}
+impl TreeNode for PhoenixTraderNode {
+    fn left(&self) -> u32 {
+        self.left
+    }
+    fn right(&self) -> u32 {
+        self.right
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PhoenixOrder {
+    pub is_bid: bool,
+    pub price_in_ticks: u64,
+    pub order_sequence_number: u64,
+    pub trader_index: u64,
+    pub base_lots: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PhoenixTrader {
+    pub owner: Pubkey,
+    pub quote_lots_locked: u64,
+    pub quote_lots_free: u64,
+    pub base_lots_locked: u64,
+    pub base_lots_free: u64,
+}
+
+/*
+ * Decoded phoenix orderbook, bids best first and asks best first
+ */
+#[derive(Debug, Clone, Default)]
+pub struct PhoenixBook {
+    pub sequence_number: u64,
+    pub bids: Vec<PhoenixOrder>,
+    pub asks: Vec<PhoenixOrder>,
+    pub traders: HashMap<u64, PhoenixTrader>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PhoenixFill {
+    pub order: PhoenixOrder,
+    pub maker: Pubkey,
+    pub base_lots: u64,
+}
+
+/*
+ * Phoenix audit log header leading every self-CPI log instruction
+ */
+#[derive(Debug, Clone, AnchorDeserialize)]
+#[allow(dead_code)]
+pub struct PhoenixAuditLogHeader {
+    pub instruction: u8,
+    pub sequence_number: u64,
+    pub timestamp: i64,
+    pub slot: u64,
+    pub market: [u8; 32],
+    pub signer: [u8; 32],
+    pub total_events: u16,
+}
+
+#[derive(Debug, Clone, AnchorDeserialize)]
+#[allow(dead_code)]
+pub struct PhoenixFillEvent {
+    pub index: u16,
+    pub maker_id: [u8; 32],
+    pub order_sequence_number: u64,
+    pub price_in_ticks: u64,
+    pub base_lots_filled: u64,
+    pub base_lots_remaining: u64,
+}
+
+#[derive(Debug, Clone, AnchorDeserialize)]
+#[allow(dead_code)]
+pub struct PhoenixPlaceEvent {
+    pub index: u16,
+    pub order_sequence_number: u64,
+    pub client_order_id: u128,
+    pub price_in_ticks: u64,
+    pub base_lots_placed: u64,
+}
+
+#[derive(Debug, Clone, AnchorDeserialize)]
+#[allow(dead_code)]
+pub struct PhoenixReduceEvent {
+    pub index: u16,
+    pub order_sequence_number: u64,
+    pub price_in_ticks: u64,
+    pub base_lots_removed: u64,
+    pub base_lots_remaining: u64,
+}
+
+#[derive(Debug, Clone, AnchorDeserialize)]
+#[allow(dead_code)]
+pub struct PhoenixEvictEvent {
+    pub index: u16,
+    pub maker_id: [u8; 32],
+    pub order_sequence_number: u64,
+    pub price_in_ticks: u64,
+    pub base_lots_evicted: u64,
+}
+
+#[derive(Debug, Clone, AnchorDeserialize)]
+#[allow(dead_code)]
+pub struct PhoenixFillSummaryEvent {
+    pub index: u16,
+    pub client_order_id: u128,
+    pub total_base_lots_filled: u64,
+    pub total_quote_lots_filled: u64,
+    pub total_fee_in_quote_lots: u64,
+}
+
+#[derive(Debug, Clone, AnchorDeserialize)]
+#[allow(dead_code)]
+pub struct PhoenixFeeEvent {
+    pub index: u16,
+    pub fees_collected_in_quote_lots: u64,
+}
+
+#[derive(Debug, Clone, AnchorDeserialize)]
+#[allow(dead_code)]
+pub struct PhoenixTimeInForceEvent {
+    pub index: u16,
+    pub order_sequence_number: u64,
+    pub last_valid_slot: u64,
+    pub last_valid_unix_timestamp_in_seconds: u64,
+}
+
+#[derive(Debug, Clone, AnchorDeserialize)]
+#[allow(dead_code)]
+pub struct PhoenixExpiredOrderEvent {
+    pub index: u16,
+    pub maker_id: [u8; 32],
+    pub order_sequence_number: u64,
+    pub price_in_ticks: u64,
+    pub base_lots_removed: u64,
+}
+
+/*
+ * Phoenix market event, borsh enum emitted by market instructions through self-CPI log instruction
+ */
+#[derive(Debug, Clone, AnchorDeserialize)]
+#[allow(dead_code)]
+pub enum PhoenixMarketEvent {
+    Uninitialized,
+    Header(PhoenixAuditLogHeader),
+    Fill(PhoenixFillEvent),
+    Place(PhoenixPlaceEvent),
+    Reduce(PhoenixReduceEvent),
+    Evict(PhoenixEvictEvent),
+    FillSummary(PhoenixFillSummaryEvent),
+    Fee(PhoenixFeeEvent),
+    TimeInForce(PhoenixTimeInForceEvent),
+    ExpiredOrder(PhoenixExpiredOrderEvent),
+}
+
+/*
+ * Fill events of one phoenix log instruction with their audit header
+ */
+#[derive(Debug, Clone)]
+pub struct PhoenixLog {
+    pub header: PhoenixAuditLogHeader,
+    pub fills: Vec<PhoenixFillEvent>,
+}
+
+/*
+ * Function: parse_phoenix_log
+ * 1. Skip instruction data not tagged as phoenix log instruction
+ * 2. Decode header event, then market events until data is exhausted
+ * Returns None if data isn't a well formed log instruction
+ */
+pub fn parse_phoenix_log(data: &[u8]) -> Option<PhoenixLog> {
+    let (tag, mut bytes) = data.split_first()?;
+    if *tag != PHOENIX_LOG_INSTRUCTION_TAG {
+        return None;
+    }
+
+    let header = match PhoenixMarketEvent::deserialize(&mut bytes).ok()? {
+        PhoenixMarketEvent::Header(header) => header,
+        _ => return None,
+    };
+    // Events follow header without length prefix, a log may hold a batch of the instruction's events
+    let mut fills: Vec<PhoenixFillEvent> = Vec::new();
+    while !bytes.is_empty() {
+        if let PhoenixMarketEvent::Fill(fill) = PhoenixMarketEvent::deserialize(&mut bytes).ok()? {
+            fills.push(fill);
+        }
+    }
+
+    Some(PhoenixLog { header, fills })
+}
+
+/*
+ * Helper function for side of resting order, bid sequence numbers are stored bit inverted
+ */
+pub fn phoenix_is_bid(order_sequence_number: u64) -> bool {
+    order_sequence_number.leading_zeros() == 0
+}
+
+/*
+ * Helper function for in-order walking of sokoban red black tree, nodes are addressed from 1
+ */
+fn walk_tree<T: TreeNode>(data: &[u8], offset: usize, max_size: usize) -> Vec<(u64, T)> {
+    let nodes_start = offset + size_of::<PhoenixTreeHeader>();
+    let header: &PhoenixTreeHeader = match data
+        .get(offset..nodes_start)
+        .and_then(|x| try_from_bytes(x).ok())
+    {
+        Some(header) => header,
+        None => return vec![],
+    };
+    let node_at = |index: u32| -> Option<T> {
+        if index == SENTINEL || index as usize > max_size {
+            return None;
+        }
+        let start = nodes_start + (index as usize - 1) * size_of::<T>();
+        data.get(start..start + size_of::<T>())
+            .and_then(|x| try_from_bytes::<T>(x).ok())
+            .copied()
+    };
+
+    let mut nodes: Vec<(u64, T)> = Vec::new();
+    let mut stack: Vec<(u32, T)> = Vec::new();
+    let mut current = header.root as u32;
+    while nodes.len() < max_size {
+        while let Some(node) = node_at(current) {
+            stack.push((current, node));
+            current = node.left();
+            if stack.len() > max_size {
+                return nodes;
+            }
+        }
+        match stack.pop() {
+            Some((index, node)) => {
+                nodes.push((index as u64, node));
+                current = node.right();
+            }
+            None => break,
+        }
+    }
+
+    nodes
+}
+
+/*
+ * Function: parse_phoenix_book
+ * 1. Read bids/asks/traders trees following market header and fifo market fields
+ * 2. Sort bids by price descending and asks by price ascending, time priority within a price
+ */
+pub fn parse_phoenix_book(data: &[u8], market: &PhoenixMarketInfo) -> Option<PhoenixBook> {
+    let header = PhoenixMarketHeader::load(data)?;
+    let bids_offset = size_of::<PhoenixMarketHeader>() + size_of::<PhoenixFifoMarket>();
+    let asks_offset = bids_offset
+        + size_of::<PhoenixTreeHeader>()
+        + market.bids_size * size_of::<PhoenixOrderNode>();
+    let traders_offset = asks_offset
+        + size_of::<PhoenixTreeHeader>()
+        + market.asks_size * size_of::<PhoenixOrderNode>();
+    if data.len()
+        < traders_offset
+            + size_of::<PhoenixTreeHeader>()
+            + market.num_seats * size_of::<PhoenixTraderNode>()
+    {
+        return None;
+    }
+
+    let to_order = |is_bid: bool, node: PhoenixOrderNode| PhoenixOrder {
+        is_bid,
+        price_in_ticks: node.price_in_ticks,
+        order_sequence_number: node.order_sequence_number,
+        trader_index: node.trader_index,
+        base_lots: node.num_base_lots,
+    };
+    let mut bids: Vec<PhoenixOrder> =
+        walk_tree::<PhoenixOrderNode>(data, bids_offset, market.bids_size)
+            .into_iter()
+            .map(|(_, node)| to_order(true, node))
+            .collect();
+    let mut asks: Vec<PhoenixOrder> =
+        walk_tree::<PhoenixOrderNode>(data, asks_offset, market.asks_size)
+            .into_iter()
+            .map(|(_, node)| to_order(false, node))
+            .collect();
+    // Bid sequence numbers are stored bit inverted, so older bids have larger ones
+    bids.sort_by(|a, b| {
+        b.price_in_ticks
+            .cmp(&a.price_in_ticks)
+            .then(b.order_sequence_number.cmp(&a.order_sequence_number))
+    });
+    asks.sort_by(|a, b| {
+        a.price_in_ticks
+            .cmp(&b.price_in_ticks)
+            .then(a.order_sequence_number.cmp(&b.order_sequence_number))
+    });
+
+    let traders = walk_tree::<PhoenixTraderNode>(data, traders_offset, market.num_seats)
+        .into_iter()
+        .map(|(index, node)| {
+            (
+                index,
+                PhoenixTrader {
+                    owner: Pubkey::new_from_array(node.trader),
+                    quote_lots_locked: node.quote_lots_locked,
+                    quote_lots_free: node.quote_lots_free,
+                    base_lots_locked: node.base_lots_locked,
+                    base_lots_free: node.base_lots_free,
+                },
+            )
+        })
+        .collect();
+
+    Some(PhoenixBook {
+        sequence_number: header.market_sequence_number,
+        bids,
+        asks,
+        traders,
+    })
+}
+
+/*
+ * Function: detect_phoenix_fills
+ * 1. Skip if market sequence number didn't move
+ * 2. Compare resting orders of previous book against next book, removed or reduced orders are candidates
+ * 3. Count candidate as fill only if maker seat got credited in counter token, otherwise it was cancel/reduce
+ */
+pub fn detect_phoenix_fills(prev: &PhoenixBook, next: &PhoenixBook) -> Vec<PhoenixFill> {
+    let mut fills: Vec<PhoenixFill> = Vec::new();
+    if next.sequence_number == prev.sequence_number {
+        return fills;
+    }
+
+    let next_lots: HashMap<(bool, u64, u64), u64> = next
+        .bids
+        .iter()
+        .chain(next.asks.iter())
+        .map(|x| {
+            (
+                (x.is_bid, x.price_in_ticks, x.order_sequence_number),
+                x.base_lots,
+            )
+        })
+        .collect();
+
+    for order in prev.bids.iter().chain(prev.asks.iter()) {
+        let remaining = next_lots
+            .get(&(
+                order.is_bid,
+                order.price_in_ticks,
+                order.order_sequence_number,
+            ))
+            .cloned()
+            .unwrap_or_default();
+        if remaining >= order.base_lots {
+            continue;
+        }
+
+        let (prev_trader, next_trader) = match (
+            prev.traders.get(&order.trader_index),
+            next.traders.get(&order.trader_index),
+        ) {
+            (Some(prev_trader), Some(next_trader)) => (prev_trader, next_trader),
+            _ => continue,
+        };
+        // Filled bid credits base, filled ask credits quote
+        let credited = if order.is_bid {
+            next_trader.base_lots_free > prev_trader.base_lots_free
+        } else {
+            next_trader.quote_lots_free > prev_trader.quote_lots_free
+        };
+        if !credited {
+            continue;
+        }
+
+        fills.push(PhoenixFill {
+            order: *order,
+            maker: prev_trader.owner,
+            base_lots: order.base_lots - remaining,
+        });
+    }
+
+    fills
+}
+
+pub fn construct_phoenix_levels(
+    orders: &Vec<PhoenixOrder>,
+    market: &PhoenixMarketInfo,
+    depth: usize,
+) -> Vec<MarketOrder> {
+    let mut levels: Vec<(u64, u64)> = vec![];
+    for x in orders {
+        let len = levels.len();
+        if len > 0 && levels[len - 1].0 == x.price_in_ticks {
+            levels[len - 1].1 += x.base_lots;
+        } else if len == depth {
+            break;
+        } else {
+            levels.push((x.price_in_ticks, x.base_lots));
+        }
+    }
+    levels
+        .into_iter()
+        .map(|x| MarketOrder {
+            price: market.readable_price(x.0).to_f64().unwrap_or_default(),
+            amount: market.readable_quantity(x.1).to_f64().unwrap_or_default(),
+            price_lots: x.0,
+            size_lots: x.1,
+            venue: Venue::Phoenix,
+        })
+        .collect()
+}
+
+pub fn construct_phoenix_quotes(
+    orders: &Vec<PhoenixOrder>,
+    traders: &HashMap<u64, PhoenixTrader>,
+    market: &PhoenixMarketInfo,
+) -> Vec<MakerQuote> {
+    orders
+        .iter()
+        .map(|x| MakerQuote {
+            maker: traders
+                .get(&x.trader_index)
+                .map(|t| t.owner.to_string())
+                .unwrap_or_default(),
+            price: market
+                .readable_price(x.price_in_ticks)
+                .to_f64()
+                .unwrap_or_default(),
+            amount: market
+                .readable_quantity(x.base_lots)
+                .to_f64()
+                .unwrap_or_default(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structs::fixtures::{phoenix_log_data, phoenix_market, phoenix_market_data};
+
+    const BID_SEQ_BIT: u64 = 1 << 63;
+
+    fn order(
+        is_bid: bool,
+        price_in_ticks: u64,
+        seq: u64,
+        trader_index: u64,
+        base_lots: u64,
+    ) -> PhoenixOrder {
+        PhoenixOrder {
+            is_bid,
+            price_in_ticks,
+            order_sequence_number: if is_bid { !seq } else { seq },
+            trader_index,
+            base_lots,
+        }
+    }
+
+    fn trader(owner: Pubkey, base_lots_free: u64, quote_lots_free: u64) -> PhoenixTrader {
+        PhoenixTrader {
+            owner,
+            base_lots_free,
+            quote_lots_free,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn walks_sokoban_tree_in_order_following_pointers() {
+        let market = phoenix_market("SOL-USDC");
+        let asks = [
+            order(false, 101, 1, 1, 10),
+            order(false, 102, 2, 1, 20),
+            order(false, 103, 3, 2, 30),
+            order(false, 104, 4, 2, 40),
+        ];
+        let data = phoenix_market_data(&market, 1, &[], &asks, &[]);
+        let bids_offset = size_of::<PhoenixMarketHeader>() + size_of::<PhoenixFifoMarket>();
+        let asks_offset = bids_offset
+            + size_of::<PhoenixTreeHeader>()
+            + market.bids_size * size_of::<PhoenixOrderNode>();
+
+        let nodes = walk_tree::<PhoenixOrderNode>(&data, asks_offset, market.asks_size);
+        let walked: Vec<(u64, u64)> = nodes
+            .iter()
+            .map(|(index, node)| (*index, node.price_in_ticks))
+            .collect();
+        // Root is the third node, in-order walk still yields key order
+        assert_eq!(walked, vec![(1, 101), (2, 102), (3, 103), (4, 104)]);
+
+        // Empty tree and out of bounds offset yield no nodes
+        assert!(walk_tree::<PhoenixOrderNode>(&data, bids_offset, market.bids_size).is_empty());
+        assert!(walk_tree::<PhoenixOrderNode>(&data, data.len(), 4).is_empty());
+    }
+
+    #[test]
+    fn stops_walking_cyclic_tree_at_capacity() {
+        let market = phoenix_market("SOL-USDC");
+        let asks = [order(false, 101, 1, 1, 10), order(false, 102, 2, 1, 20)];
+        let mut data = phoenix_market_data(&market, 1, &[], &asks, &[]);
+        let asks_offset = size_of::<PhoenixMarketHeader>()
+            + size_of::<PhoenixFifoMarket>()
+            + size_of::<PhoenixTreeHeader>()
+            + market.bids_size * size_of::<PhoenixOrderNode>();
+        // Root is the second node, point its right child back at itself
+        let root_node =
+            asks_offset + size_of::<PhoenixTreeHeader>() + size_of::<PhoenixOrderNode>();
+        data[root_node + 4..root_node + 8].copy_from_slice(&2u32.to_le_bytes());
+
+        let nodes = walk_tree::<PhoenixOrderNode>(&data, asks_offset, market.asks_size);
+        assert!(nodes.len() <= market.asks_size);
+    }
+
+    #[test]
+    fn parses_book_sorted_by_price_with_seat_owners() {
+        let market = phoenix_market("SOL-USDC");
+        let (alice, bob) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let bids = [
+            order(true, 98, 3, 2, 30),
+            order(true, 99, 1, 1, 10),
+            order(true, 99, 2, 2, 20),
+        ];
+        let asks = [order(false, 102, 5, 1, 50), order(false, 101, 4, 2, 40)];
+        let traders = [trader(alice, 1, 2), trader(bob, 3, 4)];
+        let data = phoenix_market_data(&market, 42, &bids, &asks, &traders);
+
+        let book = parse_phoenix_book(&data, &market).unwrap();
+        assert_eq!(book.sequence_number, 42);
+        let prices = |orders: &Vec<PhoenixOrder>| -> Vec<(u64, u64)> {
+            orders
+                .iter()
+                .map(|x| (x.price_in_ticks, x.base_lots))
+                .collect()
+        };
+        // Older bid first within a price
+        assert_eq!(prices(&book.bids), vec![(99, 10), (99, 20), (98, 30)]);
+        assert_eq!(prices(&book.asks), vec![(101, 40), (102, 50)]);
+        assert!(book.bids.iter().all(|x| x.is_bid));
+        assert_eq!(book.traders.get(&1), Some(&trader(alice, 1, 2)));
+        assert_eq!(book.traders.get(&2), Some(&trader(bob, 3, 4)));
+
+        let levels = construct_phoenix_levels(&book.bids, &market, 10);
+        assert_eq!(levels.len(), 2);
+        assert_eq!(levels[0].size_lots, 30);
+        assert_eq!(levels[0].price, 0.099);
+        assert_eq!(levels[0].amount, 0.03);
+
+        // Truncated account doesn't parse
+        assert!(parse_phoenix_book(&data[..data.len() - 1], &market).is_none());
+    }
+
+    #[test]
+    fn detects_fills_only_when_maker_seat_is_credited() {
+        let market = phoenix_market("SOL-USDC");
+        let (maker, other) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let asks = [order(false, 101, 1, 1, 10), order(false, 102, 2, 2, 20)];
+        let prev_data = phoenix_market_data(
+            &market,
+            1,
+            &[],
+            &asks,
+            &[trader(maker, 0, 0), trader(other, 0, 0)],
+        );
+        let prev = parse_phoenix_book(&prev_data, &market).unwrap();
+
+        // First ask partially filled crediting quote, second one cancelled returning base
+        let next_data = phoenix_market_data(
+            &market,
+            2,
+            &[],
+            &[order(false, 101, 1, 1, 4)],
+            &[trader(maker, 0, 606), trader(other, 20, 0)],
+        );
+        let next = parse_phoenix_book(&next_data, &market).unwrap();
+        let fills = detect_phoenix_fills(&prev, &next);
+        assert_eq!(fills.len(), 1);
+        assert_eq!(fills[0].base_lots, 6);
+        assert_eq!(fills[0].maker, maker);
+
+        // Unchanged sequence number yields no fills
+        let mut same_seq = next.clone();
+        same_seq.sequence_number = prev.sequence_number;
+        assert!(detect_phoenix_fills(&prev, &same_seq).is_empty());
+    }
+
+    #[test]
+    fn parses_log_fills_with_header_skipping_other_events() {
+        let (market, signer, maker) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let data = phoenix_log_data(
+            &market,
+            &signer,
+            1_700_000_123,
+            &[(maker, 5, 101, 6), (maker, !7, 99, 3)],
+        );
+
+        let log = parse_phoenix_log(&data).unwrap();
+        assert_eq!(log.header.timestamp, 1_700_000_123);
+        assert_eq!(Pubkey::new_from_array(log.header.market), market);
+        assert_eq!(Pubkey::new_from_array(log.header.signer), signer);
+        assert_eq!(log.fills.len(), 2);
+        assert_eq!(log.fills[0].price_in_ticks, 101);
+        assert_eq!(log.fills[0].base_lots_filled, 6);
+        assert_eq!(Pubkey::new_from_array(log.fills[1].maker_id), maker);
+        assert!(!phoenix_is_bid(log.fills[0].order_sequence_number));
+        assert!(phoenix_is_bid(log.fills[1].order_sequence_number));
+        assert_eq!(
+            log.fills[1].order_sequence_number & BID_SEQ_BIT,
+            BID_SEQ_BIT
+        );
+
+        // Other instructions and truncated logs aren't decoded
+        let mut swap = data.clone();
+        swap[0] = 0;
+        assert!(parse_phoenix_log(&swap).is_none());
+        assert!(parse_phoenix_log(&data[..data.len() - 1]).is_none());
+    }
+}
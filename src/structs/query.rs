@@ -0,0 +1,59 @@
+use postgrest::Postgrest;
+use redis::Client;
+
+/*
+ * Query answered from supabase or redis, requested by path of the query http endpoint
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum Query {
+    // Orderbook of market rebuilt from archived snapshot and deltas at slot/timestamp
+    Orderbook {
+        market: String,
+        slot: Option<u64>,
+        timestamp: Option<u64>,
+    },
+    // Latest gigadex balance transitions of uid in market
+    Balances {
+        market: String,
+        uid: u64,
+        limit: u64,
+    },
+    // Indicator history of market's candle unit between begin timestamps, up to now if to_ts is None
+    Indicators {
+        market: String,
+        unit: String,
+        from_ts: u64,
+        to_ts: Option<u64>,
+    },
+    // Latest published aum
+    Aum,
+    // Aum time series between timestamps, up to now if to_ts is None
+    AumHistory {
+        from_ts: u64,
+        to_ts: Option<u64>,
+    },
+    // Ledger positions of market, only trader's position if set
+    Ledger {
+        market: String,
+        trader: Option<String>,
+    },
+    // Gigadex uid to wallet map of market, only uid's wallet if set
+    UidWallets {
+        market: String,
+        uid: Option<u64>,
+    },
+    // Maker leaderboard of market's day, today if day_ts is None
+    Makers {
+        market: String,
+        day_ts: Option<u64>,
+    },
+}
+
+/*
+ * Clients shared by requests of the query http endpoint
+ */
+#[derive(Clone)]
+pub struct QueryState {
+    pub supabase_client: Postgrest,
+    pub redis_client: Client,
+}
@@ -0,0 +1,278 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use redis::{ConnectionLike, RedisResult, Value};
+use solana_sdk::pubkey::Pubkey;
+use std::{collections::HashMap, sync::Mutex};
+
+use super::{
+    analytics::BookMetrics,
+    arbitrage::ArbOpportunity,
+    archive::{OrderbookDelta, OrderbookSnapshot},
+    aum::AumData,
+    geyser::Account,
+    gigadex::{GdBalanceEvent, GdUidWallet},
+    makers::MakerStats,
+    market::{EventData, MarketConfig},
+};
+
+/*
+ * First record of recording log, market configs and accounts adapters read at startup
+ */
+#[derive(Debug, Clone, PartialEq, BorshSerialize, BorshDeserialize)]
+pub struct RecordingHeader {
+    pub recorded_at_ms: u64,
+    // Market configs as json, as they are kept in redis
+    pub markets: String,
+    pub accounts: Vec<([u8; 32], Vec<u8>)>,
+}
+
+impl RecordingHeader {
+    pub fn new(
+        markets: &[MarketConfig],
+        accounts: HashMap<Pubkey, Vec<u8>>,
+        recorded_at_ms: u64,
+    ) -> anyhow::Result<Self> {
+        let mut accounts: Vec<([u8; 32], Vec<u8>)> = accounts
+            .into_iter()
+            .map(|(pubkey, data)| (pubkey.to_bytes(), data))
+            .collect();
+        accounts.sort_by_key(|x| x.0);
+
+        Ok(Self {
+            recorded_at_ms,
+            markets: serde_json::to_string(markets)?,
+            accounts,
+        })
+    }
+
+    pub fn market_configs(&self) -> anyhow::Result<Vec<MarketConfig>> {
+        Ok(serde_json::from_str(&self.markets)?)
+    }
+
+    pub fn account_map(&self) -> HashMap<Pubkey, Vec<u8>> {
+        self.accounts
+            .iter()
+            .map(|(pubkey, data)| (Pubkey::new_from_array(*pubkey), data.clone()))
+            .collect()
+    }
+}
+
+/*
+ * Account update as stored in recording log, prefixed by its u32 length
+ */
+#[derive(Debug, Clone, PartialEq, BorshSerialize, BorshDeserialize)]
+pub struct RecordedAccount {
+    pub received_at_ms: u64,
+    pub slot: u64,
+    pub pubkey: [u8; 32],
+    pub owner: [u8; 32],
+    pub lamports: u64,
+    pub data: Vec<u8>,
+    pub write_version: u64,
+    pub txn_signature: String,
+}
+
+impl RecordedAccount {
+    pub fn new(account: &Account, received_at_ms: u64) -> Self {
+        Self {
+            received_at_ms,
+            slot: account.slot,
+            pubkey: account.pubkey.to_bytes(),
+            owner: account.owner.to_bytes(),
+            lamports: account.lamports,
+            data: account.data.clone(),
+            write_version: account.write_version,
+            txn_signature: account.txn_signature.clone(),
+        }
+    }
+}
+
+impl From<RecordedAccount> for Account {
+    fn from(record: RecordedAccount) -> Self {
+        Self {
+            is_startup: false,
+            slot: record.slot,
+            pubkey: Pubkey::new_from_array(record.pubkey),
+            lamports: record.lamports,
+            owner: Pubkey::new_from_array(record.owner),
+            executable: false,
+            rent_epoch: 0,
+            data: record.data,
+            write_version: record.write_version,
+            txn_signature: record.txn_signature,
+        }
+    }
+}
+
+/*
+ * In-memory redis keeping published messages, strings and hashes written by parsers and publishers
+ */
+#[derive(Debug, Default)]
+pub struct MemoryRedis {
+    pub published: Vec<(String, String)>,
+    pub strings: HashMap<String, String>,
+    pub hashes: HashMap<String, HashMap<String, String>>,
+}
+
+impl MemoryRedis {
+    /*
+     * Helper function for applying a command, only commands used by parsers are stored
+     */
+    fn apply(&mut self, args: Vec<String>) -> Value {
+        let name = args.first().map(|x| x.to_uppercase()).unwrap_or_default();
+        match name.as_str() {
+            "PUBLISH" if args.len() == 3 => {
+                self.published.push((args[1].clone(), args[2].clone()));
+                Value::Int(0)
+            }
+            "SET" if args.len() >= 3 => {
+                let nx = args[3..].iter().any(|x| x.to_uppercase() == "NX");
+                if nx && self.strings.contains_key(&args[1]) {
+                    return Value::Nil;
+                }
+                self.strings.insert(args[1].clone(), args[2].clone());
+                Value::Okay
+            }
+            "GET" if args.len() == 2 => match self.strings.get(&args[1]) {
+                Some(value) => Value::Data(value.clone().into_bytes()),
+                None => Value::Nil,
+            },
+            "HGET" if args.len() == 3 => {
+                match self.hashes.get(&args[1]).and_then(|x| x.get(&args[2])) {
+                    Some(value) => Value::Data(value.clone().into_bytes()),
+                    None => Value::Nil,
+                }
+            }
+            "DEL" => {
+                let removed = args[1..]
+                    .iter()
+                    .filter(|x| {
+                        let string = self.strings.remove(*x).is_some();
+                        let hash = self.hashes.remove(*x).is_some();
+                        string || hash
+                    })
+                    .count();
+                Value::Int(removed as i64)
+            }
+            "HGETALL" if args.len() == 2 => Value::Bulk(
+                self.hashes
+                    .get(&args[1])
+                    .map(|hash| {
+                        hash.iter()
+                            .flat_map(|(field, value)| {
+                                [
+                                    Value::Data(field.clone().into_bytes()),
+                                    Value::Data(value.clone().into_bytes()),
+                                ]
+                            })
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+            ),
+            "SMEMBERS" => Value::Bulk(vec![]),
+            "HSET" | "HMSET" if args.len() >= 2 => {
+                let hash = self.hashes.entry(args[1].clone()).or_default();
+                let mut added = 0;
+                for pair in args[2..].chunks(2) {
+                    if let [field, value] = pair {
+                        if hash.insert(field.clone(), value.clone()).is_none() {
+                            added += 1;
+                        }
+                    }
+                }
+                if name == "HSET" {
+                    Value::Int(added)
+                } else {
+                    Value::Okay
+                }
+            }
+            _ => Value::Okay,
+        }
+    }
+}
+
+/*
+ * Helper function for decoding packed RESP commands into their arguments
+ */
+fn parse_packed_commands(mut cmd: &[u8]) -> Vec<Vec<String>> {
+    fn read_line<'a>(cmd: &mut &'a [u8]) -> Option<&'a [u8]> {
+        let end = cmd.windows(2).position(|x| x == b"\r\n")?;
+        let line = &cmd[..end];
+        *cmd = &cmd[end + 2..];
+        Some(line)
+    }
+    fn read_number(line: &[u8], prefix: u8) -> Option<usize> {
+        if line.first() != Some(&prefix) {
+            return None;
+        }
+        std::str::from_utf8(&line[1..]).ok()?.parse::<usize>().ok()
+    }
+
+    let mut commands: Vec<Vec<String>> = Vec::new();
+    while let Some(count) = read_line(&mut cmd).and_then(|x| read_number(x, b'*')) {
+        let mut args: Vec<String> = Vec::new();
+        for _ in 0..count {
+            let len = match read_line(&mut cmd).and_then(|x| read_number(x, b'$')) {
+                Some(len) if cmd.len() >= len + 2 => len,
+                _ => return commands,
+            };
+            args.push(String::from_utf8_lossy(&cmd[..len]).to_string());
+            cmd = &cmd[len + 2..];
+        }
+        commands.push(args);
+    }
+
+    commands
+}
+
+impl ConnectionLike for MemoryRedis {
+    fn req_packed_command(&mut self, cmd: &[u8]) -> RedisResult<Value> {
+        let mut values: Vec<Value> = parse_packed_commands(cmd)
+            .into_iter()
+            .map(|args| self.apply(args))
+            .collect();
+
+        Ok(values.pop().unwrap_or(Value::Nil))
+    }
+
+    fn req_packed_commands(
+        &mut self,
+        cmd: &[u8],
+        offset: usize,
+        count: usize,
+    ) -> RedisResult<Vec<Value>> {
+        let values: Vec<Value> = parse_packed_commands(cmd)
+            .into_iter()
+            .map(|args| self.apply(args))
+            .collect();
+
+        Ok(values.into_iter().skip(offset).take(count).collect())
+    }
+
+    fn get_db(&self) -> i64 {
+        0
+    }
+
+    fn check_connection(&mut self) -> bool {
+        true
+    }
+
+    fn is_open(&self) -> bool {
+        true
+    }
+}
+
+/*
+ * In-memory db keeping rows inserted while processing updates
+ */
+#[derive(Debug, Default)]
+pub struct MemoryDb {
+    pub events: Mutex<Vec<EventData>>,
+    pub balance_events: Mutex<Vec<GdBalanceEvent>>,
+    pub uid_wallets: Mutex<Vec<GdUidWallet>>,
+    pub book_metrics: Mutex<Vec<BookMetrics>>,
+    pub orderbook_deltas: Mutex<Vec<OrderbookDelta>>,
+    pub orderbook_snapshots: Mutex<Vec<OrderbookSnapshot>>,
+    pub arb_opportunities: Mutex<Vec<ArbOpportunity>>,
+    pub maker_stats: Mutex<Vec<MakerStats>>,
+    pub aums: Mutex<Vec<AumData>>,
+}
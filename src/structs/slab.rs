@@ -8,9 +8,14 @@ use std::{
     mem::{align_of, size_of},
 };
 
-use crate::utils::token_factor;
+use crate::utils::{array_to_pubkey, token_factor};
 
-use super::{market::MarketOrder, openbook::ObMarketInfo};
+use super::{
+    decode::DecodeError,
+    makers::MakerQuote,
+    market::{MarketOrder, Venue},
+    openbook::ObMarketInfo,
+};
 
 pub type NodeHandle = u32;
 
@@ -156,6 +161,17 @@ unsafe impl Pod for SlabHeader {}
 
 const SLAB_HEADER_LEN: usize = size_of::<SlabHeader>();
 
+const ACCOUNT_HEAD_PADDING: &[u8; 5] = b"serum";
+const ACCOUNT_TAIL_PADDING: &[u8; 7] = b"padding";
+const ACCOUNT_FLAGS_LEN: usize = 8;
+const ACCOUNT_PADDING_LEN: usize =
+    ACCOUNT_HEAD_PADDING.len() + ACCOUNT_FLAGS_LEN + ACCOUNT_TAIL_PADDING.len();
+
+pub const ACCOUNT_FLAG_INITIALIZED: u64 = 1;
+pub const ACCOUNT_FLAG_EVENT_QUEUE: u64 = 1 << 4;
+pub const ACCOUNT_FLAG_BIDS: u64 = 1 << 5;
+pub const ACCOUNT_FLAG_ASKS: u64 = 1 << 6;
+
 #[cfg(debug_assertions)]
 unsafe fn invariant(check: bool) {
     if check {
@@ -176,19 +192,43 @@ unsafe fn invariant(check: bool) {
 pub struct Slab([u8]);
 
 impl Slab {
-    /// Creates a slab that holds and references the bytes
+    /// Creates a slab that holds and references the bytes of a bids or asks account
     #[inline]
     #[allow(unsafe_code)]
-    pub fn new(raw_bytes: &mut [u8]) -> &mut Self {
-        let data_end = raw_bytes.len() - 7;
-        let bytes = &mut raw_bytes[13..data_end];
-        let len_without_header = bytes.len().checked_sub(SLAB_HEADER_LEN).unwrap();
+    pub fn new(raw_bytes: &mut [u8], is_bid: bool) -> Result<&mut Self, DecodeError> {
+        let account = if is_bid {
+            "openbook bids"
+        } else {
+            "openbook asks"
+        };
+        let flags = check_account_padding(raw_bytes, account)?;
+        let side_flag = if is_bid {
+            ACCOUNT_FLAG_BIDS
+        } else {
+            ACCOUNT_FLAG_ASKS
+        };
+        if flags & ACCOUNT_FLAG_INITIALIZED == 0 || flags & side_flag == 0 {
+            return Err(DecodeError::InvalidFlags { account, flags });
+        }
+
+        let raw_len = raw_bytes.len();
+        let data_end = raw_len - ACCOUNT_TAIL_PADDING.len();
+        let bytes = &mut raw_bytes[ACCOUNT_HEAD_PADDING.len() + ACCOUNT_FLAGS_LEN..data_end];
+        let len_without_header =
+            bytes
+                .len()
+                .checked_sub(SLAB_HEADER_LEN)
+                .ok_or(DecodeError::InvalidLength {
+                    account,
+                    expected: ACCOUNT_PADDING_LEN + SLAB_HEADER_LEN,
+                    actual: raw_len,
+                })?;
         let slop = len_without_header % size_of::<AnyNode>();
         let truncated_len = bytes.len() - slop;
         let bytes = &mut bytes[..truncated_len];
         let slab: &mut Self = unsafe { &mut *(bytes as *mut [u8] as *mut Slab) };
         slab.check_size_align(); // check alignment
-        slab
+        Ok(slab)
     }
 
     pub fn get(&self, key: u32) -> Option<&AnyNode> {
@@ -237,36 +277,92 @@ impl Slab {
         Some(self.header().root_node)
     }
 
-    pub fn traverse(&self, descending: bool) -> Vec<&LeafNode> {
-        fn walk_rec<'a>(
-            slab: &'a Slab,
-            sub_root: NodeHandle,
-            buf: &mut Vec<&'a LeafNode>,
-            descending: bool,
-        ) {
-            match slab.get(sub_root).unwrap().case().unwrap() {
-                NodeRef::Leaf(leaf) => {
-                    buf.push(leaf);
-                }
-                NodeRef::Inner(inner) => {
+    /*
+     * Function: traverse
+     * 1. Walk tree from root in key order, children of inner nodes by descending flag
+     * 2. Fail on handles outside of nodes, visits beyond node count or leaf count mismatch
+     */
+    pub fn traverse(&self, descending: bool) -> Result<Vec<&LeafNode>, DecodeError> {
+        let header = self.header();
+        let nodes = self.nodes();
+        let leaf_count = header.leaf_count as usize;
+        if leaf_count > nodes.len() {
+            return Err(DecodeError::OutOfBounds {
+                account: "openbook slab",
+                index: leaf_count,
+                len: nodes.len(),
+            });
+        }
+
+        let mut buf = Vec::with_capacity(leaf_count);
+        let mut stack: Vec<NodeHandle> = self.root().into_iter().collect();
+        let mut visited = 0;
+        while let Some(handle) = stack.pop() {
+            // A valid tree visits every node at most once, more means a cycle
+            visited += 1;
+            if visited > nodes.len() {
+                return Err(DecodeError::Corrupted {
+                    account: "openbook slab",
+                    reason: "cycle in node tree",
+                });
+            }
+
+            let node = nodes.get(handle as usize).ok_or(DecodeError::OutOfBounds {
+                account: "openbook slab",
+                index: handle as usize,
+                len: nodes.len(),
+            })?;
+            match node.case() {
+                Some(NodeRef::Leaf(leaf)) => buf.push(leaf),
+                Some(NodeRef::Inner(inner)) => {
+                    let children = inner.children;
                     if descending {
-                        walk_rec(slab, inner.children[1], buf, descending);
-                        walk_rec(slab, inner.children[0], buf, descending);
+                        stack.push(children[0]);
+                        stack.push(children[1]);
                     } else {
-                        walk_rec(slab, inner.children[0], buf, descending);
-                        walk_rec(slab, inner.children[1], buf, descending);
+                        stack.push(children[1]);
+                        stack.push(children[0]);
                     }
                 }
+                None => {
+                    return Err(DecodeError::Corrupted {
+                        account: "openbook slab",
+                        reason: "free node in tree",
+                    })
+                }
             }
         }
 
-        let mut buf = Vec::with_capacity(self.header().leaf_count as usize);
-        if let Some(r) = self.root() {
-            walk_rec(self, r, &mut buf, descending);
+        if buf.len() != leaf_count {
+            return Err(DecodeError::Corrupted {
+                account: "openbook slab",
+                reason: "leaf count mismatch",
+            });
         }
-        assert_eq!(buf.len(), buf.capacity());
-        buf
+        Ok(buf)
+    }
+}
+
+/*
+ * Helper function for checking serum head/tail padding of openbook account
+ * Returns account flags following head padding
+ */
+pub fn check_account_padding(data: &[u8], account: &'static str) -> Result<u64, DecodeError> {
+    if data.len() < ACCOUNT_PADDING_LEN {
+        return Err(DecodeError::InvalidLength {
+            account,
+            expected: ACCOUNT_PADDING_LEN,
+            actual: data.len(),
+        });
+    }
+    if !data.starts_with(ACCOUNT_HEAD_PADDING) || !data.ends_with(ACCOUNT_TAIL_PADDING) {
+        return Err(DecodeError::InvalidPadding { account });
     }
+
+    let flags_start = ACCOUNT_HEAD_PADDING.len();
+    let mut flags = [0u8; ACCOUNT_FLAGS_LEN];
+    flags.copy_from_slice(&data[flags_start..flags_start + ACCOUNT_FLAGS_LEN]);
+    Ok(u64::from_le_bytes(flags))
 }
 
 pub fn readable_price(price_lots: u64, market: &ObMarketInfo) -> f64 {
@@ -311,6 +407,215 @@ pub fn construct_levels(
             amount: readable_quantity(x.1, market),
             price_lots: x.0,
             size_lots: x.1,
+            venue: Venue::Openbook,
         })
         .collect()
 }
+
+pub fn construct_quotes(leaves: &Vec<&LeafNode>, market: &ObMarketInfo) -> Vec<MakerQuote> {
+    leaves
+        .iter()
+        .map(|x| MakerQuote {
+            maker: array_to_pubkey(x.owner).to_string(),
+            price: readable_price(x.price(), market),
+            amount: readable_quantity(x.quantity(), market),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use crate::structs::fixtures::{ob_leaf, ob_market, ob_slab, Lcg};
+
+    use super::*;
+
+    fn levels_of(leaves: &[LeafNode], is_bid: bool, depth: usize) -> Vec<MarketOrder> {
+        let mut data = ob_slab(leaves, is_bid);
+        let slab = Slab::new(&mut data, is_bid).unwrap();
+        construct_levels(
+            slab.traverse(is_bid).unwrap(),
+            &ob_market("sol-usdc"),
+            depth,
+        )
+    }
+
+    #[test]
+    fn traverses_leaves_in_key_order() {
+        let mut data = ob_slab(
+            &[
+                ob_leaf(50_100, 1, 1, [1; 4]),
+                ob_leaf(49_900, 2, 2, [2; 4]),
+                ob_leaf(50_000, 3, 3, [3; 4]),
+                ob_leaf(50_000, 4, 4, [4; 4]),
+            ],
+            true,
+        );
+        let slab = Slab::new(&mut data, true).unwrap();
+
+        let ascending: Vec<u64> = slab
+            .traverse(false)
+            .unwrap()
+            .iter()
+            .map(|x| x.quantity())
+            .collect();
+        let descending: Vec<u64> = slab
+            .traverse(true)
+            .unwrap()
+            .iter()
+            .map(|x| x.quantity())
+            .collect();
+        assert_eq!(ascending, vec![2, 3, 4, 1]);
+        assert_eq!(descending, vec![1, 4, 3, 2]);
+    }
+
+    #[test]
+    fn traverses_empty_and_single_leaf_slabs() {
+        let mut data = ob_slab(&[], true);
+        assert!(Slab::new(&mut data, true)
+            .unwrap()
+            .traverse(true)
+            .unwrap()
+            .is_empty());
+
+        let mut data = ob_slab(&[ob_leaf(50_000, 7, 1, [1; 4])], false);
+        let leaves = Slab::new(&mut data, false)
+            .unwrap()
+            .traverse(false)
+            .unwrap();
+        assert_eq!(leaves.len(), 1);
+        assert_eq!(leaves[0].price(), 50_000);
+        assert_eq!(leaves[0].quantity(), 7);
+    }
+
+    #[test]
+    fn rejects_malformed_slab_accounts() {
+        let leaves = [ob_leaf(50_000, 1, 1, [1; 4]), ob_leaf(50_100, 2, 2, [2; 4])];
+
+        let mut data = ob_slab(&leaves, true)[..12].to_vec();
+        assert!(matches!(
+            Slab::new(&mut data, true),
+            Err(DecodeError::InvalidLength { .. })
+        ));
+
+        let mut data = ob_slab(&leaves, true);
+        data.truncate(data.len() - 1);
+        assert_eq!(
+            Slab::new(&mut data, true).err(),
+            Some(DecodeError::InvalidPadding {
+                account: "openbook bids"
+            })
+        );
+
+        let mut data = ob_slab(&leaves, true);
+        assert_eq!(
+            Slab::new(&mut data, false).err(),
+            Some(DecodeError::InvalidFlags {
+                account: "openbook asks",
+                flags: ACCOUNT_FLAG_INITIALIZED | ACCOUNT_FLAG_BIDS,
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_corrupted_slab_trees() {
+        let leaves = [ob_leaf(50_000, 1, 1, [1; 4]), ob_leaf(50_100, 2, 2, [2; 4])];
+        // Root inner node is node 2 at slab offset 13 + header 32 + 2 nodes
+        let root_start = 13 + SLAB_HEADER_LEN + 2 * _NODE_SIZE;
+
+        // Root's upper child points past the nodes
+        let mut data = ob_slab(&leaves, true);
+        data[root_start + 28..root_start + 32].copy_from_slice(&99u32.to_le_bytes());
+        assert!(matches!(
+            Slab::new(&mut data, true).unwrap().traverse(true),
+            Err(DecodeError::OutOfBounds { index: 99, .. })
+        ));
+
+        // Root's upper child points back to root
+        let mut data = ob_slab(&leaves, true);
+        data[root_start + 28..root_start + 32].copy_from_slice(&2u32.to_le_bytes());
+        assert!(matches!(
+            Slab::new(&mut data, true).unwrap().traverse(true),
+            Err(DecodeError::Corrupted { .. })
+        ));
+
+        // Header claims more leaves than the tree holds
+        let mut data = ob_slab(&leaves, true);
+        data[13 + 24..13 + 32].copy_from_slice(&3u64.to_le_bytes());
+        assert!(matches!(
+            Slab::new(&mut data, true).unwrap().traverse(true),
+            Err(DecodeError::Corrupted { .. })
+        ));
+    }
+
+    #[test]
+    fn constructs_aggregated_levels() {
+        let leaves = [
+            ob_leaf(50_100, 100, 1, [1; 4]),
+            ob_leaf(50_000, 200, 2, [2; 4]),
+            ob_leaf(50_100, 50, 3, [3; 4]),
+            ob_leaf(50_200, 10, 4, [4; 4]),
+        ];
+
+        let asks = levels_of(&leaves, false, 20);
+        let lots: Vec<(u64, u64)> = asks.iter().map(|x| (x.price_lots, x.size_lots)).collect();
+        assert_eq!(lots, vec![(50_000, 200), (50_100, 150), (50_200, 10)]);
+        assert_eq!(asks[0].price, 50.0);
+        assert_eq!(asks[0].amount, 0.2);
+        assert_eq!(asks[1].price, 50.1);
+        assert_eq!(asks[1].amount, 0.15);
+        assert!(asks.iter().all(|x| x.venue == Venue::Openbook));
+
+        let bids = levels_of(&leaves, true, 2);
+        let lots: Vec<(u64, u64)> = bids.iter().map(|x| (x.price_lots, x.size_lots)).collect();
+        assert_eq!(lots, vec![(50_200, 10), (50_100, 150)]);
+    }
+
+    #[test]
+    fn constructs_maker_quotes() {
+        let mut data = ob_slab(&[ob_leaf(50_000, 200, 1, [5; 4])], true);
+        let leaves = Slab::new(&mut data, true).unwrap().traverse(true).unwrap();
+        let quotes = construct_quotes(&leaves, &ob_market("sol-usdc"));
+
+        assert_eq!(quotes.len(), 1);
+        assert_eq!(quotes[0].maker, array_to_pubkey([5; 4]).to_string());
+        assert_eq!(quotes[0].price, 50.0);
+        assert_eq!(quotes[0].amount, 0.2);
+    }
+
+    #[test]
+    fn levels_are_sorted_aggregated_and_within_depth() {
+        let mut rng = Lcg(7);
+        for _ in 0..200 {
+            let num_leaves = rng.range(0, 40);
+            let leaves: Vec<LeafNode> = (0..num_leaves)
+                .map(|i| ob_leaf(rng.range(49_990, 50_010), rng.range(1, 1_000), i, [i; 4]))
+                .collect();
+            let is_bid = rng.range(0, 2) == 1;
+            let depth = rng.range(1, 10) as usize;
+
+            // Expected levels are the best distinct prices with summed quantities
+            let mut expected: BTreeMap<u64, u64> = BTreeMap::new();
+            for leaf in leaves.iter() {
+                *expected.entry(leaf.price()).or_default() += leaf.quantity();
+            }
+            let mut expected: Vec<(u64, u64)> = expected.into_iter().collect();
+            if is_bid {
+                expected.reverse();
+            }
+            expected.truncate(depth);
+
+            let levels = levels_of(&leaves, is_bid, depth);
+            let lots: Vec<(u64, u64)> =
+                levels.iter().map(|x| (x.price_lots, x.size_lots)).collect();
+            assert!(levels.len() <= depth);
+            assert_eq!(lots, expected);
+            assert!(levels.windows(2).all(|x| if is_bid {
+                x[0].price > x[1].price
+            } else {
+                x[0].price < x[1].price
+            }));
+        }
+    }
+}
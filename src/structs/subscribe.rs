@@ -0,0 +1,10 @@
+/*
+ * Settings of subscribe_geyser from environment
+ */
+#[derive(Debug, Clone, Default)]
+pub struct SubscribeOptions {
+    pub analytics_notionals: Vec<f64>,
+    pub sol_oracle_address: Option<String>,
+    pub aum_account_address: Option<String>,
+    pub subscribe_transactions: bool,
+}
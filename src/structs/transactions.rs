@@ -0,0 +1,34 @@
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+
+use super::market::MarketTrade;
+
+/*
+ * Taker instruction of a transaction hitting a venue market
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct TxFill {
+    pub slot: u64,
+    pub signature: String,
+    pub tx_index: u64,
+    pub instruction_index: u32,
+    pub inner_instruction_index: Option<u32>,
+    pub market: String,
+    pub accounts: Vec<String>,
+    pub taker: Option<String>,
+    pub fee_payer: String,
+}
+
+/*
+ * Transactions and trades waiting for attribution
+ */
+#[derive(Debug, Default)]
+pub struct TxFillTracker {
+    pub enabled: bool,
+    pub last_slot: u64,
+    // Subscribed venue account to its market address
+    pub market_accounts: HashMap<Pubkey, String>,
+    pub tx_fills: HashMap<String, Vec<TxFill>>,
+    pub seen_signatures: HashMap<String, u64>,
+    pub pending_trades: Vec<Vec<MarketTrade>>,
+}
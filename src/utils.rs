@@ -1,8 +1,14 @@
 use num_traits::FromPrimitive;
-use solana_sdk::pubkey::Pubkey;
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::{nonblocking::rpc_client::RpcClient, rpc_config::RpcAccountInfoConfig};
+use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey};
 use sqlx::types::Decimal;
+use std::collections::HashMap;
 
-use crate::structs::market::{PublishAllData, PublishUidData};
+use crate::{
+    constants::RPC_MAX_MULTIPLE_ACCOUNTS,
+    structs::market::{PublishAllData, PublishUidData},
+};
 
 pub fn generate_publish_data<F>(market: &str, data: &F, id: Option<String>) -> String
 where
@@ -19,7 +25,12 @@ where
     json_string
 }
 
-pub fn generate_publish_uid_data<F>(market: &str, data: &F, uid: u64) -> String
+pub fn generate_publish_uid_data<F>(
+    market: &str,
+    data: &F,
+    uid: u64,
+    owner: Option<String>,
+) -> String
 where
     F: serde::Serialize + Clone,
 {
@@ -27,6 +38,7 @@ where
         _type: uid,
         market: market.to_string(),
         data: data.clone(),
+        owner,
     };
 
     let json_string = serde_json::to_string(&publish_data).expect("Failed to serialize to JSON");
@@ -46,3 +58,35 @@ pub fn array_to_pubkey(data: [u64; 4]) -> Pubkey {
             .unwrap_or_else(|_| [0; 32]),
     )
 }
+
+/*
+ * Function: fetch_accounts
+ * 1. Get account data of keys using rpc client, in chunks of rpc's multiple accounts limit
+ * 2. Skip accounts not found, so callers decide which missing account fails
+ */
+pub async fn fetch_accounts(
+    rpc_client: &RpcClient,
+    keys: &[Pubkey],
+) -> anyhow::Result<HashMap<Pubkey, Vec<u8>>> {
+    let rpc_config = RpcAccountInfoConfig {
+        encoding: Some(UiAccountEncoding::Base64),
+        data_slice: None,
+        commitment: Some(CommitmentConfig::confirmed()),
+        min_context_slot: None,
+    };
+
+    let mut accounts: HashMap<Pubkey, Vec<u8>> = HashMap::new();
+    for chunk in keys.chunks(RPC_MAX_MULTIPLE_ACCOUNTS) {
+        let results = rpc_client
+            .get_multiple_accounts_with_config(chunk, rpc_config.clone())
+            .await?
+            .value;
+        for (key, result) in chunk.iter().zip(results) {
+            if let Some(account) = result {
+                accounts.insert(*key, account.data);
+            }
+        }
+    }
+
+    Ok(accounts)
+}